@@ -1,8 +1,16 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
-use crate::Money;
+use chrono::NaiveDate;
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::{
+    money::PercentOrMoney,
+    moneybag::{Cost, Interval, Invoice, Rate, Scope},
+    Money,
+};
 
 #[derive(Debug, Parser)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct Args {
     /// File to store data in
     #[arg(short, long, default_value = "~/.moneybags")]
@@ -10,30 +18,287 @@ pub(crate) struct Args {
 
     #[arg(short, long, default_value_t = false)]
     pub(crate) autosave: bool,
+
+    /// Config file, in TOML. Its `[aliases]` table defines words that expand to longer commands.
+    #[arg(long, default_value = "~/.config/moneybags/config.toml")]
+    pub(crate) config: String,
+
+    /// Print what commands would change, without changing anything
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+
+    /// Don't print what commands did, only what was asked for and errors. For scripts.
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub(crate) quiet: bool,
+
+    /// Print every entry commands add, remove, or change, with old and new values
+    #[arg(short, long)]
+    pub(crate) verbose: bool,
+
+    /// Don't color output. Colors are also off when `NO_COLOR` is set or output isn't a terminal.
+    #[arg(long)]
+    pub(crate) no_color: bool,
+
+    /// Trace what moneybags does, e.g. loading, saving, commands run, and entries changed, at
+    /// this level or above: error, warn, info, debug, or trace. To stderr unless --log-file is
+    /// given.
+    #[arg(long)]
+    pub(crate) log_level: Option<LevelFilter>,
+
+    /// Append the trace to this file, at info unless --log-level is given
+    #[arg(long)]
+    pub(crate) log_file: Option<String>,
+
+    /// Run commands from a file, one per line, instead of starting the shell. Commands are also
+    /// read from stdin when it isn't a terminal.
+    #[arg(short, long, conflicts_with = "command")]
+    pub(crate) script: Option<String>,
+
+    /// Keep running a script after a line fails, instead of stopping without saving
+    #[arg(short, long)]
+    pub(crate) keep_going: bool,
+
+    /// Run a single command, e.g. "balance", save, and exit, instead of starting the shell
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub(crate) command: Vec<String>,
 }
 
 #[derive(Debug, Parser)]
 #[command(multicall = true, disable_help_flag = true)]
 pub(crate) enum Command {
-    /// Add a rate, invoice, or cost
+    /// Add a rate, invoice, cost, customer, or asset
     #[clap(subcommand, alias = "a")]
     Add(AddCommand),
-    /// List rates, invoices, or costs
-    #[clap(subcommand, alias = "l")]
-    List(ListCommand),
-    /// Interactively edit a rate, invoice, or cost
+    /// List rates, invoices, costs, customers, or assets
+    #[clap(alias = "l")]
+    List(ListArgs),
+    /// Interactively edit a rate, invoice, cost, customer, or asset
     #[clap(subcommand, alias = "e")]
     Edit(EditCommand),
-    /// Delete a rate, invoice, or cost
-    #[clap(subcommand, alias = "d")]
-    Delete(DeleteCommand),
-    /// Write pending changes to file. There is currently no way to see pending changes
+    /// Delete a rate, invoice, cost, customer, or asset
+    #[clap(alias = "d")]
+    Delete(DeleteArgs),
+    /// Show every detail of an entry
+    #[clap(subcommand)]
+    Show(ShowCommand),
+    /// Search invoices, costs, and rates for a term, ignoring case
+    Search { term: String },
+    /// Write pending changes to file. See what a command would change first with preview or --dry-run
     #[clap(alias = "s")]
     Save { path: Option<String> },
+    /// Print what a command would change, without changing anything, e.g. "preview add cost monthly 100 rent"
+    Preview {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Quit, offering to save any pending changes
+    #[clap(alias = "exit", alias = "q")]
+    Quit,
+
+    /// Show this month's income and costs, unpaid invoices, costs coming up, VAT owed, and
+    /// progress towards the goal
+    #[clap(alias = "dash")]
+    Dashboard,
 
     /// Calculate difference between costs and invoices
     #[clap(alias = "b")]
-    Balance,
+    Balance {
+        #[clap(flatten)]
+        filter: BalanceFilter,
+        /// Print as JSON
+        #[clap(short, long)]
+        json: bool,
+    },
+
+    /// Show spending against budgets for the period containing date (default today)
+    Budget { date: Option<String> },
+
+    /// Project the balance over the coming months from unpaid invoices, recurring costs, and
+    /// average income
+    Forecast {
+        /// Number of months, starting with this one
+        #[clap(default_value_t = 6)]
+        months: u32,
+        /// Only count business or personal entries
+        #[clap(short, long)]
+        scope: Option<Scope>,
+    },
+
+    /// Show how this year's invoicing compares to the revenue goal (see set revenue-goal)
+    Goal,
+
+    /// Summarize income and costs over a period
+    Report(ReportArgs),
+
+    /// Write the whole moneybag to a file for other programs
+    #[clap(subcommand)]
+    Export(ExportCommand),
+
+    /// Aggregate invoices or costs, e.g. "query sum amount from costs where date >= 2025-01 group
+    /// by category". Aggregates are count, sum, avg, min, and max, and conditions compare with =,
+    /// !=, <, <=, >, >=, or ~ (contains).
+    Query {
+        #[clap(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+
+    /// Show statistics of entries
+    #[clap(subcommand)]
+    Stats(StatsCommand),
+
+    /// Bill expenses on to the invoice's customer. Bills all their unbilled expenses, unless
+    /// specific costs are given.
+    Bill {
+        /// Invoice ID (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        invoice: usize,
+        /// Cost IDs (see list billable)
+        costs: Vec<usize>,
+    },
+
+    /// Mark an invoice as paid
+    Pay {
+        /// Invoice ID (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        invoice: usize,
+        /// Date of payment (default today)
+        date: Option<NaiveDate>,
+    },
+
+    /// Notify of invoices that became overdue, budgets exceeded, and subscriptions renewing soon,
+    /// on a webhook or the desktop as the config says (see its [notify] table). Each is notified
+    /// of once. Suited to running from cron.
+    Notify {
+        /// Notify of everything that needs attention, also what was notified of before
+        #[clap(long)]
+        all: bool,
+        /// Only list what would be notified of
+        #[clap(long)]
+        list: bool,
+    },
+
+    /// Email customers reminders of their overdue invoices, over the SMTP server in the config (see
+    /// its [email] table), and note the date on the invoices. Suited to running from cron.
+    Remind {
+        /// Days to wait before reminding of an invoice again
+        #[clap(long, default_value_t = 7)]
+        every: u32,
+        /// Only list the reminders that would be sent, with what they say
+        #[clap(long)]
+        list: bool,
+    },
+
+    /// Merge what changed here with what changed in another copy of the moneybag, e.g. in a shared
+    /// folder, since they were last synced, and save the merge to both. Entries changed differently
+    /// in both are asked about.
+    Sync {
+        /// The other copy (default sync in the config)
+        path: Option<String>,
+        /// Keep this version or the other copy's of entries changed in both, without asking
+        #[clap(long, value_enum)]
+        keep: Option<Keep>,
+    },
+
+    /// Import entries from other programs
+    #[clap(subcommand, alias = "i")]
+    Import(ImportCommand),
+
+    /// Change a setting stored in the moneybag
+    #[clap(subcommand)]
+    Set(SetCommand),
+
+    /// Exchange rates from the European Central Bank
+    #[clap(subcommand)]
+    Fx(FxCommand),
+
+    /// Browse invoices, costs, and the balance in full screen, editing and filtering with keys
+    Tui,
+
+    /// Serve the moneybag over HTTP as a JSON API, with a page at / for entering costs from a
+    /// phone. Runs until stopped, saving changes as they're made. Only from the command line.
+    Serve {
+        /// Address and port to listen on
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+        /// Token requests must give as "Authorization: Bearer <token>" (default `MONEYBAGS_TOKEN`)
+        #[clap(long)]
+        token: Option<String>,
+    },
+
+    /// Keep the moneybag loaded and run commands other terminals give, e.g. "moneybags balance",
+    /// one at a time until stopped, so only one process writes the file. Only from the command line.
+    Daemon,
+
+    /// Run a custom command from the scripts directory, or list them without a name. Scripts
+    /// also run by name, e.g. "travel 2025" for travel.rhai.
+    Script {
+        name: Option<String>,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    /// Print example command sequences for common tasks, e.g. "examples invoicing"
+    Examples { topic: Option<ExampleTopic> },
+
+    /// Adjust recurring costs from a month onward, by a percentage ("5%") or fixed amount ("100")
+    Adjust {
+        /// First month to adjust, e.g. 2025-04
+        #[clap(value_parser = parse_month)]
+        from: NaiveDate,
+        #[clap(allow_negative_numbers = true)]
+        change: PercentOrMoney,
+        /// Adjust every cost with this name, instead of all monthly costs
+        #[clap(short, long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum Keep {
+    /// The version here
+    Mine,
+    /// The version in the other copy
+    Theirs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum TopKind {
+    /// Customers by what they were invoiced
+    Invoices,
+    /// Costs by name, or category with --category
+    Costs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum ExampleTopic {
+    /// Setting up rates, tiers, and currencies
+    Rates,
+    /// Invoicing a month's work, with expenses, and getting paid
+    Invoicing,
+    /// Recording VAT on costs
+    Vat,
+    /// Importing costs from the bank
+    Import,
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct ListArgs {
+    /// Print as JSON, same as --format json
+    #[clap(short, long, global = true, conflicts_with = "format")]
+    pub(crate) json: bool,
+    /// Print as text, JSON, or CSV. CSV is available for rates, invoices, and costs.
+    #[clap(short, long, global = true, default_value = "text")]
+    pub(crate) format: ListFormat,
+    #[clap(flatten)]
+    pub(crate) filter: ListFilter,
+    /// Sort rates, invoices, or costs. Indices stay those of the entries, for edit and delete.
+    #[clap(long, global = true)]
+    pub(crate) sort: Option<ListSort>,
+    /// List in reverse order
+    #[clap(long, global = true)]
+    pub(crate) reverse: bool,
+    #[clap(subcommand)]
+    pub(crate) command: ListCommand,
 }
 
 #[derive(Debug, Subcommand)]
@@ -43,48 +308,734 @@ pub(crate) enum ListCommand {
     Rates,
     /// List invoices
     #[clap(alias = "i")]
-    Invoices,
+    Invoices {
+        #[clap(short, long)]
+        scope: Option<Scope>,
+        /// Add a column with the running total, in the base currency, ordering by date unless
+        /// sorted otherwise
+        #[clap(long)]
+        running: bool,
+    },
     /// List costs
     #[clap(alias = "c")]
-    Costs,
+    Costs {
+        #[clap(short, long)]
+        scope: Option<Scope>,
+        /// Add a column with the running total, ordering by date unless sorted otherwise
+        #[clap(long)]
+        running: bool,
+    },
+    /// List invoices and costs together by date, with the balance after each
+    #[clap(alias = "t")]
+    Transactions {
+        #[clap(short, long)]
+        scope: Option<Scope>,
+    },
+    /// List subscriptions by upcoming renewal, with their annualized total
+    #[clap(alias = "s")]
+    Subscriptions,
+    /// List customers, with the total invoiced to each
+    #[clap(alias = "cu")]
+    Customers {
+        /// Draw bars of what was invoiced to each
+        #[clap(long)]
+        chart: bool,
+    },
+    /// List assets and their monthly depreciation
+    Assets,
+    /// List costs that are billable to a customer, but not yet billed
+    Billable,
+    /// List bank import rules
+    Rules,
+}
+
+/// Filters for listing rates, invoices, and costs. Entries without the filtered field don't match.
+#[derive(Debug, clap::Args)]
+pub(crate) struct ListFilter {
+    /// Only invoices to this customer, and costs for or billable to them
+    #[clap(long, global = true)]
+    pub(crate) customer: Option<String>,
+    /// Only entries whose name contains this, ignoring case. Invoices match on customer.
+    #[clap(long, global = true)]
+    pub(crate) name_contains: Option<String>,
+    /// Only entries of at least this amount. Invoices match on total in the base currency.
+    #[clap(long, global = true)]
+    pub(crate) min: Option<Money>,
+    /// Only entries of at most this amount. Invoices match on total in the base currency.
+    #[clap(long, global = true)]
+    pub(crate) max: Option<Money>,
+    /// Only invoices at this rate, or only this rate
+    #[clap(long, global = true)]
+    pub(crate) rate: Option<String>,
+}
+
+impl ListFilter {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.customer.is_none()
+            && self.name_contains.is_none()
+            && self.min.is_none()
+            && self.max.is_none()
+            && self.rate.is_none()
+    }
+
+    fn matches(
+        &self,
+        customer: Option<&str>,
+        name: Option<&str>,
+        amount: Money,
+        rate: Option<&str>,
+    ) -> bool {
+        let matches = |filter: Option<&String>, value: Option<&str>| {
+            filter.is_none_or(|filter| value == Some(filter.as_str()))
+        };
+        matches(self.customer.as_ref(), customer)
+            && matches(self.rate.as_ref(), rate)
+            && self.name_contains.as_ref().is_none_or(|part| {
+                name.is_some_and(|name| name.to_lowercase().contains(&part.to_lowercase()))
+            })
+            && self.min.is_none_or(|min| amount >= min)
+            && self.max.is_none_or(|max| amount <= max)
+    }
+
+    pub(crate) fn invoice(&self, invoice: &Invoice) -> bool {
+        self.matches(
+            invoice.customer.as_deref(),
+            invoice.customer.as_deref(),
+            invoice.base_total(),
+            invoice.rate_name.as_deref(),
+        )
+    }
+
+    pub(crate) fn cost(&self, cost: &Cost) -> bool {
+        self.matches(cost.attributed_to(), Some(&cost.name), cost.amount, None)
+    }
+
+    pub(crate) fn rate(&self, name: &str, rate: &Rate) -> bool {
+        self.matches(None, Some(name), rate.rate, Some(name))
+    }
+}
+
+/// Filters for the balance. Entries without the filtered field don't count, so assets only count
+/// without a customer or category.
+#[derive(Debug, clap::Args)]
+pub(crate) struct BalanceFilter {
+    /// Only count business or personal entries
+    #[clap(short, long)]
+    pub(crate) scope: Option<Scope>,
+    /// Only count entries dated in this year, and depreciation during it
+    #[clap(short, long)]
+    pub(crate) year: Option<i32>,
+    /// Only count invoices to this customer, and costs for or billable to them
+    #[clap(short, long)]
+    pub(crate) customer: Option<String>,
+    /// Only count costs in this category
+    #[clap(long)]
+    pub(crate) category: Option<String>,
+}
+
+impl BalanceFilter {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scope.is_none()
+            && self.year.is_none()
+            && self.customer.is_none()
+            && self.category.is_none()
+    }
+
+    fn in_year(&self, date: &str) -> bool {
+        self.year
+            .is_none_or(|year| date.starts_with(&year.to_string()))
+    }
+
+    pub(crate) fn invoice(&self, invoice: &Invoice) -> bool {
+        self.scope.is_none_or(|scope| scope == invoice.scope)
+            && self.in_year(&invoice.date)
+            && self
+                .customer
+                .as_ref()
+                .is_none_or(|customer| invoice.customer.as_ref() == Some(customer))
+            && self.category.is_none()
+    }
+
+    pub(crate) fn cost(&self, cost: &Cost) -> bool {
+        self.scope.is_none_or(|scope| scope == cost.scope)
+            && self.in_year(&cost.date)
+            && self
+                .customer
+                .as_ref()
+                .is_none_or(|customer| cost.attributed_to() == Some(customer))
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| cost.category.as_ref() == Some(category))
+    }
+
+    /// Whether assets count, which are always business purchases
+    pub(crate) fn assets(&self) -> bool {
+        self.scope.is_none_or(|scope| scope == Scope::Business)
+            && self.customer.is_none()
+            && self.category.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum ListFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub(crate) enum ListSort {
+    Date,
+    #[value(alias = "value")]
+    Amount,
+    Name,
+    Customer,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum StatsCommand {
+    /// Count, total, mean, median, range, and 90th percentile of invoice amounts
+    Invoices {
+        /// Year, quarter, or month, e.g. 2025, 2025-Q2, or 2025-05 (default all time)
+        #[clap(short, long)]
+        period: Option<String>,
+        /// Only invoices to this customer
+        #[clap(short, long)]
+        customer: Option<String>,
+    },
+    /// Entries of each kind, the file's size and when it was saved, and the dates it spans
+    File,
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct ReportArgs {
+    /// Save the report to a .csv, .html, or .pdf file instead of printing it
+    #[clap(short, long, global = true)]
+    pub(crate) out: Option<String>,
+    #[clap(subcommand)]
+    pub(crate) command: ReportCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum ReportCommand {
+    /// Invoiced income, costs, and net per month of a year (default this year)
+    #[clap(alias = "m")]
+    Monthly {
+        year: Option<i32>,
+        /// Only count business or personal entries
+        #[clap(short, long)]
+        scope: Option<Scope>,
+        /// Draw bars of what was invoiced, and a line of the balance over the year
+        #[clap(long)]
+        chart: bool,
+    },
+    /// Invoiced income, costs, net, and invoice count and average of every year with entries
+    #[clap(alias = "y")]
+    Yearly {
+        /// Only count business or personal entries
+        #[clap(short, long)]
+        scope: Option<Scope>,
+    },
+    /// Profit and loss for a period: revenue, costs per category, operating result, and VAT
+    Pnl {
+        /// Year, quarter, or month, e.g. 2025, 2025-Q2, or 2025-05 (default this year)
+        #[clap(short, long)]
+        period: Option<String>,
+        /// Only count business or personal entries
+        #[clap(short, long)]
+        scope: Option<Scope>,
+        /// Print as text, JSON, or CSV
+        #[clap(short, long, default_value = "text")]
+        format: ListFormat,
+    },
+    /// VAT charged and deducted in a quarter, by box of the VAT declaration
+    Vat {
+        /// Quarter, e.g. 2025-Q2 (default this quarter)
+        #[clap(short, long)]
+        quarter: Option<String>,
+    },
+    /// Costs against income per month of a year (default this year), with the invoices and hours
+    /// it took to break even
+    Breakeven { year: Option<i32> },
+    /// Hours billed per month of a year (default this year), against the target set with set
+    /// hours-target
+    Utilization {
+        year: Option<i32>,
+        /// Hours a month to bill, instead of the target set
+        #[clap(short, long)]
+        target: Option<Money>,
+    },
+    /// Income tax and social fees to set aside from a year's profit (default this year), from the
+    /// rates set with set tax-rate and set social-fees
+    Tax { year: Option<i32> },
+    /// Invoiced and costs per month with their 3- and 12-month moving averages, and whether the
+    /// net is trending up or down
+    Trend {
+        /// Number of months, ending with this one
+        #[clap(default_value_t = 12)]
+        months: u32,
+        /// Only count business or personal entries
+        #[clap(short, long)]
+        scope: Option<Scope>,
+    },
+    /// Revenue per customer less the costs spent serving them (see add cost --for), with hours
+    /// billed and the profit per hour
+    Profitability {
+        /// Year, quarter, or month, e.g. 2025, 2025-Q2, or 2025-05 (default all time)
+        #[clap(short, long)]
+        period: Option<String>,
+    },
+    /// Unpaid invoices per customer, by how many days past due they are
+    Aging,
+    /// Customers by revenue, or costs by spend, largest first, with their running share of the total
+    Top {
+        #[clap(default_value = "invoices")]
+        kind: TopKind,
+        /// How many to show
+        #[clap(short, long, default_value_t = 10)]
+        n: usize,
+        /// Year, quarter, or month, e.g. 2025, 2025-Q2, or 2025-05 (default all time)
+        #[clap(short, long)]
+        period: Option<String>,
+        /// Rank costs by category instead of name
+        #[clap(short, long)]
+        category: bool,
+    },
+    /// Spend per cost category, in a year or all time, most first
+    #[clap(alias = "c")]
+    Categories {
+        #[clap(short, long)]
+        year: Option<i32>,
+        /// Spend per month of the year (default this year) instead of totals
+        #[clap(short, long)]
+        monthly: bool,
+        /// Only count business or personal entries
+        #[clap(short, long)]
+        scope: Option<Scope>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum ShowCommand {
+    /// Show an invoice, identified by ID (see list)
+    #[clap(alias = "i")]
+    Invoice {
+        /// ID (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+    },
+    /// Show a cost, identified by ID (see list)
+    #[clap(alias = "c")]
+    Cost {
+        /// ID (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+    },
+    /// Show a customer's details, identified by name
+    #[clap(alias = "cu")]
+    Customer { name: String },
 }
 
 #[derive(Debug, Subcommand)]
 pub(crate) enum AddCommand {
     /// Add an hourly rate, with a name
     #[clap(alias = "r")]
-    Rate { rate: Money, name: String },
+    Rate {
+        rate: Money,
+        name: String,
+        /// Currency code, if not the base currency. Needs an exchange rate (see set).
+        #[clap(long)]
+        currency: Option<String>,
+        /// Bill hours in blocks of this size, e.g. 0.25 for 15 minutes
+        #[clap(short, long)]
+        increment: Option<Money>,
+    },
     /// Add an invoice, with a date and amount. If a rate is given, assumes amount
-    /// to be hours and calculates total.
+    /// to be hours and calculates total. Without arguments, asks for each field.
     #[clap(alias = "i")]
-    Invoice {
+    Invoice(InvoiceArgs),
+    /// Add a cost. If date is "monthly", an entry will be generated for each month.
+    #[clap(
+        alias = "c",
+        args_conflicts_with_subcommands = true,
+        subcommand_negates_reqs = true
+    )]
+    Cost(CostArgs),
+    /// Add a tier to a rate, e.g. overtime billed at 1.5 times the rate. Replaces any existing
+    /// tier with the same name.
+    #[clap(alias = "t")]
+    Tier {
+        rate: String,
+        tier: String,
+        multiplier: Money,
+    },
+    /// Add a customer, which invoices and billable costs can then refer to
+    #[clap(alias = "cu")]
+    Customer(CustomerArgs),
+    /// Add an asset, which is expensed evenly over the given number of years
+    Asset {
         date: String,
         amount: Money,
+        name: String,
+        years: u32,
+    },
+    /// Add an import rule, naming and categorizing bank transactions whose description contains pattern
+    Rule {
+        pattern: String,
+        name: String,
+        #[clap(short, long)]
+        category: Option<String>,
+    },
+    /// Add a budget for a cost category. Replaces any existing budget for the category.
+    #[clap(alias = "b")]
+    Budget {
+        category: String,
+        amount: Money,
+        #[clap(short, long, default_value = "monthly")]
+        interval: Interval,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct InvoiceArgs {
+    /// Leave out date and amount to be asked for each field instead
+    #[clap(requires = "amount")]
+    pub(crate) date: Option<String>,
+    pub(crate) amount: Option<Money>,
+    #[clap(short, long)]
+    pub(crate) rate: Option<String>,
+    #[clap(short, long)]
+    pub(crate) customer: Option<String>,
+    /// Hours billed at one of the rate's tiers, e.g. --tier weekend=4. Can be given several times.
+    #[clap(short, long, value_parser = parse_tier_hours, requires = "rate")]
+    pub(crate) tier: Vec<(String, Money)>,
+    #[clap(short, long, default_value = "business")]
+    pub(crate) scope: Scope,
+    /// VAT rate charged on top, e.g. 25%
+    #[clap(long, value_parser = parse_percent)]
+    pub(crate) vat: Option<Money>,
+    /// Add the invoice even if an identical one exists
+    #[clap(short, long)]
+    pub(crate) force: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct CustomerArgs {
+    pub(crate) name: String,
+    #[clap(short, long)]
+    pub(crate) address: Option<String>,
+    #[clap(short, long)]
+    pub(crate) email: Option<String>,
+    /// Organisation number
+    #[clap(short, long)]
+    pub(crate) org_number: Option<String>,
+    #[clap(short, long)]
+    pub(crate) vat_number: Option<String>,
+    /// Days from invoice date to due date
+    #[clap(short, long)]
+    pub(crate) terms: Option<u32>,
+    /// Number this customer's invoices in their own series, e.g. ACME gives ACME-2025-001
+    #[clap(short, long)]
+    pub(crate) prefix: Option<String>,
+}
+
+/// Stands for the entry added last until resolved against the moneybag
+pub(crate) const LAST: usize = usize::MAX;
+
+fn parse_index(index: &str) -> Result<usize, String> {
+    if index == "last" {
+        return Ok(LAST);
+    }
+    index
+        .parse()
+        .map_err(|e| format!("expected a number or last: {e}"))
+}
+
+fn parse_percent(percent: &str) -> Result<Money, String> {
+    percent
+        .strip_suffix('%')
+        .unwrap_or(percent)
+        .parse()
+        .map_err(|e| format!("could not parse percentage: {e}"))
+}
+
+/// The first day of a month given as YYYY-MM
+fn parse_month(month: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+        .ok()
+        // chrono reads "25-4" as April of year 25
+        .filter(|first| first.format("%Y-%m").to_string() == month)
+        .ok_or_else(|| format!("expected a month like 2025-04, not {month}"))
+}
+
+fn parse_tier_hours(tier_hours: &str) -> Result<(String, Money), String> {
+    let (tier, hours) = tier_hours
+        .split_once('=')
+        .ok_or("expected <tier>=<hours>")?;
+    let hours = hours
+        .parse()
+        .map_err(|e| format!("could not parse hours: {e}"))?;
+    Ok((tier.to_string(), hours))
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct CostArgs {
+    #[clap(required = true)]
+    pub(crate) date: Option<String>,
+    #[clap(required = true)]
+    pub(crate) amount: Option<Money>,
+    #[clap(required = true)]
+    pub(crate) name: Option<String>,
+    /// Deductible VAT included in amount, as a rate ("25%") or an amount
+    #[clap(long)]
+    pub(crate) vat: Option<PercentOrMoney>,
+    /// Mark the cost as a subscription billed at this interval
+    #[clap(long, requires = "renews")]
+    pub(crate) subscription: Option<Interval>,
+    /// Next renewal date of the subscription, e.g. 2025-09-01
+    #[clap(long, requires = "subscription")]
+    pub(crate) renews: Option<NaiveDate>,
+    /// Category, used for budgets
+    #[clap(short, long)]
+    pub(crate) category: Option<String>,
+    /// Customer to bill the cost on to (see bill)
+    #[clap(short, long)]
+    pub(crate) billable: Option<String>,
+    /// Customer the cost was spent serving, for report profitability
+    #[clap(long = "for", value_name = "CUSTOMER")]
+    pub(crate) customer: Option<String>,
+    #[clap(short, long)]
+    pub(crate) note: Option<String>,
+    #[clap(short, long, default_value = "business")]
+    pub(crate) scope: Scope,
+    /// Add the cost even if an identical one exists
+    #[clap(short, long)]
+    pub(crate) force: bool,
+    #[clap(subcommand)]
+    pub(crate) calculated: Option<CalculatedCost>,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum CalculatedCost {
+    /// Add a mileage allowance, calculated from the mileage rate (see set)
+    #[clap(alias = "m")]
+    Mileage { date: String, km: Money },
+    /// Add a per diem allowance, calculated from the per diem rate (see set)
+    #[clap(alias = "p")]
+    Perdiem { date: String, days: Money },
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum FxCommand {
+    /// Set exchange rates to the reference rates of the European Central Bank, for the currencies
+    /// given, those with an exchange rate, and those of rates (see set base-currency)
+    Update {
+        currencies: Vec<String>,
+        /// Use the rates fetched last, without fetching
+        #[clap(long)]
+        offline: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum SetCommand {
+    /// Set the mileage allowance per km
+    MileageRate { rate: Money },
+    /// Set the per diem allowance per day
+    PerDiem { rate: Money },
+    /// Set the prefix of invoice numbers, e.g. INV gives INV-2025-001. Customers can have their own
+    /// (see add customer).
+    InvoicePrefix { prefix: String },
+    /// Set the value in the base currency of one unit of another currency
+    ExchangeRate { currency: String, rate: Money },
+    /// Set the currency the books are kept in, e.g. SEK, for fx update
+    BaseCurrency { currency: String },
+    /// Set the income tax rate, in percent of profit after social fees, for report tax
+    TaxRate {
+        #[clap(value_parser = parse_percent)]
+        rate: Money,
+    },
+    /// Set how much business revenue to invoice in a year, or a month or quarter with --interval
+    RevenueGoal {
+        amount: Money,
+        #[clap(short, long, default_value = "yearly")]
+        interval: Interval,
+    },
+    /// Set how many hours a month to bill, for report utilization
+    HoursTarget { hours: Money },
+    /// Set the social fees, in percent of profit, for report tax
+    SocialFees {
+        #[clap(value_parser = parse_percent)]
+        rate: Money,
+    },
+    /// Set which columns of a bank CSV export hold date, description, and amount, counting from 0
+    BankColumns {
+        date: usize,
+        description: usize,
+        amount: usize,
+        #[clap(short, long, default_value_t = ',')]
+        delimiter: char,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum ImportCommand {
+    /// Import outgoing transactions from a bank CSV export as costs, using import rules (see add
+    /// rule), and flag incoming ones that pay an invoice
+    #[clap(alias = "b")]
+    Bank { file: String },
+    /// Import recent transactions from the bank through `GoCardless` Bank Account Data, as import
+    /// bank does (see the [bank] table of the config)
+    Gocardless {
+        /// How many days back to import
+        #[clap(short, long, default_value_t = 30)]
+        days: u32,
+    },
+    /// Import a GnuCash book, XML or SQLite: income transactions as invoices, expenses as costs
+    #[clap(alias = "g")]
+    Gnucash {
+        file: String,
+        /// TOML file mapping account names to what their transactions become, e.g.
+        /// "Income:Consulting:Acme" = "invoice Acme" or "Expenses:Hosting" = "cost hosting"
         #[clap(short, long)]
-        rate: Option<String>,
+        accounts: Option<String>,
+    },
+    /// Import a Stripe balance transactions CSV export: payments as paid invoices, fees as costs
+    #[clap(alias = "s")]
+    Stripe {
+        file: String,
+        /// Customer the payments are from, instead of the one with the payer's email
         #[clap(short, long)]
         customer: Option<String>,
     },
-    /// Add a cost. If date is "monthly", an entry will be generated for each month.
-    #[clap(alias = "c")]
-    Cost {
-        date: String,
-        amount: Money,
-        name: String,
+    /// Import the verifications of an SIE file, e.g. from an accountant: income as invoices,
+    /// expenses as costs, and payments of invoices (see the [sie] table of the config)
+    Sie {
+        file: String,
+        /// Customer the income is from
+        #[clap(short, long)]
+        customer: Option<String>,
+    },
+    /// Import worked hours from a calendar, an iCalendar file or URL: events whose title matches
+    /// become invoices of their hours at a rate
+    Ical {
+        /// File, or http(s):// or webcal:// URL
+        source: String,
+        /// Rate to invoice the hours at
+        #[clap(short, long)]
+        rate: String,
+        /// Only events whose title contains this, ignoring case (default the customer)
+        #[clap(short, long)]
+        matching: Option<String>,
+        /// Customer the hours are invoiced to
+        #[clap(short, long)]
+        customer: Option<String>,
+    },
+    /// Import hours tracked in Toggl Track, from a detailed report CSV export or its API (see the
+    /// [time] table of the config), as an invoice per customer and rate
+    Toggl(TrackedArgs),
+    /// Import hours tracked in Clockify, from a detailed report CSV export or its API (see the
+    /// [time] table of the config), as an invoice per customer and rate
+    Clockify(TrackedArgs),
+    /// Import a PayPal activity CSV export: payments as paid invoices, fees as costs
+    #[clap(alias = "p")]
+    Paypal {
+        file: String,
+        /// Customer the payments are from, instead of the one with the payer's name or email
+        #[clap(short, long)]
+        customer: Option<String>,
     },
 }
 
+#[derive(Debug, clap::Args)]
+pub(crate) struct TrackedArgs {
+    /// Detailed report CSV export (default fetch the period from the API)
+    pub(crate) file: Option<String>,
+    /// Year, quarter, or month to invoice, e.g. 2025, 2025-Q2, or 2025-05 (default each month of
+    /// the export)
+    #[clap(short, long)]
+    pub(crate) period: Option<String>,
+    /// Rate to invoice the hours at, for projects not mapped to one in the config
+    #[clap(short, long)]
+    pub(crate) rate: Option<String>,
+    /// Only hours marked billable
+    #[clap(long)]
+    pub(crate) billable: bool,
+}
+
 #[derive(Debug, Subcommand)]
+pub(crate) enum ExportCommand {
+    /// Excel workbook with invoices, costs, rates, and a monthly summary on separate sheets
+    Xlsx { file: String },
+    /// SIE 4 file with the business invoices and costs of a year, booked on BAS accounts (see the
+    /// [sie] table of the config), for a Swedish accountant or bookkeeping program
+    Sie {
+        year: i32,
+        /// File to write, default <year>.se
+        file: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
 pub(crate) enum EditCommand {
     /// Edit a rate, identified by name
     #[clap(alias = "r")]
-    Rate { name: String },
-    /// Edit an invoice, identified by index (see list)
+    Rate {
+        name: String,
+        /// Rename the rate, and the invoices referring to it, instead of editing it
+        #[clap(long, conflicts_with = "editor")]
+        rename: Option<String>,
+        /// Edit as JSON in $EDITOR, instead of field by field
+        #[clap(long)]
+        editor: bool,
+    },
+    /// Edit an invoice, identified by ID (see list)
     #[clap(alias = "i")]
-    Invoice { index: usize },
-    /// Edit a cost, identified by index (see list)
+    Invoice {
+        /// ID (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+        /// Edit as JSON in $EDITOR, instead of field by field
+        #[clap(long)]
+        editor: bool,
+    },
+    /// Edit a cost, identified by ID (see list)
     #[clap(alias = "c")]
-    Cost { index: usize },
+    Cost {
+        /// ID (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+        /// Edit as JSON in $EDITOR, instead of field by field
+        #[clap(long)]
+        editor: bool,
+    },
+    /// Edit a customer, identified by name. Renaming updates invoices and costs referring to it.
+    #[clap(alias = "cu")]
+    Customer {
+        name: String,
+        /// Edit as JSON in $EDITOR, instead of field by field. Can't rename.
+        #[clap(long)]
+        editor: bool,
+    },
+    /// Edit an asset, identified by index (see list)
+    Asset {
+        /// Index (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+        /// Edit as JSON in $EDITOR, instead of field by field
+        #[clap(long)]
+        editor: bool,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct DeleteArgs {
+    /// Delete without asking for confirmation
+    #[clap(short = 'y', long, global = true)]
+    pub(crate) force: bool,
+    #[clap(subcommand)]
+    pub(crate) command: DeleteCommand,
 }
 
 #[derive(Debug, Subcommand)]
@@ -92,10 +1043,39 @@ pub(crate) enum DeleteCommand {
     /// Delete a rate, identified by name
     #[clap(alias = "r")]
     Rate { name: String },
-    /// Delete an invoice, identified by index (see list)
+    /// Delete an invoice, identified by ID (see list)
     #[clap(alias = "i")]
-    Invoice { index: usize },
-    /// Delete a cost, identified by index (see list)
+    Invoice {
+        /// ID (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+    },
+    /// Delete a cost, identified by ID (see list)
     #[clap(alias = "c")]
-    Cost { index: usize },
+    Cost {
+        /// ID (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+    },
+    /// Delete a tier from a rate
+    #[clap(alias = "t")]
+    Tier { rate: String, tier: String },
+    /// Delete a customer, identified by name. Customers with invoices or costs can't be deleted.
+    #[clap(alias = "cu")]
+    Customer { name: String },
+    /// Delete an asset, identified by index (see list)
+    Asset {
+        /// Index (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+    },
+    /// Delete an import rule, identified by index (see list)
+    Rule {
+        /// Index (see list), or `last` for the one added last
+        #[clap(value_parser = parse_index)]
+        index: usize,
+    },
+    /// Delete the budget for a category
+    #[clap(alias = "b")]
+    Budget { category: String },
 }
@@ -0,0 +1,242 @@
+//! Importing recent transactions straight from the bank, through `GoCardless` Bank Account Data
+//! (formerly Nordigen), configured in the `[bank]` table of the config. Transactions go through
+//! the same import rules as a CSV export, and keep the bank's transaction ID as their reference,
+//! so they can be imported again.
+
+use serde::Deserialize;
+
+use crate::{error::MoneybagsError, moneybag::Moneybag};
+
+/// The `GoCardless` Bank Account Data API, and the accounts to import, from the `[bank]` table of
+/// the config
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct Bank {
+    /// Secret ID of the user secret made in the `GoCardless` portal
+    pub(crate) secret_id: Option<String>,
+    /// Secret key of the user secret, unless set in `MONEYBAGS_BANK_SECRET_KEY`
+    pub(crate) secret_key: Option<String>,
+    /// IDs of the accounts linked through a requisition
+    pub(crate) accounts: Vec<String>,
+    /// The API, default <https://bankaccountdata.gocardless.com/api/v2>
+    pub(crate) url: String,
+}
+
+impl Default for Bank {
+    fn default() -> Self {
+        Bank {
+            secret_id: None,
+            secret_key: None,
+            accounts: vec![],
+            url: "https://bankaccountdata.gocardless.com/api/v2".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+mod api {
+    use serde::Deserialize;
+
+    use super::Bank;
+    use crate::import::Transaction;
+
+    #[derive(Deserialize)]
+    struct Token {
+        access: String,
+    }
+
+    /// An access token for the user secret
+    fn token(config: &Bank) -> Result<String, String> {
+        let secret_id = config
+            .secret_id
+            .clone()
+            .ok_or("Set secret_id in the [bank] table of the config")?;
+        let secret_key = std::env::var("MONEYBAGS_BANK_SECRET_KEY")
+            .ok()
+            .or_else(|| config.secret_key.clone())
+            .filter(|key| !key.is_empty())
+            .ok_or(
+                "Set secret_key in the [bank] table of the config, or MONEYBAGS_BANK_SECRET_KEY",
+            )?;
+        let url = format!("{}/token/new/", config.url);
+        let body = serde_json::json!({ "secret_id": secret_id, "secret_key": secret_key });
+        let response = ureq::post(&url)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .send(body.to_string())
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(|e| format!("Could not get an access token from {url}: {e}"))?;
+        serde_json::from_str::<Token>(&response)
+            .map(|token| token.access)
+            .map_err(|e| format!("Could not read the access token {url} returned: {e}"))
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Amount {
+        amount: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub(super) struct Booked {
+        transaction_id: Option<String>,
+        internal_transaction_id: Option<String>,
+        booking_date: Option<String>,
+        value_date: Option<String>,
+        transaction_amount: Amount,
+        creditor_name: Option<String>,
+        debtor_name: Option<String>,
+        remittance_information_unstructured: Option<String>,
+        #[serde(default)]
+        remittance_information_unstructured_array: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Transactions {
+        booked: Vec<Booked>,
+    }
+
+    #[derive(Deserialize)]
+    struct Account {
+        transactions: Transactions,
+    }
+
+    /// A booked transaction as one to import, unless it has no date or its amount is unreadable
+    pub(super) fn transaction(booked: Booked) -> Option<Transaction> {
+        let description = [
+            booked.creditor_name.or(booked.debtor_name),
+            booked.remittance_information_unstructured.or_else(|| {
+                Some(booked.remittance_information_unstructured_array.join(" "))
+                    .filter(|text| !text.is_empty())
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+        Some(Transaction {
+            date: booked.booking_date.or(booked.value_date)?,
+            description,
+            amount: booked.transaction_amount.amount.parse().ok()?,
+            reference: booked
+                .transaction_id
+                .or(booked.internal_transaction_id)
+                .map(|id| format!("gocardless {id}")),
+        })
+    }
+
+    /// The transactions booked on the accounts since the date, oldest first
+    pub(super) fn transactions(config: &Bank, since: &str) -> Result<Vec<Transaction>, String> {
+        let token = token(config)?;
+        let mut transactions = vec![];
+        for account in &config.accounts {
+            let url = format!(
+                "{}/accounts/{account}/transactions/?date_from={since}",
+                config.url
+            );
+            let body = ureq::get(&url)
+                .header("Authorization", &format!("Bearer {token}"))
+                .header("Accept", "application/json")
+                .call()
+                .and_then(|mut response| response.body_mut().read_to_string())
+                .map_err(|e| format!("Could not fetch {url}: {e}"))?;
+            let account: Account = serde_json::from_str(&body)
+                .map_err(|e| format!("Could not read what {url} returned: {e}"))?;
+            transactions.extend(account.transactions.booked.into_iter().map(transaction));
+        }
+        let skipped = transactions.iter().filter(|t| t.is_none()).count();
+        if skipped > 0 {
+            crate::output::note(&format!("Skipping {skipped} unreadable transactions"));
+        }
+        let mut transactions: Vec<Transaction> = transactions.into_iter().flatten().collect();
+        transactions.sort_by(|a, b| a.date.cmp(&b.date));
+        Ok(transactions)
+    }
+}
+
+/// Import the transactions of the last `days` on the accounts of the config, as
+/// [`crate::import::import_transactions`] does
+pub(crate) fn import_gocardless(
+    days: u32,
+    moneybag: &mut Moneybag,
+    config: &Bank,
+) -> Result<Vec<String>, MoneybagsError> {
+    if config.accounts.is_empty() {
+        return Err(MoneybagsError::Invalid(
+            "List the accounts to import in the [bank] table of the config".to_string(),
+        ));
+    }
+    let since = chrono::Local::now().date_naive() - chrono::Days::new(days.into());
+    #[cfg(feature = "http")]
+    {
+        let transactions =
+            api::transactions(config, &since.to_string()).map_err(MoneybagsError::Invalid)?;
+        crate::import::import_transactions(transactions, 0, moneybag)
+    }
+    #[cfg(not(feature = "http"))]
+    {
+        let _ = (since, moneybag);
+        Err(MoneybagsError::Invalid(
+            "moneybags was built without http, build it with --features http".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_transaction() {
+        let booked = serde_json::from_str(
+            r#"{
+                "transactionId": "2025051600001",
+                "bookingDate": "2025-05-16",
+                "valueDate": "2025-05-16",
+                "transactionAmount": { "amount": "-45.30", "currency": "EUR" },
+                "creditorName": "AWS EMEA",
+                "remittanceInformationUnstructured": "Invoice EUINSE25-123"
+            }"#,
+        )
+        .unwrap();
+        let transaction = api::transaction(booked).unwrap();
+        assert_eq!(transaction.date, "2025-05-16");
+        assert_eq!(transaction.description, "AWS EMEA Invoice EUINSE25-123");
+        assert_eq!(transaction.amount, "-45.30".parse().unwrap());
+        assert_eq!(
+            transaction.reference.as_deref(),
+            Some("gocardless 2025051600001")
+        );
+    }
+
+    #[test]
+    fn test_import_gocardless() {
+        let port = fixtures::closed_port();
+        let mut config = Bank {
+            secret_id: Some("id".to_string()),
+            secret_key: Some("key".to_string()),
+            accounts: vec![],
+            url: format!("http://127.0.0.1:{port}/api/v2"),
+        };
+        let mut moneybag = Moneybag::default();
+        assert!(matches!(
+            import_gocardless(30, &mut moneybag, &config),
+            Err(MoneybagsError::Invalid(_))
+        ));
+        config.accounts.push("account".to_string());
+        let Err(MoneybagsError::Invalid(error)) = import_gocardless(30, &mut moneybag, &config)
+        else {
+            panic!("Expected the import to fail");
+        };
+        if cfg!(feature = "http") {
+            assert!(
+                error.starts_with("Could not get an access token"),
+                "{error}"
+            );
+        }
+        assert!(moneybag.costs.is_empty() && moneybag.pending.is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+use crate::money::Money;
+
+const BLOCKS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A horizontal bar as long as value is of max, where max fills width characters. Negative values
+/// get no bar.
+pub(crate) fn bar(value: Money, max: Money, width: usize) -> String {
+    let eighths = value.steps_of(max, i64::try_from(width * 8).unwrap_or(i64::MAX));
+    let eighths = usize::try_from(eighths).unwrap_or_default();
+    let mut bar = "█".repeat(eighths / 8);
+    if eighths % 8 != 0 {
+        bar.push(BLOCKS[eighths % 8 - 1]);
+    }
+    bar
+}
+
+/// One character per value, higher the larger it is, from the lowest value to the highest
+pub(crate) fn sparkline(values: &[Money]) -> String {
+    let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    values
+        .iter()
+        .map(|&value| {
+            let level = (value - min).steps_of(max - min, 7);
+            LEVELS[usize::try_from(level).unwrap_or_default()]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charts() {
+        let money = |amount: &str| amount.parse::<Money>().unwrap();
+        assert_eq!(bar(money("50"), money("100"), 4), "██");
+        assert_eq!(bar(money("10"), money("100"), 4), "▍");
+        assert_eq!(bar(money("-10"), money("100"), 4), "");
+        assert_eq!(sparkline(&[money("0"), money("50"), money("100")]), "▁▄█");
+        assert_eq!(sparkline(&[money("7"), money("7")]), "▁▁");
+        assert_eq!(bar(money("50"), Money::default(), 4), "");
+        assert_eq!(bar(money("50"), money("-100"), 4), "");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
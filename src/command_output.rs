@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    dashboard::Dashboard,
+    forecast::Forecast,
+    fx::ExchangeRate,
+    goal::Progress,
+    money::Money,
+    moneybag::{Asset, Budget, BudgetStatus, Cost, Customer, ImportRule, Invoice, Rate},
+    query::QueryResult,
+    remind::Reminder,
+    report::Report,
+    stats::InvoiceStats,
+};
+
+/// What a command has to show, for the shell to print as text, or a program using the library to
+/// look at. Commands that only print, like examples or lists other than of invoices, costs, and
+/// transactions, return `Nothing`.
+#[derive(Debug, Serialize)]
+pub enum CommandOutput {
+    Nothing,
+    /// What was done, a line each
+    Messages(Vec<String>),
+    /// Invoices in the order listed, with a running total of their amounts if asked for
+    Invoices {
+        invoices: Vec<Invoice>,
+        running: bool,
+    },
+    /// Costs in the order listed, with a running total net of deductible VAT if asked for
+    Costs {
+        costs: Vec<Cost>,
+        running: bool,
+    },
+    /// Invoices in the base currency and costs, by date
+    Transactions(Vec<Transaction>),
+    Balance(Balance),
+    /// Status of each budget in the current period
+    Budgets(Vec<BudgetStatus>),
+    /// Entries matching a search
+    Found {
+        invoices: Vec<Invoice>,
+        costs: Vec<Cost>,
+        rates: BTreeMap<String, Rate>,
+    },
+    Invoice(Invoice),
+    Cost(Cost),
+    Customer {
+        name: String,
+        customer: Customer,
+    },
+    Report(Report),
+    Added(Added),
+    Forecast(Forecast),
+    Dashboard(Dashboard),
+    Goal(Progress),
+    Query(QueryResult),
+    InvoiceStats(InvoiceStats),
+    /// Reminders listed instead of sent, and the overdue invoices no one can be reminded of
+    Reminders {
+        reminders: Vec<Reminder>,
+        unreachable: Vec<String>,
+    },
+    /// Exchange rates updated to the reference rates of a date
+    ExchangeRates {
+        date: String,
+        rates: Vec<ExchangeRate>,
+    },
+}
+
+/// What an add command added. Rates and budgets are replaced when added again, and tiers are
+/// added to a rate, so those are the ones that changed.
+#[derive(Debug, Default, Serialize)]
+pub struct Added {
+    pub invoices: Vec<Invoice>,
+    pub costs: Vec<Cost>,
+    /// With their index
+    pub assets: Vec<(usize, Asset)>,
+    /// With their index
+    pub rules: Vec<(usize, ImportRule)>,
+    pub rates: BTreeMap<String, Rate>,
+    pub customers: BTreeMap<String, Customer>,
+    pub budgets: BTreeMap<String, Budget>,
+}
+
+/// An invoice or a cost, with the balance after it
+#[derive(Debug, Serialize)]
+pub struct Transaction {
+    pub date: String,
+    /// Negative for a cost
+    pub amount: Money,
+    /// The customer of an invoice, or the name of a cost
+    pub name: String,
+    pub balance: Money,
+}
+
+/// Invoiced less costs and depreciation, for the entries the balance was asked for
+#[derive(Debug, Serialize)]
+pub struct Balance {
+    pub costs: Money,
+    pub input_vat: Money,
+    pub depreciation: Money,
+    pub invoices: Money,
+    pub total: Money,
+    pub average_invoice: Money,
+    /// Invoices of the average size it would take to make a negative total zero
+    pub invoices_to_break_even: Option<Money>,
+    /// Invoiced in each foreign currency
+    pub currencies: BTreeMap<String, CurrencyTotal>,
+    /// Progress towards the revenue goal, if there is one and the balance isn't filtered
+    #[serde(skip)]
+    pub goal: Option<String>,
+    pub exceeded_budgets: Vec<BudgetStatus>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CurrencyTotal {
+    /// In the currency
+    pub total: Money,
+    /// In the base currency, at the exchange rates when invoiced
+    pub base_total: Money,
+}
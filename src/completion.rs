@@ -0,0 +1,258 @@
+use std::collections::BTreeSet;
+
+use clap::CommandFactory;
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, history::DefaultHistory,
+    validate::Validator, Context, Editor, Helper,
+};
+
+use crate::{args::Command, error::MoneybagsError, moneybag::Moneybag};
+
+/// Completes commands, flags, and the names of rates, customers, and categories at the prompt
+pub(crate) struct Completion {
+    command: clap::Command,
+    rates: Vec<String>,
+    customers: Vec<String>,
+    categories: Vec<String>,
+}
+
+impl Completion {
+    pub(crate) fn new(moneybag: &Moneybag) -> Self {
+        let categories: BTreeSet<_> = moneybag
+            .costs
+            .iter()
+            .filter_map(|cost| cost.category.clone())
+            .chain(moneybag.budgets.keys().cloned())
+            .chain(
+                moneybag
+                    .import_rules
+                    .iter()
+                    .filter_map(|rule| rule.category.clone()),
+            )
+            .collect();
+        Completion {
+            command: Command::command(),
+            rates: moneybag.rates.keys().cloned().collect(),
+            customers: moneybag.customers.keys().cloned().collect(),
+            categories: categories.into_iter().collect(),
+        }
+    }
+
+    /// Candidates for the word being typed, given the words before it
+    fn candidates(&self, words: &[&str], word: &str) -> Vec<String> {
+        let mut command = &self.command;
+        let mut rest = words;
+        while let Some((first, tail)) = rest.split_first() {
+            match command.find_subcommand(first) {
+                Some(subcommand) => {
+                    command = subcommand;
+                    rest = tail;
+                }
+                None => break,
+            }
+        }
+
+        if word.starts_with('-') {
+            return command
+                .get_arguments()
+                .filter_map(clap::Arg::get_long)
+                .map(|long| format!("--{long}"))
+                .collect();
+        }
+
+        let mut positional = 0;
+        let mut flag_value = None;
+        for word in rest {
+            if flag_value.take().is_some() {
+                continue;
+            }
+            match find_flag(command, word) {
+                Some(flag) if flag.get_num_args().is_none_or(|n| n.takes_values()) => {
+                    flag_value = Some(flag);
+                }
+                Some(_) => {}
+                None => positional += 1,
+            }
+        }
+        if let Some(flag) = flag_value {
+            return self.values(command, flag);
+        }
+        if rest.is_empty() && command.has_subcommands() {
+            return command
+                .get_subcommands()
+                .map(|subcommand| subcommand.get_name().to_string())
+                .collect();
+        }
+        command
+            .get_positionals()
+            .nth(positional)
+            .map(|arg| self.values(command, arg))
+            .unwrap_or_default()
+    }
+
+    /// Values an argument can take, from the moneybag or the argument's possible values
+    fn values(&self, command: &clap::Command, arg: &clap::Arg) -> Vec<String> {
+        match (command.get_name(), arg.get_id().as_str()) {
+            ("rate", "rate") => vec![],
+            ("rate", "name") | (_, "rate") => self.rates.clone(),
+            ("customer", "name") | (_, "customer" | "billable") => self.customers.clone(),
+            (_, "category") => self.categories.clone(),
+            _ => arg
+                .get_possible_values()
+                .iter()
+                .map(|value| value.get_name().to_string())
+                .collect(),
+        }
+    }
+}
+
+fn find_flag<'a>(command: &'a clap::Command, word: &str) -> Option<&'a clap::Arg> {
+    if let Some(long) = word.strip_prefix("--") {
+        command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long))
+    } else if let Some(short) = word.strip_prefix('-') {
+        let mut chars = short.chars();
+        let short = chars.next()?;
+        if chars.next().is_some() {
+            return None;
+        }
+        command
+            .get_arguments()
+            .find(|arg| arg.get_short() == Some(short))
+    } else {
+        None
+    }
+}
+
+/// Completes a single answer from a list of names
+struct NameCompletion(Vec<String>);
+
+impl Completer for NameCompletion {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let candidates = self
+            .0
+            .iter()
+            .filter(|name| name.starts_with(&line[..pos]))
+            .cloned()
+            .collect();
+        Ok((0, candidates))
+    }
+}
+
+impl Hinter for NameCompletion {
+    type Hint = String;
+}
+
+impl Highlighter for NameCompletion {}
+
+impl Validator for NameCompletion {}
+
+impl Helper for NameCompletion {}
+
+/// Prompt for a line of input, completing it from names with tab
+pub(crate) fn prompt_completing(
+    prompt: &str,
+    names: Vec<String>,
+) -> Result<String, MoneybagsError> {
+    let Ok(mut editor) = Editor::<NameCompletion, DefaultHistory>::new() else {
+        return crate::prompt(prompt);
+    };
+    editor.set_helper(Some(NameCompletion(names)));
+    crate::readline(&mut editor, prompt)
+}
+
+impl Completer for Completion {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let mut words: Vec<_> = line.split_whitespace().collect();
+        let word = if line.ends_with(char::is_whitespace) {
+            ""
+        } else {
+            words.pop().unwrap_or("")
+        };
+        let candidates = self
+            .candidates(&words, word)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| {
+                if candidate.contains(char::is_whitespace) {
+                    format!("\"{candidate}\"")
+                } else {
+                    candidate
+                }
+            })
+            .collect();
+        Ok((pos - word.len(), candidates))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_candidates() {
+        let moneybag = fixtures::moneybag(
+            r#"{
+                "rates": { "hourly": { "rate": { "amount": 90000 } } },
+                "customers": { "Acme": {} },
+                "budgets": { "hosting": { "amount": { "amount": 10000 }, "interval": "Monthly" } }
+            }"#,
+        );
+        let completion = Completion::new(&moneybag);
+        assert!(completion.candidates(&[], "").contains(&"add".to_string()));
+        assert!(completion
+            .candidates(&["add"], "")
+            .contains(&"invoice".to_string()));
+        assert!(completion
+            .candidates(&["add", "invoice"], "--")
+            .contains(&"--customer".to_string()));
+        assert_eq!(
+            completion.candidates(&["add", "invoice", "2025-01-31", "10", "-r"], ""),
+            ["hourly"]
+        );
+        assert_eq!(
+            completion.candidates(&["a", "i", "2025-01-31", "10", "--customer"], ""),
+            ["Acme"]
+        );
+        assert_eq!(completion.candidates(&["edit", "rate"], ""), ["hourly"]);
+        assert_eq!(
+            completion.candidates(&["add", "rate"], ""),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            completion.candidates(&["add", "cost", "2025-05", "129", "vpn", "-c"], ""),
+            ["hosting"]
+        );
+        assert_eq!(
+            completion.candidates(&["balance", "--scope"], ""),
+            ["business", "personal"]
+        );
+    }
+}
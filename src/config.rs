@@ -0,0 +1,147 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use clap::{CommandFactory, Parser};
+use serde::Deserialize;
+
+#[cfg(feature = "scripts")]
+use crate::script;
+use crate::{
+    args::Command, banking::Bank, hooks::Hooks, i18n::Language, notify::Notify, remind::Email,
+    sie::Sie, tracking::TimeTracking,
+};
+
+/// Settings read from the config file, which is TOML
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Words that stand for the start of a longer command, e.g. `ai = "add invoice -r standard"`
+    pub(crate) aliases: BTreeMap<String, String>,
+    /// The shell's prompt. `{file}` is replaced by the name of the moneybag file, and `{unsaved}`
+    /// by a `*` when there are unsaved changes, e.g. `"{file}{unsaved}> "`.
+    pub(crate) prompt: Option<String>,
+    /// Language of labels and messages, "en" or "sv". Taken from the locale if not set.
+    pub(crate) language: Option<Language>,
+    /// Commands to run when things change, from the `[hooks]` table
+    pub(crate) hooks: Hooks,
+    /// Accounts for export sie, from the `[sie]` table
+    pub(crate) sie: Sie,
+    /// SMTP server and templates for remind, from the `[email]` table
+    pub(crate) email: Email,
+    /// Where notify sends notifications, from the `[notify]` table
+    pub(crate) notify: Notify,
+    /// The other copy of the moneybag that sync merges with, e.g. in a shared folder
+    pub(crate) sync: Option<String>,
+    /// Time tracker APIs and what their projects are billed as, from the `[time]` table
+    pub(crate) time: TimeTracking,
+    /// Open banking API and accounts for import gocardless, from the `[bank]` table
+    pub(crate) bank: Bank,
+    /// Directory of custom commands, one Rhai script each. Default `scripts` next to the config.
+    pub(crate) scripts: Option<PathBuf>,
+    /// The scripts found there when the config was loaded, by the command they add
+    #[serde(skip)]
+    pub(crate) script_commands: BTreeMap<String, PathBuf>,
+}
+
+impl Config {
+    /// The config at `path`. A missing file gives the defaults, as does one that can't be parsed,
+    /// after a warning.
+    pub(crate) fn load(path: &str) -> Self {
+        let mut config = match std::fs::read_to_string(path) {
+            Ok(toml) => toml::from_str(&toml).unwrap_or_else(|e| {
+                println!("Could not parse config {path}, ignoring it: {e}");
+                Config::default()
+            }),
+            Err(_) => Config::default(),
+        };
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        let scripts = config.scripts.get_or_insert_with(|| dir.join("scripts"));
+        *scripts = PathBuf::from(shellexpand::tilde(&scripts.to_string_lossy()).as_ref());
+        #[cfg(feature = "scripts")]
+        {
+            config.script_commands = script::find(scripts);
+        }
+        config
+    }
+
+    #[cfg(feature = "scripts")]
+    pub(crate) fn scripts_dir(&self) -> PathBuf {
+        self.scripts.clone().unwrap_or_default()
+    }
+
+    /// The words with an alias in first place replaced by what it stands for, or the name of a
+    /// script run with `script`. Aliases don't expand inside other aliases, and commands that are
+    /// built in come before scripts of the same name.
+    pub(crate) fn expand(&self, mut words: Vec<String>) -> Vec<String> {
+        let Some(first) = words.first() else {
+            return words;
+        };
+        if let Some(expansion) = self
+            .aliases
+            .get(first)
+            .and_then(|alias| shlex::split(alias))
+        {
+            words.splice(..1, expansion);
+        } else if self.script_commands.contains_key(first)
+            && Command::command().find_subcommand(first).is_none()
+        {
+            words.insert(0, "script".to_string());
+        }
+        words
+    }
+
+    /// The prompt to show in the shell, "> " unless configured
+    pub(crate) fn prompt(&self, filepath: &str, unsaved: bool) -> String {
+        let Some(prompt) = &self.prompt else {
+            return "> ".to_string();
+        };
+        let file = Path::new(filepath)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        prompt
+            .replace("{file}", file.trim_start_matches('.'))
+            .replace("{unsaved}", if unsaved { "*" } else { "" })
+    }
+
+    /// Parse a command, expanding aliases first
+    pub(crate) fn parse(&self, words: Vec<String>) -> Result<Command, clap::Error> {
+        Command::try_parse_from(self.expand(words))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let config: Config = toml::from_str(
+            r#"
+            [aliases]
+            ai = "add invoice -r standard -c 'Acme Inc'"
+            "#,
+        )
+        .unwrap();
+        let words = |line: &str| shlex::split(line).unwrap();
+        assert_eq!(
+            config.expand(words("ai 2025-01-31 10")),
+            words("add invoice -r standard -c 'Acme Inc' 2025-01-31 10")
+        );
+        assert_eq!(config.expand(words("balance")), words("balance"));
+        assert_eq!(config.expand(vec![]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_prompt() {
+        let config = Config {
+            prompt: Some("{file}{unsaved}> ".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(config.prompt("/home/me/company.json", true), "company*> ");
+        assert_eq!(config.prompt("/home/me/.moneybags", false), "moneybags> ");
+        assert_eq!(Config::default().prompt("/home/me/.moneybags", true), "> ");
+    }
+}
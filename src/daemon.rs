@@ -0,0 +1,241 @@
+//! One process owning a moneybag file, running the commands other invocations send it over a
+//! unix socket next to the file, one at a time. The moneybag stays loaded between commands, and
+//! only the daemon writes the file.
+
+use std::{
+    io::{Read, Write},
+    net::Shutdown,
+    os::{
+        fd::AsRawFd,
+        unix::net::{UnixListener, UnixStream},
+    },
+    panic::AssertUnwindSafe,
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    args::{Args, Command},
+    error::MoneybagsError,
+    moneybag::Moneybag,
+    output::{self, Failure, Verbosity},
+    run_once, Session,
+};
+
+/// A command for the daemon to run, and how to print what it has to say
+#[derive(Serialize, Deserialize)]
+struct Request {
+    words: Vec<String>,
+    quiet: bool,
+    verbose: bool,
+    color: bool,
+}
+
+/// Ends what the daemon sends, followed by the exit code. Output never has it.
+const END: u8 = 0;
+
+/// The socket of the daemon for a moneybag file: moneybag.json has moneybag.sock
+pub(crate) fn socket_path(filepath: &str) -> PathBuf {
+    PathBuf::from(filepath).with_extension("sock")
+}
+
+/// Run the command line through the daemon owning the file, if one is running, and return how
+/// it went. None if there is no daemon, to run it here. The shell and scripts don't start while a
+/// daemon owns the file, since they would write it too.
+pub(crate) fn client(args: &Args, filepath: &str, color: bool) -> Option<ExitCode> {
+    let mut stream = UnixStream::connect(socket_path(filepath)).ok()?;
+    if args.command.first().is_some_and(|first| first == "daemon") {
+        return None;
+    }
+    if args.command.is_empty() || args.dry_run {
+        return Some(failed(&MoneybagsError::Invalid(format!(
+            "A daemon owns {filepath}. Run single commands through it, e.g. moneybags balance, or \
+             stop it first"
+        ))));
+    }
+    let request = Request {
+        words: args.command.clone(),
+        quiet: args.quiet,
+        verbose: args.verbose,
+        color,
+    };
+    let mut line = serde_json::to_string(&request).expect("Could not serialize request");
+    line.push('\n');
+    let sent = stream.write_all(line.as_bytes()).and_then(|()| {
+        // Questions the command asks are answered from stdin, as if it ran here
+        let mut input = stream.try_clone()?;
+        std::thread::spawn(move || {
+            let _ = std::io::copy(&mut std::io::stdin(), &mut input);
+            let _ = input.shutdown(Shutdown::Write);
+        });
+        print_reply(&mut stream)
+    });
+    let end = match sent {
+        Ok(end) => end,
+        Err(e) => {
+            return Some(failed(&MoneybagsError::Io(format!(
+                "Could not talk to the daemon: {e}"
+            ))))
+        }
+    };
+    let [END, code] = end[..] else {
+        return Some(failed(&MoneybagsError::Io(
+            "The daemon stopped before the command finished".to_string(),
+        )));
+    };
+    Some(ExitCode::from(code))
+}
+
+/// Print why the client failed, and return the exit code for it
+fn failed(e: &MoneybagsError) -> ExitCode {
+    e.report();
+    e.failure().exit_code()
+}
+
+/// Print what the daemon sends as it comes, so questions show before they're answered, and
+/// return the last two bytes, which end it
+fn print_reply(stream: &mut UnixStream) -> std::io::Result<Vec<u8>> {
+    let mut stdout = std::io::stdout();
+    let mut pending = vec![];
+    let mut chunk = [0; 4096];
+    loop {
+        let read = stream.read(&mut chunk)?;
+        if read == 0 {
+            return Ok(pending);
+        }
+        pending.extend_from_slice(&chunk[..read]);
+        let done = pending.len().saturating_sub(2);
+        stdout.write_all(&pending[..done])?;
+        stdout.flush()?;
+        pending.drain(..done);
+    }
+}
+
+/// Listen for commands until stopped, running them on the moneybag and saving after each change
+pub(crate) fn run_daemon(moneybag: &mut Moneybag, session: &Session) -> Result<(), MoneybagsError> {
+    let location = session.storage.location();
+    let path = socket_path(&location);
+    if UnixStream::connect(&path).is_ok() {
+        return Err(MoneybagsError::Invalid(format!(
+            "A daemon is already running for {location}"
+        )));
+    }
+    // Left behind by a daemon that didn't stop cleanly
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| MoneybagsError::Io(format!("Could not listen on {}: {e}", path.display())))?;
+    let socket = path.clone();
+    let stopped = ctrlc::set_handler(move || {
+        let _ = std::fs::remove_file(&socket);
+        std::process::exit(0);
+    });
+    if let Err(e) = stopped {
+        println!(
+            "Could not set Ctrl-C handler, remove {} after stopping: {e}",
+            path.display()
+        );
+    }
+    println!("Running commands for {location} sent to {}", path.display());
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => answer(&stream, moneybag, session),
+            Err(e) => println!("Could not accept a client: {e}"),
+        }
+    }
+    Ok(())
+}
+
+/// Run the command a client sent, with its stdin, stdout, and stderr in place of the daemon's
+fn answer(stream: &UnixStream, moneybag: &mut Moneybag, session: &Session) {
+    let Some(request) = read_request(stream) else {
+        warn!("Could not read a request");
+        return;
+    };
+    info!(words = ?request.words, "Running command for a client");
+    let ran = with_stdio(stream, || {
+        output::set_verbosity(if request.quiet {
+            Verbosity::Quiet
+        } else if request.verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        });
+        output::set_color(request.color);
+        let failure = match session.config.parse(request.words) {
+            Ok(Command::Daemon | Command::Serve { .. } | Command::Tui) => {
+                let e =
+                    MoneybagsError::Invalid("Not through the daemon, stop it first".to_string());
+                e.report();
+                Some(e.failure())
+            }
+            Ok(command) => run_once(command, moneybag, session),
+            Err(e) => {
+                let _ = e.print();
+                e.use_stderr().then_some(Failure::Parse)
+            }
+        };
+        failure.map_or(0, |failure| failure as u8)
+    });
+    // A client that went away mid-command makes printing panic, which ends only the command
+    let code = ran.unwrap_or(Failure::Io as u8);
+    let mut stream = stream;
+    let _ = stream.write_all(&[END, code]);
+}
+
+/// The first line a client sends, read a byte at a time so none of the input after it is taken
+fn read_request(mut stream: &UnixStream) -> Option<Request> {
+    let mut line = vec![];
+    let mut byte = [0];
+    while stream.read_exact(&mut byte).is_ok() && byte[0] != b'\n' {
+        line.push(byte[0]);
+    }
+    serde_json::from_slice(&line).ok()
+}
+
+/// Run f with the stream as stdin, stdout, and stderr, and put the daemon's own back after, also
+/// if f panics
+fn with_stdio<T>(stream: &UnixStream, f: impl FnOnce() -> T) -> std::thread::Result<T> {
+    let _ = std::io::stdout().flush();
+    // SAFETY: dup and dup2 only take and give file descriptors, the stream's stays open for the
+    // whole call, and the copies of the daemon's own are closed once they're back in place
+    let saved = [0, 1, 2].map(|fd| unsafe { libc::dup(fd) });
+    for fd in 0..3 {
+        unsafe { libc::dup2(stream.as_raw_fd(), fd) };
+    }
+    let result = std::panic::catch_unwind(AssertUnwindSafe(f));
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+    for (fd, saved) in (0..3).zip(saved) {
+        unsafe {
+            libc::dup2(saved, fd);
+            libc::close(saved);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_no_daemon_to_talk_to() {
+        let (mut sent, received) = UnixStream::pair().unwrap();
+        sent.write_all(b"balance\n{\"words\": [\"balance\"]}\n")
+            .unwrap();
+        sent.shutdown(Shutdown::Write).unwrap();
+        // Not JSON, not all of a request, and nothing
+        assert!(read_request(&received).is_none());
+        assert!(read_request(&received).is_none());
+        assert!(read_request(&received).is_none());
+        let filepath =
+            std::env::temp_dir().join(format!("moneybags-no-daemon-{}.json", std::process::id()));
+        let args = Args::parse_from(["moneybags", "balance"]);
+        assert!(client(&args, &filepath.to_string_lossy(), false).is_none());
+    }
+}
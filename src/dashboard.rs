@@ -0,0 +1,104 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::{
+    forecast::costs_due_this_month,
+    goal::Progress,
+    i18n::tr,
+    money::Money,
+    moneybag::{sum_costs, sum_invoices, Interval, Moneybag},
+    output::{print_field, Style},
+    report::vat_to_pay,
+};
+
+/// The key numbers at a glance: this month's income and costs, unpaid invoices, costs coming up,
+/// VAT owed this quarter, and progress towards the goal
+#[derive(Debug, Serialize)]
+pub struct Dashboard {
+    pub invoiced: Money,
+    pub costs: Money,
+    pub unpaid: Money,
+    pub unpaid_invoices: usize,
+    pub overdue: Money,
+    pub overdue_invoices: usize,
+    /// Costs falling due in the rest of the month
+    pub costs_coming: Money,
+    pub vat_to_pay: Money,
+    /// The quarter the VAT is for, e.g. 2025-Q2
+    pub quarter: String,
+    pub goal: Option<Progress>,
+}
+
+impl Dashboard {
+    pub(crate) fn of(moneybag: &Moneybag, today: NaiveDate) -> Self {
+        let month = today.format("%Y-%m").to_string();
+        let invoiced = sum_invoices(
+            moneybag
+                .invoices
+                .iter()
+                .filter(|invoice| invoice.date.starts_with(&month)),
+        );
+        let costs = sum_costs(
+            moneybag
+                .costs
+                .iter()
+                .filter(|cost| cost.date.starts_with(&month)),
+        );
+        let unpaid: Vec<_> = moneybag
+            .invoices
+            .iter()
+            .filter(|invoice| invoice.paid.is_none())
+            .collect();
+        let overdue: Vec<_> = unpaid
+            .iter()
+            .copied()
+            .filter(|invoice| moneybag.is_overdue(invoice, today))
+            .collect();
+        let quarter = Interval::Quarterly.period_of(&month);
+        Dashboard {
+            invoiced,
+            costs,
+            unpaid: sum_invoices(unpaid.iter().copied()),
+            unpaid_invoices: unpaid.len(),
+            overdue: sum_invoices(overdue.iter().copied()),
+            overdue_invoices: overdue.len(),
+            costs_coming: costs_due_this_month(moneybag, today),
+            vat_to_pay: vat_to_pay(&quarter, moneybag),
+            quarter,
+            goal: Progress::of(moneybag, today),
+        }
+    }
+
+    pub(crate) fn print(&self) {
+        print_field("Invoiced this month", self.invoiced);
+        print_field("Costs this month", self.costs);
+        print_field(
+            "Unpaid",
+            tr!(
+                "{amount} in {count} invoices",
+                amount = self.unpaid,
+                count = self.unpaid_invoices
+            ),
+        );
+        if self.overdue_invoices > 0 {
+            let overdue = tr!(
+                "{amount} in {count} invoices",
+                amount = self.overdue,
+                count = self.overdue_invoices
+            );
+            print_field("Overdue", Style::Red.paint(&overdue));
+        }
+        print_field("Costs coming this month", self.costs_coming);
+        print_field(
+            "VAT to pay",
+            tr!(
+                "{amount} for {quarter}",
+                amount = self.vat_to_pay,
+                quarter = self.quarter
+            ),
+        );
+        if let Some(progress) = &self.goal {
+            print_field("Goal", progress.summary());
+        }
+    }
+}
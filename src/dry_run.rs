@@ -0,0 +1,116 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use serde::Serialize;
+
+use crate::{
+    i18n::tr,
+    moneybag::{Cost, Invoice, Moneybag},
+};
+
+/// Print the entries a dry run would have added, removed, or changed
+pub(crate) fn print_changes(before: &Moneybag, after: &Moneybag) {
+    let changes = changes(before, after);
+    if changes.is_empty() {
+        println!("{}", tr!("Dry run, nothing would change"));
+    } else {
+        println!("{}", tr!("Dry run, would change:"));
+        for change in changes {
+            println!("{change}");
+        }
+    }
+}
+
+/// The entries a command added ("+"), removed ("-"), or changed ("~"), one per line
+pub(crate) fn changes(before: &Moneybag, after: &Moneybag) -> Vec<String> {
+    let mut changes = vec![];
+    changes.extend(diff_list(
+        "invoice",
+        &before.invoices,
+        &after.invoices,
+        |_, invoice: &Invoice| invoice.id,
+    ));
+    changes.extend(diff_list(
+        "cost",
+        &before.costs,
+        &after.costs,
+        |_, cost: &Cost| cost.id,
+    ));
+    changes.extend(diff_list("asset", &before.assets, &after.assets, |i, _| i));
+    changes.extend(diff_list(
+        "rule",
+        &before.import_rules,
+        &after.import_rules,
+        |i, _| i,
+    ));
+    changes.extend(diff_map("rate", &before.rates, &after.rates));
+    changes.extend(diff_map("customer", &before.customers, &after.customers));
+    changes.extend(diff_map("budget", &before.budgets, &after.budgets));
+    if json(&before.settings) != json(&after.settings) {
+        changes.push("~ settings".to_string());
+    }
+    changes
+}
+
+fn json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("Could not serialize entry")
+}
+
+/// Entries only in before are removed, entries only in after are added. When the length is the
+/// same, entries were edited in place, and show as old and new value. Entries are numbered by
+/// number, given an entry and its index.
+fn diff_list<T: Serialize + Display>(
+    kind: &str,
+    before: &[T],
+    after: &[T],
+    number: impl Fn(usize, &T) -> usize,
+) -> Vec<String> {
+    if before.len() == after.len() {
+        return before
+            .iter()
+            .zip(after)
+            .enumerate()
+            .filter(|(_, (old, new))| json(old) != json(new))
+            .map(|(i, (old, new))| format!("~ {kind} {}: {old} → {new}", number(i, old)))
+            .collect();
+    }
+    let mut remaining: Vec<_> = before.iter().map(|entry| Some(json(entry))).collect();
+    let mut added = vec![];
+    for (i, entry) in after.iter().enumerate() {
+        let entry_json = json(entry);
+        match remaining
+            .iter_mut()
+            .find(|existing| existing.as_ref() == Some(&entry_json))
+        {
+            Some(existing) => *existing = None,
+            None => added.push(format!("+ {kind} {}: {entry}", number(i, entry))),
+        }
+    }
+    let removed = before
+        .iter()
+        .zip(remaining)
+        .enumerate()
+        .filter(|(_, (_, remaining))| remaining.is_some())
+        .map(|(i, (entry, _))| format!("- {kind} {}: {entry}", number(i, entry)));
+    removed.chain(added).collect()
+}
+
+fn diff_map<T: Serialize + Display>(
+    kind: &str,
+    before: &BTreeMap<String, T>,
+    after: &BTreeMap<String, T>,
+) -> Vec<String> {
+    let removed = before
+        .keys()
+        .filter(|name| !after.contains_key(*name))
+        .map(|name| format!("- {kind} {name}"));
+    let added_or_changed = after
+        .iter()
+        .filter_map(|(name, entry)| match before.get(name) {
+            None => Some(format!("+ {kind} {name}: {entry}")),
+            Some(existing) if json(existing) != json(entry) => {
+                Some(format!("~ {kind} {name}: {existing} → {entry}"))
+            }
+            Some(_) => None,
+        });
+    removed.chain(added_or_changed).collect()
+}
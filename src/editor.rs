@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{confirm, error::MoneybagsError};
+
+/// Edit an entry as JSON in `$VISUAL` or `$EDITOR` (default vi). The edited entry replaces the
+/// original only if it parses and passes validate.
+pub(crate) fn edit_in_editor<T: Serialize + DeserializeOwned>(
+    entry: &mut T,
+    validate: impl Fn(&T) -> Result<(), String>,
+) -> Result<(), MoneybagsError> {
+    let path = std::env::temp_dir().join(format!("moneybags-{}.json", std::process::id()));
+    let edited = edit_file(&path, entry, validate);
+    // Best effort, it's a temporary file
+    let _ = std::fs::remove_file(&path);
+    if let Some(edited) = edited? {
+        *entry = edited;
+    }
+    Ok(())
+}
+
+/// The entry as edited in the file, or None if editing it was given up on
+fn edit_file<T: Serialize + DeserializeOwned>(
+    path: &Path,
+    entry: &T,
+    validate: impl Fn(&T) -> Result<(), String>,
+) -> Result<Option<T>, MoneybagsError> {
+    let write_error = |source| MoneybagsError::Write {
+        path: path.display().to_string(),
+        source,
+    };
+    let mut json =
+        serde_json::to_string_pretty(entry).map_err(|e| write_error(std::io::Error::other(e)))?;
+    loop {
+        std::fs::write(path, &json).map_err(write_error)?;
+        open_editor(path).map_err(MoneybagsError::Invalid)?;
+        json = std::fs::read_to_string(path).map_err(|source| MoneybagsError::Read {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let edited = serde_json::from_str(&json)
+            .map_err(|e| e.to_string())
+            .and_then(|edited| validate(&edited).map(|()| edited));
+        match edited {
+            Ok(edited) => return Ok(Some(edited)),
+            Err(e) => {
+                println!("Invalid entry: {e}");
+                if !confirm("Edit again?")? {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+fn open_editor(path: &Path) -> Result<(), String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = shlex::split(&editor)
+        .filter(|words| !words.is_empty())
+        .ok_or_else(|| format!("Could not parse editor command {editor}"))?;
+    let program = words.remove(0);
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Could not run {editor}: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{editor} exited with {status}, entry left unchanged"
+        ))
+    }
+}
@@ -0,0 +1,76 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::output::Failure;
+
+/// What can go wrong reading, writing, or running commands on a moneybag
+#[derive(Debug, Error)]
+pub enum MoneybagsError {
+    #[error("Could not read {path}: {source}")]
+    Read { path: String, source: io::Error },
+    #[error("Could not parse {path} as a moneybag: {source}")]
+    Parse {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[error("Could not parse line {line} of the log {path}: {source}")]
+    Log {
+        path: String,
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("Could not write {path}: {source}")]
+    Write { path: String, source: io::Error },
+    #[error("Could not import {path}: {source}")]
+    Import { path: String, source: csv::Error },
+    #[error("Could not import {path}: {reason}")]
+    ImportBook { path: String, reason: String },
+    /// Something set up in a way that can't work
+    #[error("{0}")]
+    Invalid(String),
+    /// An entry a command refers to that doesn't exist
+    #[error("{0}")]
+    NotFound(String),
+    /// A command that doesn't parse, with clap's explanation
+    #[error("{0}")]
+    Usage(String),
+    /// A command that changed the moneybag without the events to log it, a bug in moneybags
+    #[error(
+        "The command made changes it didn't log ({0}), so none were made. Please report this."
+    )]
+    Unlogged(String),
+    /// Something outside the moneybag that couldn't be done, like sending an email or reaching a
+    /// server
+    #[error("{0}")]
+    Io(String),
+    /// A prompt was cancelled with Ctrl-C, which leaves the moneybag as it was
+    #[error("Cancelled")]
+    Cancelled,
+}
+
+impl MoneybagsError {
+    pub(crate) fn failure(&self) -> Failure {
+        match self {
+            MoneybagsError::Read { .. }
+            | MoneybagsError::Write { .. }
+            | MoneybagsError::Import { .. }
+            | MoneybagsError::ImportBook { .. }
+            | MoneybagsError::Io(_) => Failure::Io,
+            MoneybagsError::Parse { .. }
+            | MoneybagsError::Log { .. }
+            | MoneybagsError::Usage(_) => Failure::Parse,
+            MoneybagsError::Invalid(_)
+            | MoneybagsError::Unlogged(_)
+            | MoneybagsError::Cancelled => Failure::Invalid,
+            MoneybagsError::NotFound(_) => Failure::NotFound,
+        }
+    }
+
+    /// Print the error to stderr, with the identifier of its failure
+    pub(crate) fn report(&self) {
+        let failure = self.failure();
+        eprintln!("error[{}]: {self}", failure.id());
+        tracing::warn!(failure = failure.id(), reason = %self, "Failed");
+    }
+}
@@ -0,0 +1,443 @@
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::{
+    error::MoneybagsError,
+    moneybag::{Asset, Budget, Cost, Customer, ImportRule, Invoice, Moneybag, Rate, Settings},
+};
+
+/// A change to a moneybag. Replaying every event in the log from an empty moneybag builds the
+/// moneybag as it is now.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum Event {
+    /// The moneybag as it was when the log started, for files from before there was a log, or
+    /// as it was found after a change outside moneybags
+    Started {
+        moneybag: Box<Moneybag>,
+    },
+    InvoiceAdded {
+        invoice: Invoice,
+    },
+    /// The invoice with the same ID, as it is after the edit
+    InvoiceEdited {
+        invoice: Invoice,
+    },
+    InvoiceDeleted {
+        id: usize,
+    },
+    CostAdded {
+        cost: Cost,
+    },
+    /// The cost with the same ID, as it is after the edit
+    CostEdited {
+        cost: Cost,
+    },
+    CostDeleted {
+        id: usize,
+    },
+    /// Assets and import rules don't have IDs, so a change to them replaces all of them
+    AssetsChanged {
+        assets: Vec<Asset>,
+    },
+    ImportRulesChanged {
+        import_rules: Vec<ImportRule>,
+    },
+    /// A rate added or edited, or deleted if there is none
+    RateSet {
+        name: String,
+        rate: Option<Rate>,
+    },
+    CustomerSet {
+        name: String,
+        customer: Option<Customer>,
+    },
+    BudgetSet {
+        category: String,
+        budget: Option<Budget>,
+    },
+    SettingsChanged {
+        settings: Settings,
+    },
+}
+
+/// The events of one command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub at: DateTime<Local>,
+    pub events: Vec<Event>,
+    /// [`checksum`] of the moneybag after the events, which tells a moneybag that is what the
+    /// log builds from one changed outside moneybags without replaying the log
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<u64>,
+}
+
+impl Event {
+    /// The kind of event, with the ID of the entry it's about, e.g. `CostDeleted 3`
+    #[must_use]
+    pub fn name(&self) -> String {
+        match self {
+            Event::Started { .. } => "Started".to_string(),
+            Event::InvoiceAdded { invoice } => format!("InvoiceAdded {}", invoice.id),
+            Event::InvoiceEdited { invoice } => format!("InvoiceEdited {}", invoice.id),
+            Event::InvoiceDeleted { id } => format!("InvoiceDeleted {id}"),
+            Event::CostAdded { cost } => format!("CostAdded {}", cost.id),
+            Event::CostEdited { cost } => format!("CostEdited {}", cost.id),
+            Event::CostDeleted { id } => format!("CostDeleted {id}"),
+            Event::AssetsChanged { .. } => "AssetsChanged".to_string(),
+            Event::ImportRulesChanged { .. } => "ImportRulesChanged".to_string(),
+            Event::RateSet { name, .. } => format!("RateSet {name}"),
+            Event::CustomerSet { name, .. } => format!("CustomerSet {name}"),
+            Event::BudgetSet { category, .. } => format!("BudgetSet {category}"),
+            Event::SettingsChanged { .. } => "SettingsChanged".to_string(),
+        }
+    }
+
+    /// Whether applying the event would change the moneybag
+    pub(crate) fn changes(&self, moneybag: &Moneybag) -> bool {
+        match self {
+            Event::InvoiceEdited { invoice } => moneybag
+                .invoices
+                .iter()
+                .find(|old| old.id == invoice.id)
+                .is_some_and(|old| json(old) != json(invoice)),
+            Event::InvoiceDeleted { id } => moneybag.invoices.iter().any(|old| old.id == *id),
+            Event::CostEdited { cost } => moneybag
+                .costs
+                .iter()
+                .find(|old| old.id == cost.id)
+                .is_some_and(|old| json(old) != json(cost)),
+            Event::CostDeleted { id } => moneybag.costs.iter().any(|old| old.id == *id),
+            Event::AssetsChanged { assets } => json(&moneybag.assets) != json(assets),
+            Event::ImportRulesChanged { import_rules } => {
+                json(&moneybag.import_rules) != json(import_rules)
+            }
+            Event::RateSet { name, rate } => json(&moneybag.rates.get(name)) != json(rate),
+            Event::CustomerSet { name, customer } => {
+                json(&moneybag.customers.get(name)) != json(customer)
+            }
+            Event::BudgetSet { category, budget } => {
+                json(&moneybag.budgets.get(category)) != json(budget)
+            }
+            Event::SettingsChanged { settings } => json(&moneybag.settings) != json(settings),
+            Event::Started { .. } | Event::InvoiceAdded { .. } | Event::CostAdded { .. } => true,
+        }
+    }
+
+    pub fn apply(&self, moneybag: &mut Moneybag) {
+        match self {
+            Event::Started { moneybag: started } => *moneybag = Moneybag::clone(started),
+            Event::InvoiceAdded { invoice } => {
+                moneybag.next_invoice_id = moneybag.next_invoice_id.max(invoice.id + 1);
+                moneybag.invoices.push(invoice.clone());
+            }
+            Event::InvoiceEdited { invoice } => {
+                let edited = moneybag
+                    .invoices
+                    .iter_mut()
+                    .find(|old| old.id == invoice.id);
+                if let Some(edited) = edited {
+                    *edited = invoice.clone();
+                }
+            }
+            Event::InvoiceDeleted { id } => moneybag.invoices.retain(|invoice| invoice.id != *id),
+            Event::CostAdded { cost } => {
+                moneybag.next_cost_id = moneybag.next_cost_id.max(cost.id + 1);
+                moneybag.costs.push(cost.clone());
+            }
+            Event::CostEdited { cost } => {
+                if let Some(edited) = moneybag.costs.iter_mut().find(|old| old.id == cost.id) {
+                    *edited = cost.clone();
+                }
+            }
+            Event::CostDeleted { id } => moneybag.costs.retain(|cost| cost.id != *id),
+            Event::AssetsChanged { assets } => moneybag.assets.clone_from(assets),
+            Event::ImportRulesChanged { import_rules } => {
+                moneybag.import_rules.clone_from(import_rules);
+            }
+            Event::RateSet { name, rate } => set(&mut moneybag.rates, name, rate.as_ref()),
+            Event::CustomerSet { name, customer } => {
+                set(&mut moneybag.customers, name, customer.as_ref());
+            }
+            Event::BudgetSet { category, budget } => {
+                set(&mut moneybag.budgets, category, budget.as_ref());
+            }
+            Event::SettingsChanged { settings } => moneybag.settings.clone_from(settings),
+        }
+    }
+}
+
+fn set<T: Clone>(map: &mut BTreeMap<String, T>, name: &str, value: Option<&T>) {
+    match value {
+        Some(value) => map.insert(name.to_string(), value.clone()),
+        None => map.remove(name),
+    };
+}
+
+fn json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("Could not serialize entry")
+}
+
+/// The events that change before into after
+#[must_use]
+pub fn between(before: &Moneybag, after: &Moneybag) -> Vec<Event> {
+    let mut events = vec![];
+    for invoice in &before.invoices {
+        match after.invoices.iter().find(|new| new.id == invoice.id) {
+            None => events.push(Event::InvoiceDeleted { id: invoice.id }),
+            Some(new) if json(new) != json(invoice) => {
+                events.push(Event::InvoiceEdited {
+                    invoice: new.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for invoice in &after.invoices {
+        if !before.invoices.iter().any(|old| old.id == invoice.id) {
+            events.push(Event::InvoiceAdded {
+                invoice: invoice.clone(),
+            });
+        }
+    }
+    for cost in &before.costs {
+        match after.costs.iter().find(|new| new.id == cost.id) {
+            None => events.push(Event::CostDeleted { id: cost.id }),
+            Some(new) if json(new) != json(cost) => {
+                events.push(Event::CostEdited { cost: new.clone() });
+            }
+            Some(_) => {}
+        }
+    }
+    for cost in &after.costs {
+        if !before.costs.iter().any(|old| old.id == cost.id) {
+            events.push(Event::CostAdded { cost: cost.clone() });
+        }
+    }
+    if json(&before.assets) != json(&after.assets) {
+        events.push(Event::AssetsChanged {
+            assets: after.assets.clone(),
+        });
+    }
+    if json(&before.import_rules) != json(&after.import_rules) {
+        events.push(Event::ImportRulesChanged {
+            import_rules: after.import_rules.clone(),
+        });
+    }
+    for (name, rate) in changed(&before.rates, &after.rates) {
+        events.push(Event::RateSet { name, rate });
+    }
+    for (name, customer) in changed(&before.customers, &after.customers) {
+        events.push(Event::CustomerSet { name, customer });
+    }
+    for (category, budget) in changed(&before.budgets, &after.budgets) {
+        events.push(Event::BudgetSet { category, budget });
+    }
+    if json(&before.settings) != json(&after.settings) {
+        events.push(Event::SettingsChanged {
+            settings: after.settings.clone(),
+        });
+    }
+    events
+}
+
+/// Entries added, edited, or removed (None) from before to after
+fn changed<T: Clone + Serialize>(
+    before: &BTreeMap<String, T>,
+    after: &BTreeMap<String, T>,
+) -> Vec<(String, Option<T>)> {
+    let removed = before
+        .keys()
+        .filter(|name| !after.contains_key(*name))
+        .map(|name| (name.clone(), None));
+    let added_or_edited = after
+        .iter()
+        .filter(|(name, entry)| {
+            before
+                .get(*name)
+                .is_none_or(|old| json(old) != json(*entry))
+        })
+        .map(|(name, entry)| (name.clone(), Some(entry.clone())));
+    removed.chain(added_or_edited).collect()
+}
+
+/// FNV-1a of a moneybag as JSON, which unlike std's hasher stays the same between versions of
+/// Rust
+pub(crate) fn checksum(json: &str) -> u64 {
+    json.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Log the events of a command, which made its changes to a copy of the moneybag, and make the
+/// copy the moneybag. Returns the moneybag as it was.
+///
+/// # Errors
+///
+/// If the events don't make the moneybag into the copy, as the command changed something without
+/// an event, which the log would miss. Nothing is changed then.
+pub(crate) fn record(
+    moneybag: &mut Moneybag,
+    mut changed: Moneybag,
+) -> Result<Moneybag, MoneybagsError> {
+    let events = std::mem::take(&mut changed.pending);
+    let log = std::mem::take(&mut moneybag.log);
+    let mut replayed = moneybag.clone();
+    moneybag.log = log;
+    for event in &events {
+        debug!(?event, "Applying");
+        event.apply(&mut replayed);
+    }
+    let after = json(&changed);
+    if json(&replayed) != after {
+        let unlogged = between(&replayed, &changed);
+        return Err(MoneybagsError::Unlogged(
+            unlogged
+                .iter()
+                .map(Event::name)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ));
+    }
+    if !events.is_empty() {
+        info!(
+            events = ?events.iter().map(Event::name).collect::<Vec<_>>(),
+            "Recorded changes"
+        );
+        changed.log.push(LogEntry {
+            at: Local::now(),
+            events,
+            checksum: Some(checksum(&after)),
+        });
+    }
+    Ok(std::mem::replace(moneybag, changed))
+}
+
+/// Build a moneybag from an empty one by applying the events in a log
+#[must_use]
+pub fn replay(log: &[LogEntry]) -> Moneybag {
+    let mut moneybag = Moneybag::default();
+    for event in log.iter().flat_map(|entry| &entry.events) {
+        event.apply(&mut moneybag);
+    }
+    moneybag.log = log.to_vec();
+    moneybag
+}
+
+/// The log of a moneybag file, next to it: moneybag.json has moneybag.log, and books.log, which
+/// would be its own log, has books.log.log
+#[must_use]
+pub fn log_path(filepath: &str) -> PathBuf {
+    let path = Path::new(filepath);
+    if path.extension().is_some_and(|extension| extension == "log") {
+        PathBuf::from(format!("{filepath}.log"))
+    } else {
+        path.with_extension("log")
+    }
+}
+
+/// Read a log, one entry per line. None if there is none.
+pub(crate) fn read_log(path: &Path) -> Result<Option<Vec<LogEntry>>, MoneybagsError> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => {
+            return Err(MoneybagsError::Read {
+                path: path.display().to_string(),
+                source,
+            })
+        }
+    };
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|source| MoneybagsError::Log {
+                path: path.display().to_string(),
+                line: i + 1,
+                source,
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// Write entries to a log, after those in it already if `append`, or else in place of them
+pub(crate) fn write_log(log: &[LogEntry], path: &Path, append: bool) -> Result<(), MoneybagsError> {
+    let write_error = |source| MoneybagsError::Write {
+        path: path.display().to_string(),
+        source,
+    };
+    let mut text = String::new();
+    for entry in log {
+        text += &serde_json::to_string(entry).map_err(|e| write_error(std::io::Error::other(e)))?;
+        text.push('\n');
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(write_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_refuses_unlogged_changes() {
+        let mut moneybag = Moneybag::default();
+        let mut changed = moneybag.clone();
+        changed.change(Event::BudgetSet {
+            category: "office".to_string(),
+            budget: None,
+        });
+        changed.settings.tax_rate = "30".parse().unwrap();
+        assert!(matches!(
+            record(&mut moneybag, changed),
+            Err(MoneybagsError::Unlogged(_))
+        ));
+        assert!(moneybag.log.is_empty());
+        assert!(moneybag.settings.tax_rate.is_zero());
+
+        let mut changed = moneybag.clone();
+        let mut settings = changed.settings.clone();
+        settings.tax_rate = "30".parse().unwrap();
+        changed.change(Event::SettingsChanged { settings });
+        record(&mut moneybag, changed).unwrap();
+        assert_eq!(moneybag.log.len(), 1);
+        assert!(moneybag.log[0].checksum.is_some());
+    }
+
+    #[test]
+    fn test_read_corrupt_log() {
+        let path =
+            std::env::temp_dir().join(format!("moneybags-corrupt-{}.log", std::process::id()));
+        assert!(read_log(&path).unwrap().is_none());
+        let mut moneybag = Moneybag::default();
+        let mut changed = moneybag.clone();
+        let mut settings = changed.settings.clone();
+        settings.tax_rate = "30".parse().unwrap();
+        changed.change(Event::SettingsChanged { settings });
+        record(&mut moneybag, changed).unwrap();
+        write_log(&moneybag.log, &path, false).unwrap();
+        // A line cut short, as by a crash while writing it
+        std::fs::write(
+            &path,
+            std::fs::read_to_string(&path).unwrap() + r#"{"at": "2025-"#,
+        )
+        .unwrap();
+        let result = read_log(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(MoneybagsError::Log { line: 2, .. })));
+    }
+}
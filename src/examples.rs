@@ -0,0 +1,90 @@
+use crate::args::ExampleTopic;
+
+const RATES: &str = "\
+# Your usual hourly rate, and a cheaper one for support billed in 15 minute blocks
+add rate 950 standard
+add rate 700 support --increment 0.25
+# Weekend hours are billed at 1.5 times the standard rate
+add tier standard weekend 1.5
+# A rate in another currency needs to know what a euro is worth
+set exchange-rate EUR 11.5
+add rate 95 eu --currency EUR
+list rates";
+
+const INVOICING: &str = "\
+# Once per customer: payment terms, and their own invoice number series
+add customer Acme --email billing@acme.example --terms 30 --prefix ACME
+# At the end of the month, invoice the hours, with weekend hours at the weekend tier
+add invoice 2025-05-31 120 --rate standard --customer Acme --tier weekend=8
+# Train tickets paid for Acme's project are billed on to them
+add cost 2025-05-12 450 train --billable Acme
+list billable
+# Bill all of Acme's unbilled expenses on invoice 1 (see list invoices)
+bill 1
+show invoice 1
+# When the money comes in
+pay 1 2025-06-28
+list invoices --customer Acme";
+
+const VAT: &str = "\
+# Cost amounts include VAT. Give the deductible part as a rate...
+add cost 2025-05-03 1250 laptop-bag --vat 25%
+# ...or as an amount, when the receipt mixes rates
+add cost 2025-05-10 560 conference-lunch --vat 60
+# The VAT to deduct shows up as input VAT
+balance --scope business
+# Invoices charge VAT on top of the amount
+add invoice 2025-05-31 120 --rate standard --vat 25%
+# What to fill in on the VAT declaration for the quarter
+report vat --quarter 2025-Q2";
+
+const IMPORT: &str = "\
+# Tell moneybags which columns of your bank's CSV export hold date, description, and amount
+set bank-columns 0 2 4 --delimiter ;
+# Transactions matching a rule are added without asking
+add rule SPOTIFY spotify --category subscriptions
+add budget subscriptions 300
+import bank ~/Downloads/transactions.csv
+budget";
+
+fn recipe(topic: ExampleTopic) -> &'static str {
+    match topic {
+        ExampleTopic::Rates => RATES,
+        ExampleTopic::Invoicing => INVOICING,
+        ExampleTopic::Vat => VAT,
+        ExampleTopic::Import => IMPORT,
+    }
+}
+
+/// Print the recipe for a topic, or all of them
+pub(crate) fn print_examples(topic: Option<ExampleTopic>) {
+    let topics = match topic {
+        Some(topic) => vec![topic],
+        None => vec![
+            ExampleTopic::Rates,
+            ExampleTopic::Invoicing,
+            ExampleTopic::Vat,
+            ExampleTopic::Import,
+        ],
+    };
+    let recipes: Vec<_> = topics.into_iter().map(recipe).collect();
+    println!("{}", recipes.join("\n\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::args::Command;
+
+    #[test]
+    fn test_examples_parse() {
+        for recipe in [RATES, INVOICING, VAT, IMPORT] {
+            for line in recipe.lines().filter(|line| !line.starts_with('#')) {
+                let words = shlex::split(line).unwrap();
+                assert!(Command::try_parse_from(words).is_ok(), "{line}");
+            }
+        }
+    }
+}
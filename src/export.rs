@@ -0,0 +1,230 @@
+use std::{fs, io, path::Path};
+
+use crate::{
+    args::ExportCommand,
+    config::Config,
+    error::MoneybagsError,
+    moneybag::Moneybag,
+    output::{Align, Table},
+    sie, CommandOutput,
+};
+
+/// Lines of text on each PDF page
+const LINES_PER_PAGE: usize = 64;
+
+pub(crate) fn handle_export(
+    export_command: &ExportCommand,
+    moneybag: &Moneybag,
+    config: &Config,
+) -> Result<CommandOutput, MoneybagsError> {
+    match export_command {
+        #[cfg(feature = "xlsx")]
+        ExportCommand::Xlsx { file } => {
+            let path = shellexpand::tilde(file).to_string();
+            crate::xlsx::export_xlsx(moneybag, &path)
+                .map_err(|e| MoneybagsError::Io(format!("Could not save {path}: {e}")))?;
+            Ok(CommandOutput::Messages(vec![format!("Saved {path}")]))
+        }
+        #[cfg(not(feature = "xlsx"))]
+        ExportCommand::Xlsx { .. } => {
+            let _ = moneybag;
+            Err(crate::without("xlsx"))
+        }
+        ExportCommand::Sie { year, file } => {
+            let path = file.as_ref().map_or_else(
+                || format!("{year}.se"),
+                |file| shellexpand::tilde(file).to_string(),
+            );
+            if let Err(source) = sie::export_sie(*year, moneybag, &config.sie, &path) {
+                return Err(MoneybagsError::Write { path, source });
+            }
+            Ok(CommandOutput::Messages(vec![format!("Saved {path}")]))
+        }
+    }
+}
+
+/// Write a table to a file, as CSV, HTML, or PDF depending on its extension
+pub(crate) fn export(table: &Table, path: &str) -> io::Result<()> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    let contents = match extension.as_deref() {
+        Some("csv") => csv(table)?,
+        Some("html" | "htm") => html(table, path).into_bytes(),
+        Some("pdf") => pdf(&table.plain()),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "expected a .csv, .html, or .pdf file",
+            ))
+        }
+    };
+    fs::write(path, contents)
+}
+
+fn csv(table: &Table) -> io::Result<Vec<u8>> {
+    let (columns, rows) = table.visible();
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(columns.iter().map(|(header, _)| header))?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.into_inner().map_err(csv::IntoInnerError::into_error)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// A standalone page with the table, titled by the file name
+fn html(table: &Table, path: &str) -> String {
+    let (columns, rows) = table.visible();
+    let title = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let cell = |tag: &str, text: &str, align: Align| {
+        let align = match align {
+            Align::Left => "left",
+            Align::Right => "right",
+        };
+        format!(
+            "<{tag} style=\"text-align: {align}\">{}</{tag}>",
+            escape_html(text)
+        )
+    };
+    let header: String = columns
+        .iter()
+        .map(|(header, align)| cell("th", header, *align))
+        .collect();
+    let rows = rows.iter().map(|row| {
+        row.iter()
+            .zip(&columns)
+            .map(|(text, (_, align))| cell("td", text, *align))
+            .collect::<String>()
+    });
+    let rows: Vec<_> = std::iter::once(header)
+        .chain(rows)
+        .map(|cells| format!("<tr>{cells}</tr>\n"))
+        .collect();
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+         <style>body {{ font-family: sans-serif; }} td, th {{ padding: 2px 8px; }}</style>\n\
+         </head>\n<body>\n<h1>{0}</h1>\n<table>\n{1}</table>\n</body>\n</html>\n",
+        escape_html(&title),
+        rows.concat()
+    )
+}
+
+/// Text in a PDF string, in the Latin-1 range the standard fonts cover. Bars become '#' and
+/// other characters '?'.
+fn escape_pdf(line: &str) -> Vec<u8> {
+    let mut bytes = vec![];
+    for c in line.chars() {
+        match c {
+            '▁'..='▏' => bytes.push(b'#'),
+            c => {
+                if matches!(c, '(' | ')' | '\\') {
+                    bytes.push(b'\\');
+                }
+                bytes.push(u8::try_from(c).unwrap_or(b'?'));
+            }
+        }
+    }
+    bytes
+}
+
+/// A PDF of text in a monospace font on A4 pages
+fn pdf(text: &str) -> Vec<u8> {
+    let mut lines: Vec<_> = text.lines().collect();
+    if lines.is_empty() {
+        lines.push("");
+    }
+    let pages: Vec<_> = lines.chunks(LINES_PER_PAGE).collect();
+    // Catalog, page tree, and font come first, then a page and its contents for each page
+    let kids: Vec<_> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + i * 2))
+        .collect();
+    let mut objects = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    for page in pages {
+        let contents = objects.len() + 2;
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {contents} 0 R >>"
+            )
+            .into_bytes(),
+        );
+        let mut stream = b"BT /F1 9 Tf 11 TL 40 800 Td\n".to_vec();
+        for line in page {
+            stream.push(b'(');
+            stream.extend(escape_pdf(line));
+            stream.extend(b") Tj T*\n");
+        }
+        stream.extend(b"ET");
+        let mut object = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+        object.extend(stream);
+        object.extend(b"\nendstream");
+        objects.push(object);
+    }
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{offset:010} 00000 n \n").into_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .into_bytes(),
+    );
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        let mut table = Table::new(&[("name", Align::Left), ("amount", Align::Right)]);
+        table.add_row(vec!["R&D (lab)".to_string(), "100.00".to_string()]);
+        assert_eq!(
+            String::from_utf8(csv(&table).unwrap()).unwrap(),
+            "name,amount\nR&D (lab),100.00\n"
+        );
+        assert!(html(&table, "costs.html")
+            .contains("<td style=\"text-align: left\">R&amp;D (lab)</td>"));
+        let pdf = pdf(&table.plain());
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.windows(11).any(|window| window == b"R&D \\(lab\\)"));
+        let dir = std::env::temp_dir().join(format!("moneybags-export-{}", std::process::id()));
+        let error = export(&table, &dir.join("report.txt").to_string_lossy()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(export(&table, &dir.join("report").to_string_lossy()).is_err());
+        // The directory doesn't exist
+        let error = export(&table, &dir.join("report.csv").to_string_lossy()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,26 @@
+//! Moneybags for tests, written as JSON of only the fields a test is about
+
+use serde_json::{json, Value};
+
+use crate::moneybag::Moneybag;
+
+/// A moneybag from a JSON object, without invoices, rates, or costs unless it has them
+pub(crate) fn moneybag(json: &str) -> Moneybag {
+    let mut value: Value = serde_json::from_str(json).expect("Fixture should be JSON");
+    let fields = value
+        .as_object_mut()
+        .expect("Fixture should be a JSON object");
+    fields.entry("invoices").or_insert_with(|| json!([]));
+    fields.entry("rates").or_insert_with(|| json!({}));
+    fields.entry("costs").or_insert_with(|| json!([]));
+    serde_json::from_value(value).expect("Fixture should be a moneybag")
+}
+
+/// A local port nothing listens on, for requests that should fail to connect
+pub(crate) fn closed_port() -> u16 {
+    // The port is free again once the listener is dropped
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("Should bind a local port")
+        .port()
+}
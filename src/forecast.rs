@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::Serialize;
+
+use crate::{
+    i18n::tr,
+    money::Money,
+    moneybag::{Cost, Invoice, Moneybag, Scope},
+    output::{Align, Style, Table},
+};
+
+/// Money expected in and out during one month, and the balance at its end
+#[derive(Debug, Serialize)]
+pub struct Projection {
+    pub month: String,
+    /// Unpaid invoices due this month, and invoices already dated in it
+    pub invoiced: Money,
+    /// Average income, for a month without invoices yet
+    pub expected: Money,
+    pub costs: Money,
+    pub balance: Money,
+}
+
+/// The balance now, and projected over the coming months, starting with this one
+#[derive(Debug, Serialize)]
+pub struct Forecast {
+    pub now: Money,
+    pub months: Vec<Projection>,
+}
+
+impl Forecast {
+    pub(crate) fn of(
+        months: u32,
+        scope: Option<Scope>,
+        moneybag: &Moneybag,
+        today: NaiveDate,
+    ) -> Self {
+        let (now, months) = forecast(months, scope, moneybag, today);
+        Forecast { now, months }
+    }
+
+    pub(crate) fn print(&self) {
+        let mut table = Table::new(&[
+            ("month", Align::Left),
+            ("invoiced", Align::Right),
+            ("expected", Align::Right),
+            ("costs", Align::Right),
+            ("balance", Align::Right),
+        ]);
+        table.add_row(vec![
+            tr!("now").to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            self.now.to_string(),
+        ]);
+        for month in &self.months {
+            let style = if month.balance.is_negative() {
+                Style::Red
+            } else {
+                Style::Plain
+            };
+            table.add_styled_row(
+                vec![
+                    month.month.clone(),
+                    month.invoiced.to_string(),
+                    month.expected.to_string(),
+                    month.costs.to_string(),
+                    month.balance.to_string(),
+                ],
+                style,
+            );
+        }
+        table.print();
+    }
+}
+
+/// Costs entered or recurring that fall due after today and within this month
+pub(crate) fn costs_due_this_month(moneybag: &Moneybag, today: NaiveDate) -> Money {
+    let (_, forecast) = forecast(1, None, moneybag, today);
+    forecast[0].costs
+}
+
+/// The balance today, of paid invoices less costs up to today, and the months after it. Amounts
+/// are what moves in cash: invoices in the base currency, costs including VAT.
+fn forecast(
+    months: u32,
+    scope: Option<Scope>,
+    moneybag: &Moneybag,
+    today: NaiveDate,
+) -> (Money, Vec<Projection>) {
+    let in_scope = |entry_scope: Scope| scope.is_none_or(|scope| scope == entry_scope);
+    let invoices: Vec<_> = moneybag
+        .invoices
+        .iter()
+        .filter(|invoice| in_scope(invoice.scope))
+        .collect();
+    let costs: Vec<_> = moneybag
+        .costs
+        .iter()
+        .filter(|cost| in_scope(cost.scope))
+        .collect();
+    let today_text = today.format("%Y-%m-%d").to_string();
+    let paid: Money = invoices
+        .iter()
+        .filter(|invoice| invoice.paid.is_some_and(|paid| paid <= today))
+        .map(|invoice| invoice.base_total())
+        .sum();
+    let spent: Money = costs
+        .iter()
+        .filter(|cost| cost.date <= today_text)
+        .map(|cost| cost.amount)
+        .sum();
+    let now = paid - spent;
+    let mut balance = now;
+
+    let this_month = today.with_day(1).expect("Every month has a first day");
+    let average = average_monthly_income(&invoices, this_month);
+    let recurring = latest_recurring(&costs);
+    let mut forecast = vec![];
+    for i in 0..months {
+        let start = this_month + Months::new(i);
+        let end = start + Months::new(1);
+        let month = start.format("%Y-%m").to_string();
+        // Unpaid invoices are expected on their due date, or right away if that has passed
+        let due: Money = invoices
+            .iter()
+            .filter(|invoice| invoice.paid.is_none_or(|paid| paid > today))
+            .filter_map(|invoice| {
+                let expected = moneybag
+                    .due_date(invoice)
+                    .or_else(|| invoice.parsed_date())?
+                    .max(today);
+                (start <= expected && expected < end).then(|| invoice.base_total())
+            })
+            .sum();
+        let planned = invoices
+            .iter()
+            .any(|invoice| invoice.date.starts_with(&month));
+        let expected = if planned { Money::default() } else { average };
+        let entered: Vec<_> = costs
+            .iter()
+            .filter(|cost| cost.date.starts_with(&month) && cost.date > today_text)
+            .collect();
+        // Recurring costs not entered for the month yet
+        let projected = recurring.iter().filter(|cost| {
+            !costs
+                .iter()
+                .any(|other| other.name == cost.name && other.date.starts_with(&month))
+                && falls_due(cost, start.max(today + Days::new(1)), end)
+        });
+        let spending = entered
+            .into_iter()
+            .chain(projected)
+            .map(|cost| cost.amount)
+            .sum();
+        balance = balance + due + expected - spending;
+        forecast.push(Projection {
+            month,
+            invoiced: due,
+            expected,
+            costs: spending,
+            balance,
+        });
+    }
+    (now, forecast)
+}
+
+/// Income per month over the twelve months before this one, or since the first invoice if that
+/// is more recent
+fn average_monthly_income(invoices: &[&Invoice], this_month: NaiveDate) -> Money {
+    let year_ago = this_month - Months::new(12);
+    let past: Vec<_> = invoices
+        .iter()
+        .filter_map(|invoice| Some((invoice.parsed_date()?, invoice.base_total())))
+        .filter(|(date, _)| year_ago <= *date && *date < this_month)
+        .collect();
+    let Some(first) = past.iter().map(|(date, _)| *date).min() else {
+        return Money::default();
+    };
+    let mut months = 0;
+    let mut month = first.with_day(1).expect("Every month has a first day");
+    while month < this_month {
+        month = month + Months::new(1);
+        months += 1;
+    }
+    past.iter().map(|(_, amount)| *amount).sum::<Money>() / months
+}
+
+/// The latest entry of each monthly cost and subscription, which carry on into the future
+fn latest_recurring<'a>(costs: &[&'a Cost]) -> Vec<&'a Cost> {
+    let mut latest: HashMap<&str, &Cost> = HashMap::new();
+    for cost in costs {
+        if cost.recurring || cost.subscription.is_some() {
+            match latest.get(cost.name.as_str()) {
+                Some(existing) if existing.date > cost.date => {}
+                _ => {
+                    latest.insert(&cost.name, cost);
+                }
+            }
+        }
+    }
+    latest.into_values().collect()
+}
+
+/// Whether a recurring cost falls due from start until end. Monthly costs do every month.
+fn falls_due(cost: &Cost, start: NaiveDate, end: NaiveDate) -> bool {
+    cost.subscription
+        .is_none_or(|subscription| subscription.next_renewal(start) < end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_forecast() {
+        let moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [
+                    {"date": "2025-04-30", "amount": {"amount": 300000}, "paid": "2025-05-20"},
+                    {"date": "2025-05-31", "amount": {"amount": 300000}}
+                ],
+                "costs": [
+                    {"date": "2025-05", "amount": {"amount": 50000}, "name": "rent", "recurring": true},
+                    {"date": "2025-06", "amount": {"amount": 50000}, "name": "rent", "recurring": true}
+                ]
+            }"#,
+        );
+        let today = NaiveDate::from_ymd_opt(2025, 6, 15).unwrap();
+        let (now, forecast) = forecast(2, None, &moneybag, today);
+        assert_eq!(now, "2000".parse().unwrap());
+        // The unpaid invoice has no due date, so it's expected right away
+        assert_eq!(forecast[0].invoiced, "3000".parse().unwrap());
+        // Rent for June is entered, and already paid
+        assert_eq!(forecast[0].costs, Money::default());
+        // Two months of invoices, 6000, over the two months since the first
+        assert_eq!(forecast[0].expected, "3000".parse().unwrap());
+        assert_eq!(forecast[1].costs, "500".parse().unwrap());
+        assert_eq!(forecast[1].balance, "10500".parse().unwrap());
+    }
+
+    #[test]
+    fn test_forecast_leaves_out_misdated_invoices() {
+        let moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [{"date": "end of June", "amount": {"amount": 300000}}]
+            }"#,
+        );
+        let today = NaiveDate::from_ymd_opt(2025, 6, 15).unwrap();
+        assert!(forecast(0, None, &moneybag, today).1.is_empty());
+        let (now, forecast) = forecast(3, None, &moneybag, today);
+        assert!(now.is_zero());
+        assert!(forecast.iter().all(|month| month.invoiced.is_zero()
+            && month.expected.is_zero()
+            && month.balance.is_zero()));
+    }
+}
@@ -0,0 +1,208 @@
+//! Exchange rates from the reference rates the European Central Bank publishes each working day.
+//! The rates fetched last are kept in a cache, to fall back on offline.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+#[cfg(feature = "http")]
+use tracing::warn;
+
+use crate::{
+    error::MoneybagsError, events::Event, moneybag::Moneybag, output::note, CommandOutput, Money,
+};
+
+const URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+
+/// Where the reference rates fetched last are kept
+fn cache_path() -> PathBuf {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|cache| !cache.is_empty())
+        .map_or_else(
+            || PathBuf::from(shellexpand::tilde("~/.cache").to_string()),
+            PathBuf::from,
+        );
+    cache.join("moneybags").join("eurofxref-daily.xml")
+}
+
+/// The value of an attribute of an XML tag, in single or double quotes
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!(" {name}="))? + name.len() + 2;
+    let quote = tag[start..].chars().next()?;
+    let value = &tag[start + 1..];
+    Some(&value[..value.find(quote)?])
+}
+
+/// The date of the reference rates, and how many of each currency one euro buys
+fn parse(xml: &str) -> Option<(String, BTreeMap<String, f64>)> {
+    let mut date = None;
+    let mut rates = BTreeMap::from([("EUR".to_string(), 1.0)]);
+    for tag in xml.split('<').filter(|tag| tag.starts_with("Cube ")) {
+        if let Some(time) = attribute(tag, "time") {
+            date = Some(time.to_string());
+        }
+        if let (Some(currency), Some(rate)) = (attribute(tag, "currency"), attribute(tag, "rate")) {
+            rates.insert(currency.to_string(), rate.parse().ok()?);
+        }
+    }
+    Some((date?, rates))
+}
+
+/// The value in the base currency of one unit of the currency
+fn value_in(base: &str, currency: &str, rates: &BTreeMap<String, f64>) -> Option<Money> {
+    let value = rates.get(base)? / rates.get(currency)?;
+    format!("{value:.2}").parse().ok()
+}
+
+/// The reference rates fetched from `url` unless offline, or else those cached at `cache`
+fn reference_rates(url: &str, cache: &Path, offline: bool) -> Result<String, String> {
+    #[cfg(feature = "http")]
+    if !offline {
+        let fetched = ureq::get(url)
+            .call()
+            .and_then(|mut response| response.body_mut().read_to_string());
+        match fetched {
+            Ok(xml) => {
+                let cached = std::fs::create_dir_all(cache.parent().unwrap_or(cache))
+                    .and_then(|()| std::fs::write(cache, &xml));
+                if let Err(e) = cached {
+                    warn!(path = %cache.display(), error = %e, "Could not cache reference rates");
+                }
+                return Ok(xml);
+            }
+            Err(e) => note(&format!(
+                "Could not fetch {url}, using the cached rates: {e}"
+            )),
+        }
+    }
+    #[cfg(not(feature = "http"))]
+    if !offline {
+        let _ = url;
+        note("moneybags was built without http, using the cached rates");
+    }
+    std::fs::read_to_string(cache)
+        .map_err(|e| format!("No reference rates cached in {}: {e}", cache.display()))
+}
+
+/// An exchange rate as updated, and what it was before
+#[derive(Debug, Serialize)]
+pub struct ExchangeRate {
+    pub currency: String,
+    pub rate: Money,
+    pub was: Option<Money>,
+}
+
+impl std::fmt::Display for ExchangeRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.was {
+            Some(was) if was != self.rate => {
+                write!(f, "{} {} (was {was})", self.currency, self.rate)
+            }
+            Some(_) => write!(f, "{} {}", self.currency, self.rate),
+            None => write!(f, "{} {} (new)", self.currency, self.rate),
+        }
+    }
+}
+
+/// Update the exchange rates of the currencies given, those with a rate already, and those of
+/// rates, to the ECB's reference rates. None is updated unless the ECB has rates for all.
+pub(crate) fn handle_update(
+    currencies: &[String],
+    offline: bool,
+    moneybag: &mut Moneybag,
+) -> Result<CommandOutput, MoneybagsError> {
+    let Some(base) = moneybag.settings.base_currency.clone() else {
+        return Err(MoneybagsError::Invalid(
+            "Set the currency the books are kept in first, e.g. set base-currency SEK".to_string(),
+        ));
+    };
+    let mut currencies: Vec<String> = currencies
+        .iter()
+        .map(|currency| currency.to_uppercase())
+        .chain(moneybag.settings.exchange_rates.keys().cloned())
+        .chain(
+            moneybag
+                .rates
+                .values()
+                .filter_map(|rate| rate.currency.clone()),
+        )
+        .filter(|currency| *currency != base)
+        .collect();
+    currencies.sort();
+    currencies.dedup();
+    if currencies.is_empty() {
+        return Ok(CommandOutput::Messages(vec![
+            "No currencies to update, name them, e.g. fx update USD".to_string(),
+        ]));
+    }
+    let xml = reference_rates(URL, &cache_path(), offline).map_err(MoneybagsError::Io)?;
+    let (date, rates) = parse(&xml)
+        .ok_or_else(|| MoneybagsError::Io("Could not read the reference rates".to_string()))?;
+    let values = currencies
+        .into_iter()
+        .map(|currency| match value_in(&base, &currency, &rates) {
+            Some(value) => Ok((currency, value)),
+            None => Err(MoneybagsError::NotFound(format!(
+                "The ECB has no reference rate for {currency} to {base}"
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut settings = moneybag.settings.clone();
+    let rates = values
+        .into_iter()
+        .map(|(currency, rate)| ExchangeRate {
+            was: settings.exchange_rates.insert(currency.clone(), rate),
+            currency,
+            rate,
+        })
+        .collect();
+    moneybag.change(Event::SettingsChanged { settings });
+    Ok(CommandOutput::ExchangeRates { date, rates })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_parse() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+	<gesmes:subject>Reference rates</gesmes:subject>
+	<Cube>
+		<Cube time='2025-05-16'>
+			<Cube currency='USD' rate='1.1194'/>
+			<Cube currency='JPY' rate='162.85'/>
+			<Cube currency='SEK' rate='10.8930'/>
+		</Cube>
+	</Cube>
+</gesmes:Envelope>"#;
+        let (date, rates) = parse(xml).unwrap();
+        assert_eq!(date, "2025-05-16");
+        assert_eq!(rates.len(), 4);
+        assert_eq!(value_in("SEK", "EUR", &rates), "10.89".parse().ok());
+        assert_eq!(value_in("SEK", "USD", &rates), "9.73".parse().ok());
+        assert_eq!(value_in("EUR", "SEK", &rates), "0.09".parse().ok());
+        assert_eq!(value_in("SEK", "GBP", &rates), None);
+    }
+
+    #[test]
+    fn test_reference_rates() {
+        let port = fixtures::closed_port();
+        let url = format!("http://127.0.0.1:{port}/eurofxref-daily.xml");
+        let cache = std::env::temp_dir().join(format!("moneybags-fx-{}.xml", std::process::id()));
+        let _ = std::fs::remove_file(&cache);
+        let error = reference_rates(&url, &cache, false).unwrap_err();
+        assert!(error.starts_with("No reference rates cached"), "{error}");
+        std::fs::write(&cache, "<Cube time='2025-05-16'>").unwrap();
+        assert_eq!(
+            reference_rates(&url, &cache, false).as_deref(),
+            Ok("<Cube time='2025-05-16'>")
+        );
+        std::fs::remove_file(&cache).unwrap();
+        assert_eq!(parse("<html>Service unavailable</html>"), None);
+    }
+}
@@ -0,0 +1,512 @@
+//! Importing a GnuCash book, saved as XML (compressed or not) or SQLite. Transactions on income
+//! accounts become invoices and those on expense accounts costs, unless a mapping of accounts
+//! says otherwise.
+
+use std::{collections::BTreeMap, io::Read};
+
+use chrono::NaiveDate;
+use flate2::read::GzDecoder;
+use roxmltree::{Document, Node};
+
+use crate::{
+    error::MoneybagsError,
+    events::Event,
+    moneybag::{Cost, Invoice, Moneybag, Scope},
+    Money,
+};
+
+const GNC: &str = "http://www.gnucash.org/XML/gnc";
+
+struct Account {
+    name: String,
+    /// GnuCash's account type, e.g. INCOME, EXPENSE, or BANK
+    kind: String,
+    parent: Option<String>,
+}
+
+/// One account's part of a transaction. Money into the account is positive.
+struct Split {
+    account: String,
+    value: Money,
+    memo: String,
+}
+
+struct Transaction {
+    date: String,
+    description: String,
+    splits: Vec<Split>,
+}
+
+/// Accounts by GUID, and the transactions between them
+#[derive(Default)]
+struct Book {
+    accounts: BTreeMap<String, Account>,
+    transactions: Vec<Transaction>,
+}
+
+impl Book {
+    /// The full name of an account, e.g. Expenses:Hosting, leaving out the root
+    fn full_name(&self, guid: &str) -> String {
+        let mut names = vec![];
+        let mut next = self.accounts.get(guid);
+        while let Some(account) = next.filter(|account| account.kind != "ROOT") {
+            names.push(account.name.as_str());
+            next = account
+                .parent
+                .as_ref()
+                .and_then(|guid| self.accounts.get(guid));
+        }
+        names.reverse();
+        names.join(":")
+    }
+
+    fn kind(&self, guid: &str) -> &str {
+        self.accounts
+            .get(guid)
+            .map_or("", |account| account.kind.as_str())
+    }
+}
+
+/// What the transactions of an account become
+#[derive(Debug, Clone, PartialEq)]
+enum Mapping {
+    Invoice {
+        customer: Option<String>,
+        scope: Scope,
+    },
+    Cost {
+        category: Option<String>,
+        scope: Scope,
+    },
+    Skip,
+}
+
+impl Mapping {
+    /// Parse e.g. "invoice Acme", "cost hosting", "personal cost food", or "skip"
+    fn parse(text: &str) -> Option<Self> {
+        let (scope, text) = match text.trim().strip_prefix("personal ") {
+            Some(text) => (Scope::Personal, text.trim()),
+            None => (Scope::Business, text.trim()),
+        };
+        let (kind, name) = text.split_once(' ').unwrap_or((text, ""));
+        let name = Some(name.trim().to_string()).filter(|name| !name.is_empty());
+        match kind {
+            "invoice" => Some(Mapping::Invoice {
+                customer: name,
+                scope,
+            }),
+            "cost" => Some(Mapping::Cost {
+                category: name,
+                scope,
+            }),
+            "skip" if name.is_none() => Some(Mapping::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// Account mappings by account name, read from a TOML file of e.g.
+/// `"Expenses:Hosting" = "cost hosting"`. A mapping covers the accounts below it too.
+fn read_mappings(path: &str) -> Result<BTreeMap<String, Mapping>, MoneybagsError> {
+    let toml = std::fs::read_to_string(path).map_err(|source| MoneybagsError::Read {
+        path: path.to_string(),
+        source,
+    })?;
+    let table: BTreeMap<String, String> =
+        toml::from_str(&toml).map_err(|e| MoneybagsError::ImportBook {
+            path: path.to_string(),
+            reason: e.to_string(),
+        })?;
+    table
+        .into_iter()
+        .map(|(account, text)| match Mapping::parse(&text) {
+            Some(mapping) => Ok((account, mapping)),
+            None => Err(MoneybagsError::Invalid(format!(
+                "Could not map {account} to \"{text}\", expected e.g. \"invoice Acme\", \
+                 \"cost hosting\", \"personal cost\", or \"skip\""
+            ))),
+        })
+        .collect()
+}
+
+/// What an account's transactions become: the mapping of it or the closest account above it,
+/// or, without one, invoices for income accounts and costs in a category named after the
+/// account for expense accounts
+fn mapping_for(name: &str, kind: &str, mappings: &BTreeMap<String, Mapping>) -> Mapping {
+    let mut prefix = name;
+    loop {
+        if let Some(mapping) = mappings.get(prefix) {
+            return mapping.clone();
+        }
+        match prefix.rsplit_once(':') {
+            Some((parent, _)) => prefix = parent,
+            None => break,
+        }
+    }
+    let last = name.rsplit(':').next().unwrap_or(name);
+    match kind {
+        "INCOME" => Mapping::Invoice {
+            customer: None,
+            scope: Scope::Business,
+        },
+        "EXPENSE" => Mapping::Cost {
+            category: Some(last.to_lowercase()),
+            scope: Scope::Business,
+        },
+        _ => Mapping::Skip,
+    }
+}
+
+/// Import the transactions of a GnuCash book on income and expense accounts as invoices and
+/// costs, mapped by the accounts file if given. Entries already in the moneybag are skipped, so a
+/// book can be imported again after more was added to it.
+pub(crate) fn import_gnucash(
+    path: &str,
+    accounts: Option<&str>,
+    moneybag: &mut Moneybag,
+) -> Result<Vec<String>, MoneybagsError> {
+    let mappings = accounts.map(read_mappings).transpose()?.unwrap_or_default();
+    let book = read_book(path).map_err(|reason| MoneybagsError::ImportBook {
+        path: path.to_string(),
+        reason,
+    })?;
+    let (mut invoices, mut costs, mut skipped) = (0, 0, 0);
+    for transaction in &book.transactions {
+        // Income paid straight into a bank account was paid when it was booked
+        let paid = transaction
+            .splits
+            .iter()
+            .any(|split| matches!(book.kind(&split.account), "BANK" | "CASH" | "ASSET"))
+            .then(|| NaiveDate::parse_from_str(&transaction.date, "%Y-%m-%d").ok())
+            .flatten();
+        for split in &transaction.splits {
+            let name = book.full_name(&split.account);
+            match mapping_for(&name, book.kind(&split.account), &mappings) {
+                Mapping::Invoice { customer, scope } => {
+                    let invoice = Invoice {
+                        id: 0,
+                        date: transaction.date.clone(),
+                        amount: -split.value,
+                        rate: None,
+                        rate_name: None,
+                        customer,
+                        expenses: vec![],
+                        scope,
+                        exchange_rate: None,
+                        tier_hours: BTreeMap::new(),
+                        number: None,
+                        paid,
+                        vat: None,
+                        reference: None,
+                        reminded: None,
+                    };
+                    if add_invoice(invoice, moneybag) {
+                        invoices += 1;
+                    } else {
+                        skipped += 1;
+                    }
+                }
+                Mapping::Cost { category, scope } => {
+                    let name = if split.memo.is_empty() {
+                        &transaction.description
+                    } else {
+                        &split.memo
+                    };
+                    let cost = Cost {
+                        id: 0,
+                        date: transaction.date.clone(),
+                        amount: split.value,
+                        name: name.clone(),
+                        recurring: false,
+                        vat: None,
+                        subscription: None,
+                        category,
+                        billable: None,
+                        customer: None,
+                        note: None,
+                        scope,
+                        reference: None,
+                    };
+                    if add_cost(cost, moneybag) {
+                        costs += 1;
+                    } else {
+                        skipped += 1;
+                    }
+                }
+                Mapping::Skip => {}
+            }
+        }
+    }
+    moneybag.add_missing_customers();
+    Ok(vec![format!(
+        "Imported {invoices} invoices and {costs} costs, skipped {skipped}"
+    )])
+}
+
+/// Add an invoice unless it's for nothing or there's one on the same date for the same amount to
+/// the same customer. Returns whether it was added.
+fn add_invoice(invoice: Invoice, moneybag: &mut Moneybag) -> bool {
+    let exists = moneybag.invoices.iter().any(|existing| {
+        existing.date == invoice.date
+            && existing.amount == invoice.amount
+            && existing.customer == invoice.customer
+    });
+    if exists || invoice.amount.is_zero() {
+        return false;
+    }
+    moneybag.change(Event::InvoiceAdded { invoice });
+    true
+}
+
+/// Add a cost unless it's for nothing or there's one on the same date for the same amount with
+/// the same name. Returns whether it was added.
+fn add_cost(cost: Cost, moneybag: &mut Moneybag) -> bool {
+    let exists = moneybag.costs.iter().any(|existing| {
+        existing.date == cost.date && existing.amount == cost.amount && existing.name == cost.name
+    });
+    if exists || cost.amount.is_zero() {
+        return false;
+    }
+    moneybag.change(Event::CostAdded { cost });
+    true
+}
+
+fn read_book(path: &str) -> Result<Book, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    if bytes.starts_with(b"SQLite format 3\0") {
+        return read_sqlite(path).map_err(|e| e.to_string());
+    }
+    let xml = if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut xml = String::new();
+        GzDecoder::new(&bytes[..])
+            .read_to_string(&mut xml)
+            .map_err(|e| e.to_string())?;
+        xml
+    } else {
+        String::from_utf8(bytes).map_err(|e| e.to_string())?
+    };
+    read_xml(&xml)
+}
+
+/// The text of the first child element with a local name, e.g. "name" for act:name
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.tag_name().name() == name)
+        .and_then(|child| child.text())
+}
+
+fn read_xml(xml: &str) -> Result<Book, String> {
+    let document = Document::parse(xml).map_err(|e| e.to_string())?;
+    let mut book = Book::default();
+    for node in document.descendants() {
+        if node.has_tag_name((GNC, "account")) {
+            let guid = child_text(node, "id").ok_or("An account has no ID")?;
+            book.accounts.insert(
+                guid.to_string(),
+                Account {
+                    name: child_text(node, "name").unwrap_or_default().to_string(),
+                    kind: child_text(node, "type").unwrap_or_default().to_string(),
+                    parent: child_text(node, "parent").map(str::to_string),
+                },
+            );
+        } else if node.has_tag_name((GNC, "transaction")) {
+            book.transactions.push(read_xml_transaction(node)?);
+        }
+    }
+    Ok(book)
+}
+
+fn read_xml_transaction(node: Node) -> Result<Transaction, String> {
+    let date = node
+        .children()
+        .find(|child| child.tag_name().name() == "date-posted")
+        .and_then(|posted| child_text(posted, "date"))
+        .ok_or("A transaction has no date")?;
+    let splits = node
+        .children()
+        .find(|child| child.tag_name().name() == "splits")
+        .into_iter()
+        .flat_map(|splits| splits.children().filter(Node::is_element))
+        .map(|split| {
+            let value = child_text(split, "value").unwrap_or_default();
+            Ok(Split {
+                account: child_text(split, "account")
+                    .ok_or("A split has no account")?
+                    .to_string(),
+                value: parse_value(value).ok_or(format!("Could not parse amount {value}"))?,
+                memo: child_text(split, "memo").unwrap_or_default().to_string(),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Transaction {
+        date: date.chars().take(10).collect(),
+        description: child_text(node, "description")
+            .unwrap_or_default()
+            .to_string(),
+        splits,
+    })
+}
+
+/// GnuCash writes amounts as fractions, e.g. "-12345/100"
+fn parse_value(value: &str) -> Option<Money> {
+    let (numerator, denominator) = value.split_once('/').unwrap_or((value, "1"));
+    let numerator: i64 = numerator.parse().ok()?;
+    let denominator: i64 = denominator.parse().ok().filter(|d| *d != 0)?;
+    Some(numerator.to_string().parse::<Money>().ok()? / denominator)
+}
+
+fn read_sqlite(path: &str) -> rusqlite::Result<Book> {
+    let connection =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut book = Book::default();
+    let mut accounts =
+        connection.prepare("SELECT guid, name, account_type, parent_guid FROM accounts")?;
+    for row in accounts.query_map([], |row| {
+        Ok((
+            row.get(0)?,
+            Account {
+                name: row.get(1)?,
+                kind: row.get(2)?,
+                parent: row.get(3)?,
+            },
+        ))
+    })? {
+        let (guid, account) = row?;
+        book.accounts.insert(guid, account);
+    }
+    let mut splits = connection.prepare(
+        "SELECT t.guid, t.post_date, t.description, s.account_guid, s.value_num, s.value_denom, \
+         s.memo FROM transactions t JOIN splits s ON s.tx_guid = t.guid \
+         ORDER BY t.post_date, t.guid",
+    )?;
+    let mut rows = splits.query([])?;
+    let mut last_guid = String::new();
+    while let Some(row) = rows.next()? {
+        let guid: String = row.get(0)?;
+        if guid != last_guid {
+            let date: Option<String> = row.get(1)?;
+            book.transactions.push(Transaction {
+                date: date.unwrap_or_default().chars().take(10).collect(),
+                description: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                splits: vec![],
+            });
+            last_guid = guid;
+        }
+        let (numerator, denominator): (i64, i64) = (row.get(4)?, row.get(5)?);
+        let split = Split {
+            account: row.get(3)?,
+            value: parse_value(&format!("{numerator}/{denominator}")).unwrap_or_default(),
+            memo: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+        };
+        if let Some(transaction) = book.transactions.last_mut() {
+            transaction.splits.push(split);
+        }
+    }
+    Ok(book)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOK: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<gnc-v2 xmlns:gnc="http://www.gnucash.org/XML/gnc" xmlns:act="http://www.gnucash.org/XML/act"
+        xmlns:trn="http://www.gnucash.org/XML/trn" xmlns:split="http://www.gnucash.org/XML/split"
+        xmlns:ts="http://www.gnucash.org/XML/ts">
+<gnc:book version="2.0.0">
+<gnc:account version="2.0.0"><act:name>Root Account</act:name><act:id type="guid">root</act:id>
+  <act:type>ROOT</act:type></gnc:account>
+<gnc:account version="2.0.0"><act:name>Income</act:name><act:id type="guid">inc</act:id>
+  <act:type>INCOME</act:type><act:parent type="guid">root</act:parent></gnc:account>
+<gnc:account version="2.0.0"><act:name>Acme</act:name><act:id type="guid">acme</act:id>
+  <act:type>INCOME</act:type><act:parent type="guid">inc</act:parent></gnc:account>
+<gnc:account version="2.0.0"><act:name>Expenses</act:name><act:id type="guid">exp</act:id>
+  <act:type>EXPENSE</act:type><act:parent type="guid">root</act:parent></gnc:account>
+<gnc:account version="2.0.0"><act:name>Hosting</act:name><act:id type="guid">host</act:id>
+  <act:type>EXPENSE</act:type><act:parent type="guid">exp</act:parent></gnc:account>
+<gnc:account version="2.0.0"><act:name>Checking</act:name><act:id type="guid">bank</act:id>
+  <act:type>BANK</act:type><act:parent type="guid">root</act:parent></gnc:account>
+<gnc:transaction version="2.0.0">
+  <trn:date-posted><ts:date>2025-03-01 10:59:00 +0000</ts:date></trn:date-posted>
+  <trn:description>March work</trn:description>
+  <trn:splits>
+    <trn:split><split:value>1200000/100</split:value><split:account type="guid">bank</split:account></trn:split>
+    <trn:split><split:value>-1200000/100</split:value><split:account type="guid">acme</split:account></trn:split>
+  </trn:splits>
+</gnc:transaction>
+<gnc:transaction version="2.0.0">
+  <trn:date-posted><ts:date>2025-03-05 10:59:00 +0000</ts:date></trn:date-posted>
+  <trn:description>VPS</trn:description>
+  <trn:splits>
+    <trn:split><split:value>-9900/100</split:value><split:account type="guid">bank</split:account></trn:split>
+    <trn:split><split:value>9900/100</split:value><split:account type="guid">host</split:account></trn:split>
+  </trn:splits>
+</gnc:transaction>
+</gnc:book>
+</gnc-v2>"#;
+
+    #[test]
+    fn test_read_xml() {
+        let book = read_xml(BOOK).unwrap();
+        assert_eq!(book.full_name("host"), "Expenses:Hosting");
+        let mappings = BTreeMap::from([(
+            "Income:Acme".to_string(),
+            Mapping::parse("invoice Acme").unwrap(),
+        )]);
+        let invoice = &book.transactions[0].splits[1];
+        assert_eq!(invoice.value, "-12000".parse().unwrap());
+        assert_eq!(
+            mapping_for(&book.full_name(&invoice.account), "INCOME", &mappings),
+            Mapping::Invoice {
+                customer: Some("Acme".to_string()),
+                scope: Scope::Business
+            }
+        );
+        assert_eq!(
+            mapping_for("Expenses:Hosting", "EXPENSE", &mappings),
+            Mapping::Cost {
+                category: Some("hosting".to_string()),
+                scope: Scope::Business
+            }
+        );
+        assert_eq!(mapping_for("Checking", "BANK", &mappings), Mapping::Skip);
+        assert_eq!(Mapping::parse("refund"), None);
+    }
+
+    #[test]
+    fn test_read_bad_books() {
+        assert!(read_xml("<gnc-v2><gnc:book>").is_err());
+        let undated = BOOK.replace("<ts:date>2025-03-05 10:59:00 +0000</ts:date>", "");
+        assert_eq!(
+            read_xml(&undated).err().as_deref(),
+            Some("A transaction has no date")
+        );
+        let unreadable = BOOK.replace("9900/100</split:value>", "99,00</split:value>");
+        assert!(read_xml(&unreadable).is_err());
+        assert_eq!(parse_value("1/0"), None);
+        assert_eq!(parse_value("12.50"), None);
+
+        let dir = std::env::temp_dir().join(format!("moneybags-gnucash-{}", std::process::id()));
+        std::fs::create_dir(&dir).unwrap();
+        let book = dir.join("book.gnucash");
+        let accounts = dir.join("accounts.toml");
+        std::fs::write(&book, BOOK).unwrap();
+        std::fs::write(&accounts, r#""Income:Acme" = "refund""#).unwrap();
+        let mut moneybag = Moneybag::default();
+        let missing = dir.join("missing.gnucash");
+        assert!(matches!(
+            import_gnucash(&missing.to_string_lossy(), None, &mut moneybag),
+            Err(MoneybagsError::ImportBook { .. })
+        ));
+        assert!(matches!(
+            import_gnucash(
+                &book.to_string_lossy(),
+                Some(&accounts.to_string_lossy()),
+                &mut moneybag
+            ),
+            Err(MoneybagsError::Invalid(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(moneybag.invoices.is_empty() && moneybag.costs.is_empty());
+    }
+}
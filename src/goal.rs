@@ -0,0 +1,119 @@
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+use crate::{
+    i18n::tr,
+    money::Money,
+    moneybag::{sum_invoices, Moneybag, Scope},
+    output::{print_field, Style},
+};
+
+/// How business invoicing in a year compares to the revenue goal
+#[derive(Debug, Serialize)]
+pub struct Progress {
+    pub goal: Money,
+    pub invoiced: Money,
+    /// What would have been invoiced by today, going at an even pace towards the goal
+    pub on_pace: Money,
+    /// Months left of the year, counting this one
+    pub months_left: u32,
+}
+
+impl Progress {
+    /// Progress in the year of today, if a goal is set
+    pub(crate) fn of(moneybag: &Moneybag, today: NaiveDate) -> Option<Self> {
+        let goal = moneybag.settings.revenue_goal?;
+        let year = today.year().to_string();
+        let invoiced =
+            sum_invoices(moneybag.invoices.iter().filter(|invoice| {
+                invoice.scope == Scope::Business && invoice.date.starts_with(&year)
+            }));
+        let days_in_year = if today.leap_year() { 366 } else { 365 };
+        Some(Progress {
+            goal,
+            invoiced,
+            on_pace: goal * i64::from(today.ordinal()) / days_in_year,
+            months_left: 13 - today.month(),
+        })
+    }
+
+    fn percent(&self) -> Money {
+        if self.goal.is_zero() {
+            Money::default()
+        } else {
+            self.invoiced * 100 / self.goal
+        }
+    }
+
+    /// Ahead of pace when positive, behind when negative
+    fn ahead(&self) -> Money {
+        self.invoiced - self.on_pace
+    }
+
+    /// What each month left has to bring in to reach the goal
+    fn needed_per_month(&self) -> Money {
+        (self.goal - self.invoiced).max(Money::default()) / i64::from(self.months_left)
+    }
+
+    fn pace(&self) -> String {
+        let ahead = self.ahead();
+        if ahead.is_negative() {
+            Style::Red.paint(&tr!("{amount} behind", amount = -ahead))
+        } else {
+            tr!("{amount} ahead", amount = ahead)
+        }
+    }
+
+    pub(crate) fn print(&self) {
+        print_field("Goal", self.goal);
+        print_field(
+            "Invoiced",
+            tr!(
+                "{amount} ({percent}%)",
+                amount = self.invoiced,
+                percent = self.percent()
+            ),
+        );
+        print_field("Pace", self.pace());
+        print_field("Needed per month", self.needed_per_month());
+    }
+
+    /// One line, for the balance
+    pub(crate) fn summary(&self) -> String {
+        tr!(
+            "{percent}% of {goal}, {pace}",
+            percent = self.percent(),
+            goal = self.goal,
+            pace = self.pace()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_progress() {
+        let mut moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [{"date": "2025-03-31", "amount": {"amount": 30000000}}]
+            }"#,
+        );
+        let today = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+        assert!(Progress::of(&moneybag, today).is_none());
+        moneybag.settings.revenue_goal = "1200000".parse().ok();
+        let progress = Progress::of(&moneybag, today).unwrap();
+        assert_eq!(progress.percent(), "25".parse().unwrap());
+        // A fifth of the year has gone by
+        assert_eq!(progress.ahead(), "60000".parse().unwrap());
+        assert_eq!(progress.needed_per_month(), "90000".parse().unwrap());
+        // A goal of nothing is already reached, however far into the year
+        moneybag.settings.revenue_goal = Some(Money::default());
+        let today = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+        let progress = Progress::of(&moneybag, today).unwrap();
+        assert!(progress.percent().is_zero());
+        assert!(progress.needed_per_month().is_zero());
+    }
+}
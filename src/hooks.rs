@@ -0,0 +1,168 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{events::Event, moneybag::Moneybag};
+
+/// Shell commands the config runs when things change, with what changed as JSON on stdin, e.g.
+/// `on_add_invoice = "./notify.sh"`. A hook that fails is reported, and changes nothing.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+#[allow(clippy::struct_field_names)]
+pub(crate) struct Hooks {
+    /// After saving, with the moneybag on stdin and the path saved to in `MONEYBAGS_FILE`
+    pub(crate) on_save: Option<String>,
+    /// After any command that changes something, with its events on stdin
+    pub(crate) on_change: Option<String>,
+    pub(crate) on_add_invoice: Option<String>,
+    pub(crate) on_edit_invoice: Option<String>,
+    pub(crate) on_delete_invoice: Option<String>,
+    pub(crate) on_add_cost: Option<String>,
+    pub(crate) on_edit_cost: Option<String>,
+    pub(crate) on_delete_cost: Option<String>,
+}
+
+impl Hooks {
+    /// Run the hooks for the events of a command, on the moneybag as it was before. Edits get the
+    /// entry as it is after, and deletes the entry as it was.
+    pub(crate) fn after_command(&self, before: &Moneybag, events: &[Event]) {
+        if events.is_empty() {
+            return;
+        }
+        if let Some(hook) = &self.on_change {
+            run("on_change", hook, &json(&events), &[]);
+        }
+        let deleted_invoice = |id: &usize| before.invoices.iter().find(|old| old.id == *id);
+        let deleted_cost = |id: &usize| before.costs.iter().find(|old| old.id == *id);
+        for event in events {
+            let (name, hook, entry) = match event {
+                Event::InvoiceAdded { invoice } => {
+                    ("on_add_invoice", &self.on_add_invoice, json(invoice))
+                }
+                Event::InvoiceEdited { invoice } => {
+                    ("on_edit_invoice", &self.on_edit_invoice, json(invoice))
+                }
+                Event::InvoiceDeleted { id } => (
+                    "on_delete_invoice",
+                    &self.on_delete_invoice,
+                    json(&deleted_invoice(id)),
+                ),
+                Event::CostAdded { cost } => ("on_add_cost", &self.on_add_cost, json(cost)),
+                Event::CostEdited { cost } => ("on_edit_cost", &self.on_edit_cost, json(cost)),
+                Event::CostDeleted { id } => (
+                    "on_delete_cost",
+                    &self.on_delete_cost,
+                    json(&deleted_cost(id)),
+                ),
+                _ => continue,
+            };
+            if let Some(hook) = hook {
+                run(name, hook, &entry, &[]);
+            }
+        }
+    }
+
+    pub(crate) fn after_save(&self, moneybag: &Moneybag, filepath: &str) {
+        if let Some(hook) = &self.on_save {
+            run(
+                "on_save",
+                hook,
+                &json(moneybag),
+                &[("MONEYBAGS_FILE", filepath)],
+            );
+        }
+    }
+}
+
+fn json(value: &impl Serialize) -> String {
+    serde_json::to_string_pretty(value).expect("Could not serialize entry")
+}
+
+/// Run a hook through the shell with input on stdin, and wait for it to finish
+fn run(name: &str, hook: &str, input: &str, env: &[(&str, &str)]) {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let child = Command::new(shell)
+        .args([flag, hook])
+        .env("MONEYBAGS_HOOK", name)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .spawn();
+    let status = child.and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            // A hook that doesn't read its input closes the pipe early, which is fine
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        child.wait()
+    });
+    match status {
+        Ok(status) if status.success() => info!(name, hook, "Ran hook"),
+        Ok(status) => {
+            warn!(name, hook, %status, "Hook failed");
+            println!("Hook {name} failed: {status}");
+        }
+        Err(e) => {
+            warn!(name, hook, error = %e, "Could not run hook");
+            println!("Could not run hook {name}: {e}");
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_after_command() {
+        let path = std::env::temp_dir().join(format!("moneybags-hook-{}", std::process::id()));
+        let hooks = Hooks {
+            on_delete_cost: Some(format!("cat > '{}'", path.display())),
+            ..Hooks::default()
+        };
+        let before = fixtures::moneybag(
+            r#"{
+                "costs": [{"id": 1, "date": "2025-05", "amount": "100", "name": "rent"}]
+            }"#,
+        );
+        hooks.after_command(&before, &[]);
+        assert!(!path.exists());
+        hooks.after_command(&before, &[Event::CostDeleted { id: 1 }]);
+        let deleted: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(deleted["name"], "rent");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_failing_hook() {
+        let path =
+            std::env::temp_dir().join(format!("moneybags-failing-hook-{}", std::process::id()));
+        let hooks = Hooks {
+            on_change: Some("exit 3".to_string()),
+            on_add_cost: Some("false".to_string()),
+            on_delete_cost: Some(format!("cat > '{}'", path.display())),
+            ..Hooks::default()
+        };
+        let before = fixtures::moneybag(
+            r#"{
+                "costs": [{"id": 1, "date": "2025-05", "amount": "100", "name": "rent"}]
+            }"#,
+        );
+        let cost = before.costs[0].clone();
+        // Hooks that fail don't keep the others from running
+        hooks.after_command(
+            &before,
+            &[Event::CostAdded { cost }, Event::CostDeleted { id: 1 }],
+        );
+        assert!(std::fs::read_to_string(&path).unwrap().contains("rent"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
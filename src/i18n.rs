@@ -0,0 +1,322 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use serde::Deserialize;
+
+/// Language of labels, prompts, and messages
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub(crate) enum Language {
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "sv")]
+    Swedish,
+}
+
+impl Language {
+    /// The language of the locale in the environment, English unless it's Swedish
+    pub(crate) fn from_locale() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(std::env::var_os)
+            .find(|locale| !locale.is_empty())
+            .unwrap_or_default();
+        if locale.to_string_lossy().starts_with("sv") {
+            Language::Swedish
+        } else {
+            Language::English
+        }
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+pub(crate) fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+/// Translations of English text, with the same `{placeholders}`
+const SWEDISH: &[(&str, &str)] = &[
+    // Balance
+    ("Costs", "Kostnader"),
+    ("Input VAT", "Ingående moms"),
+    ("Depreciation", "Avskrivningar"),
+    ("Invoices", "Fakturor"),
+    ("Total", "Totalt"),
+    ("Average invoice", "Snittfaktura"),
+    (
+        "Invoices left to break even",
+        "Fakturor kvar till nollresultat",
+    ),
+    ("Invoiced in {currency}", "Fakturerat i {currency}"),
+    ("Warning", "Varning"),
+    // Dashboard
+    ("Invoiced this month", "Fakturerat denna månad"),
+    ("Costs this month", "Kostnader denna månad"),
+    ("Unpaid", "Obetalt"),
+    ("Overdue", "Förfallet"),
+    (
+        "{amount} in {count} invoices",
+        "{amount} i {count} fakturor",
+    ),
+    ("Costs coming this month", "Kommande kostnader denna månad"),
+    ("{amount} for {quarter}", "{amount} för {quarter}"),
+    // Revenue goal
+    ("Goal", "Mål"),
+    ("Invoiced", "Fakturerat"),
+    ("Pace", "Takt"),
+    ("Needed per month", "Behövs per månad"),
+    ("{amount} ahead", "{amount} före"),
+    ("{amount} behind", "{amount} efter"),
+    (
+        "{percent}% of {goal}, {pace}",
+        "{percent} % av {goal}, {pace}",
+    ),
+    // Invoice statistics
+    ("Mean", "Medelvärde"),
+    ("Median", "Median"),
+    ("Smallest", "Minsta"),
+    ("Largest", "Största"),
+    ("90th percentile", "90:e percentilen"),
+    ("No invoices to summarize", "Inga fakturor att sammanfatta"),
+    // Recovery
+    (
+        "Unsaved changes were written to {file}, start moneybags to load them",
+        "Osparade ändringar skrevs till {file}, starta moneybags för att läsa in dem",
+    ),
+    (
+        "{file} has changes that weren't saved before moneybags stopped. Load them? Otherwise \
+         they're discarded.",
+        "{file} har ändringar som inte sparades innan moneybags stannade. Läsa in dem? Annars \
+         slängs de.",
+    ),
+    (
+        "Loaded, save to keep them",
+        "Inlästa, spara för att behålla dem",
+    ),
+    // File statistics
+    ("File", "Fil"),
+    ("Size", "Storlek"),
+    ("not saved yet", "inte sparad än"),
+    ("Log size", "Loggens storlek"),
+    ("Last saved", "Senast sparad"),
+    ("never", "aldrig"),
+    ("Assets", "Tillgångar"),
+    ("Rates", "Timpriser"),
+    ("Budgets", "Budgetar"),
+    ("Import rules", "Importregler"),
+    ("Logged changes", "Loggade ändringar"),
+    ("Customers", "Kunder"),
+    ("Categories", "Kategorier"),
+    ("Oldest entry", "Äldsta post"),
+    ("Newest entry", "Nyaste post"),
+    // Profit and loss
+    ("Revenue", "Intäkter"),
+    ("Work", "Arbete"),
+    ("Billed expenses", "Vidarefakturerade utlägg"),
+    ("Total revenue", "Summa intäkter"),
+    ("Total costs", "Summa kostnader"),
+    ("Result", "Resultat"),
+    ("Operating result", "Rörelseresultat"),
+    // Tax estimate
+    ("Profit", "Vinst"),
+    ("Social fees", "Egenavgifter"),
+    ("Income tax", "Inkomstskatt"),
+    ("To set aside", "Att sätta undan"),
+    ("Left after tax", "Kvar efter skatt"),
+    // VAT return
+    ("Taxable sales", "Momspliktig försäljning"),
+    ("Output VAT {rate}%", "Utgående moms {rate} %"),
+    ("VAT to pay", "Moms att betala"),
+    // Table headers and fields
+    ("name", "namn"),
+    ("amount", "belopp"),
+    ("details", "detaljer"),
+    ("date", "datum"),
+    ("category", "kategori"),
+    ("years", "år"),
+    ("tiers", "nivåer"),
+    ("renews", "förnyas"),
+    ("rate", "timpris"),
+    ("per month", "per månad"),
+    ("pattern", "mönster"),
+    ("number", "nummer"),
+    ("invoiced", "fakturerat"),
+    ("interval", "intervall"),
+    ("increment", "avrundning"),
+    ("in", "om"),
+    ("due", "förfaller"),
+    ("customer", "kund"),
+    ("month", "månad"),
+    ("section", "avsnitt"),
+    ("item", "post"),
+    ("box", "ruta"),
+    ("target", "mål"),
+    ("utilization", "debiteringsgrad"),
+    ("invoices needed", "fakturor som krävs"),
+    ("hours needed", "timmar som krävs"),
+    ("now", "nu"),
+    ("expected", "förväntat"),
+    ("balance", "saldo"),
+    ("share", "andel"),
+    ("running share", "ackumulerad andel"),
+    ("running total", "ackumulerat"),
+    ("profit", "vinst"),
+    ("margin", "marginal"),
+    ("per hour", "per timme"),
+    ("3-month avg", "snitt 3 mån"),
+    ("12-month avg", "snitt 12 mån"),
+    ("trend", "trend"),
+    ("count", "antal"),
+    ("sum", "summa"),
+    ("avg", "snitt"),
+    ("up", "upp"),
+    ("down", "ned"),
+    ("flat", "oförändrad"),
+    ("no customer", "ingen kund"),
+    ("current", "ej förfallet"),
+    ("1-30 days", "1-30 dagar"),
+    ("31-60 days", "31-60 dagar"),
+    ("over 60 days", "över 60 dagar"),
+    ("uncategorized", "okategoriserat"),
+    ("year", "år"),
+    ("invoices", "fakturor"),
+    ("average invoice", "snittfaktura"),
+    ("costs", "kostnader"),
+    ("depreciation", "avskrivningar"),
+    ("net", "netto"),
+    ("currency", "valuta"),
+    ("VAT", "moms"),
+    ("hours", "timmar"),
+    ("{tier} hours", "{tier}-timmar"),
+    ("expense", "utlägg"),
+    ("total", "totalt"),
+    ("exchange rate", "växelkurs"),
+    ("base total", "totalt i basvaluta"),
+    ("scope", "omfattning"),
+    ("status", "status"),
+    ("paid {date}", "betald {date}"),
+    ("overdue", "förfallen"),
+    ("unpaid", "obetald"),
+    ("vat", "moms"),
+    ("recurring", "återkommande"),
+    ("monthly", "månadsvis"),
+    ("subscription", "prenumeration"),
+    ("{interval}, renews {date}", "{interval}, förnyas {date}"),
+    ("billable to", "vidarefaktureras till"),
+    ("for customer", "för kund"),
+    ("billed", "fakturerad"),
+    ("not billed", "ej fakturerad"),
+    ("note", "anteckning"),
+    ("reference", "referens"),
+    ("reminded", "påmind"),
+    ("address", "adress"),
+    ("email", "e-post"),
+    ("org number", "organisationsnummer"),
+    ("vat number", "momsregistreringsnummer"),
+    ("payment terms", "betalningsvillkor"),
+    ("{days} days", "{days} dagar"),
+    ("invoice prefix", "fakturaprefix"),
+    // Messages
+    ("invoice", "faktura"),
+    ("cost", "kostnad"),
+    ("asset", "tillgång"),
+    ("rule", "regel"),
+    ("Invoice {index} not found", "Faktura {index} hittades inte"),
+    ("Cost {index} not found", "Kostnad {index} hittades inte"),
+    ("Asset {index} not found", "Tillgång {index} hittades inte"),
+    ("Customer {name} not found", "Kunden {name} hittades inte"),
+    (
+        "Rate {name} not found in rates",
+        "Timpriset {name} hittades inte",
+    ),
+    (
+        "No {entry} {number} (have {first}–{last})",
+        "Ingen {entry} {number} (finns {first}–{last})",
+    ),
+    (
+        "No {entry} {number}, there are none",
+        "Ingen {entry} {number}, det finns inga",
+    ),
+    (
+        "No {entry} added this session to refer to as last",
+        "Ingen {entry} tillagd under sessionen att syfta på som senaste",
+    ),
+    (", did you mean {names}?", ", menade du {names}?"),
+    ("Could not parse amount", "Kunde inte tolka beloppet"),
+    ("Could not parse date", "Kunde inte tolka datumet"),
+    ("Cancelled", "Avbrutet"),
+    ("Delete {entry}?", "Ta bort {entry}?"),
+    ("Save changes to {file}?", "Spara ändringarna i {file}?"),
+    (
+        "Exiting with unsaved changes",
+        "Avslutar med osparade ändringar",
+    ),
+    ("Stopping, nothing saved", "Avbryter, inget sparat"),
+    (
+        "Press Ctrl-C again to quit",
+        "Tryck Ctrl-C igen för att avsluta",
+    ),
+    (
+        "Dry run, nothing would change",
+        "Provkörning, inget skulle ändras",
+    ),
+    ("Dry run, would change:", "Provkörning, skulle ändra:"),
+    ("[y/N]", "[j/N]"),
+];
+
+/// The text in the current language. Text without a translation stays in English.
+pub(crate) fn translate(text: &'static str) -> &'static str {
+    if LANGUAGE.load(Ordering::Relaxed) != Language::Swedish as u8 {
+        return text;
+    }
+    SWEDISH
+        .iter()
+        .find(|(english, _)| *english == text)
+        .map_or(text, |(_, swedish)| swedish)
+}
+
+/// Text with each `{name}` replaced by its value
+pub(crate) fn fill(text: &str, values: &[(&str, &dyn Display)]) -> String {
+    values.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), &value.to_string())
+    })
+}
+
+/// Translate text, filling in `{placeholders}` given as `name = value`
+macro_rules! tr {
+    ($text:literal) => {
+        $crate::i18n::translate($text)
+    };
+    ($text:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::translate($text),
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        text.split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn test_swedish() {
+        for (english, swedish) in SWEDISH {
+            assert_eq!(placeholders(english), placeholders(swedish), "{english}");
+        }
+        assert_eq!(
+            fill("Invoice {index} not found", &[("index", &3)]),
+            "Invoice 3 not found"
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Importing worked hours from a calendar in iCalendar format, a file or a URL to subscribe to:
+//! each event blocked for billable work becomes an invoice of its hours at a rate
+
+use chrono::{DateTime, Duration, Local, NaiveDateTime, Utc};
+
+use crate::{
+    error::MoneybagsError,
+    events,
+    moneybag::{Invoice, Moneybag, Scope},
+    Money,
+};
+
+/// An event of the calendar, with what's needed of it
+#[derive(Debug, PartialEq)]
+struct Event {
+    /// UID, with the RECURRENCE-ID of a moved occurrence of a recurring event
+    uid: String,
+    summary: String,
+    start: NaiveDateTime,
+    length: Duration,
+    /// Has an RRULE, whose other occurrences aren't expanded
+    recurring: bool,
+}
+
+/// Lines folded over more than one are joined, as continuation lines start with a space or tab
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Text with iCalendar's escapes undone, and line breaks made spaces
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push(' '),
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+/// A DTSTART or DTEND: in UTC with a Z, taken as local time, or floating or with a TZID, taken
+/// as is. None for a date without a time, as all-day events have no hours.
+fn date_time(value: &str) -> Option<NaiveDateTime> {
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            Some(
+                DateTime::<Utc>::from_naive_utc_and_offset(utc, Utc)
+                    .with_timezone(&Local)
+                    .naive_local(),
+            )
+        }
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok(),
+    }
+}
+
+/// A DURATION, e.g. PT1H30M or P1D
+fn duration(value: &str) -> Option<Duration> {
+    let value = value.strip_prefix('+').unwrap_or(value).strip_prefix('P')?;
+    let mut length = Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let n: i64 = if c == 'T' { 0 } else { number.parse().ok()? };
+        number.clear();
+        length += match c {
+            'W' => Duration::weeks(n),
+            'D' => Duration::days(n),
+            'H' => Duration::hours(n),
+            'M' => Duration::minutes(n),
+            'S' => Duration::seconds(n),
+            'T' => Duration::zero(),
+            _ => return None,
+        };
+    }
+    Some(length)
+}
+
+/// The timed events of a calendar. Cancelled events, and all-day ones, are left out.
+fn events(text: &str) -> Vec<Event> {
+    let mut events = vec![];
+    let mut properties: Option<Vec<(String, String)>> = None;
+    for line in unfold(text) {
+        match line.as_str() {
+            "BEGIN:VEVENT" => properties = Some(vec![]),
+            "END:VEVENT" => {
+                if let Some(event) = properties.take().and_then(|properties| event(&properties)) {
+                    events.push(event);
+                }
+            }
+            _ => {
+                let (Some(properties), Some((name, value))) =
+                    (&mut properties, line.split_once(':'))
+                else {
+                    continue;
+                };
+                // Parameters, like ;TZID=Europe/Stockholm, come before the value
+                let name = name.split(';').next().unwrap_or(name);
+                properties.push((name.to_ascii_uppercase(), value.to_string()));
+            }
+        }
+    }
+    events
+}
+
+fn event(properties: &[(String, String)]) -> Option<Event> {
+    let get = |name: &str| {
+        properties
+            .iter()
+            .find(|(property, _)| property == name)
+            .map(|(_, value)| value.as_str())
+    };
+    if get("STATUS") == Some("CANCELLED") {
+        return None;
+    }
+    let start = date_time(get("DTSTART")?)?;
+    let length = match get("DTEND") {
+        Some(end) => date_time(end)? - start,
+        None => duration(get("DURATION")?)?,
+    };
+    let uid = get("UID").unwrap_or_default();
+    Some(Event {
+        uid: match get("RECURRENCE-ID") {
+            Some(occurrence) => format!("{uid} {occurrence}"),
+            None => uid.to_string(),
+        },
+        summary: unescape(get("SUMMARY").unwrap_or_default()),
+        start,
+        length,
+        recurring: get("RRULE").is_some(),
+    })
+}
+
+/// The calendar at a URL (webcal:// is fetched over https), or in a file
+fn read(source: &str) -> Result<String, MoneybagsError> {
+    let url = source
+        .strip_prefix("webcal://")
+        .map_or(source.to_string(), |rest| format!("https://{rest}"));
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return std::fs::read_to_string(source).map_err(|source_error| MoneybagsError::Read {
+            path: source.to_string(),
+            source: source_error,
+        });
+    }
+    #[cfg(feature = "http")]
+    {
+        ureq::get(&url)
+            .call()
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(|e| MoneybagsError::ImportBook {
+                path: source.to_string(),
+                reason: e.to_string(),
+            })
+    }
+    #[cfg(not(feature = "http"))]
+    Err(MoneybagsError::Invalid(
+        "moneybags was built without http, build it with --features http".to_string(),
+    ))
+}
+
+/// Import the events of a calendar whose summary contains `matching`, ignoring case, as invoices
+/// of their hours at the rate, to the customer. Events are matched on the customer's name if
+/// matching isn't given, and all are imported if neither is. Events imported before, by UID, are
+/// skipped.
+pub(crate) fn import_ical(
+    source: &str,
+    rate_name: &str,
+    matching: Option<&str>,
+    customer: Option<&str>,
+    moneybag: &mut Moneybag,
+) -> Result<Vec<String>, MoneybagsError> {
+    let Some(rate) = moneybag.rates.get(rate_name).cloned() else {
+        return Err(MoneybagsError::Invalid(format!(
+            "Rate {rate_name} not found in rates"
+        )));
+    };
+    let exchange_rate = moneybag
+        .settings
+        .exchange_rate_for(rate.currency.as_deref())
+        .map_err(MoneybagsError::Invalid)?;
+    let pattern = matching.or(customer).unwrap_or_default().to_lowercase();
+    let (mut imported, mut skipped, mut recurring) = (0, 0, 0);
+    let mut hours = Money::default();
+    for event in events(&read(source)?) {
+        if !event.summary.to_lowercase().contains(&pattern) || event.length <= Duration::zero() {
+            continue;
+        }
+        if event.recurring {
+            recurring += 1;
+            continue;
+        }
+        let reference = Some(format!("ical {}", event.uid));
+        if moneybag
+            .invoices
+            .iter()
+            .any(|invoice| invoice.reference == reference)
+        {
+            skipped += 1;
+            continue;
+        }
+        let minutes: Money = event
+            .length
+            .num_minutes()
+            .to_string()
+            .parse()
+            .unwrap_or_default();
+        let billed = rate.billable_hours(minutes / 60);
+        let date = event.start.date().to_string();
+        let number = moneybag.next_invoice_number(customer, &date);
+        let invoice = Invoice {
+            id: 0,
+            date,
+            amount: billed,
+            rate: Some(rate.clone()),
+            rate_name: Some(rate_name.to_string()),
+            customer: customer.map(str::to_string),
+            expenses: vec![],
+            scope: Scope::Business,
+            exchange_rate,
+            tier_hours: std::collections::BTreeMap::new(),
+            number,
+            paid: None,
+            vat: None,
+            reference,
+            reminded: None,
+        };
+        moneybag.change(events::Event::InvoiceAdded { invoice });
+        imported += 1;
+        hours = hours + billed;
+    }
+    moneybag.add_missing_customers();
+    let mut messages = vec![format!(
+        "Imported {imported} events as invoices of {hours} hours, skipped {skipped} imported before"
+    )];
+    if recurring > 0 {
+        messages.push(format!(
+            "Skipped {recurring} recurring events, whose occurrences can't be told apart; add them by hand"
+        ));
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_events() {
+        let calendar = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:1@example.com\r\n\
+            DTSTART;TZID=Europe/Stockholm:20250505T090000\r\n\
+            DTEND;TZID=Europe/Stockholm:20250505T113000\r\n\
+            SUMMARY:[Acme] Workshop\\, part\r\n  one\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:2@example.com\r\n\
+            DTSTART:20250506T130000\r\n\
+            DURATION:PT45M\r\n\
+            SUMMARY:Lunch\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:3@example.com\r\n\
+            DTSTART;VALUE=DATE:20250507\r\n\
+            SUMMARY:[Acme] Offsite\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:4@example.com\r\n\
+            DTSTART:20250508T090000\r\n\
+            DTEND:20250508T100000\r\n\
+            STATUS:CANCELLED\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let events = events(calendar);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].uid, "1@example.com");
+        assert_eq!(events[0].summary, "[Acme] Workshop, part one");
+        assert_eq!(events[0].length, Duration::minutes(150));
+        assert_eq!(events[1].length, Duration::minutes(45));
+        assert_eq!(
+            events[1].start.date(),
+            NaiveDate::from_ymd_opt(2025, 5, 6).unwrap()
+        );
+        assert_eq!(duration("P1DT2H"), Some(Duration::hours(26)));
+    }
+
+    #[test]
+    fn test_bad_calendars() {
+        let unfinished = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:1\r\nDTSTART:20250505T090000\r\n";
+        assert!(events(unfinished).is_empty());
+        let undated =
+            "BEGIN:VEVENT\r\nUID:1\r\nDTSTART:tomorrow\r\nDURATION:PT1H\r\nEND:VEVENT\r\n";
+        assert!(events(undated).is_empty());
+        assert_eq!(duration("1 hour"), None);
+
+        let mut moneybag = fixtures::moneybag(
+            r#"{"rates": {"standard": {"rate": "950"}, "euro": {"rate": "90", "currency": "EUR"}}}"#,
+        );
+        let missing = std::env::temp_dir().join("moneybags-test-missing.ics");
+        let missing = missing.to_str().unwrap();
+        for rate in ["hourly", "euro"] {
+            assert!(matches!(
+                import_ical(missing, rate, None, None, &mut moneybag),
+                Err(MoneybagsError::Invalid(_))
+            ));
+        }
+        assert!(matches!(
+            import_ical(missing, "standard", None, None, &mut moneybag),
+            Err(MoneybagsError::Read { .. })
+        ));
+        let port = fixtures::closed_port();
+        let url = format!("http://127.0.0.1:{port}/calendar.ics");
+        let result = import_ical(&url, "standard", None, None, &mut moneybag);
+        if cfg!(feature = "http") {
+            assert!(matches!(result, Err(MoneybagsError::ImportBook { .. })));
+        } else {
+            assert!(matches!(result, Err(MoneybagsError::Invalid(_))));
+        }
+        assert!(moneybag.invoices.is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+use chrono::NaiveDate;
+
+use crate::{
+    args::ImportCommand,
+    banking,
+    config::Config,
+    confirm,
+    error::MoneybagsError,
+    events::Event,
+    ical,
+    moneybag::{Cost, ImportRule, Invoice, Moneybag, Scope},
+    payments::{self, Provider},
+    prompt, sie,
+    tracking::{self, Tracker},
+    CommandOutput, Money,
+};
+
+/// Import from a file or service, returning what was imported
+pub(crate) fn handle_import(
+    import_command: ImportCommand,
+    moneybag: &mut Moneybag,
+    config: &Config,
+) -> Result<CommandOutput, MoneybagsError> {
+    let messages = match import_command {
+        ImportCommand::Bank { file } => import_bank(&shellexpand::tilde(&file), moneybag),
+        ImportCommand::Gocardless { days } => {
+            banking::import_gocardless(days, moneybag, &config.bank)
+        }
+        #[cfg(feature = "gnucash")]
+        ImportCommand::Gnucash { file, accounts } => crate::gnucash::import_gnucash(
+            &shellexpand::tilde(&file),
+            accounts
+                .map(|accounts| shellexpand::tilde(&accounts).to_string())
+                .as_deref(),
+            moneybag,
+        ),
+        #[cfg(not(feature = "gnucash"))]
+        ImportCommand::Gnucash { .. } => Err(crate::without("gnucash")),
+        ImportCommand::Stripe { file, customer } => payments::import_payments(
+            Provider::Stripe,
+            &shellexpand::tilde(&file),
+            customer.as_deref(),
+            moneybag,
+        ),
+        ImportCommand::Sie { file, customer } => sie::import_sie(
+            &shellexpand::tilde(&file),
+            customer.as_deref(),
+            moneybag,
+            &config.sie,
+        ),
+        ImportCommand::Ical {
+            source,
+            rate,
+            matching,
+            customer,
+        } => ical::import_ical(
+            &shellexpand::tilde(&source),
+            &rate,
+            matching.as_deref(),
+            customer.as_deref(),
+            moneybag,
+        ),
+        ImportCommand::Toggl(args) => {
+            tracking::import_tracked(Tracker::Toggl, &args, moneybag, &config.time)
+        }
+        ImportCommand::Clockify(args) => {
+            tracking::import_tracked(Tracker::Clockify, &args, moneybag, &config.time)
+        }
+        ImportCommand::Paypal { file, customer } => payments::import_payments(
+            Provider::Paypal,
+            &shellexpand::tilde(&file),
+            customer.as_deref(),
+            moneybag,
+        ),
+    }?;
+    Ok(CommandOutput::Messages(messages))
+}
+
+/// Bank exports write amounts like "-1 234,50", which `Money` can't parse as is
+pub(crate) fn parse_bank_amount(amount: &str) -> Option<Money> {
+    let mut amount: String = amount
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\'')
+        .collect();
+    if amount.contains('.') {
+        amount = amount.replace(',', "");
+    } else {
+        amount = amount.replace(',', ".");
+    }
+    amount.parse().ok()
+}
+
+/// A transaction on the bank account
+pub(crate) struct Transaction {
+    pub(crate) date: String,
+    pub(crate) description: String,
+    /// Negative when money went out
+    pub(crate) amount: Money,
+    /// The bank's ID of the transaction, if it has one
+    pub(crate) reference: Option<String>,
+}
+
+/// Import transactions from a bank CSV export, as [`import_transactions`] does
+fn import_bank(path: &str, moneybag: &mut Moneybag) -> Result<Vec<String>, MoneybagsError> {
+    let columns = moneybag.settings.bank_columns;
+    let delimiter = u8::try_from(columns.delimiter).map_err(|_| {
+        MoneybagsError::Invalid(format!(
+            "The bank column delimiter {} isn't a single byte",
+            columns.delimiter
+        ))
+    })?;
+    let import_error = |source| MoneybagsError::Import {
+        path: path.to_string(),
+        source,
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .map_err(import_error)?;
+
+    let mut transactions = vec![];
+    let mut unreadable = 0;
+    for record in reader.records() {
+        let record = record.map_err(import_error)?;
+        let (Some(date), Some(description), Some(amount)) = (
+            record.get(columns.date),
+            record.get(columns.description),
+            record.get(columns.amount).and_then(parse_bank_amount),
+        ) else {
+            println!(
+                "Skipping unreadable row: {}",
+                record.iter().collect::<Vec<_>>().join(", ")
+            );
+            unreadable += 1;
+            continue;
+        };
+        transactions.push(Transaction {
+            date: date.to_string(),
+            description: description.to_string(),
+            amount,
+            reference: None,
+        });
+    }
+    import_transactions(transactions, unreadable, moneybag)
+}
+
+/// Import outgoing transactions as costs. Transactions matching an import rule are added
+/// directly, the rest are prompted for. Incoming ones that match an unpaid invoice are flagged
+/// as its payment, and it's marked paid if confirmed. Transactions with a reference that's been
+/// imported before are skipped, as are the `skipped` left out before.
+pub(crate) fn import_transactions(
+    transactions: impl IntoIterator<Item = Transaction>,
+    mut skipped: usize,
+    moneybag: &mut Moneybag,
+) -> Result<Vec<String>, MoneybagsError> {
+    let (mut by_rule, mut prompted, mut paid) = (0, 0, 0);
+    for Transaction {
+        date,
+        description,
+        amount,
+        reference,
+    } in transactions
+    {
+        if reference.is_some()
+            && (moneybag
+                .costs
+                .iter()
+                .any(|cost| cost.reference == reference)
+                || moneybag
+                    .invoices
+                    .iter()
+                    .any(|invoice| invoice.reference == reference))
+        {
+            skipped += 1;
+            continue;
+        }
+        // Money coming in is income, not a cost
+        if !amount.is_negative() {
+            if flag_payment(&date, &description, amount, reference, moneybag)? {
+                paid += 1;
+            } else {
+                skipped += 1;
+            }
+            continue;
+        }
+        let amount = -amount;
+
+        if let Some(rule) = moneybag
+            .import_rules
+            .iter()
+            .find(|rule| rule.matches(&description))
+        {
+            let cost = Cost {
+                id: 0,
+                date,
+                amount,
+                name: rule.name.clone(),
+                recurring: false,
+                vat: None,
+                subscription: None,
+                category: rule.category.clone(),
+                billable: None,
+                customer: None,
+                note: None,
+                scope: Scope::Business,
+                reference,
+            };
+            moneybag.change(Event::CostAdded { cost });
+            by_rule += 1;
+            continue;
+        }
+
+        println!("{date} {amount} {description}");
+        let name = prompt("name (empty to skip): ")?;
+        if name.is_empty() {
+            skipped += 1;
+            continue;
+        }
+        let category = Some(prompt("category: ")?).filter(|category| !category.is_empty());
+        let pattern = prompt("save as rule for descriptions containing (empty to not save): ")?;
+        if !pattern.is_empty() {
+            let mut import_rules = moneybag.import_rules.clone();
+            import_rules.push(ImportRule {
+                pattern,
+                name: name.clone(),
+                category: category.clone(),
+            });
+            moneybag.change(Event::ImportRulesChanged { import_rules });
+        }
+        let cost = Cost {
+            id: 0,
+            date,
+            amount,
+            name,
+            recurring: false,
+            vat: None,
+            subscription: None,
+            category,
+            billable: None,
+            customer: None,
+            note: None,
+            scope: Scope::Business,
+            reference,
+        };
+        moneybag.change(Event::CostAdded { cost });
+        prompted += 1;
+    }
+    Ok(vec![format!(
+        "Imported {} costs ({by_rule} by rules), marked {paid} invoices paid, skipped {skipped}",
+        by_rule + prompted
+    )])
+}
+
+/// Flag money coming in as the probable payment of the oldest unpaid invoice for the amount, and
+/// mark it paid if confirmed. Returns whether it was.
+fn flag_payment(
+    date: &str,
+    description: &str,
+    amount: Money,
+    reference: Option<String>,
+    moneybag: &mut Moneybag,
+) -> Result<bool, MoneybagsError> {
+    let Some(invoice) = moneybag
+        .invoices
+        .iter()
+        .find(|invoice| invoice.paid.is_none() && invoice.base_total() + invoice.vat() == amount)
+    else {
+        return Ok(false);
+    };
+    println!(
+        "{date} {amount} {description} is probably the payment of invoice {}: {invoice}",
+        invoice.id
+    );
+    let Ok(paid) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+        return Ok(false);
+    };
+    if !confirm("Mark it paid?")? {
+        return Ok(false);
+    }
+    let invoice = Invoice {
+        paid: Some(paid),
+        reference,
+        ..invoice.clone()
+    };
+    moneybag.change(Event::InvoiceEdited { invoice });
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bank_amount() {
+        let expected = "-1234.50".parse().ok();
+        assert_eq!(parse_bank_amount("-1 234,50"), expected);
+        assert_eq!(parse_bank_amount("-1,234.50"), expected);
+        assert_eq!(parse_bank_amount("-1234.5"), expected);
+        assert_eq!(parse_bank_amount("abc"), None);
+    }
+}
@@ -0,0 +1,76 @@
+/// Whether input goes on over the next line: it ends with a backslash, or a quote is left open
+pub(crate) fn continues(input: &str) -> bool {
+    input.ends_with('\\') || shlex::split(input).is_none()
+}
+
+/// Input with the next line added. A trailing backslash joins the lines, like in a shell, and
+/// an open quote gets a newline.
+pub(crate) fn join_lines(input: &str, next: &str) -> String {
+    match input.strip_suffix('\\') {
+        Some(start) => format!("{start}{next}"),
+        None => format!("{input}\n{next}"),
+    }
+}
+
+/// The commands in input separated by semicolons, leaving out empty ones. Semicolons in quotes
+/// or after a backslash are part of a command.
+pub(crate) fn split_commands(input: &str) -> Vec<String> {
+    let mut commands = vec![String::new()];
+    let mut quote = None;
+    let mut escaped = false;
+    for c in input.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quote != Some('\'') => escaped = true,
+            '\'' | '"' if quote.is_none() => quote = Some(c),
+            _ if quote == Some(c) => quote = None,
+            ';' if quote.is_none() => {
+                commands.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        commands.last_mut().expect("Starts with a command").push(c);
+    }
+    commands
+        .into_iter()
+        .map(|command| command.trim().to_string())
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_continuation() {
+        assert!(!continues("add cost 2025-05 99 vpn"));
+        assert!(continues("add invoice 2025-05-31 10 \\"));
+        assert!(continues("add cost 2025-05 99 vpn --note \"first line"));
+        assert_eq!(
+            join_lines("add invoice 2025-05-31 10 \\", "-r standard"),
+            "add invoice 2025-05-31 10 -r standard"
+        );
+        let note = join_lines("add cost 2025-05 99 vpn --note \"first", "second\"");
+        assert!(!continues(&note));
+        assert_eq!(shlex::split(&note).unwrap()[6], "first\nsecond");
+    }
+
+    #[test]
+    fn test_split_commands() {
+        assert_eq!(
+            split_commands("add cost 2025-05 99 vpn; balance"),
+            ["add cost 2025-05 99 vpn", "balance"]
+        );
+        assert_eq!(
+            split_commands("add cost 2025-05 99 vpn --note 'a; b' ;; list costs;"),
+            ["add cost 2025-05 99 vpn --note 'a; b'", "list costs"]
+        );
+        assert_eq!(
+            split_commands(r#"set bank-columns 0 1 2 -d \; ; budget "x;\"y""#),
+            [r"set bank-columns 0 1 2 -d \;", r#"budget "x;\"y""#]
+        );
+        assert!(split_commands("  ").is_empty());
+    }
+}
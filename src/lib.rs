@@ -0,0 +1,3227 @@
+//! Keep track of invoices, costs, and assets, and what's left of the money.
+//!
+//! [`moneybag::Moneybag`] holds everything, and [`money::Money`] is the amount type used
+//! throughout. [`load_moneybag`] and [`save_moneybag`] read and write the JSON file the command
+//! line works on, [`run_command`] runs a command on a moneybag in memory and returns a
+//! [`CommandOutput`], and [`run`] is the whole command line program.
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::error::ErrorKind;
+use completion::{prompt_completing, Completion};
+use config::Config;
+use dry_run::print_changes;
+use editor::edit_in_editor;
+use events::Event;
+use i18n::tr;
+use rustyline::{error::ReadlineError, history::DefaultHistory, DefaultEditor, Editor};
+use storage::{JsonFile, Storage};
+use tracing_subscriber::filter::LevelFilter;
+
+mod args;
+mod banking;
+mod chart;
+mod command_output;
+mod completion;
+mod config;
+#[cfg(all(unix, feature = "daemon"))]
+mod daemon;
+mod dashboard;
+mod dry_run;
+mod editor;
+mod error;
+pub mod events;
+mod examples;
+mod export;
+#[cfg(test)]
+mod fixtures;
+mod forecast;
+mod fx;
+#[cfg(feature = "gnucash")]
+mod gnucash;
+mod goal;
+mod hooks;
+mod i18n;
+mod ical;
+mod import;
+mod input;
+pub mod money;
+pub mod moneybag;
+mod notify;
+mod output;
+mod payments;
+mod query;
+mod recovery;
+mod remind;
+mod report;
+#[cfg(feature = "scripts")]
+mod script;
+#[cfg(feature = "server")]
+mod server;
+mod sie;
+mod stats;
+pub mod storage;
+mod sync;
+mod tracking;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+use args::{
+    AddCommand, Args, BalanceFilter, CalculatedCost, Command, CostArgs, CustomerArgs, DeleteArgs,
+    DeleteCommand, EditCommand, FxCommand, InvoiceArgs, ListArgs, ListCommand, ListFilter,
+    ListFormat, ListSort, SetCommand, ShowCommand, StatsCommand, LAST,
+};
+use chrono::{Local, NaiveDate};
+use clap::{Parser, ValueEnum};
+use moneybag::{
+    average_invoice, resolve_name, sum_costs, sum_depreciation, sum_input_vat, sum_invoices, Asset,
+    BankColumns, Billable, Budget, Cost, Customer, Expense, ImportRule, Invoice, Moneybag, Rate,
+    Scope, Subscription,
+};
+
+use money::{Money, PercentOrMoney};
+use output::{
+    note, print_costs_csv, print_field, print_invoices_csv, print_json, print_rates_csv, Align,
+    Indexed, Named, Style, Table, Verbosity,
+};
+use serde::Serialize;
+
+pub use command_output::{Added, Balance, CommandOutput, CurrencyTotal, Transaction};
+pub use dashboard::Dashboard;
+pub use error::MoneybagsError;
+pub use forecast::{Forecast, Projection};
+pub use fx::ExchangeRate;
+pub use goal::Progress;
+pub use output::Failure;
+pub use query::QueryResult;
+pub use remind::Reminder;
+pub use report::Report;
+pub use stats::InvoiceStats;
+
+pub(crate) fn prompt(prompt: &str) -> Result<String, MoneybagsError> {
+    if !std::io::stdin().is_terminal() {
+        return read_line(prompt)?.ok_or_else(|| stdin_error(ReadlineError::Eof));
+    }
+    let mut editor = DefaultEditor::new().map_err(stdin_error)?;
+    readline(&mut editor, prompt)
+}
+
+/// Read a line with a line editor, failing with [`MoneybagsError::Cancelled`] on Ctrl-C
+pub(crate) fn readline<H: rustyline::Helper>(
+    editor: &mut Editor<H, DefaultHistory>,
+    prompt: &str,
+) -> Result<String, MoneybagsError> {
+    match editor.readline(prompt) {
+        Ok(input) => Ok(input.trim().to_string()),
+        Err(ReadlineError::Interrupted) => Err(MoneybagsError::Cancelled),
+        Err(e) => Err(stdin_error(e)),
+    }
+}
+
+fn stdin_error(error: ReadlineError) -> MoneybagsError {
+    let source = match error {
+        ReadlineError::Io(source) => source,
+        ReadlineError::Eof => std::io::ErrorKind::UnexpectedEof.into(),
+        error => std::io::Error::other(error),
+    };
+    MoneybagsError::Read {
+        path: "stdin".to_string(),
+        source,
+    }
+}
+
+/// The error of a command whose cargo feature this build was made without
+#[cfg(not(all(
+    feature = "daemon",
+    feature = "email",
+    feature = "gnucash",
+    feature = "http",
+    feature = "scripts",
+    feature = "server",
+    feature = "tui",
+    feature = "xlsx"
+)))]
+pub(crate) fn without(feature: &str) -> MoneybagsError {
+    MoneybagsError::Invalid(format!(
+        "moneybags was built without {feature}, build it with --features {feature}"
+    ))
+}
+
+/// What something that can be cancelled with Ctrl-C gave, or None, after saying so, if it was
+fn cancellable<T>(result: Result<T, MoneybagsError>) -> Result<Option<T>, MoneybagsError> {
+    match result {
+        Err(MoneybagsError::Cancelled) => {
+            println!("{}", tr!("Cancelled"));
+            Ok(None)
+        }
+        result => result.map(Some),
+    }
+}
+
+/// Prompt for a line of input, or None at end of input
+fn read_line(prompt: &str) -> Result<Option<String>, MoneybagsError> {
+    print!("{prompt}");
+    std::io::stdout()
+        .flush()
+        .map_err(|source| MoneybagsError::Write {
+            path: "stdout".to_string(),
+            source,
+        })?;
+    let mut input = String::new();
+    let read = std::io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| stdin_error(e.into()))?;
+    Ok((read > 0).then(|| input.trim().to_string()))
+}
+
+/// Where changes are saved, and how commands are read and run
+struct Session {
+    storage: Box<dyn Storage>,
+    autosave: bool,
+    dry_run: bool,
+    config: Config,
+    last_added: RefCell<LastAdded>,
+}
+
+impl Session {
+    /// Save the moneybag to its storage, or to a JSON file at `path`, and run the save hook
+    fn save(&self, moneybag: &Moneybag, path: Option<&str>) -> Result<(), MoneybagsError> {
+        let location = if let Some(path) = path {
+            save_moneybag(moneybag, path)?;
+            path.to_string()
+        } else {
+            self.storage.save(moneybag)?;
+            recovery::saved(moneybag, &self.storage.location());
+            self.storage.location()
+        };
+        self.config.hooks.after_save(moneybag, &location);
+        Ok(())
+    }
+}
+
+/// IDs of the invoice and cost, and indexes of the asset and rule, added last in a session, for
+/// `last` to refer to
+#[derive(Default)]
+struct LastAdded {
+    invoice: Option<usize>,
+    cost: Option<usize>,
+    asset: Option<usize>,
+    rule: Option<usize>,
+}
+
+impl LastAdded {
+    /// Note what a command added, from the events it logged. Deleting an asset or rule moves the
+    /// ones after it, so the last added is forgotten then.
+    fn note(&mut self, events: &[Event], before: &Moneybag, after: &Moneybag) {
+        for event in events {
+            match event {
+                Event::InvoiceAdded { invoice } => self.invoice = Some(invoice.id),
+                Event::CostAdded { cost } => self.cost = Some(cost.id),
+                _ => {}
+            }
+        }
+        let added = |before: usize, after: usize, last: &mut Option<usize>| {
+            if after > before {
+                *last = Some(after - 1);
+            } else if after < before {
+                *last = None;
+            }
+        };
+        added(before.assets.len(), after.assets.len(), &mut self.asset);
+        added(
+            before.import_rules.len(),
+            after.import_rules.len(),
+            &mut self.rule,
+        );
+    }
+}
+
+/// The command line program: parse the arguments, then run the command given, a script, or the
+/// interactive shell
+pub fn run() -> ExitCode {
+    let args = Args::parse();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        recovery::write();
+    }));
+    if let Err(e) = init_tracing(args.log_level, args.log_file.as_deref()) {
+        e.report();
+        return e.failure().exit_code();
+    }
+    let filepath = shellexpand::tilde(&args.file).to_string();
+    let color = !args.no_color
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        && std::io::stdout().is_terminal();
+    #[cfg(all(unix, feature = "daemon"))]
+    if let Some(code) = daemon::client(&args, &filepath, color) {
+        return code;
+    }
+    let session = Session {
+        storage: Box::new(JsonFile::new(filepath)),
+        autosave: args.autosave,
+        dry_run: args.dry_run,
+        config: Config::load(&shellexpand::tilde(&args.config)),
+        last_added: RefCell::default(),
+    };
+    i18n::set_language(
+        session
+            .config
+            .language
+            .unwrap_or_else(i18n::Language::from_locale),
+    );
+    let mut moneybag = match storage::open(session.storage.as_ref()) {
+        Ok(moneybag) => moneybag,
+        Err(e) => {
+            e.report();
+            return e.failure().exit_code();
+        }
+    };
+    output::set_verbosity(if args.quiet {
+        Verbosity::Quiet
+    } else if args.verbose {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    });
+    output::set_color(color);
+    recovery::saved(&moneybag, &session.storage.location());
+    if !args.command.is_empty() || args.script.is_some() || !std::io::stdin().is_terminal() {
+        recovery::remind(&session.storage.location());
+    }
+    if !args.command.is_empty() {
+        let command = session
+            .config
+            .parse(args.command)
+            .unwrap_or_else(|e| e.exit());
+        return run_once(command, &mut moneybag, &session)
+            .map_or(ExitCode::SUCCESS, Failure::exit_code);
+    }
+    match args.script {
+        Some(script) => match std::fs::read_to_string(&script) {
+            Ok(script) => run_script(
+                script.lines().map(str::to_string),
+                args.keep_going,
+                &mut moneybag,
+                &session,
+            ),
+            Err(source) => {
+                let e = MoneybagsError::Read {
+                    path: script,
+                    source,
+                };
+                e.report();
+                e.failure().exit_code()
+            }
+        },
+        None if !std::io::stdin().is_terminal() => run_script(
+            std::iter::from_fn(|| {
+                read_line("").unwrap_or_else(|e| {
+                    e.report();
+                    None
+                })
+            }),
+            args.keep_going,
+            &mut moneybag,
+            &session,
+        ),
+        None => run_shell(&mut moneybag, &session),
+    }
+}
+
+/// Print what moneybags does at the level given, to stderr or appended to a file. Off unless one
+/// of them is given, and at info if only the file is.
+fn init_tracing(level: Option<LevelFilter>, file: Option<&str>) -> Result<(), MoneybagsError> {
+    if level.is_none() && file.is_none() {
+        return Ok(());
+    }
+    let subscriber = tracing_subscriber::fmt().with_max_level(level.unwrap_or(LevelFilter::INFO));
+    let Some(file) = file else {
+        subscriber.with_writer(std::io::stderr).init();
+        return Ok(());
+    };
+    let path = shellexpand::tilde(file).to_string();
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| MoneybagsError::Io(format!("Could not open {path}: {e}")))?;
+    subscriber
+        .with_writer(std::sync::Mutex::new(file))
+        .with_ansi(false)
+        .init();
+    Ok(())
+}
+
+fn run_shell(moneybag: &mut Moneybag, session: &Session) -> ExitCode {
+    let location = session.storage.location();
+    let mut saved = serialize(moneybag);
+    if let Err(e) = cancellable(recovery::offer(moneybag, &location)) {
+        e.report();
+    }
+    if session.config.notify.on_start {
+        let notified = notify::handle_notify(
+            moneybag,
+            &session.config.notify,
+            &location,
+            false,
+            false,
+            Local::now().date_naive(),
+        );
+        if let Err(e) = notified {
+            e.report();
+        }
+    }
+    let config = rustyline::Config::builder()
+        .max_history_size(10_000)
+        .expect("Could not set history size")
+        .build();
+    let mut editor: Editor<Completion, DefaultHistory> = match Editor::with_config(config) {
+        Ok(editor) => editor,
+        Err(e) => {
+            stdin_error(e).report();
+            return Failure::Io.exit_code();
+        }
+    };
+    let history = history_path();
+    // No history yet is fine, and so is one that can't be read: it's only a convenience
+    let _ = editor.load_history(&history);
+    // The prompts read Ctrl-C as a key and cancel. This keeps it from killing the shell while
+    // something else has the terminal, like $EDITOR.
+    ctrlc::set_handler(|| {}).expect("Could not set Ctrl-C handler");
+    let mut interrupted = false;
+
+    loop {
+        editor.set_helper(Some(Completion::new(moneybag)));
+        let prompt = session
+            .config
+            .prompt(&location, serialize(moneybag) != saved);
+        let input = match editor.readline(&prompt) {
+            Ok(input) => {
+                interrupted = false;
+                input
+            }
+            Err(ReadlineError::Interrupted) if interrupted => {
+                if offer_to_save(moneybag, &saved, session) {
+                    return ExitCode::SUCCESS;
+                }
+                interrupted = false;
+                continue;
+            }
+            Err(ReadlineError::Interrupted) => {
+                println!("{}", tr!("Press Ctrl-C again to quit"));
+                interrupted = true;
+                continue;
+            }
+            Err(ReadlineError::Eof) => {
+                if serialize(moneybag) != saved {
+                    println!("{}", tr!("Exiting with unsaved changes"));
+                    return ExitCode::FAILURE;
+                }
+                return ExitCode::SUCCESS;
+            }
+            Err(e) => {
+                let e = stdin_error(e);
+                e.report();
+                return e.failure().exit_code();
+            }
+        };
+        let Some(input) = read_continuation(&mut editor, input) else {
+            continue;
+        };
+        if input.trim().is_empty() {
+            continue;
+        }
+        if let Err(e) = editor
+            .add_history_entry(&input)
+            .and_then(|_| append_history(&mut editor, &history))
+        {
+            println!("Could not save history to {}: {e}", history.display());
+        }
+        // Commands chained with semicolons run in turn, until one fails to parse
+        for line in input::split_commands(&input) {
+            let Some(words) = shlex::split(&line) else {
+                println!("Unbalanced quotes");
+                break;
+            };
+            match session.config.parse(words) {
+                Ok(Command::Quit) => {
+                    if offer_to_save(moneybag, &saved, session) {
+                        return ExitCode::SUCCESS;
+                    }
+                    break;
+                }
+                Ok(command) => run_shell_command(command, moneybag, session, &mut saved),
+                Err(e) => {
+                    println!("{e}");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Run a command in the shell, saving afterwards if autosaving, and keeping track of what was
+/// last saved
+fn run_shell_command(
+    command: Command,
+    moneybag: &mut Moneybag,
+    session: &Session,
+    saved: &mut String,
+) {
+    let saving = match command {
+        Command::Save { path } if !session.dry_run => {
+            path.filter(|path| *path != session.storage.location())
+        }
+        command => {
+            if let Err(e) = execute(command, moneybag, session, session.dry_run) {
+                e.report();
+            }
+            if !session.autosave || session.dry_run {
+                return;
+            }
+            None
+        }
+    };
+    match session.save(moneybag, saving.as_deref()) {
+        Ok(()) if saving.is_none() => *saved = serialize(moneybag),
+        Ok(()) => {}
+        Err(e) => e.report(),
+    }
+}
+
+/// Where commands typed at the prompt are kept between sessions
+fn history_path() -> PathBuf {
+    let state = std::env::var_os("XDG_STATE_HOME")
+        .filter(|state| !state.is_empty())
+        .map_or_else(
+            || PathBuf::from(shellexpand::tilde("~/.local/state").to_string()),
+            PathBuf::from,
+        );
+    state.join("moneybags").join("history")
+}
+
+fn append_history(
+    editor: &mut Editor<Completion, DefaultHistory>,
+    history: &Path,
+) -> rustyline::Result<()> {
+    if let Some(dir) = history.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    editor.append_history(history)
+}
+
+/// Read lines for as long as the input goes on over the next one. None if cancelled with Ctrl-C.
+fn read_continuation(
+    editor: &mut Editor<Completion, DefaultHistory>,
+    mut input: String,
+) -> Option<String> {
+    while input::continues(&input) {
+        match editor.readline("... ") {
+            Ok(next) => input = input::join_lines(&input, &next),
+            Err(ReadlineError::Interrupted) => return None,
+            Err(_) => break,
+        }
+    }
+    Some(input)
+}
+
+/// Ask whether to save before quitting, if there are unsaved changes. Returns false if the
+/// question was cancelled with Ctrl-C, to stay in the shell.
+fn offer_to_save(moneybag: &Moneybag, saved: &str, session: &Session) -> bool {
+    if serialize(moneybag) == saved {
+        return true;
+    }
+    let location = session.storage.location();
+    match cancellable(confirm(&tr!("Save changes to {file}?", file = location))) {
+        Ok(Some(true)) => match session.save(moneybag, None) {
+            Ok(()) => true,
+            Err(e) => {
+                e.report();
+                false
+            }
+        },
+        Ok(Some(false)) => true,
+        Ok(None) => false,
+        Err(e) => {
+            e.report();
+            true
+        }
+    }
+}
+
+/// Run a single command given on the command line, saving any changes. Returns how it failed, if
+/// it did, after printing why.
+fn run_once(command: Command, moneybag: &mut Moneybag, session: &Session) -> Option<Failure> {
+    let saved = serialize(moneybag);
+    let mut failure = None;
+    if let Err(e) = run_session_command(command, moneybag, session) {
+        e.report();
+        failure = Some(e.failure());
+    }
+    if serialize(moneybag) != saved {
+        if let Err(e) = session.save(moneybag, None) {
+            e.report();
+            failure = Some(e.failure());
+        }
+    }
+    failure
+}
+
+/// Run commands line by line, saving any changes at the end. Unless `keep_going` is set, stops
+/// at the first line that fails to parse, without saving.
+fn run_script(
+    lines: impl Iterator<Item = String>,
+    keep_going: bool,
+    moneybag: &mut Moneybag,
+    session: &Session,
+) -> ExitCode {
+    let saved = serialize(moneybag);
+    let mut failed = None;
+    let mut lines = lines.enumerate();
+    while let Some((number, line)) = lines.next() {
+        let mut line = line.trim().to_string();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        while input::continues(&line) {
+            match lines.next() {
+                Some((_, next)) => line = input::join_lines(&line, next.trim()),
+                None => break,
+            }
+        }
+        match run_script_line(&line, number + 1, moneybag, session) {
+            Ok(true) => continue,
+            Ok(false) => break,
+            Err(e) => {
+                e.report();
+                failed = Some(e.failure());
+            }
+        }
+        if !keep_going {
+            println!("{}", tr!("Stopping, nothing saved"));
+            return failed.map_or(ExitCode::FAILURE, Failure::exit_code);
+        }
+    }
+    if serialize(moneybag) != saved {
+        if let Err(e) = session.save(moneybag, None) {
+            e.report();
+            return Failure::Io.exit_code();
+        }
+    }
+    failed.map_or(ExitCode::SUCCESS, Failure::exit_code)
+}
+
+/// Run the commands on a script line, which can be several separated by semicolons. Returns
+/// false if one quit, and stops at the first that fails.
+fn run_script_line(
+    line: &str,
+    number: usize,
+    moneybag: &mut Moneybag,
+    session: &Session,
+) -> Result<bool, MoneybagsError> {
+    for command in input::split_commands(line) {
+        match shlex::split(&command).map(|words| session.config.parse(words)) {
+            Some(Ok(command)) => {
+                if !run_session_command(command, moneybag, session)? {
+                    return Ok(false);
+                }
+            }
+            Some(Err(e)) if e.kind() == ErrorKind::DisplayHelp => println!("{e}"),
+            Some(Err(e)) => return Err(MoneybagsError::Usage(format!("line {number}: {e}"))),
+            None => {
+                return Err(MoneybagsError::Usage(format!(
+                    "line {number}: unbalanced quotes"
+                )))
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Parse and run a command given as words, e.g. `["add", "cost", "2025-05", "100", "rent"]`, on a
+/// moneybag in memory, and return what it has to show. Commands that only print, like the
+/// forecast, print to stdout and return [`CommandOutput::Nothing`]. Saving is left to the caller.
+/// Each command is a session of its own, so `last` refers to nothing.
+///
+/// # Errors
+///
+/// If the words don't parse as a command, the command only works in the shell, or the command
+/// fails, e.g. on an invoice that doesn't exist, with why. A command that returns an error leaves
+/// the moneybag as it was.
+pub fn run_command(
+    words: Vec<String>,
+    moneybag: &mut Moneybag,
+) -> Result<CommandOutput, MoneybagsError> {
+    let config = Config::default();
+    let command = config
+        .parse(words)
+        .map_err(|e| MoneybagsError::Usage(e.to_string()))?;
+    if matches!(
+        command,
+        Command::Quit | Command::Preview { .. } | Command::Save { path: None }
+    ) {
+        return Err(MoneybagsError::Usage(
+            "quit, preview, and save without a path only work in the shell".to_string(),
+        ));
+    }
+
+    let _span = tracing::info_span!("command", ?command).entered();
+    tracing::info!("Running command");
+    let mut changed = moneybag.clone();
+    let output = handle_command(command, &mut changed, &config, &LastAdded::default())?;
+    events::record(moneybag, changed)?;
+    Ok(output)
+}
+
+/// Run a command outside the shell. Returns false if it was quit.
+fn run_session_command(
+    command: Command,
+    moneybag: &mut Moneybag,
+    session: &Session,
+) -> Result<bool, MoneybagsError> {
+    match command {
+        Command::Quit => return Ok(false),
+        Command::Save { path } if !session.dry_run => session.save(moneybag, path.as_deref())?,
+        #[cfg(all(unix, feature = "daemon"))]
+        Command::Daemon => daemon::run_daemon(moneybag, session)?,
+        #[cfg(not(unix))]
+        Command::Daemon => {
+            return Err(MoneybagsError::Invalid(
+                "The daemon only runs on Unix".to_string(),
+            ))
+        }
+        #[cfg(all(unix, not(feature = "daemon")))]
+        Command::Daemon => return Err(without("daemon")),
+        #[cfg(not(feature = "server"))]
+        Command::Serve { .. } => return Err(without("server")),
+        #[cfg(feature = "server")]
+        Command::Serve { listen, token } => {
+            match token.or_else(|| std::env::var("MONEYBAGS_TOKEN").ok()) {
+                Some(token) if !token.is_empty() => {
+                    server::serve(&listen, &token, moneybag, session)?;
+                }
+                _ => {
+                    return Err(MoneybagsError::Invalid(
+                        "No token, give --token or set MONEYBAGS_TOKEN".to_string(),
+                    ))
+                }
+            }
+        }
+        command => execute(command, moneybag, session, session.dry_run)?,
+    }
+    Ok(true)
+}
+
+/// Handle a command. In a dry run, it is handled on a copy of the moneybag, and the changes it
+/// would have made are printed instead. A command that returns an error leaves the moneybag as
+/// it was.
+fn execute(
+    command: Command,
+    moneybag: &mut Moneybag,
+    session: &Session,
+    dry_run: bool,
+) -> Result<(), MoneybagsError> {
+    let _span = tracing::info_span!("command", ?command, dry_run).entered();
+    tracing::info!("Running command");
+    let config = &session.config;
+    match command {
+        Command::Preview { command } => match config.parse(command) {
+            Ok(command) => execute(command, moneybag, session, true)?,
+            Err(e) => return Err(MoneybagsError::Usage(e.to_string())),
+        },
+        Command::Save { .. } if dry_run => println!("Dry run, not saving"),
+        #[cfg(feature = "scripts")]
+        Command::Script { name, args } => {
+            script::handle_script(name.as_deref(), &args, config, moneybag)?;
+        }
+        #[cfg(not(feature = "scripts"))]
+        Command::Script { .. } => return Err(without("scripts")),
+        Command::Stats(StatsCommand::File) => {
+            stats::print_file_stats(moneybag, &session.storage.location());
+        }
+        Command::Notify { all, list } => notify::handle_notify(
+            moneybag,
+            &config.notify,
+            &session.storage.location(),
+            all,
+            list || dry_run,
+            Local::now().date_naive(),
+        )?,
+        Command::Sync { path, keep } => match path.or_else(|| config.sync.clone()) {
+            Some(path) => {
+                sync::handle_sync(&shellexpand::tilde(&path), keep, moneybag, session, dry_run)?;
+            }
+            None => {
+                return Err(MoneybagsError::Invalid(
+                    "No copy to sync with, give one or set sync in the config".to_string(),
+                ))
+            }
+        },
+        // Sending reminders can't be undone, so a dry run only lists them
+        Command::Remind { every, .. } if dry_run => {
+            execute(
+                Command::Remind { every, list: true },
+                moneybag,
+                session,
+                false,
+            )?;
+        }
+        command if dry_run => {
+            let mut preview = moneybag.clone();
+            let last_added = &session.last_added.borrow();
+            let output = handle_command(command, &mut preview, config, last_added);
+            if let Some(output) = cancellable(output)? {
+                render(&output, &preview);
+                print_changes(moneybag, &preview);
+            }
+        }
+        command => {
+            recovery::keep(moneybag);
+            let mut changed = moneybag.clone();
+            let output = {
+                let last_added = session.last_added.borrow();
+                handle_command(command, &mut changed, config, &last_added)
+            };
+            let Some(output) = cancellable(output)? else {
+                return Ok(());
+            };
+            let before = events::record(moneybag, changed)?;
+            render(&output, moneybag);
+            if output::verbosity() == Verbosity::Verbose {
+                for change in dry_run::changes(&before, moneybag) {
+                    println!("{change}");
+                }
+            }
+            if let Some(logged) = moneybag.log.get(before.log.len()) {
+                session
+                    .last_added
+                    .borrow_mut()
+                    .note(&logged.events, &before, moneybag);
+                config.hooks.after_command(&before, &logged.events);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print what a command has to show as text
+fn render(output: &CommandOutput, moneybag: &Moneybag) {
+    match output {
+        CommandOutput::Nothing => {}
+        CommandOutput::Messages(messages) => {
+            for message in messages {
+                note(message);
+            }
+        }
+        CommandOutput::Invoices { invoices, running } => {
+            list_invoices(invoices.iter(), *running, moneybag);
+        }
+        CommandOutput::Costs { costs, running } => list_costs(costs.iter(), *running),
+        CommandOutput::Transactions(transactions) => list_transactions(transactions),
+        CommandOutput::Balance(balance) => print_balance(balance),
+        CommandOutput::Budgets(statuses) => {
+            for status in statuses {
+                println!("{status}");
+            }
+        }
+        CommandOutput::Found {
+            invoices,
+            costs,
+            rates,
+        } => {
+            for invoice in invoices {
+                println!("invoice {}: {invoice}", invoice.id);
+            }
+            for cost in costs {
+                println!("cost {}: {cost}", cost.id);
+            }
+            for (name, rate) in rates {
+                println!("rate {name}: {rate}");
+            }
+        }
+        CommandOutput::Invoice(invoice) => show_invoice(invoice, moneybag),
+        CommandOutput::Cost(cost) => show_cost(cost),
+        CommandOutput::Customer { name, customer } => show_customer(name, customer),
+        CommandOutput::Report(report) => report.print(),
+        CommandOutput::Added(added) => {
+            if output::verbosity() > Verbosity::Quiet {
+                print_added(added, moneybag);
+            }
+        }
+        CommandOutput::Forecast(forecast) => forecast.print(),
+        CommandOutput::Dashboard(dashboard) => dashboard.print(),
+        CommandOutput::Goal(progress) => progress.print(),
+        CommandOutput::Query(result) => result.print(),
+        CommandOutput::InvoiceStats(stats) => stats.print(),
+        CommandOutput::Reminders {
+            reminders,
+            unreachable,
+        } => {
+            for message in unreachable {
+                note(message);
+            }
+            if reminders.is_empty() {
+                note("No overdue invoices to remind of");
+            }
+            for reminder in reminders {
+                println!(
+                    "Invoice {} to {}: {}",
+                    reminder.invoice, reminder.to, reminder.subject
+                );
+                for line in reminder.body.lines() {
+                    println!("{}", format!("    {line}").trim_end());
+                }
+            }
+        }
+        CommandOutput::ExchangeRates { date, rates } => {
+            note(&format!("Reference rates of {date} from the ECB"));
+            for rate in rates {
+                println!("{rate}");
+            }
+        }
+    }
+}
+
+/// Snapshot of the moneybag, to tell whether there are unsaved changes
+fn serialize(moneybag: &Moneybag) -> String {
+    serde_json::to_string(moneybag)
+        .unwrap_or_else(|_| panic!("Could not serialize moneybag. Contents: {moneybag:?}"))
+}
+
+/// Read a moneybag from a JSON file, or start an empty one if there is no file, along with its
+/// log. See [`storage::open`] for how it's brought up to date.
+///
+/// # Errors
+///
+/// If the file or the log can't be read, or isn't a moneybag or a log
+pub fn load_moneybag(filepath: &str) -> Result<Moneybag, MoneybagsError> {
+    storage::open(&JsonFile::new(filepath))
+}
+
+/// Write a moneybag to a JSON file, and its log next to it, replacing what was there
+///
+/// # Errors
+///
+/// If a file can't be written
+pub fn save_moneybag(moneybag: &Moneybag, filepath: &str) -> Result<(), MoneybagsError> {
+    JsonFile::new(filepath).save(moneybag)
+}
+
+/// Replace the rate and customer names a command refers to with the full names they resolve to.
+/// Fails with suggestions if one doesn't resolve.
+fn resolve_names(command: &mut Command, moneybag: &Moneybag) -> Result<(), MoneybagsError> {
+    let mut rates = vec![];
+    let mut customers = vec![];
+    match command {
+        Command::Add(AddCommand::Invoice(invoice_args)) => {
+            rates.extend(invoice_args.rate.as_mut());
+            customers.extend(invoice_args.customer.as_mut());
+        }
+        Command::Add(AddCommand::Cost(cost_args)) => {
+            customers.extend(cost_args.billable.as_mut());
+            customers.extend(cost_args.customer.as_mut());
+        }
+        Command::Add(AddCommand::Tier { rate, .. })
+        | Command::Delete(DeleteArgs {
+            command: DeleteCommand::Tier { rate, .. },
+            ..
+        }) => rates.push(rate),
+        Command::Edit(EditCommand::Rate { name, .. })
+        | Command::Delete(DeleteArgs {
+            command: DeleteCommand::Rate { name },
+            ..
+        }) => rates.push(name),
+        Command::Edit(EditCommand::Customer { name, .. })
+        | Command::Delete(DeleteArgs {
+            command: DeleteCommand::Customer { name },
+            ..
+        })
+        | Command::Show(ShowCommand::Customer { name }) => customers.push(name),
+        _ => {}
+    }
+    for rate in rates {
+        match resolve_name(moneybag.rates.keys(), rate) {
+            Ok(name) => *rate = name,
+            Err(candidates) => {
+                let message = tr!("Rate {name} not found in rates", name = rate);
+                return Err(MoneybagsError::NotFound(
+                    message + did_you_mean(&candidates).as_str(),
+                ));
+            }
+        }
+    }
+    for customer in customers {
+        match resolve_name(moneybag.customers.keys(), customer) {
+            Ok(name) => *customer = name,
+            Err(candidates) => {
+                let message = tr!("Customer {name} not found", name = customer);
+                return Err(MoneybagsError::NotFound(
+                    message + did_you_mean(&candidates).as_str(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replace an invoice or cost ID with the index of the entry that has it, and `last` with the
+/// index of the entry added last in the session. Fails unless there is such an entry.
+fn resolve_index(
+    command: &mut Command,
+    moneybag: &Moneybag,
+    last_added: &LastAdded,
+) -> Result<(), MoneybagsError> {
+    let (index, numbers, entry, last): (_, Vec<usize>, _, _) = match command {
+        Command::Show(ShowCommand::Invoice { index })
+        | Command::Edit(EditCommand::Invoice { index, .. })
+        | Command::Delete(DeleteArgs {
+            command: DeleteCommand::Invoice { index },
+            ..
+        })
+        | Command::Bill { invoice: index, .. }
+        | Command::Pay { invoice: index, .. } => (
+            index,
+            moneybag.invoices.iter().map(|invoice| invoice.id).collect(),
+            tr!("invoice"),
+            last_added.invoice,
+        ),
+        Command::Show(ShowCommand::Cost { index })
+        | Command::Edit(EditCommand::Cost { index, .. })
+        | Command::Delete(DeleteArgs {
+            command: DeleteCommand::Cost { index },
+            ..
+        }) => (
+            index,
+            moneybag.costs.iter().map(|cost| cost.id).collect(),
+            tr!("cost"),
+            last_added.cost,
+        ),
+        Command::Edit(EditCommand::Asset { index, .. })
+        | Command::Delete(DeleteArgs {
+            command: DeleteCommand::Asset { index },
+            ..
+        }) => (
+            index,
+            (0..moneybag.assets.len()).collect(),
+            tr!("asset"),
+            last_added.asset,
+        ),
+        Command::Delete(DeleteArgs {
+            command: DeleteCommand::Rule { index },
+            ..
+        }) => (
+            index,
+            (0..moneybag.import_rules.len()).collect(),
+            tr!("rule"),
+            last_added.rule,
+        ),
+        _ => return Ok(()),
+    };
+    if *index == LAST {
+        let Some(last) = last else {
+            return Err(MoneybagsError::NotFound(tr!(
+                "No {entry} added this session to refer to as last",
+                entry = entry
+            )));
+        };
+        *index = last;
+    }
+    let Some(position) = numbers.iter().position(|number| number == index) else {
+        return Err(MoneybagsError::NotFound(not_found(entry, *index, &numbers)));
+    };
+    *index = position;
+    Ok(())
+}
+
+/// Message that there is no entry with a number, saying which numbers there are
+fn not_found(entry: &str, number: usize, numbers: &[usize]) -> String {
+    match (numbers.iter().min(), numbers.iter().max()) {
+        (Some(first), Some(last)) => tr!(
+            "No {entry} {number} (have {first}–{last})",
+            entry = entry,
+            number = number,
+            first = first,
+            last = last
+        ),
+        _ => tr!(
+            "No {entry} {number}, there are none",
+            entry = entry,
+            number = number
+        ),
+    }
+}
+
+fn did_you_mean(candidates: &[String]) -> String {
+    if candidates.is_empty() {
+        String::new()
+    } else {
+        tr!(", did you mean {names}?", names = candidates.join(", "))
+    }
+}
+
+fn handle_command(
+    mut command: Command,
+    moneybag: &mut Moneybag,
+    config: &Config,
+    last_added: &LastAdded,
+) -> Result<CommandOutput, MoneybagsError> {
+    resolve_names(&mut command, moneybag)?;
+    resolve_index(&mut command, moneybag, last_added)?;
+    let output = match command {
+        Command::List(list_args) => handle_list(&list_args, moneybag),
+        Command::Balance { ref filter, json } => {
+            let balance = balance(filter, moneybag);
+            if json {
+                print_json(&balance);
+                CommandOutput::Nothing
+            } else {
+                CommandOutput::Balance(balance)
+            }
+        }
+        Command::Budget { date } => {
+            let date = date.unwrap_or_else(|| Local::now().format("%Y-%m-%d").to_string());
+            let statuses = moneybag
+                .budgets
+                .keys()
+                .filter_map(|category| moneybag.budget_status(category, &date))
+                .collect();
+            CommandOutput::Budgets(statuses)
+        }
+        Command::Bill { invoice, costs } => bill_expenses(invoice, &costs, moneybag)?,
+        Command::Search { term } => search(&term, moneybag),
+        Command::Add(add_command) => {
+            let before = moneybag.clone();
+            handle_add(add_command, moneybag)?;
+            CommandOutput::Added(added(&before, moneybag))
+        }
+        Command::Show(show_command) => handle_show(show_command, moneybag)?,
+        Command::Report(report_args) => report::handle_report(&report_args, moneybag)?,
+        Command::Edit(edit_command) => {
+            handle_edit(edit_command.clone(), moneybag)?;
+            CommandOutput::Messages(vec![edited(&edit_command, moneybag)])
+        }
+        Command::Delete(delete_args) => handle_delete(delete_args, moneybag)?,
+        Command::Adjust { from, change, name } => {
+            handle_adjust(from, change, name.as_deref(), moneybag)
+        }
+        Command::Save { path: Some(path) } => {
+            save_moneybag(moneybag, &path)?;
+            CommandOutput::Nothing
+        }
+        Command::Forecast { months, scope } => CommandOutput::Forecast(forecast::Forecast::of(
+            months,
+            scope,
+            moneybag,
+            Local::now().date_naive(),
+        )),
+        Command::Dashboard => CommandOutput::Dashboard(dashboard::Dashboard::of(
+            moneybag,
+            Local::now().date_naive(),
+        )),
+        Command::Goal => match goal::Progress::of(moneybag, Local::now().date_naive()) {
+            Some(progress) => CommandOutput::Goal(progress),
+            None => {
+                return Err(MoneybagsError::NotFound(
+                    "No revenue goal, set one with set revenue-goal".to_string(),
+                ))
+            }
+        },
+        Command::Export(export_command) => {
+            export::handle_export(&export_command, moneybag, config)?
+        }
+        Command::Remind { every, list } => remind::handle_remind(
+            every,
+            list,
+            moneybag,
+            &config.email,
+            Local::now().date_naive(),
+        )?,
+        Command::Query { ref query } => CommandOutput::Query(query::handle_query(query, moneybag)?),
+        Command::Stats(StatsCommand::Invoices { period, customer }) => CommandOutput::InvoiceStats(
+            stats::invoice_stats(period.as_deref(), customer.as_deref(), moneybag)?,
+        ),
+        #[cfg(feature = "tui")]
+        Command::Tui => {
+            tui::handle_tui(moneybag)?;
+            CommandOutput::Nothing
+        }
+        Command::Examples { topic } => {
+            examples::print_examples(topic);
+            CommandOutput::Nothing
+        }
+        Command::Import(import_command) => import::handle_import(import_command, moneybag, config)?,
+        Command::Pay { invoice, date } => {
+            pay(invoice, date, moneybag)?;
+            CommandOutput::Nothing
+        }
+        Command::Set(set_command) => {
+            handle_set(set_command, moneybag)?;
+            CommandOutput::Nothing
+        }
+        Command::Fx(FxCommand::Update {
+            currencies,
+            offline,
+        }) => fx::handle_update(&currencies, offline, moneybag)?,
+        command => return Err(elsewhere(&command)),
+    };
+    Ok(output)
+}
+
+/// The error of a command that only runs from the shell or the command line, or isn't in this
+/// build, run where it can't be
+fn elsewhere(command: &Command) -> MoneybagsError {
+    let message = match command {
+        Command::Script { .. } => {
+            "scripts only run from the shell, script files, or the command line"
+        }
+        Command::Serve { .. } => "serve only works from the command line, e.g. moneybags serve",
+        Command::Daemon => "daemon only works from the command line, e.g. moneybags daemon",
+        Command::Save { .. } => "save needs a file to save to here, e.g. save moneybag.json",
+        Command::Quit => "quit only works in the shell",
+        Command::Preview { .. } => "preview only works from the shell or the command line",
+        Command::Stats(StatsCommand::File) => {
+            "stats file only works from the shell or the command line"
+        }
+        Command::Notify { .. } => "notify only works from the shell or the command line",
+        Command::Sync { .. } => "sync only works from the shell or the command line",
+        #[cfg(not(feature = "tui"))]
+        Command::Tui => return without("tui"),
+        _ => "This command can't run here",
+    };
+    MoneybagsError::Invalid(message.to_string())
+}
+
+fn pay(
+    index: usize,
+    date: Option<NaiveDate>,
+    moneybag: &mut Moneybag,
+) -> Result<(), MoneybagsError> {
+    let invoice = Invoice {
+        paid: Some(date.unwrap_or_else(|| Local::now().date_naive())),
+        ..invoice_at(index, moneybag)?
+    };
+    moneybag.change(Event::InvoiceEdited { invoice });
+    Ok(())
+}
+
+fn handle_adjust(
+    from: NaiveDate,
+    change: PercentOrMoney,
+    name: Option<&str>,
+    moneybag: &mut Moneybag,
+) -> CommandOutput {
+    let from = from.format("%Y-%m").to_string();
+    let adjusted: Vec<_> = moneybag
+        .costs
+        .iter()
+        .filter(|cost| {
+            let matches = match name {
+                Some(name) => cost.name == name,
+                None => cost.recurring,
+            };
+            matches && cost.date >= from
+        })
+        .map(|cost| Cost {
+            amount: change.apply(cost.amount),
+            ..cost.clone()
+        })
+        .collect();
+    let count = adjusted.len();
+    for cost in adjusted {
+        moneybag.change(Event::CostEdited { cost });
+    }
+    CommandOutput::Messages(vec![format!("Adjusted {count} costs by {change}")])
+}
+
+fn balance(filter: &BalanceFilter, moneybag: &Moneybag) -> Balance {
+    let costs: Vec<_> = moneybag
+        .costs
+        .iter()
+        .filter(|cost| filter.cost(cost))
+        .collect();
+    let invoices: Vec<_> = moneybag
+        .invoices
+        .iter()
+        .filter(|invoice| filter.invoice(invoice))
+        .collect();
+    let input_vat = sum_input_vat(costs.iter().copied());
+    let costs = sum_costs(costs);
+    let this_month = Local::now().format("%Y-%m").to_string();
+    let depreciation = if filter.assets() {
+        let through = |month: &str| sum_depreciation(&moneybag.assets, month.min(&this_month));
+        match filter.year {
+            Some(year) => through(&format!("{year}-12")) - through(&format!("{}-12", year - 1)),
+            None => through(&this_month),
+        }
+    } else {
+        Money::default()
+    };
+    let average = average_invoice(invoices.iter().copied());
+    let invoice_sum = sum_invoices(invoices.iter().copied());
+    let total = invoice_sum - costs - depreciation;
+    let mut currencies: BTreeMap<String, CurrencyTotal> = BTreeMap::new();
+    for invoice in &invoices {
+        if let Some(currency) = invoice.currency() {
+            let currency = currencies.entry(currency.to_string()).or_default();
+            currency.total = currency.total + invoice.total();
+            currency.base_total = currency.base_total + invoice.base_total();
+        }
+    }
+    Balance {
+        costs,
+        input_vat,
+        depreciation,
+        invoices: invoice_sum,
+        total,
+        average_invoice: average,
+        invoices_to_break_even: (!average.is_zero()).then(|| -total / average),
+        currencies,
+        goal: goal::Progress::of(moneybag, Local::now().date_naive())
+            .filter(|_| filter.is_empty())
+            .map(|progress| progress.summary()),
+        exceeded_budgets: moneybag.exceeded_budgets(),
+    }
+}
+
+fn print_balance(balance: &Balance) {
+    let total_style = if balance.total.is_negative() {
+        Style::Red
+    } else {
+        Style::Bold
+    };
+    print_field("Costs", balance.costs);
+    print_field("Input VAT", balance.input_vat);
+    print_field("Depreciation", balance.depreciation);
+    print_field("Invoices", balance.invoices);
+    println!(
+        "{}",
+        total_style.paint(&format!("{}: {}", tr!("Total"), balance.total))
+    );
+    print_field("Average invoice", balance.average_invoice);
+    if let Some(invoices) = balance.invoices_to_break_even {
+        print_field("Invoices left to break even", invoices);
+    }
+    for (currency, CurrencyTotal { total, base_total }) in &balance.currencies {
+        println!(
+            "{}: {total} ({base_total})",
+            tr!("Invoiced in {currency}", currency = currency)
+        );
+    }
+    if let Some(goal) = &balance.goal {
+        print_field("Goal", goal);
+    }
+    for status in &balance.exceeded_budgets {
+        println!(
+            "{}",
+            Style::Yellow.paint(&format!("{}: {status}", tr!("Warning")))
+        );
+    }
+}
+
+/// Invoices matching on customer or number, costs on name, category, or note, and rates on name
+fn search(term: &str, moneybag: &Moneybag) -> CommandOutput {
+    let term = term.to_lowercase();
+    let matches =
+        |field: Option<&String>| field.is_some_and(|field| field.to_lowercase().contains(&term));
+    CommandOutput::Found {
+        invoices: moneybag
+            .invoices
+            .iter()
+            .filter(|invoice| {
+                matches(invoice.customer.as_ref()) || matches(invoice.number.as_ref())
+            })
+            .cloned()
+            .collect(),
+        costs: moneybag
+            .costs
+            .iter()
+            .filter(|cost| {
+                matches(Some(&cost.name))
+                    || matches(cost.category.as_ref())
+                    || matches(cost.note.as_ref())
+            })
+            .cloned()
+            .collect(),
+        rates: moneybag
+            .rates
+            .iter()
+            .filter(|(name, _)| matches(Some(name)))
+            .map(|(name, rate)| (name.clone(), rate.clone()))
+            .collect(),
+    }
+}
+
+fn handle_show(
+    show_command: ShowCommand,
+    moneybag: &Moneybag,
+) -> Result<CommandOutput, MoneybagsError> {
+    Ok(match show_command {
+        ShowCommand::Invoice { index } => CommandOutput::Invoice(invoice_at(index, moneybag)?),
+        ShowCommand::Cost { index } => CommandOutput::Cost(cost_at(index, moneybag)?),
+        ShowCommand::Customer { name } => {
+            let customer = moneybag
+                .customers
+                .get(&name)
+                .cloned()
+                .ok_or_else(|| customer_not_found(&name))?;
+            CommandOutput::Customer { name, customer }
+        }
+    })
+}
+
+fn show_invoice(invoice: &Invoice, moneybag: &Moneybag) {
+    if let Some(number) = &invoice.number {
+        print_field("number", number);
+    }
+    print_field("date", &invoice.date);
+    let currency = invoice.currency_suffix();
+    match &invoice.rate {
+        Some(rate) => {
+            print_field("hours", invoice.amount);
+            match &invoice.rate_name {
+                Some(name) => print_field("rate", format!("{name} ({rate})")),
+                None => print_field("rate", rate),
+            }
+            for (tier, hours) in &invoice.tier_hours {
+                println!(
+                    "{}: {hours} * {}{currency}",
+                    tr!("{tier} hours", tier = tier),
+                    rate.tier_rate(tier)
+                );
+            }
+        }
+        None => print_field("amount", invoice.amount),
+    }
+    for expense in &invoice.expenses {
+        print_field(
+            "expense",
+            format!("{} {}{currency}", expense.name, expense.amount),
+        );
+    }
+    print_field("total", format!("{}{currency}", invoice.total()));
+    if let Some(exchange_rate) = invoice.exchange_rate {
+        print_field("exchange rate", exchange_rate);
+        print_field("base total", invoice.base_total());
+    }
+    if let Some(vat) = invoice.vat {
+        print_field("vat", format!("{} ({vat}%)", invoice.vat()));
+    }
+    if let Some(customer) = &invoice.customer {
+        print_field("customer", customer);
+    }
+    print_field("scope", invoice.scope);
+    if let Some(reference) = &invoice.reference {
+        print_field("reference", reference);
+    }
+    if let Some(reminded) = invoice.reminded {
+        print_field("reminded", reminded);
+    }
+    if let Some(due) = moneybag.due_date(invoice) {
+        print_field("due", due);
+    }
+    match invoice.paid {
+        Some(paid) => print_field("status", tr!("paid {date}", date = paid)),
+        None if moneybag.is_overdue(invoice, Local::now().date_naive()) => {
+            print_field("status", Style::Red.paint(tr!("overdue")));
+        }
+        None => print_field("status", tr!("unpaid")),
+    }
+}
+
+fn show_cost(cost: &Cost) {
+    print_field("date", &cost.date);
+    print_field("amount", cost.amount);
+    if let Some(vat) = cost.vat {
+        print_field("vat", format!("{} ({vat})", cost.vat()));
+    }
+    print_field("name", &cost.name);
+    if let Some(category) = &cost.category {
+        print_field("category", category);
+    }
+    print_field("scope", cost.scope);
+    if cost.recurring {
+        print_field("recurring", tr!("monthly"));
+    }
+    if let Some(subscription) = &cost.subscription {
+        print_field(
+            "subscription",
+            tr!(
+                "{interval}, renews {date}",
+                interval = subscription.interval,
+                date = subscription.renews
+            ),
+        );
+    }
+    if let Some(billable) = &cost.billable {
+        let status = if billable.billed {
+            tr!("billed")
+        } else {
+            tr!("not billed")
+        };
+        print_field("billable to", format!("{} ({status})", billable.customer));
+    }
+    if let Some(customer) = &cost.customer {
+        print_field("for customer", customer);
+    }
+    if let Some(note) = &cost.note {
+        print_field("note", note);
+    }
+    if let Some(reference) = &cost.reference {
+        print_field("reference", reference);
+    }
+}
+
+fn show_customer(name: &str, customer: &Customer) {
+    print_field("name", name);
+    if let Some(address) = &customer.address {
+        print_field("address", address);
+    }
+    if let Some(email) = &customer.email {
+        print_field("email", email);
+    }
+    if let Some(org_number) = &customer.org_number {
+        print_field("org number", org_number);
+    }
+    if let Some(vat_number) = &customer.vat_number {
+        print_field("vat number", vat_number);
+    }
+    if let Some(days) = customer.payment_terms {
+        print_field("payment terms", tr!("{days} days", days = days));
+    }
+    if let Some(prefix) = &customer.invoice_prefix {
+        print_field("invoice prefix", prefix);
+    }
+    if let Some(note) = &customer.note {
+        print_field("note", note);
+    }
+}
+
+fn bill_expenses(
+    invoice_index: usize,
+    cost_ids: &[usize],
+    moneybag: &mut Moneybag,
+) -> Result<CommandOutput, MoneybagsError> {
+    let ids: Vec<_> = moneybag.costs.iter().map(|cost| cost.id).collect();
+    if let Some(id) = cost_ids.iter().find(|id| !ids.contains(id)) {
+        return Err(MoneybagsError::NotFound(not_found(tr!("cost"), *id, &ids)));
+    }
+    let Some(invoice) = moneybag.invoices.get(invoice_index) else {
+        return Err(MoneybagsError::NotFound(tr!(
+            "Invoice {index} not found",
+            index = invoice_index
+        )));
+    };
+    let Some(customer) = &invoice.customer else {
+        return Err(MoneybagsError::Invalid(
+            "Invoice has no customer to bill expenses to".to_string(),
+        ));
+    };
+    if let Some(rate) = invoice
+        .exchange_rate
+        .filter(|rate| rate.is_zero() || rate.is_negative())
+    {
+        return Err(MoneybagsError::Invalid(format!(
+            "Invoice has an exchange rate of {rate}, expenses can't be converted to its currency"
+        )));
+    }
+    let unbilled = |cost: &Cost| {
+        cost.billable
+            .as_ref()
+            .is_some_and(|billable| !billable.billed && &billable.customer == customer)
+    };
+    // Every cost asked for is checked before any is billed, so none are if one can't be
+    if let Some(cost) = moneybag
+        .costs
+        .iter()
+        .find(|cost| cost_ids.contains(&cost.id) && !unbilled(cost))
+    {
+        return Err(MoneybagsError::Invalid(format!(
+            "Cost {} is not an unbilled expense for {customer}",
+            cost.id
+        )));
+    }
+    let mut invoice = invoice.clone();
+    let mut costs = vec![];
+    let mut billed = vec![];
+    for cost in &moneybag.costs {
+        if !unbilled(cost) || (!cost_ids.is_empty() && !cost_ids.contains(&cost.id)) {
+            continue;
+        }
+        // Expenses are in the base currency, the invoice might not be
+        let amount = match invoice.exchange_rate {
+            Some(exchange_rate) => cost.net() / exchange_rate,
+            None => cost.net(),
+        };
+        invoice.expenses.push(Expense {
+            name: cost.name.clone(),
+            amount,
+            cost: Some(cost.id),
+        });
+        let mut cost = cost.clone();
+        if let Some(billable) = &mut cost.billable {
+            billable.billed = true;
+        }
+        billed.push(format!("Billed {}: {} {}", cost.id, cost.name, amount));
+        costs.push(cost);
+    }
+    moneybag.change(Event::InvoiceEdited { invoice });
+    for cost in costs {
+        moneybag.change(Event::CostEdited { cost });
+    }
+    Ok(CommandOutput::Messages(billed))
+}
+
+fn handle_set(set_command: SetCommand, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    let mut settings = moneybag.settings.clone();
+    match set_command {
+        SetCommand::MileageRate { rate } => settings.mileage_rate = rate,
+        SetCommand::ExchangeRate { currency, rate } => {
+            if rate.is_zero() || rate.is_negative() {
+                return Err(MoneybagsError::Invalid(format!(
+                    "An exchange rate must be more than zero, not {rate}"
+                )));
+            }
+            settings
+                .exchange_rates
+                .insert(currency.to_uppercase(), rate);
+        }
+        SetCommand::BaseCurrency { currency } => {
+            settings.base_currency = Some(currency.to_uppercase());
+        }
+        SetCommand::PerDiem { rate } => settings.per_diem = rate,
+        SetCommand::TaxRate { rate } => settings.tax_rate = rate,
+        SetCommand::RevenueGoal { amount, interval } => {
+            settings.revenue_goal = Some(amount * interval.per_year());
+        }
+        SetCommand::HoursTarget { hours } => settings.hours_target = Some(hours),
+        SetCommand::SocialFees { rate } => settings.social_fees = rate,
+        SetCommand::InvoicePrefix { prefix } => settings.invoice_prefix = Some(prefix),
+        SetCommand::BankColumns {
+            date,
+            description,
+            amount,
+            delimiter,
+        } => {
+            settings.bank_columns = BankColumns {
+                date,
+                description,
+                amount,
+                delimiter,
+            };
+        }
+    }
+    moneybag.change(Event::SettingsChanged { settings });
+    Ok(())
+}
+
+fn handle_delete(
+    delete_args: DeleteArgs,
+    moneybag: &mut Moneybag,
+) -> Result<CommandOutput, MoneybagsError> {
+    let DeleteArgs { force, command } = delete_args;
+    // Deleted from a copy, kept if confirmed
+    let mut deleted = moneybag.clone();
+    let entry = delete(command, &mut deleted)?;
+    if !force && !confirm(&tr!("Delete {entry}?", entry = entry))? {
+        return Ok(CommandOutput::Nothing);
+    }
+    *moneybag = deleted;
+    Ok(CommandOutput::Messages(vec![format!("Deleted {entry}")]))
+}
+
+/// Delete an entry, returning what it was
+fn delete(command: DeleteCommand, moneybag: &mut Moneybag) -> Result<String, MoneybagsError> {
+    let entry = match command {
+        DeleteCommand::Rate { name } => {
+            let rate = moneybag
+                .rates
+                .get(&name)
+                .ok_or_else(|| rate_not_found(&name))?;
+            let entry = format!("rate {name}: {rate}");
+            moneybag.change(Event::RateSet { name, rate: None });
+            entry
+        }
+        DeleteCommand::Invoice { index } => {
+            let invoice = invoice_at(index, moneybag)?;
+            moneybag.delete_invoice(index);
+            format!("invoice {}: {invoice}", invoice.id)
+        }
+        DeleteCommand::Cost { index } => {
+            let cost = cost_at(index, moneybag)?;
+            moneybag.change(Event::CostDeleted { id: cost.id });
+            format!("cost {}: {cost}", cost.id)
+        }
+        DeleteCommand::Tier { rate: name, tier } => {
+            let mut rate = moneybag
+                .rates
+                .get(&name)
+                .ok_or_else(|| rate_not_found(&name))?
+                .clone();
+            let multiplier = rate
+                .tiers
+                .remove(&tier)
+                .ok_or_else(|| MoneybagsError::NotFound(format!("Tier {tier} not found")))?;
+            moneybag.change(Event::RateSet {
+                name,
+                rate: Some(rate),
+            });
+            format!("tier {tier} ({multiplier}x)")
+        }
+        DeleteCommand::Customer { name } => {
+            if moneybag.is_customer_referenced(&name) {
+                return Err(MoneybagsError::Invalid(format!(
+                    "Customer {name} has invoices or costs, and can't be deleted"
+                )));
+            }
+            if !moneybag.customers.contains_key(&name) {
+                return Err(customer_not_found(&name));
+            }
+            let entry = format!("customer {name}");
+            moneybag.change(Event::CustomerSet {
+                name,
+                customer: None,
+            });
+            entry
+        }
+        DeleteCommand::Asset { index } => {
+            let mut assets = moneybag.assets.clone();
+            if index >= assets.len() {
+                return Err(asset_not_found(index));
+            }
+            let asset = assets.remove(index);
+            moneybag.change(Event::AssetsChanged { assets });
+            format!("asset {index}: {asset}")
+        }
+        DeleteCommand::Rule { index } => {
+            let mut import_rules = moneybag.import_rules.clone();
+            if index >= import_rules.len() {
+                return Err(MoneybagsError::NotFound(format!("Rule {index} not found")));
+            }
+            let rule = import_rules.remove(index);
+            moneybag.change(Event::ImportRulesChanged { import_rules });
+            format!("rule {index}: {rule}")
+        }
+        DeleteCommand::Budget { category } => {
+            let budget = moneybag
+                .budgets
+                .get(&category)
+                .ok_or_else(|| MoneybagsError::NotFound(format!("No budget for {category}")))?;
+            let entry = format!(
+                "budget for {category}: {} {}",
+                budget.amount, budget.interval
+            );
+            moneybag.change(Event::BudgetSet {
+                category,
+                budget: None,
+            });
+            entry
+        }
+    };
+    Ok(entry)
+}
+
+fn rate_not_found(name: &str) -> MoneybagsError {
+    MoneybagsError::NotFound(tr!("Rate {name} not found in rates", name = name))
+}
+
+fn customer_not_found(name: &str) -> MoneybagsError {
+    MoneybagsError::NotFound(tr!("Customer {name} not found", name = name))
+}
+
+fn asset_not_found(index: usize) -> MoneybagsError {
+    MoneybagsError::NotFound(tr!("Asset {index} not found", index = index))
+}
+
+fn handle_edit(edit_command: EditCommand, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    match edit_command {
+        EditCommand::Rate {
+            name,
+            rename: Some(new),
+            ..
+        } => {
+            if !moneybag.rates.contains_key(&name) {
+                return Err(rate_not_found(&name));
+            }
+            if moneybag.rates.contains_key(&new) {
+                return Err(MoneybagsError::Invalid(format!(
+                    "Rate {new} already exists"
+                )));
+            }
+            moneybag.rename_rate(&name, &new);
+        }
+        EditCommand::Rate {
+            name, editor: true, ..
+        } => {
+            let mut rate = moneybag
+                .rates
+                .get(&name)
+                .ok_or_else(|| rate_not_found(&name))?
+                .clone();
+            edit_in_editor(&mut rate, |_| Ok(()))?;
+            moneybag.change(Event::RateSet {
+                name,
+                rate: Some(rate),
+            });
+        }
+        EditCommand::Rate { name, .. } => edit_rate(&name, moneybag)?,
+        EditCommand::Invoice {
+            index,
+            editor: true,
+        } => {
+            let mut invoice = invoice_at(index, moneybag)?;
+            let id = invoice.id;
+            edit_in_editor(&mut invoice, |invoice: &Invoice| {
+                check_id(invoice.id, id)?;
+                check_customer(invoice.customer.as_ref(), &moneybag.customers)
+            })?;
+            moneybag.change(Event::InvoiceEdited { invoice });
+        }
+        EditCommand::Invoice { index, .. } => edit_invoice(index, moneybag)?,
+        EditCommand::Cost {
+            index,
+            editor: true,
+        } => {
+            let mut cost = cost_at(index, moneybag)?;
+            let id = cost.id;
+            edit_in_editor(&mut cost, |cost: &Cost| {
+                check_id(cost.id, id)?;
+                check_customer(
+                    cost.billable.as_ref().map(|billable| &billable.customer),
+                    &moneybag.customers,
+                )?;
+                check_customer(cost.customer.as_ref(), &moneybag.customers)
+            })?;
+            moneybag.change(Event::CostEdited { cost });
+        }
+        EditCommand::Cost { index, .. } => edit_cost(index, moneybag)?,
+        EditCommand::Customer { name, editor: true } => {
+            let mut customer = moneybag
+                .customers
+                .get(&name)
+                .ok_or_else(|| customer_not_found(&name))?
+                .clone();
+            edit_in_editor(&mut customer, |_| Ok(()))?;
+            moneybag.change(Event::CustomerSet {
+                name,
+                customer: Some(customer),
+            });
+        }
+        EditCommand::Customer { name, .. } => edit_customer(&name, moneybag)?,
+        EditCommand::Asset {
+            index,
+            editor: true,
+        } => {
+            let mut assets = moneybag.assets.clone();
+            let asset = assets
+                .get_mut(index)
+                .ok_or_else(|| asset_not_found(index))?;
+            edit_in_editor(asset, |asset: &Asset| check_years(asset.years))?;
+            moneybag.change(Event::AssetsChanged { assets });
+        }
+        EditCommand::Asset { index, .. } => edit_asset(index, moneybag)?,
+    }
+    Ok(())
+}
+
+/// What an edit command left the entry as
+fn edited(edit_command: &EditCommand, moneybag: &Moneybag) -> String {
+    match edit_command {
+        EditCommand::Rate {
+            name,
+            rename: Some(new),
+            ..
+        } => format!("Renamed rate {name} to {new}"),
+        EditCommand::Rate { name, .. } => match moneybag.rates.get(name) {
+            Some(rate) => format!("Edited rate {name}: {rate}"),
+            None => format!("Edited rate {name}"),
+        },
+        EditCommand::Invoice { index, .. } => match moneybag.invoices.get(*index) {
+            Some(invoice) => format!("Edited invoice {}: {invoice}", invoice.id),
+            None => format!("Edited invoice {index}"),
+        },
+        EditCommand::Cost { index, .. } => match moneybag.costs.get(*index) {
+            Some(cost) => format!("Edited cost {}: {cost}", cost.id),
+            None => format!("Edited cost {index}"),
+        },
+        EditCommand::Customer { name, .. } => format!("Edited customer {name}"),
+        EditCommand::Asset { index, .. } => match moneybag.assets.get(*index) {
+            Some(asset) => format!("Edited asset {index}: {asset}"),
+            None => format!("Edited asset {index}"),
+        },
+    }
+}
+
+/// A copy of the invoice at the index
+fn invoice_at(index: usize, moneybag: &Moneybag) -> Result<Invoice, MoneybagsError> {
+    moneybag
+        .invoices
+        .get(index)
+        .cloned()
+        .ok_or_else(|| MoneybagsError::NotFound(tr!("Invoice {index} not found", index = index)))
+}
+
+/// A copy of the cost at the index
+fn cost_at(index: usize, moneybag: &Moneybag) -> Result<Cost, MoneybagsError> {
+    moneybag
+        .costs
+        .get(index)
+        .cloned()
+        .ok_or_else(|| MoneybagsError::NotFound(tr!("Cost {index} not found", index = index)))
+}
+
+fn check_id(edited: usize, id: usize) -> Result<(), String> {
+    if edited == id {
+        Ok(())
+    } else {
+        Err(format!("The ID can't be changed, it's {id}"))
+    }
+}
+
+fn check_years(years: u32) -> Result<(), String> {
+    if years == 0 {
+        Err("An asset must be depreciated over at least one year".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn check_customer(
+    customer: Option<&String>,
+    customers: &BTreeMap<String, Customer>,
+) -> Result<(), String> {
+    match customer {
+        Some(customer) if !customers.contains_key(customer) => {
+            Err(tr!("Customer {name} not found", name = customer))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn edit_customer(name: &str, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    let mut customer = moneybag
+        .customers
+        .get(name)
+        .ok_or_else(|| customer_not_found(name))?
+        .clone();
+    edit_optional("address", &mut customer.address)?;
+    edit_optional("email", &mut customer.email)?;
+    edit_optional("org number", &mut customer.org_number)?;
+    edit_optional("vat number", &mut customer.vat_number)?;
+    customer.payment_terms = loop {
+        let input = match customer.payment_terms {
+            Some(days) => prompt(&format!(
+                "payment terms ({days} days, \"none\" to remove): "
+            ))?,
+            None => prompt("payment terms (days): ")?,
+        };
+        if input.is_empty() {
+            break customer.payment_terms;
+        }
+        if input == "none" {
+            break None;
+        }
+        if let Ok(days) = input.parse() {
+            break Some(days);
+        }
+        println!("Could not parse days");
+    };
+    edit_optional("invoice prefix", &mut customer.invoice_prefix)?;
+    edit_optional("note", &mut customer.note)?;
+    moneybag.change(Event::CustomerSet {
+        name: name.to_string(),
+        customer: Some(customer),
+    });
+
+    let input = prompt(&format!("name ({name}): "))?;
+    if !input.is_empty() && input != name {
+        if moneybag.customers.contains_key(&input) {
+            println!("Customer {input} already exists");
+        } else {
+            moneybag.rename_customer(name, &input);
+        }
+    }
+    Ok(())
+}
+
+fn edit_asset(index: usize, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    let mut assets = moneybag.assets.clone();
+    let asset = assets
+        .get_mut(index)
+        .ok_or_else(|| asset_not_found(index))?;
+    let mut input = prompt(&format!("date ({}): ", asset.date))?;
+    if !input.is_empty() {
+        asset.date = input;
+    }
+
+    asset.amount = loop {
+        input = prompt(&format!("amount ({}): ", asset.amount))?;
+        if input.is_empty() {
+            break asset.amount;
+        }
+        if let Ok(amount) = input.parse() {
+            break amount;
+        }
+        println!("{}", tr!("Could not parse amount"));
+    };
+
+    input = prompt(&format!("name ({}): ", asset.name))?;
+    if !input.is_empty() {
+        asset.name = input;
+    }
+
+    asset.years = loop {
+        input = prompt(&format!("years ({}): ", asset.years))?;
+        if input.is_empty() {
+            break asset.years;
+        }
+        match input.parse() {
+            Ok(0) | Err(_) => println!("Years must be a positive number"),
+            Ok(years) => break years,
+        }
+    };
+    moneybag.change(Event::AssetsChanged { assets });
+    Ok(())
+}
+
+fn edit_cost(index: usize, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    let mut cost = cost_at(index, moneybag)?;
+    let mut input = prompt(&format!("date ({}): ", cost.date))?;
+    if !input.is_empty() {
+        cost.date = input;
+    }
+
+    cost.amount = loop {
+        input = prompt(&format!("amount ({}): ", cost.amount))?;
+        if input.is_empty() {
+            break cost.amount;
+        }
+        if let Ok(amount) = input.parse() {
+            break amount;
+        }
+        println!("{}", tr!("Could not parse amount"));
+    };
+
+    input = prompt(&format!("name ({}): ", cost.name))?;
+    if !input.is_empty() {
+        cost.name = input;
+    }
+
+    edit_optional("category", &mut cost.category)?;
+    edit_optional("note", &mut cost.note)?;
+    cost.scope = edit_scope(cost.scope)?;
+
+    cost.vat = loop {
+        if let Some(vat) = cost.vat {
+            input = prompt(&format!("vat ({vat}, \"none\" to remove): "))?;
+        } else {
+            input = prompt("vat: ")?;
+        }
+        if input.is_empty() {
+            break cost.vat;
+        }
+        if input == "none" {
+            break None;
+        }
+        if let Ok(vat) = input.parse() {
+            break Some(vat);
+        }
+        println!("Could not parse vat");
+    };
+
+    if let Some(subscription) = &mut cost.subscription {
+        subscription.renews = loop {
+            input = prompt(&format!("renews ({}): ", subscription.renews))?;
+            if input.is_empty() {
+                break subscription.renews;
+            }
+            if let Ok(renews) = input.parse() {
+                break renews;
+            }
+            println!("{}", tr!("Could not parse date"));
+        };
+    }
+    moneybag.change(Event::CostEdited { cost });
+    Ok(())
+}
+
+fn edit_scope(scope: Scope) -> Result<Scope, MoneybagsError> {
+    loop {
+        let input = prompt(&format!("scope ({scope}): "))?;
+        if input.is_empty() {
+            return Ok(scope);
+        }
+        if let Ok(scope) = Scope::from_str(&input, true) {
+            return Ok(scope);
+        }
+        println!("Scope must be business or personal");
+    }
+}
+
+/// Prompt for a new value of an optional field, where "none" removes it
+fn edit_optional(field: &str, value: &mut Option<String>) -> Result<(), MoneybagsError> {
+    let input = if let Some(current) = value {
+        prompt(&format!("{field} ({current}, \"none\" to remove): "))?
+    } else {
+        prompt(&format!("{field}: "))?
+    };
+    if input == "none" {
+        *value = None;
+    } else if !input.is_empty() {
+        *value = Some(input);
+    }
+    Ok(())
+}
+
+fn edit_invoice(index: usize, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    let mut invoice = invoice_at(index, moneybag)?;
+    edit_optional("number", &mut invoice.number)?;
+    let mut input = prompt(&format!("date ({}): ", invoice.date))?;
+    if !input.is_empty() {
+        invoice.date = input;
+    }
+
+    invoice.amount = loop {
+        input = prompt(&format!("amount ({}): ", invoice.amount))?;
+        if input.is_empty() {
+            break invoice.amount;
+        }
+        if let Ok(amount) = input.parse() {
+            break amount;
+        }
+        println!("{}", tr!("Could not parse amount"));
+    };
+
+    if let Some(customer) = &invoice.customer {
+        input = prompt(&format!("customer ({customer}): "))?;
+    } else {
+        input = prompt("customer: ")?;
+    }
+    if !input.is_empty() {
+        if moneybag.customers.contains_key(&input) {
+            invoice.customer = Some(input);
+        } else {
+            println!("Customer {input} not found in customers");
+        }
+    }
+
+    invoice.scope = edit_scope(invoice.scope)?;
+
+    invoice.vat = loop {
+        if let Some(vat) = invoice.vat {
+            input = prompt(&format!("vat ({vat}%, \"none\" to remove): "))?;
+        } else {
+            input = prompt("vat: ")?;
+        }
+        if input.is_empty() {
+            break invoice.vat;
+        }
+        if input == "none" {
+            break None;
+        }
+        if let Ok(vat) = input.trim_end_matches('%').parse() {
+            break Some(vat);
+        }
+        println!("Could not parse vat");
+    };
+
+    if let Some(name) = &invoice.rate_name {
+        input = prompt(&format!("rate ({name}): "))?;
+    } else if let Some(rate) = &invoice.rate {
+        input = prompt(&format!("rate ({}): ", rate.rate))?;
+    } else {
+        input = prompt("rate: ")?;
+    }
+    if !input.is_empty() {
+        if let Some(rate) = moneybag.rates.get(&input) {
+            match moneybag
+                .settings
+                .exchange_rate_for(rate.currency.as_deref())
+            {
+                Ok(exchange_rate) => {
+                    invoice.rate = Some(rate.clone());
+                    invoice.exchange_rate = exchange_rate;
+                    invoice.rate_name = Some(input);
+                }
+                Err(e) => println!("{e}"),
+            }
+        } else {
+            println!("{}", tr!("Rate {name} not found in rates", name = input));
+        }
+    }
+
+    if let (Some(currency), Some(exchange_rate)) = (invoice.currency(), invoice.exchange_rate) {
+        invoice.exchange_rate = loop {
+            input = prompt(&format!("exchange rate for {currency} ({exchange_rate}): "))?;
+            if input.is_empty() {
+                break Some(exchange_rate);
+            }
+            match input.parse::<Money>() {
+                Ok(exchange_rate) if !exchange_rate.is_zero() && !exchange_rate.is_negative() => {
+                    break Some(exchange_rate);
+                }
+                _ => println!("Exchange rate must be a number more than zero"),
+            }
+        };
+    }
+    moneybag.change(Event::InvoiceEdited { invoice });
+    Ok(())
+}
+
+fn edit_rate(name: &str, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    let mut rate = moneybag
+        .rates
+        .get(name)
+        .ok_or_else(|| rate_not_found(name))?
+        .clone();
+
+    rate.rate = loop {
+        let input = prompt(&format!("rate ({}): ", rate.rate))?;
+        if input.is_empty() {
+            break rate.rate;
+        }
+        if let Ok(rate) = input.parse() {
+            break rate;
+        }
+        println!("Could not parse rate");
+    };
+
+    edit_optional("currency", &mut rate.currency)?;
+    rate.currency = rate.currency.take().map(|currency| currency.to_uppercase());
+
+    rate.increment = loop {
+        let input = match rate.increment {
+            Some(increment) => prompt(&format!("increment ({increment}, \"none\" to remove): "))?,
+            None => prompt("increment: ")?,
+        };
+        if input.is_empty() {
+            break rate.increment;
+        }
+        if input == "none" {
+            break None;
+        }
+        if let Ok(increment) = input.parse() {
+            break Some(increment);
+        }
+        println!("Could not parse increment");
+    };
+    moneybag.change(Event::RateSet {
+        name: name.to_string(),
+        rate: Some(rate),
+    });
+    Ok(())
+}
+
+/// What an add command added to the moneybag
+fn added(before: &Moneybag, moneybag: &Moneybag) -> Added {
+    Added {
+        invoices: (moneybag.invoices.iter().skip(before.invoices.len()))
+            .cloned()
+            .collect(),
+        costs: (moneybag.costs.iter().skip(before.costs.len()))
+            .cloned()
+            .collect(),
+        assets: (before.assets.len()..moneybag.assets.len())
+            .map(|i| (i, moneybag.assets[i].clone()))
+            .collect(),
+        rules: (before.import_rules.len()..moneybag.import_rules.len())
+            .map(|i| (i, moneybag.import_rules[i].clone()))
+            .collect(),
+        rates: moneybag
+            .rates
+            .iter()
+            .filter(|(name, rate)| {
+                before
+                    .rates
+                    .get(*name)
+                    .is_none_or(|old| serialize_entry(old) != serialize_entry(*rate))
+            })
+            .map(|(name, rate)| (name.clone(), rate.clone()))
+            .collect(),
+        customers: moneybag
+            .customers
+            .iter()
+            .filter(|(name, _)| !before.customers.contains_key(*name))
+            .map(|(name, customer)| (name.clone(), customer.clone()))
+            .collect(),
+        budgets: moneybag
+            .budgets
+            .iter()
+            .filter(|(category, budget)| {
+                before
+                    .budgets
+                    .get(*category)
+                    .is_none_or(|old| serialize_entry(old) != serialize_entry(*budget))
+            })
+            .map(|(category, budget)| (category.clone(), budget.clone()))
+            .collect(),
+    }
+}
+
+fn print_added(added: &Added, moneybag: &Moneybag) {
+    if !added.invoices.is_empty() {
+        list_invoices(added.invoices.iter(), false, moneybag);
+    }
+    if !added.costs.is_empty() {
+        list_costs(added.costs.iter(), false);
+    }
+    if !added.assets.is_empty() {
+        list_assets(
+            added.assets.iter().map(|(i, asset)| (*i, asset)),
+            ListFormat::Text,
+        );
+    }
+    if !added.rules.is_empty() {
+        list_rules(
+            added.rules.iter().map(|(i, rule)| (*i, rule)),
+            ListFormat::Text,
+        );
+    }
+    if !added.rates.is_empty() {
+        print_rates_table(added.rates.iter().collect());
+    }
+    for (name, customer) in &added.customers {
+        show_customer(name, customer);
+    }
+    for (category, budget) in &added.budgets {
+        println!("{category}: {budget}");
+    }
+}
+
+fn serialize_entry(entry: &impl Serialize) -> String {
+    serde_json::to_string(entry).expect("Could not serialize entry")
+}
+
+fn handle_add(add_command: AddCommand, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    match add_command {
+        AddCommand::Rate {
+            rate,
+            name,
+            currency,
+            increment,
+        } => {
+            let currency = currency.map(|currency| currency.to_uppercase());
+            moneybag.change(Event::RateSet {
+                name,
+                rate: Some(Rate {
+                    rate,
+                    currency,
+                    tiers: BTreeMap::new(),
+                    increment,
+                }),
+            });
+        }
+        AddCommand::Invoice(invoice_args) if invoice_args.date.is_none() => {
+            add_invoice(invoice_wizard(invoice_args, moneybag)?, moneybag)?;
+        }
+        AddCommand::Invoice(invoice_args) => add_invoice(invoice_args, moneybag)?,
+        AddCommand::Asset {
+            date,
+            amount,
+            name,
+            years,
+        } => {
+            check_years(years).map_err(MoneybagsError::Invalid)?;
+            let mut assets = moneybag.assets.clone();
+            assets.push(Asset {
+                date,
+                amount,
+                name,
+                years,
+            });
+            moneybag.change(Event::AssetsChanged { assets });
+        }
+        AddCommand::Cost(cost_args) => add_cost(cost_args, moneybag)?,
+        AddCommand::Rule {
+            pattern,
+            name,
+            category,
+        } => {
+            let mut import_rules = moneybag.import_rules.clone();
+            import_rules.push(ImportRule {
+                pattern,
+                name,
+                category,
+            });
+            moneybag.change(Event::ImportRulesChanged { import_rules });
+        }
+        AddCommand::Tier {
+            rate: name,
+            tier,
+            multiplier,
+        } => {
+            let mut rate = moneybag
+                .rates
+                .get(&name)
+                .ok_or_else(|| rate_not_found(&name))?
+                .clone();
+            rate.tiers.insert(tier, multiplier);
+            moneybag.change(Event::RateSet {
+                name,
+                rate: Some(rate),
+            });
+        }
+        AddCommand::Customer(customer_args) => add_customer(customer_args, moneybag)?,
+        AddCommand::Budget {
+            category,
+            amount,
+            interval,
+        } => moneybag.change(Event::BudgetSet {
+            category,
+            budget: Some(Budget { amount, interval }),
+        }),
+    }
+    Ok(())
+}
+
+fn add_customer(
+    customer_args: CustomerArgs,
+    moneybag: &mut Moneybag,
+) -> Result<(), MoneybagsError> {
+    let CustomerArgs {
+        name,
+        address,
+        email,
+        org_number,
+        vat_number,
+        terms,
+        prefix,
+    } = customer_args;
+    if moneybag.customers.contains_key(&name) {
+        return Err(MoneybagsError::Invalid(format!(
+            "Customer {name} already exists, use edit customer to change it"
+        )));
+    }
+    moneybag.change(Event::CustomerSet {
+        name,
+        customer: Some(Customer {
+            note: None,
+            address,
+            email,
+            org_number,
+            vat_number,
+            payment_terms: terms,
+            invoice_prefix: prefix,
+        }),
+    });
+    Ok(())
+}
+
+pub(crate) fn confirm(question: &str) -> Result<bool, MoneybagsError> {
+    Ok(matches!(
+        prompt(&format!("{question} {} ", tr!("[y/N]")))?.as_str(),
+        "y" | "yes" | "j" | "ja"
+    ))
+}
+
+/// Ask for the date, rate, amount, and customer of an invoice, where not given as options
+fn invoice_wizard(
+    invoice_args: InvoiceArgs,
+    moneybag: &Moneybag,
+) -> Result<InvoiceArgs, MoneybagsError> {
+    let today = Local::now().date_naive().to_string();
+    let input = prompt(&format!("date ({today}): "))?;
+    let date = if input.is_empty() { today } else { input };
+
+    let rate = match invoice_args.rate {
+        Some(rate) => Some(rate),
+        None => loop {
+            let names = moneybag.rates.keys().cloned().collect();
+            let input = prompt_completing("rate (none): ", names)?;
+            if input.is_empty() {
+                break None;
+            }
+            if moneybag.rates.contains_key(&input) {
+                break Some(input);
+            }
+            println!("{}", tr!("Rate {name} not found in rates", name = input));
+        },
+    };
+
+    let amount = loop {
+        let input = prompt(if rate.is_some() {
+            "hours: "
+        } else {
+            "amount: "
+        })?;
+        if let Ok(amount) = input.parse() {
+            break amount;
+        }
+        println!("{}", tr!("Could not parse amount"));
+    };
+
+    let customer = match invoice_args.customer {
+        Some(customer) => Some(customer),
+        None => loop {
+            let names = moneybag.customers.keys().cloned().collect();
+            let input = prompt_completing("customer (none): ", names)?;
+            if input.is_empty() {
+                break None;
+            }
+            if moneybag.customers.contains_key(&input) {
+                break Some(input);
+            }
+            println!("Customer {input} not found, add it with add customer");
+        },
+    };
+
+    Ok(InvoiceArgs {
+        date: Some(date),
+        amount: Some(amount),
+        rate,
+        customer,
+        ..invoice_args
+    })
+}
+
+fn add_invoice(invoice_args: InvoiceArgs, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    let InvoiceArgs {
+        date,
+        amount,
+        rate,
+        customer,
+        mut tier,
+        scope,
+        vat,
+        force,
+    } = invoice_args;
+    let (Some(date), Some(mut amount)) = (date, amount) else {
+        return Err(MoneybagsError::Usage(
+            "Give both the date and the amount, or neither to be asked for them".to_string(),
+        ));
+    };
+    let rate_name = rate;
+    let rate = match &rate_name {
+        Some(name) => Some(
+            moneybag
+                .rates
+                .get(name)
+                .ok_or_else(|| rate_not_found(name))?
+                .clone(),
+        ),
+        None => None,
+    };
+    let exchange_rate = moneybag
+        .settings
+        .exchange_rate_for(rate.as_ref().and_then(|rate| rate.currency.as_deref()))
+        .map_err(MoneybagsError::Invalid)?;
+    check_customers(customer.iter(), moneybag)?;
+    if let Some(rate) = &rate {
+        if let Some((tier, _)) = tier.iter().find(|(tier, _)| !rate.tiers.contains_key(tier)) {
+            return Err(MoneybagsError::NotFound(format!(
+                "Tier {tier} not found in rate"
+            )));
+        }
+        for hours in std::iter::once(&mut amount).chain(tier.iter_mut().map(|(_, hours)| hours)) {
+            let billable = rate.billable_hours(*hours);
+            if billable != *hours {
+                note(&format!("Rounded {hours} hours up to {billable}"));
+                *hours = billable;
+            }
+        }
+    }
+    let invoice = Invoice {
+        id: 0,
+        date,
+        amount,
+        rate,
+        rate_name,
+        customer,
+        expenses: vec![],
+        scope,
+        exchange_rate,
+        tier_hours: tier.into_iter().collect(),
+        number: None,
+        paid: None,
+        vat,
+        reference: None,
+        reminded: None,
+    };
+    if !force {
+        if let Some(existing) = moneybag
+            .invoices
+            .iter()
+            .find(|existing| existing.is_duplicate_of(&invoice))
+        {
+            if !confirm(&format!(
+                "An identical invoice exists ({existing}), add anyway?"
+            ))? {
+                return Ok(());
+            }
+        }
+    }
+    let number = moneybag.next_invoice_number(invoice.customer.as_deref(), &invoice.date);
+    moneybag.change(Event::InvoiceAdded {
+        invoice: Invoice { number, ..invoice },
+    });
+    Ok(())
+}
+
+/// Check that the customers an entry refers to exist
+fn check_customers<'a>(
+    mut customers: impl Iterator<Item = &'a String>,
+    moneybag: &Moneybag,
+) -> Result<(), MoneybagsError> {
+    match customers.find(|customer| !moneybag.customers.contains_key(*customer)) {
+        Some(customer) => Err(MoneybagsError::NotFound(format!(
+            "Customer {customer} not found, add it with add customer"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn add_cost(cost_args: CostArgs, moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    let CostArgs {
+        date,
+        amount,
+        name,
+        vat,
+        subscription,
+        renews,
+        category,
+        billable,
+        customer,
+        note,
+        scope,
+        force,
+        calculated,
+    } = cost_args;
+    if let Some(calculated) = calculated {
+        return add_calculated_cost(calculated, moneybag);
+    }
+    let (Some(date), Some(amount), Some(name)) = (date, amount, name) else {
+        return Err(MoneybagsError::Usage(
+            "Give the date, amount, and name of the cost".to_string(),
+        ));
+    };
+    check_customers(billable.iter().chain(&customer), moneybag)?;
+    let subscription = subscription
+        .zip(renews)
+        .map(|(interval, renews)| Subscription { interval, renews });
+    let (dates, recurring) = if date == "monthly" {
+        (
+            (1..=12).map(|month| format!("2025-{month:02}")).collect(),
+            true,
+        )
+    } else {
+        (vec![date], false)
+    };
+    let costs: Vec<_> = dates
+        .into_iter()
+        .map(|date| Cost {
+            id: 0,
+            date,
+            amount,
+            name: name.clone(),
+            recurring,
+            vat,
+            subscription,
+            category: category.clone(),
+            billable: billable.clone().map(|customer| Billable {
+                customer,
+                billed: false,
+            }),
+            customer: customer.clone(),
+            note: note.clone(),
+            scope,
+            reference: None,
+        })
+        .collect();
+    if !force {
+        if let Some(existing) = moneybag
+            .costs
+            .iter()
+            .find(|existing| costs.iter().any(|cost| existing.is_duplicate_of(cost)))
+        {
+            if !confirm(&format!(
+                "An identical cost exists ({existing}), add anyway?"
+            ))? {
+                return Ok(());
+            }
+        }
+    }
+    for cost in costs {
+        moneybag.change(Event::CostAdded { cost });
+    }
+    if let Some(cost) = moneybag.costs.last() {
+        warn_exceeded_budget(cost, moneybag);
+    }
+    Ok(())
+}
+
+fn add_calculated_cost(
+    calculated: CalculatedCost,
+    moneybag: &mut Moneybag,
+) -> Result<(), MoneybagsError> {
+    let (date, amount, name) = match calculated {
+        CalculatedCost::Mileage { date, km } => {
+            let rate = moneybag.settings.mileage_rate;
+            if rate.is_zero() {
+                return Err(MoneybagsError::Invalid(
+                    "No mileage rate set, use set mileage-rate <rate>".to_string(),
+                ));
+            }
+            (date, km * rate, format!("Mileage {km} km at {rate}/km"))
+        }
+        CalculatedCost::Perdiem { date, days } => {
+            let rate = moneybag.settings.per_diem;
+            if rate.is_zero() {
+                return Err(MoneybagsError::Invalid(
+                    "No per diem rate set, use set per-diem <rate>".to_string(),
+                ));
+            }
+            (
+                date,
+                days * rate,
+                format!("Per diem {days} days at {rate}/day"),
+            )
+        }
+    };
+    moneybag.change(Event::CostAdded {
+        cost: Cost {
+            id: 0,
+            date,
+            amount,
+            name,
+            recurring: false,
+            vat: None,
+            subscription: None,
+            category: None,
+            billable: None,
+            customer: None,
+            note: None,
+            scope: Scope::Business,
+            reference: None,
+        },
+    });
+    Ok(())
+}
+
+fn warn_exceeded_budget(cost: &Cost, moneybag: &Moneybag) {
+    if let Some(category) = &cost.category {
+        if let Some(status) = moneybag.budget_status(category, &cost.date) {
+            if status.is_exceeded() {
+                println!("{}: {status}", tr!("Warning"));
+            }
+        }
+    }
+}
+
+/// Invoices and costs listed as text are returned, everything else is printed
+fn handle_list(list_args: &ListArgs, moneybag: &Moneybag) -> CommandOutput {
+    let ListArgs {
+        json,
+        format,
+        filter,
+        sort,
+        reverse,
+        command: list_command,
+    } = list_args;
+    let format = if *json { ListFormat::Json } else { *format };
+    let filterable = matches!(
+        list_command,
+        ListCommand::Rates
+            | ListCommand::Invoices { .. }
+            | ListCommand::Costs { .. }
+            | ListCommand::Billable
+    );
+    if format == ListFormat::Csv && !filterable {
+        println!("CSV is only available for rates, invoices, and costs");
+        return CommandOutput::Nothing;
+    }
+    if (!filter.is_empty() || sort.is_some() || *reverse) && !filterable {
+        println!("Filtering and sorting are only available for rates, invoices, and costs");
+        return CommandOutput::Nothing;
+    }
+    match list_command {
+        ListCommand::Rates => list_rates(filter, *sort, *reverse, format, moneybag),
+        ListCommand::Invoices { scope, running } => {
+            let mut invoices: Vec<_> = moneybag
+                .invoices
+                .iter()
+                .filter(|invoice| scope.is_none_or(|scope| scope == invoice.scope))
+                .filter(|invoice| filter.invoice(invoice))
+                .collect();
+            let sort = sort.or(running.then_some(ListSort::Date));
+            sort_invoices(&mut invoices, sort, *reverse);
+            let invoices = invoices.into_iter();
+            match format {
+                ListFormat::Text => {
+                    return CommandOutput::Invoices {
+                        invoices: invoices.cloned().collect(),
+                        running: *running,
+                    }
+                }
+                ListFormat::Json => print_invoices_json(invoices, moneybag),
+                ListFormat::Csv => print_invoices_csv(invoices, moneybag),
+            }
+        }
+        ListCommand::Costs { scope, running } => {
+            let mut costs: Vec<_> = moneybag
+                .costs
+                .iter()
+                .filter(|cost| scope.is_none_or(|scope| scope == cost.scope))
+                .filter(|cost| filter.cost(cost))
+                .collect();
+            let sort = sort.or(running.then_some(ListSort::Date));
+            sort_costs(&mut costs, sort, *reverse);
+            let costs = costs.into_iter();
+            match format {
+                ListFormat::Text => {
+                    return CommandOutput::Costs {
+                        costs: costs.cloned().collect(),
+                        running: *running,
+                    }
+                }
+                ListFormat::Json => print_json(&costs.collect::<Vec<_>>()),
+                ListFormat::Csv => print_costs_csv(costs),
+            }
+        }
+        ListCommand::Transactions { scope } => {
+            return CommandOutput::Transactions(transactions(*scope, moneybag));
+        }
+        ListCommand::Subscriptions => list_subscriptions(format, moneybag),
+        ListCommand::Billable => {
+            let mut costs: Vec<_> = moneybag
+                .costs
+                .iter()
+                .filter(|cost| {
+                    cost.billable
+                        .as_ref()
+                        .is_some_and(|billable| !billable.billed)
+                        && filter.cost(cost)
+                })
+                .collect();
+            sort_costs(&mut costs, *sort, *reverse);
+            if format == ListFormat::Json {
+                print_json(&costs);
+            } else {
+                return CommandOutput::Costs {
+                    costs: costs.into_iter().cloned().collect(),
+                    running: false,
+                };
+            }
+        }
+        ListCommand::Rules => list_rules(moneybag.import_rules.iter().enumerate(), format),
+        ListCommand::Customers { chart } => list_customers(format, *chart, moneybag),
+        ListCommand::Assets => list_assets(moneybag.assets.iter().enumerate(), format),
+    }
+    CommandOutput::Nothing
+}
+
+fn sort_invoices(invoices: &mut [&Invoice], sort: Option<ListSort>, reverse: bool) {
+    match sort {
+        Some(ListSort::Date) => invoices.sort_by(|a, b| a.date.cmp(&b.date)),
+        Some(ListSort::Amount) => invoices.sort_by_key(|invoice| invoice.base_total()),
+        Some(ListSort::Name | ListSort::Customer) => {
+            invoices.sort_by(|a, b| a.customer.cmp(&b.customer));
+        }
+        None => {}
+    }
+    if reverse {
+        invoices.reverse();
+    }
+}
+
+fn sort_costs(costs: &mut [&Cost], sort: Option<ListSort>, reverse: bool) {
+    match sort {
+        Some(ListSort::Date) => costs.sort_by(|a, b| a.date.cmp(&b.date)),
+        Some(ListSort::Amount) => costs.sort_by_key(|cost| cost.amount),
+        Some(ListSort::Name) => costs.sort_by(|a, b| a.name.cmp(&b.name)),
+        Some(ListSort::Customer) => costs.sort_by(|a, b| a.attributed_to().cmp(&b.attributed_to())),
+        None => {}
+    }
+    if reverse {
+        costs.reverse();
+    }
+}
+
+fn print_invoices_json<'a>(invoices: impl Iterator<Item = &'a Invoice>, moneybag: &Moneybag) {
+    let invoices: Vec<_> = invoices
+        .map(|invoice| {
+            let mut value = serde_json::json!(invoice);
+            value["due_date"] = serde_json::json!(moneybag.due_date(invoice));
+            value
+        })
+        .collect();
+    print_json(&invoices);
+}
+
+fn print_indexed_json<'a, T: Serialize + 'a>(entries: impl Iterator<Item = (usize, &'a T)>) {
+    let entries: Vec<_> = entries
+        .map(|(index, entry)| Indexed { index, entry })
+        .collect();
+    print_json(&entries);
+}
+
+fn list_rules<'a>(rules: impl Iterator<Item = (usize, &'a ImportRule)>, format: ListFormat) {
+    if format == ListFormat::Json {
+        print_indexed_json(rules);
+        return;
+    }
+    let mut table = Table::new(&[
+        ("#", Align::Right),
+        ("pattern", Align::Left),
+        ("name", Align::Left),
+        ("category", Align::Left),
+    ]);
+    for (i, rule) in rules {
+        table.add_row(vec![
+            i.to_string(),
+            rule.pattern.clone(),
+            rule.name.clone(),
+            rule.category.clone().unwrap_or_default(),
+        ]);
+    }
+    table.print();
+}
+
+fn list_assets<'a>(assets: impl Iterator<Item = (usize, &'a Asset)>, format: ListFormat) {
+    if format == ListFormat::Json {
+        print_indexed_json(assets);
+        return;
+    }
+    let mut table = Table::new(&[
+        ("#", Align::Right),
+        ("date", Align::Left),
+        ("amount", Align::Right),
+        ("name", Align::Left),
+        ("years", Align::Right),
+        ("per month", Align::Right),
+    ]);
+    for (i, asset) in assets {
+        table.add_row(vec![
+            i.to_string(),
+            asset.date.clone(),
+            asset.amount.to_string(),
+            asset.name.clone(),
+            asset.years.to_string(),
+            asset.monthly_depreciation().to_string(),
+        ]);
+    }
+    table.print();
+}
+
+fn list_rates(
+    filter: &ListFilter,
+    sort: Option<ListSort>,
+    reverse: bool,
+    format: ListFormat,
+    moneybag: &Moneybag,
+) {
+    let mut rates: Vec<_> = moneybag
+        .rates
+        .iter()
+        .filter(|(name, rate)| filter.rate(name, rate))
+        .collect();
+    if sort == Some(ListSort::Amount) {
+        rates.sort_by_key(|(_, rate)| rate.rate);
+    }
+    if reverse {
+        rates.reverse();
+    }
+    match format {
+        ListFormat::Text => print_rates_table(rates),
+        ListFormat::Json => print_json(
+            &rates
+                .into_iter()
+                .map(|(name, entry)| Named { name, entry })
+                .collect::<Vec<_>>(),
+        ),
+        ListFormat::Csv => print_rates_csv(rates.into_iter()),
+    }
+}
+
+fn print_rates_table(rates: Vec<(&String, &Rate)>) {
+    let mut table = Table::new(&[
+        ("name", Align::Left),
+        ("rate", Align::Right),
+        ("currency", Align::Left),
+        ("tiers", Align::Left),
+        ("increment", Align::Right),
+    ]);
+    for (name, rate) in rates {
+        let tiers: Vec<_> = rate
+            .tiers
+            .iter()
+            .map(|(tier, multiplier)| format!("{tier} {multiplier}x"))
+            .collect();
+        table.add_row(vec![
+            name.clone(),
+            rate.rate.to_string(),
+            rate.currency.clone().unwrap_or_default(),
+            tiers.join(", "),
+            rate.increment
+                .map(|increment| increment.to_string())
+                .unwrap_or_default(),
+        ]);
+    }
+    table.print();
+}
+
+fn list_invoices<'a>(
+    invoices: impl Iterator<Item = &'a Invoice>,
+    running: bool,
+    moneybag: &Moneybag,
+) {
+    let mut table = Table::new(&[
+        ("#", Align::Right),
+        ("number", Align::Left),
+        ("date", Align::Left),
+        ("amount", Align::Right),
+        ("running total", Align::Right),
+        ("customer", Align::Left),
+        ("due", Align::Left),
+        ("details", Align::Left),
+    ]);
+    let today = Local::now().date_naive();
+    let mut total = Money::default();
+    for invoice in invoices {
+        total = total + invoice.base_total();
+        let mut details: Vec<_> = invoice.breakdown().into_iter().collect();
+        if let Some(paid) = invoice.paid {
+            details.push(format!("paid {paid}"));
+        }
+        if invoice.scope == Scope::Personal {
+            details.push("personal".to_string());
+        }
+        let style = if moneybag.is_overdue(invoice, today) {
+            Style::Red
+        } else {
+            Style::Plain
+        };
+        table.add_styled_row(
+            vec![
+                invoice.id.to_string(),
+                invoice.number.clone().unwrap_or_default(),
+                invoice.date.clone(),
+                format!("{}{}", invoice.total(), invoice.currency_suffix()),
+                if running {
+                    total.to_string()
+                } else {
+                    String::new()
+                },
+                invoice.customer.clone().unwrap_or_default(),
+                moneybag
+                    .due_date(invoice)
+                    .map(|due| due.to_string())
+                    .unwrap_or_default(),
+                details.join(", "),
+            ],
+            style,
+        );
+    }
+    table.print();
+}
+
+fn list_costs<'a>(costs: impl Iterator<Item = &'a Cost>, running: bool) {
+    let mut table = Table::new(&[
+        ("#", Align::Right),
+        ("date", Align::Left),
+        ("amount", Align::Right),
+        ("running total", Align::Right),
+        ("VAT", Align::Right),
+        ("name", Align::Left),
+        ("category", Align::Left),
+        ("details", Align::Left),
+    ]);
+    let mut total = Money::default();
+    for cost in costs {
+        total = total + cost.net();
+        let mut details = vec![];
+        if let Some(subscription) = &cost.subscription {
+            details.push(format!("{} subscription", subscription.interval));
+        }
+        if cost.scope == Scope::Personal {
+            details.push("personal".to_string());
+        }
+        if let Some(billable) = &cost.billable {
+            let status = if billable.billed {
+                "billed"
+            } else {
+                "billable"
+            };
+            details.push(format!("{status} to {}", billable.customer));
+        }
+        if let Some(customer) = &cost.customer {
+            details.push(format!("for {customer}"));
+        }
+        table.add_row(vec![
+            cost.id.to_string(),
+            cost.date.clone(),
+            cost.amount.to_string(),
+            if running {
+                total.to_string()
+            } else {
+                String::new()
+            },
+            cost.vat.map(|_| cost.vat().to_string()).unwrap_or_default(),
+            cost.name.clone(),
+            cost.category.clone().unwrap_or_default(),
+            details.join(", "),
+        ]);
+    }
+    table.print();
+}
+
+/// Invoices in the base currency and costs net of deductible VAT, by date, with the balance
+/// after each. Costs dated with only a month come first in it.
+fn transactions(scope: Option<Scope>, moneybag: &Moneybag) -> Vec<Transaction> {
+    let in_scope = |entry_scope: Scope| scope.is_none_or(|scope| scope == entry_scope);
+    let invoices = moneybag
+        .invoices
+        .iter()
+        .filter(|invoice| in_scope(invoice.scope))
+        .map(|invoice| {
+            let name = invoice.customer.clone().unwrap_or_default();
+            (&invoice.date, invoice.base_total(), name)
+        });
+    let costs = moneybag
+        .costs
+        .iter()
+        .filter(|cost| in_scope(cost.scope))
+        .map(|cost| (&cost.date, -cost.net(), cost.name.clone()));
+    let mut transactions: Vec<_> = invoices.chain(costs).collect();
+    transactions.sort_by_key(|(date, _, _)| *date);
+    let mut balance = Money::default();
+    transactions
+        .into_iter()
+        .map(|(date, amount, name)| {
+            balance = balance + amount;
+            Transaction {
+                date: date.clone(),
+                amount,
+                name,
+                balance,
+            }
+        })
+        .collect()
+}
+
+fn list_transactions(transactions: &[Transaction]) {
+    let mut table = Table::new(&[
+        ("date", Align::Left),
+        ("amount", Align::Right),
+        ("name", Align::Left),
+        ("balance", Align::Right),
+    ]);
+    for transaction in transactions {
+        let style = if transaction.balance.is_negative() {
+            Style::Red
+        } else {
+            Style::Plain
+        };
+        table.add_styled_row(
+            vec![
+                transaction.date.clone(),
+                transaction.amount.to_string(),
+                transaction.name.clone(),
+                transaction.balance.to_string(),
+            ],
+            style,
+        );
+    }
+    table.print();
+}
+
+fn list_customers(format: ListFormat, chart: bool, moneybag: &Moneybag) {
+    let customers = moneybag.customers.iter().map(|(name, customer)| {
+        let invoiced = sum_invoices(
+            moneybag
+                .invoices
+                .iter()
+                .filter(|invoice| invoice.customer.as_ref() == Some(name)),
+        );
+        (name, customer, invoiced)
+    });
+    if format == ListFormat::Json {
+        let customers: Vec<_> = customers
+            .map(|(name, entry, invoiced)| {
+                let mut value = serde_json::json!(Named { name, entry });
+                value["invoiced"] = serde_json::json!(invoiced);
+                value
+            })
+            .collect();
+        print_json(&customers);
+        return;
+    }
+    let customers: Vec<_> = customers.collect();
+    let most = customers
+        .iter()
+        .map(|(_, _, invoiced)| *invoiced)
+        .max()
+        .unwrap_or_default();
+    let mut table = Table::new(&[
+        ("name", Align::Left),
+        ("invoiced", Align::Right),
+        ("", Align::Left),
+        ("details", Align::Left),
+    ]);
+    for (name, customer, invoiced) in customers {
+        table.add_row(vec![
+            name.clone(),
+            invoiced.to_string(),
+            if chart {
+                chart::bar(invoiced, most, report::CHART_WIDTH)
+            } else {
+                String::new()
+            },
+            customer.to_string(),
+        ]);
+    }
+    table.print();
+}
+
+fn list_subscriptions(format: ListFormat, moneybag: &Moneybag) {
+    let today = Local::now().date_naive();
+    let mut subscriptions = moneybag.subscriptions();
+    subscriptions.sort_by_key(|(_, subscription)| subscription.next_renewal(today));
+    let annualized = subscriptions
+        .iter()
+        .map(|(cost, subscription)| cost.amount * subscription.interval.per_year())
+        .sum::<Money>();
+    if format == ListFormat::Json {
+        let subscriptions: Vec<_> = subscriptions
+            .iter()
+            .map(|(cost, subscription)| {
+                serde_json::json!({
+                    "name": cost.name,
+                    "amount": cost.amount,
+                    "interval": subscription.interval,
+                    "renews": subscription.next_renewal(today),
+                })
+            })
+            .collect();
+        print_json(&serde_json::json!({
+            "subscriptions": subscriptions,
+            "annualized": annualized,
+        }));
+        return;
+    }
+    let mut table = Table::new(&[
+        ("name", Align::Left),
+        ("amount", Align::Right),
+        ("interval", Align::Left),
+        ("renews", Align::Left),
+        ("in", Align::Right),
+    ]);
+    for (cost, subscription) in subscriptions {
+        let renews = subscription.next_renewal(today);
+        table.add_row(vec![
+            cost.name.clone(),
+            cost.amount.to_string(),
+            subscription.interval.to_string(),
+            renews.to_string(),
+            format!("{} days", (renews - today).num_days()),
+        ]);
+    }
+    table.print();
+    println!("Annualized: {annualized}");
+}
@@ -5,17 +5,83 @@ use std::{
     str::FromStr,
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
-pub(crate) struct Money {
+/// An amount in hundredths. Stored as a decimal string, e.g. "1234.50".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Money {
     amount: i64,
 }
 
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// How amounts were stored, the current way first
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredMoney {
+    Decimal(String),
+    /// Hundredths, as files written before amounts were decimal strings have them
+    Hundredths {
+        amount: i64,
+    },
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match StoredMoney::deserialize(deserializer)? {
+            StoredMoney::Decimal(decimal) => decimal.parse().map_err(|e| {
+                serde::de::Error::custom(format!("could not parse {decimal} as an amount: {e}"))
+            }),
+            StoredMoney::Hundredths { amount } => Ok(Money { amount }),
+        }
+    }
+}
+
 impl Money {
+    #[must_use]
     pub fn is_zero(self) -> bool {
         self.amount == 0
     }
+
+    #[must_use]
+    pub fn is_negative(self) -> bool {
+        self.amount < 0
+    }
+
+    /// The amount as a floating point number, for formats that only have those
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_f64(self) -> f64 {
+        // Exact up to 2^53 hundredths, far beyond any amount kept here
+        self.amount as f64 / 100.0
+    }
+
+    /// The share of whole this is, in steps out of `steps`, rounded down. Zero if whole isn't
+    /// positive.
+    #[must_use]
+    pub fn steps_of(self, whole: Money, steps: i64) -> i64 {
+        if whole.amount <= 0 {
+            return 0;
+        }
+        self.amount * steps / whole.amount
+    }
+
+    /// Round up to the nearest multiple of increment
+    #[must_use]
+    pub fn round_up_to(self, increment: Money) -> Money {
+        if increment.amount <= 0 {
+            return self;
+        }
+        let increments = (self.amount + increment.amount - 1).div_euclid(increment.amount);
+        Money {
+            amount: increments * increment.amount,
+        }
+    }
 }
 
 impl Sub for Money {
@@ -89,24 +155,101 @@ impl Sum for Money {
 
 impl Display for Money {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}.{:0>2}", self.amount / 100, (self.amount % 100).abs())
+        let sign = if self.amount < 0 { "-" } else { "" };
+        let amount = self.amount.abs();
+        write!(f, "{sign}{}.{:0>2}", amount / 100, amount % 100)
+    }
+}
+
+/// Text that isn't an amount, e.g. "12.345" or "1.€"
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid amount, expected e.g. 12.50")]
+pub struct ParseMoneyError;
+
+impl From<std::num::ParseIntError> for ParseMoneyError {
+    fn from(_: std::num::ParseIntError) -> Self {
+        ParseMoneyError
     }
 }
 
 impl FromStr for Money {
-    type Err = std::num::ParseIntError;
+    type Err = ParseMoneyError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let amount = s
-            .split('.')
-            .map(str::parse::<i64>)
-            .collect::<Result<Vec<_>, _>>()?;
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, "0"));
+        // i64::from_str would take another sign, as in "--5"
+        if whole.is_empty() || !whole.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseMoneyError);
+        }
+        if fraction.len() > 2 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseMoneyError);
+        }
+        // "12.5" means 12.50, not 12.05
+        let fraction = format!("{fraction:0<2}").parse::<i64>()?;
+        let amount = whole
+            .parse::<i64>()?
+            .checked_mul(100)
+            .and_then(|cents| cents.checked_add(fraction))
+            .ok_or(ParseMoneyError)?;
         Ok(Money {
-            amount: amount[0] * 100 + if amount.len() == 2 { amount[1] } else { 0 },
+            amount: if negative { -amount } else { amount },
         })
     }
 }
 
+/// Either a percentage ("5%") or a fixed amount ("100")
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PercentOrMoney {
+    Percent(Money),
+    Fixed(Money),
+}
+
+impl PercentOrMoney {
+    /// Increase amount by the percentage or fixed amount
+    #[must_use]
+    pub fn apply(self, amount: Money) -> Money {
+        match self {
+            PercentOrMoney::Percent(percent) => amount + amount * percent / 100,
+            PercentOrMoney::Fixed(fixed) => amount + fixed,
+        }
+    }
+
+    /// The VAT included in a gross amount, where a percentage is the VAT rate
+    #[must_use]
+    pub fn vat_of(self, gross: Money) -> Money {
+        match self {
+            PercentOrMoney::Percent(rate) => Money {
+                amount: gross.amount * rate.amount / (10000 + rate.amount),
+            },
+            PercentOrMoney::Fixed(vat) => vat,
+        }
+    }
+}
+
+impl Display for PercentOrMoney {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PercentOrMoney::Percent(percent) => write!(f, "{percent}%"),
+            PercentOrMoney::Fixed(fixed) => write!(f, "{fixed}"),
+        }
+    }
+}
+
+impl FromStr for PercentOrMoney {
+    type Err = ParseMoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%') {
+            Some(percent) => Ok(PercentOrMoney::Percent(percent.parse()?)),
+            None => Ok(PercentOrMoney::Fixed(s.parse()?)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,6 +278,18 @@ mod tests {
         assert_eq!(format!("{c}"), "2.00");
         let d = Money { amount: -153 };
         assert_eq!(format!("{d}"), "-1.53");
+        assert_eq!(Money { amount: -5 }.to_string(), "-0.05");
+    }
+
+    #[test]
+    fn test_money_serde() {
+        let amounts = [Money { amount: 123_450 }, Money { amount: -50 }];
+        let json = serde_json::to_string(&amounts).unwrap();
+        assert_eq!(json, r#"["1234.50","-0.50"]"#);
+        assert_eq!(serde_json::from_str::<Vec<Money>>(&json).unwrap(), amounts);
+        let old: Money = serde_json::from_str(r#"{"amount": 123450}"#).unwrap();
+        assert_eq!(old, amounts[0]);
+        assert!(serde_json::from_str::<Money>(r#""12,50""#).is_err());
     }
 
     #[test]
@@ -145,5 +300,75 @@ mod tests {
         assert_eq!(b, Money { amount: 2001 });
         let c = Money::from_str("2.00").unwrap();
         assert_eq!(c, Money { amount: 200 });
+        assert_eq!(Money::from_str("-1.53").unwrap(), Money { amount: -153 });
+        assert_eq!(Money::from_str("12.5").unwrap(), Money { amount: 1250 });
+        assert_eq!(Money::from_str("7").unwrap(), Money { amount: 700 });
+        assert_eq!(Money::from_str("1.").unwrap(), Money { amount: 100 });
+    }
+
+    #[test]
+    fn test_money_from_str_invalid() {
+        assert_eq!(Money::from_str("1.€"), Err(ParseMoneyError));
+        assert_eq!(Money::from_str("12.345"), Err(ParseMoneyError));
+        assert_eq!(Money::from_str("1.-5"), Err(ParseMoneyError));
+        assert_eq!(Money::from_str("1.+5"), Err(ParseMoneyError));
+        assert_eq!(Money::from_str("12,50"), Err(ParseMoneyError));
+        assert_eq!(Money::from_str("--5"), Err(ParseMoneyError));
+        assert_eq!(Money::from_str("-+5"), Err(ParseMoneyError));
+        assert_eq!(Money::from_str("92233720368547759"), Err(ParseMoneyError));
+    }
+
+    #[test]
+    fn test_percent_or_money_apply() {
+        let rent = Money { amount: 1_000_000 };
+        let percent = PercentOrMoney::from_str("5%").unwrap();
+        assert_eq!(percent, PercentOrMoney::Percent(Money { amount: 500 }));
+        assert_eq!(percent.apply(rent), Money { amount: 1_050_000 });
+        let fixed = PercentOrMoney::from_str("-250.50").unwrap();
+        assert_eq!(fixed, PercentOrMoney::Fixed(Money { amount: -25050 }));
+        assert_eq!(fixed.apply(rent), Money { amount: 974_950 });
+    }
+
+    #[test]
+    fn test_round_up_to() {
+        let quarter = Money { amount: 25 };
+        assert_eq!(
+            Money { amount: 110 }.round_up_to(quarter),
+            Money { amount: 125 }
+        );
+        assert_eq!(
+            Money { amount: 125 }.round_up_to(quarter),
+            Money { amount: 125 }
+        );
+        assert_eq!(
+            Money { amount: 0 }.round_up_to(quarter),
+            Money { amount: 0 }
+        );
+        assert_eq!(
+            Money { amount: 110 }.round_up_to(Money::default()),
+            Money { amount: 110 }
+        );
+    }
+
+    #[test]
+    fn test_vat_of() {
+        let gross = Money { amount: 12500 };
+        let rate = PercentOrMoney::from_str("25%").unwrap();
+        assert_eq!(rate.vat_of(gross), Money { amount: 2500 });
+        let fixed = PercentOrMoney::from_str("20").unwrap();
+        assert_eq!(fixed.vat_of(gross), Money { amount: 2000 });
+    }
+
+    #[test]
+    fn test_money_serde_invalid() {
+        for json in [
+            r#""""#,
+            r#""1.234""#,
+            "12.5",
+            r#"{"amount": "100"}"#,
+            "null",
+        ] {
+            assert!(serde_json::from_str::<Money>(json).is_err(), "{json}");
+        }
     }
 }
@@ -1,79 +1,978 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::BTreeMap, fmt::Display};
 
+use chrono::{Days, Months, NaiveDate};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
-use crate::{money, Money};
+use crate::{
+    events::{Event, LogEntry},
+    money::{self, PercentOrMoney},
+    Money,
+};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct Invoice {
-    pub(crate) date: String,
-    pub(crate) amount: Money,
-    pub(crate) rate: Option<Rate>,
-    pub(crate) customer: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    /// Stays the same when other invoices are added or deleted. Starts at 1, 0 until assigned.
+    #[serde(default)]
+    pub id: usize,
+    pub date: String,
+    pub amount: Money,
+    pub rate: Option<Rate>,
+    /// Name of the rate the invoice was added with
+    pub rate_name: Option<String>,
+    pub customer: Option<String>,
+    /// Costs billed on to the customer on this invoice
+    #[serde(default)]
+    pub expenses: Vec<Expense>,
+    #[serde(default)]
+    pub scope: Scope,
+    /// Value in the base currency of one unit of the rate's currency, when the invoice was added
+    pub exchange_rate: Option<Money>,
+    /// Hours billed at one of the rate's tiers, on top of amount
+    #[serde(default)]
+    pub tier_hours: BTreeMap<String, Money>,
+    /// Invoice number, e.g. ACME-2025-007, if an invoice prefix was set when it was added
+    pub number: Option<String>,
+    /// Date the invoice was paid
+    pub paid: Option<NaiveDate>,
+    /// VAT rate in percent, charged on top of the total
+    pub vat: Option<Money>,
+    /// ID of the transaction it was imported from, e.g. a Stripe charge, so it isn't imported twice
+    pub reference: Option<String>,
+    /// Date the latest reminder of it was sent, if it was overdue
+    pub reminded: Option<NaiveDate>,
+}
+
+/// Whether an entry belongs to the business or private finances
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum Scope {
+    #[default]
+    Business,
+    Personal,
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::Business => write!(f, "business"),
+            Scope::Personal => write!(f, "personal"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Expense {
+    pub name: String,
+    pub amount: Money,
+    /// ID of the cost billed, unmarked as billed again if the invoice is deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<usize>,
+}
+
+impl Invoice {
+    /// Amount, times rate if there is one, without expenses
+    #[must_use]
+    pub fn work(&self) -> Money {
+        match &self.rate {
+            Some(rate) => {
+                self.amount * rate.rate
+                    + self
+                        .tier_hours
+                        .iter()
+                        .map(|(tier, hours)| *hours * rate.tier_rate(tier))
+                        .sum()
+            }
+            None => self.amount,
+        }
+    }
+
+    /// Work converted to the base currency
+    #[must_use]
+    pub fn base_work(&self) -> Money {
+        match self.exchange_rate {
+            Some(exchange_rate) => self.work() * exchange_rate,
+            None => self.work(),
+        }
+    }
+
+    /// Hours billed, including those at tiers, if the invoice has a rate
+    #[must_use]
+    pub fn hours(&self) -> Option<Money> {
+        self.rate.as_ref()?;
+        Some(self.amount + self.tier_hours.values().copied().sum())
+    }
+
+    /// Currency of the invoice, if it is not in the base currency
+    #[must_use]
+    pub fn currency(&self) -> Option<&str> {
+        self.rate.as_ref().and_then(|rate| rate.currency.as_deref())
+    }
+
+    #[must_use]
+    pub fn sum_expenses(&self) -> Money {
+        self.expenses.iter().map(|expense| expense.amount).sum()
+    }
+
+    /// Total in the invoice's currency
+    #[must_use]
+    pub fn total(&self) -> Money {
+        self.work() + self.sum_expenses()
+    }
+
+    /// Total converted to the base currency
+    #[must_use]
+    pub fn base_total(&self) -> Money {
+        match self.exchange_rate {
+            Some(exchange_rate) => self.total() * exchange_rate,
+            None => self.total(),
+        }
+    }
+
+    /// VAT charged on top of the total, in the base currency
+    #[must_use]
+    pub fn vat(&self) -> Money {
+        self.vat
+            .map(|rate| self.base_total() * rate / 100)
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn parsed_date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").ok()
+    }
+
+    /// " EUR" for an invoice in EUR, nothing for one in the base currency
+    #[must_use]
+    pub fn currency_suffix(&self) -> String {
+        self.currency()
+            .map(|currency| format!(" {currency}"))
+            .unwrap_or_default()
+    }
+
+    /// How the total adds up, e.g. "150.00 * 900.00 + 300.00 expenses", if it is more than the amount
+    #[must_use]
+    pub fn breakdown(&self) -> Option<String> {
+        let expenses = if self.expenses.is_empty() {
+            String::new()
+        } else {
+            format!(" + {} expenses", self.sum_expenses())
+        };
+        let currency = self.currency_suffix();
+        match &self.rate {
+            Some(rate) => {
+                let tiers = self
+                    .tier_hours
+                    .iter()
+                    .map(|(tier, hours)| {
+                        format!(" + {hours} {tier} * {}{currency}", rate.tier_rate(tier))
+                    })
+                    .collect::<Vec<_>>()
+                    .concat();
+                Some(format!(
+                    "{} * {}{currency}{tiers}{expenses}",
+                    self.amount, rate.rate
+                ))
+            }
+            None if !self.expenses.is_empty() => Some(format!("{}{expenses}", self.amount)),
+            None => None,
+        }
+    }
+
+    /// Same date, amount, and customer, which is most likely the same invoice entered twice
+    #[must_use]
+    pub fn is_duplicate_of(&self, other: &Invoice) -> bool {
+        self.date == other.date && self.amount == other.amount && self.customer == other.customer
+    }
 }
 
 impl Display for Invoice {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let amount = match self.rate {
-            Some(rate) => format!(
-                "{} ({} * {})",
-                rate.rate * self.amount,
-                self.amount,
-                rate.rate,
-            ),
+        let amount = match self.breakdown() {
+            Some(breakdown) => format!("{}{} ({breakdown})", self.total(), self.currency_suffix()),
             None => format!("{}", self.amount),
         };
-        if self.customer.is_some() {
+        if let Some(number) = &self.number {
+            write!(f, "{number} ")?;
+        }
+        if let Some(customer) = &self.customer {
+            write!(f, "{}: {} ({})", self.date, amount, customer)?;
+        } else {
+            write!(f, "{}: {}", self.date, amount)?;
+        }
+        if self.scope == Scope::Personal {
+            write!(f, " (personal)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[allow(clippy::struct_field_names)]
+pub struct Rate {
+    pub rate: Money,
+    /// Currency code, if the rate is not in the base currency
+    pub currency: Option<String>,
+    /// Multipliers of the rate for e.g. overtime or weekends, by tier name
+    #[serde(default)]
+    pub tiers: BTreeMap<String, Money>,
+    /// Hours are billed in blocks of this size, e.g. 0.25 for 15 minutes
+    pub increment: Option<Money>,
+}
+
+impl Rate {
+    /// Hours rounded up to the rate's billing increment
+    #[must_use]
+    pub fn billable_hours(&self, hours: Money) -> Money {
+        match self.increment {
+            Some(increment) => hours.round_up_to(increment),
+            None => hours,
+        }
+    }
+
+    /// The rate for hours in a tier, or the plain rate if there is no such tier
+    #[must_use]
+    pub fn tier_rate(&self, tier: &str) -> Money {
+        match self.tiers.get(tier) {
+            Some(multiplier) => self.rate * *multiplier,
+            None => self.rate,
+        }
+    }
+}
+
+impl Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.currency {
+            Some(currency) => write!(f, "{} {currency}", self.rate)?,
+            None => write!(f, "{}", self.rate)?,
+        }
+        if !self.tiers.is_empty() {
+            let tiers: Vec<_> = self
+                .tiers
+                .iter()
+                .map(|(tier, multiplier)| format!("{tier} {multiplier}x"))
+                .collect();
+            write!(f, " ({})", tiers.join(", "))?;
+        }
+        if let Some(increment) = self.increment {
+            write!(f, " (billed per {increment} hours)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cost {
+    /// Stays the same when other costs are added or deleted. Starts at 1, 0 until assigned.
+    #[serde(default)]
+    pub id: usize,
+    pub date: String,
+    pub amount: Money,
+    pub name: String,
+    /// Generated by a monthly cost, rather than entered as a one-off
+    #[serde(default)]
+    pub recurring: bool,
+    /// Deductible VAT included in amount, as a rate or a fixed amount
+    pub vat: Option<PercentOrMoney>,
+    pub subscription: Option<Subscription>,
+    pub category: Option<String>,
+    pub billable: Option<Billable>,
+    /// Customer the cost was spent serving, for profitability, whether or not it's billed on
+    pub customer: Option<String>,
+    pub note: Option<String>,
+    #[serde(default)]
+    pub scope: Scope,
+    /// ID of the transaction it was imported from, e.g. a PayPal fee, so it isn't imported twice
+    pub reference: Option<String>,
+}
+
+/// A cost to be billed on to a customer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Billable {
+    pub customer: String,
+    /// Whether the cost has been added to an invoice
+    #[serde(default)]
+    pub billed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum Interval {
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Interval {
+    #[must_use]
+    pub fn months(self) -> u32 {
+        match self {
+            Interval::Monthly => 1,
+            Interval::Quarterly => 3,
+            Interval::Yearly => 12,
+        }
+    }
+
+    #[must_use]
+    pub fn per_year(self) -> i64 {
+        12 / i64::from(self.months())
+    }
+
+    /// The period a date starting with YYYY-MM falls in, e.g. "2025-05", "2025-Q2" or "2025"
+    #[must_use]
+    pub fn period_of(self, date: &str) -> String {
+        let year = date.get(0..4).unwrap_or(date);
+        let month: u32 = date.get(5..7).and_then(|m| m.parse().ok()).unwrap_or(1);
+        match self {
+            Interval::Monthly => format!("{year}-{month:02}"),
+            Interval::Quarterly => format!("{year}-Q{}", month.div_ceil(3)),
+            Interval::Yearly => year.to_string(),
+        }
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interval::Monthly => write!(f, "monthly"),
+            Interval::Quarterly => write!(f, "quarterly"),
+            Interval::Yearly => write!(f, "yearly"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Subscription {
+    pub interval: Interval,
+    pub renews: NaiveDate,
+}
+
+impl Subscription {
+    /// First renewal on or after the given date
+    #[must_use]
+    pub fn next_renewal(self, today: NaiveDate) -> NaiveDate {
+        let mut renews = self.renews;
+        while renews < today {
+            renews = renews + Months::new(self.interval.months());
+        }
+        renews
+    }
+}
+
+impl Cost {
+    /// Same date, amount, and name, which is most likely the same cost entered twice
+    #[must_use]
+    pub fn is_duplicate_of(&self, other: &Cost) -> bool {
+        self.date == other.date && self.amount == other.amount && self.name == other.name
+    }
+
+    #[must_use]
+    pub fn vat(&self) -> Money {
+        self.vat
+            .map(|vat| vat.vat_of(self.amount))
+            .unwrap_or_default()
+    }
+
+    /// Amount excluding deductible VAT
+    #[must_use]
+    pub fn net(&self) -> Money {
+        self.amount - self.vat()
+    }
+
+    /// The customer the cost is attributed to, or the one it's billable to
+    #[must_use]
+    pub fn attributed_to(&self) -> Option<&str> {
+        self.customer.as_deref().or(self
+            .billable
+            .as_ref()
+            .map(|billable| billable.customer.as_str()))
+    }
+}
+
+impl Display for Cost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.vat.is_some() {
             write!(
                 f,
-                "{}: {} ({})",
+                "{} {} (VAT {}) {}",
                 self.date,
-                amount,
-                self.customer.as_ref().unwrap()
-            )
+                self.amount,
+                self.vat(),
+                self.name
+            )?;
         } else {
-            write!(f, "{}: {}", self.date, amount)
+            write!(f, "{} {} {}", self.date, self.amount, self.name)?;
+        }
+        if let Some(category) = &self.category {
+            write!(f, " [{category}]")?;
         }
+        if let Some(subscription) = &self.subscription {
+            write!(f, " ({} subscription)", subscription.interval)?;
+        }
+        if self.scope == Scope::Personal {
+            write!(f, " (personal)")?;
+        }
+        if let Some(billable) = &self.billable {
+            if billable.billed {
+                write!(f, " (billed to {})", billable.customer)?;
+            } else {
+                write!(f, " (billable to {})", billable.customer)?;
+            }
+        }
+        if let Some(customer) = &self.customer {
+            write!(f, " (for {customer})")?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
-pub(crate) struct Rate {
-    pub(crate) rate: Money,
+/// A purchase that is expensed evenly over a number of years, starting the month it was bought
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub date: String,
+    pub amount: Money,
+    pub name: String,
+    pub years: u32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct Cost {
-    pub(crate) date: String,
-    pub(crate) amount: Money,
-    pub(crate) name: String,
+impl Asset {
+    fn months(&self) -> i64 {
+        i64::from(self.years) * 12
+    }
+
+    #[must_use]
+    pub fn monthly_depreciation(&self) -> Money {
+        self.amount / self.months()
+    }
+
+    /// Depreciation expensed from purchase up to and including the given month
+    pub fn depreciation_until(&self, month: &str) -> Money {
+        month_number(month).map_or_else(Money::default, |end| self.depreciation_through(end))
+    }
+
+    /// Depreciation expensed in the given month
+    pub fn depreciation_in(&self, month: &str) -> Money {
+        month_number(month).map_or_else(Money::default, |end| {
+            self.depreciation_through(end) - self.depreciation_through(end - 1)
+        })
+    }
+
+    fn depreciation_through(&self, end: i64) -> Money {
+        let Some(start) = month_number(&self.date) else {
+            return Money::default();
+        };
+        let elapsed = (end - start + 1).clamp(0, self.months());
+        if elapsed == self.months() {
+            // The last month takes whatever rounding left over
+            self.amount
+        } else {
+            self.monthly_depreciation() * elapsed
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct Moneybag {
-    pub(crate) invoices: Vec<Invoice>,
-    pub(crate) rates: HashMap<String, Rate>,
-    pub(crate) costs: Vec<Cost>,
+impl Display for Asset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} ({} years, {}/month)",
+            self.date,
+            self.amount,
+            self.name,
+            self.years,
+            self.monthly_depreciation()
+        )
+    }
 }
 
-pub(crate) fn sum_costs(costs: &[Cost]) -> Money {
-    costs.iter().map(|cost| cost.amount).sum()
+/// The ID the next entry gets: the next one to give, unless an entry has it or a higher one
+fn next_id(ids: impl Iterator<Item = usize>, next: usize) -> usize {
+    ids.max().map_or(1, |id| id + 1).max(next)
 }
 
-pub(crate) fn sum_invoices(invoices: &[Invoice]) -> Money {
-    invoices
-        .iter()
-        .map(|invoice| {
-            if let Some(rate) = invoice.rate {
-                invoice.amount * rate.rate
-            } else {
-                invoice.amount
+/// Number unassigned IDs in order, from next or past the highest assigned one. Returns the ID
+/// to continue from, which stays next if there were none.
+fn assign_ids<'a>(ids: impl Iterator<Item = &'a mut usize>, next: usize) -> usize {
+    let (unassigned, assigned): (Vec<_>, Vec<_>) = ids.partition(|id| **id == 0);
+    if unassigned.is_empty() {
+        return next;
+    }
+    let highest = assigned.iter().map(|id| **id).max().unwrap_or(0);
+    let mut next = next.max(highest + 1);
+    for id in unassigned {
+        *id = next;
+        next += 1;
+    }
+    next
+}
+
+/// Months since year 0 of a date starting with YYYY-MM
+fn month_number(date: &str) -> Option<i64> {
+    let year: i64 = date.get(0..4)?.parse().ok()?;
+    let month: i64 = date.get(5..7)?.parse().ok()?;
+    Some(year * 12 + month - 1)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Moneybag {
+    pub invoices: Vec<Invoice>,
+    pub rates: BTreeMap<String, Rate>,
+    pub costs: Vec<Cost>,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+    /// Customers invoices and costs refer to, by name
+    #[serde(default)]
+    pub customers: BTreeMap<String, Customer>,
+    /// Spending limits, by cost category
+    #[serde(default)]
+    pub budgets: BTreeMap<String, Budget>,
+    #[serde(default)]
+    pub import_rules: Vec<ImportRule>,
+    #[serde(default)]
+    pub settings: Settings,
+    /// ID the next invoice gets, so the IDs of deleted invoices aren't reused
+    #[serde(default)]
+    pub next_invoice_id: usize,
+    /// ID the next cost gets
+    #[serde(default)]
+    pub next_cost_id: usize,
+    /// Every change made to the moneybag, kept in a file of its own next to it
+    #[serde(skip)]
+    pub log: Vec<LogEntry>,
+    /// Events of the command running, logged when it's done
+    #[serde(skip)]
+    pub pending: Vec<Event>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Customer {
+    pub note: Option<String>,
+    pub address: Option<String>,
+    pub email: Option<String>,
+    pub org_number: Option<String>,
+    pub vat_number: Option<String>,
+    /// Days from invoice date to due date
+    pub payment_terms: Option<u32>,
+    /// Prefix of this customer's invoice numbers, instead of the global one
+    pub invoice_prefix: Option<String>,
+}
+
+impl Display for Customer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let details: Vec<_> = [
+            self.address.clone(),
+            self.email.clone(),
+            self.org_number.as_ref().map(|org| format!("org {org}")),
+            self.vat_number.as_ref().map(|vat| format!("VAT {vat}")),
+            self.payment_terms.map(|days| format!("{days} days")),
+            self.invoice_prefix
+                .as_ref()
+                .map(|prefix| format!("numbered {prefix}")),
+            self.note.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        write!(f, "{}", details.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    pub amount: Money,
+    pub interval: Interval,
+}
+
+impl Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.amount, self.interval)
+    }
+}
+
+/// How much of a budget has been spent in one period
+#[derive(Debug, Serialize)]
+pub struct BudgetStatus {
+    pub category: String,
+    pub budget: Budget,
+    pub period: String,
+    pub spent: Money,
+}
+
+impl BudgetStatus {
+    #[must_use]
+    pub fn remaining(&self) -> Money {
+        self.budget.amount - self.spent
+    }
+
+    #[must_use]
+    pub fn is_exceeded(&self) -> bool {
+        self.remaining().is_negative()
+    }
+}
+
+impl Display for BudgetStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: spent {} of {} {}, ",
+            self.category, self.period, self.spent, self.budget.amount, self.budget.interval
+        )?;
+        if self.is_exceeded() {
+            write!(f, "{} over budget", -self.remaining())
+        } else {
+            write!(f, "{} remaining", self.remaining())
+        }
+    }
+}
+
+impl Moneybag {
+    /// Make a change, keeping its event to log when the command is done. Invoices and costs
+    /// added without an ID get the next free one, and changes that change nothing are left out.
+    pub fn change(&mut self, mut event: Event) {
+        match &mut event {
+            Event::InvoiceAdded { invoice } if invoice.id == 0 => {
+                invoice.id = next_id(
+                    self.invoices.iter().map(|invoice| invoice.id),
+                    self.next_invoice_id,
+                );
+            }
+            Event::CostAdded { cost } if cost.id == 0 => {
+                cost.id = next_id(self.costs.iter().map(|cost| cost.id), self.next_cost_id);
             }
+            _ => {}
+        }
+        if event.changes(self) {
+            event.apply(self);
+            self.pending.push(event);
+        }
+    }
+
+    /// Customers used to be free text on invoices, so create any that are referred to but missing
+    pub fn add_missing_customers(&mut self) {
+        let referenced = self
+            .invoices
+            .iter()
+            .filter_map(|invoice| invoice.customer.clone())
+            .chain(
+                self.costs
+                    .iter()
+                    .filter_map(|cost| cost.billable.as_ref())
+                    .map(|billable| billable.customer.clone()),
+            )
+            .chain(self.costs.iter().filter_map(|cost| cost.customer.clone()));
+        for name in referenced.collect::<Vec<_>>() {
+            if !self.customers.contains_key(&name) {
+                self.change(Event::CustomerSet {
+                    name,
+                    customer: Some(Customer::default()),
+                });
+            }
+        }
+    }
+
+    /// Give invoices and costs without an ID the next free one: those just added, and those
+    /// read from a file from before there were IDs
+    pub fn assign_ids(&mut self) {
+        self.next_invoice_id = assign_ids(
+            self.invoices.iter_mut().map(|invoice| &mut invoice.id),
+            self.next_invoice_id,
+        );
+        self.next_cost_id = assign_ids(
+            self.costs.iter_mut().map(|cost| &mut cost.id),
+            self.next_cost_id,
+        );
+    }
+
+    /// Delete an invoice, and unmark the expenses billed on it, so they can be billed again
+    pub fn delete_invoice(&mut self, index: usize) -> Invoice {
+        let invoice = self.invoices[index].clone();
+        self.change(Event::InvoiceDeleted { id: invoice.id });
+        for id in invoice.expenses.iter().filter_map(|expense| expense.cost) {
+            let Some(mut cost) = self.costs.iter().find(|cost| cost.id == id).cloned() else {
+                continue;
+            };
+            if let Some(billable) = &mut cost.billable {
+                billable.billed = false;
+                self.change(Event::CostEdited { cost });
+            }
+        }
+        invoice
+    }
+
+    /// Rename a rate, along with every invoice referring to it
+    pub fn rename_rate(&mut self, old: &str, new: &str) {
+        if let Some(rate) = self.rates.get(old).cloned() {
+            self.change(Event::RateSet {
+                name: new.to_string(),
+                rate: Some(rate),
+            });
+            self.change(Event::RateSet {
+                name: old.to_string(),
+                rate: None,
+            });
+        }
+        for mut invoice in self.invoices.clone() {
+            if invoice.rate_name.as_deref() == Some(old) {
+                invoice.rate_name = Some(new.to_string());
+                self.change(Event::InvoiceEdited { invoice });
+            }
+        }
+    }
+
+    /// Rename a customer, along with every invoice and cost referring to it
+    pub fn rename_customer(&mut self, old: &str, new: &str) {
+        if let Some(customer) = self.customers.get(old).cloned() {
+            self.change(Event::CustomerSet {
+                name: new.to_string(),
+                customer: Some(customer),
+            });
+            self.change(Event::CustomerSet {
+                name: old.to_string(),
+                customer: None,
+            });
+        }
+        let rename = |customer: &mut Option<String>| {
+            if customer.as_deref() == Some(old) {
+                *customer = Some(new.to_string());
+            }
+        };
+        for mut invoice in self.invoices.clone() {
+            rename(&mut invoice.customer);
+            self.change(Event::InvoiceEdited { invoice });
+        }
+        for mut cost in self.costs.clone() {
+            if let Some(billable) = &mut cost.billable {
+                if billable.customer == old {
+                    billable.customer = new.to_string();
+                }
+            }
+            rename(&mut cost.customer);
+            self.change(Event::CostEdited { cost });
+        }
+    }
+
+    /// Next number in the series of the customer's invoice prefix (or the global one) for the
+    /// year of date, e.g. ACME-2025-007. None if no prefix is set.
+    #[must_use]
+    pub fn next_invoice_number(&self, customer: Option<&str>, date: &str) -> Option<String> {
+        let prefix = customer
+            .and_then(|customer| self.customers.get(customer))
+            .and_then(|customer| customer.invoice_prefix.as_ref())
+            .or(self.settings.invoice_prefix.as_ref())?;
+        let year = date.get(..4).unwrap_or(date);
+        let series = format!("{prefix}-{year}-");
+        let last = self
+            .invoices
+            .iter()
+            .filter_map(|invoice| invoice.number.as_ref()?.strip_prefix(&series)?.parse().ok())
+            .max()
+            .unwrap_or(0u32);
+        Some(format!("{series}{:03}", last + 1))
+    }
+
+    /// Due date of an invoice, from its customer's payment terms
+    #[must_use]
+    pub fn due_date(&self, invoice: &Invoice) -> Option<NaiveDate> {
+        let customer = self.customers.get(invoice.customer.as_ref()?)?;
+        let days = customer.payment_terms?;
+        invoice
+            .parsed_date()?
+            .checked_add_days(Days::new(u64::from(days)))
+    }
+
+    /// Unpaid after its due date
+    #[must_use]
+    pub fn is_overdue(&self, invoice: &Invoice, today: NaiveDate) -> bool {
+        invoice.paid.is_none() && self.due_date(invoice).is_some_and(|due| due < today)
+    }
+
+    #[must_use]
+    pub fn is_customer_referenced(&self, customer: &str) -> bool {
+        self.invoices
+            .iter()
+            .any(|invoice| invoice.customer.as_deref() == Some(customer))
+            || self
+                .costs
+                .iter()
+                .any(|cost| cost.attributed_to() == Some(customer))
+    }
+    /// Budget status for a category in the period containing date, if it has a budget
+    pub fn budget_status(&self, category: &str, date: &str) -> Option<BudgetStatus> {
+        let (category, budget) = self.budgets.get_key_value(category)?;
+        let period = budget.interval.period_of(date);
+        let spent = self
+            .costs
+            .iter()
+            .filter(|cost| cost.category.as_deref() == Some(category))
+            .filter(|cost| budget.interval.period_of(&cost.date) == period)
+            .map(Cost::net)
+            .sum();
+        Some(BudgetStatus {
+            category: category.clone(),
+            budget: budget.clone(),
+            period,
+            spent,
         })
+    }
+
+    /// The latest cost of each subscription, by name, with its subscription. Monthly costs generate
+    /// one entry per month, so only the latest of each name counts.
+    #[must_use]
+    pub fn subscriptions(&self) -> Vec<(&Cost, Subscription)> {
+        let mut latest: BTreeMap<&str, (&Cost, Subscription)> = BTreeMap::new();
+        for cost in &self.costs {
+            if let Some(subscription) = cost.subscription {
+                match latest.get(cost.name.as_str()) {
+                    Some((existing, _)) if existing.date > cost.date => {}
+                    _ => {
+                        latest.insert(&cost.name, (cost, subscription));
+                    }
+                }
+            }
+        }
+        latest.into_values().collect()
+    }
+
+    /// Every category and period where spending has exceeded the budget
+    #[must_use]
+    pub fn exceeded_budgets(&self) -> Vec<BudgetStatus> {
+        let mut checked = vec![];
+        let mut exceeded = vec![];
+        for cost in &self.costs {
+            let Some(category) = &cost.category else {
+                continue;
+            };
+            if let Some(status) = self.budget_status(category, &cost.date) {
+                if !checked.contains(&(category, status.period.clone())) {
+                    checked.push((category, status.period.clone()));
+                    if status.is_exceeded() {
+                        exceeded.push(status);
+                    }
+                }
+            }
+        }
+        exceeded
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Mileage allowance per km
+    pub mileage_rate: Money,
+    /// Per diem allowance per day
+    pub per_diem: Money,
+    pub bank_columns: BankColumns,
+    /// Currency the books are kept in, that exchange rates are fetched to
+    pub base_currency: Option<String>,
+    /// Value in the base currency of one unit of each other currency
+    pub exchange_rates: BTreeMap<String, Money>,
+    /// Prefix of invoice numbers, for customers without a prefix of their own
+    pub invoice_prefix: Option<String>,
+    /// Income tax, in percent of profit after social fees
+    pub tax_rate: Money,
+    /// Social fees, in percent of profit
+    pub social_fees: Money,
+    /// Hours a month to bill, for report utilization
+    pub hours_target: Option<Money>,
+    /// Business revenue to invoice in a year
+    pub revenue_goal: Option<Money>,
+}
+
+impl Settings {
+    /// Exchange rate to store on an invoice in the given currency
+    ///
+    /// # Errors
+    ///
+    /// If the currency has no exchange rate set
+    pub fn exchange_rate_for(&self, currency: Option<&str>) -> Result<Option<Money>, String> {
+        match currency {
+            Some(currency) => match self.exchange_rates.get(currency) {
+                Some(exchange_rate) => Ok(Some(*exchange_rate)),
+                None => Err(format!(
+                    "No exchange rate for {currency}, set one with set exchange-rate {currency} <rate>"
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// Which columns of a bank CSV export hold what, counting from 0
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BankColumns {
+    pub date: usize,
+    pub description: usize,
+    pub amount: usize,
+    pub delimiter: char,
+}
+
+impl Default for BankColumns {
+    fn default() -> Self {
+        BankColumns {
+            date: 0,
+            description: 1,
+            amount: 2,
+            delimiter: ',',
+        }
+    }
+}
+
+/// Turns bank transactions whose description contains a pattern into costs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRule {
+    pub pattern: String,
+    pub name: String,
+    pub category: Option<String>,
+}
+
+impl ImportRule {
+    #[must_use]
+    pub fn matches(&self, description: &str) -> bool {
+        description
+            .to_lowercase()
+            .contains(&self.pattern.to_lowercase())
+    }
+}
+
+impl Display for ImportRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" -> {}", self.pattern, self.name)?;
+        if let Some(category) = &self.category {
+            write!(f, " [{category}]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Sum of costs excluding deductible VAT
+pub fn sum_costs<'a>(costs: impl IntoIterator<Item = &'a Cost>) -> Money {
+    costs.into_iter().map(Cost::net).sum()
+}
+
+pub fn sum_input_vat<'a>(costs: impl IntoIterator<Item = &'a Cost>) -> Money {
+    costs.into_iter().map(Cost::vat).sum()
+}
+
+/// Depreciation of all assets up to and including the given month
+#[must_use]
+pub fn sum_depreciation(assets: &[Asset], month: &str) -> Money {
+    assets
+        .iter()
+        .map(|asset| asset.depreciation_until(month))
         .sum()
 }
 
-pub(crate) fn average_invoice(invoices: &[Invoice]) -> Money {
+/// Sum of invoices in the base currency
+pub fn sum_invoices<'a>(invoices: impl IntoIterator<Item = &'a Invoice>) -> Money {
+    invoices.into_iter().map(Invoice::base_total).sum()
+}
+
+/// Average of invoices in the base currency, zero without any
+///
+/// # Panics
+///
+/// With more invoices than fit in an i64
+pub fn average_invoice<'a>(invoices: impl IntoIterator<Item = &'a Invoice>) -> Money {
+    let invoices: Vec<_> = invoices.into_iter().collect();
     let invoice_count = i64::try_from(invoices.len())
         .unwrap_or_else(|_| panic!("Having more than {} invoices is not supported", i64::MAX));
     if invoice_count != 0 {
@@ -82,3 +981,135 @@ pub(crate) fn average_invoice(invoices: &[Invoice]) -> Money {
         money::Money::default()
     }
 }
+
+/// The name that input refers to: the name itself, or the only one starting with it. Otherwise
+/// the names it might have meant, those starting with it or a couple of typos away.
+pub(crate) fn resolve_name<'a>(
+    names: impl IntoIterator<Item = &'a String>,
+    input: &str,
+) -> Result<String, Vec<String>> {
+    let names: Vec<_> = names.into_iter().collect();
+    if let Some(name) = names.iter().find(|name| **name == input) {
+        return Ok((*name).clone());
+    }
+    let lowercase = input.to_lowercase();
+    let prefixed: Vec<_> = names
+        .iter()
+        .filter(|name| name.to_lowercase().starts_with(&lowercase))
+        .map(|name| (*name).clone())
+        .collect();
+    if let [name] = prefixed.as_slice() {
+        return Ok(name.clone());
+    }
+    if !prefixed.is_empty() {
+        return Err(prefixed);
+    }
+    let typos = (input.chars().count() / 3).clamp(1, 3);
+    Err(names
+        .into_iter()
+        .filter(|name| edit_distance(&name.to_lowercase(), &lowercase) <= typos)
+        .cloned()
+        .collect())
+}
+
+/// Number of characters to add, remove, or change to turn a into b
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut previous: Vec<_> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let change = previous[j] + usize::from(a != *b);
+            current.push(change.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_resolve_name() {
+        let names = ["standard".to_string(), "support".to_string()];
+        assert_eq!(resolve_name(&names, "standard"), Ok("standard".to_string()));
+        assert_eq!(resolve_name(&names, "st"), Ok("standard".to_string()));
+        assert_eq!(
+            resolve_name(&names, "s"),
+            Err(vec!["standard".to_string(), "support".to_string()])
+        );
+        assert_eq!(
+            resolve_name(&names, "standrd"),
+            Err(vec!["standard".to_string()])
+        );
+        assert_eq!(resolve_name(&names, "hourly"), Err(vec![]));
+    }
+
+    #[test]
+    fn test_assign_ids() {
+        let mut moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [{"date": "2025-01-31", "amount": {"amount": 100}}],
+                "costs": [
+                    {"id": 4, "date": "2025-01", "amount": {"amount": 100}, "name": "rent"},
+                    {"date": "2025-02", "amount": {"amount": 100}, "name": "rent"}
+                ],
+                "next_cost_id": 7
+            }"#,
+        );
+        moneybag.assign_ids();
+        assert_eq!(moneybag.invoices[0].id, 1);
+        assert_eq!(moneybag.next_invoice_id, 2);
+        // IDs of deleted costs aren't reused
+        assert_eq!(moneybag.costs[0].id, 4);
+        assert_eq!(moneybag.costs[1].id, 7);
+        assert_eq!(moneybag.next_cost_id, 8);
+    }
+
+    #[test]
+    fn test_depreciation() {
+        let laptop = Asset {
+            date: "2025-03-10".to_string(),
+            amount: "1000".parse().unwrap(),
+            name: "laptop".to_string(),
+            years: 1,
+        };
+        assert_eq!(laptop.monthly_depreciation(), "83.33".parse().unwrap());
+        assert_eq!(laptop.depreciation_until("2025-02"), Money::default());
+        assert_eq!(
+            laptop.depreciation_until("2025-03"),
+            "83.33".parse().unwrap()
+        );
+        assert_eq!(
+            laptop.depreciation_until("2026-01"),
+            "916.63".parse().unwrap()
+        );
+        assert_eq!(
+            laptop.depreciation_until("2025-12"),
+            "833.30".parse().unwrap()
+        );
+        assert_eq!(
+            laptop.depreciation_until("2030-01"),
+            "1000".parse().unwrap()
+        );
+        assert_eq!(laptop.depreciation_in("2026-02"), "83.37".parse().unwrap());
+        assert_eq!(laptop.depreciation_in("2026-03"), Money::default());
+    }
+
+    #[test]
+    fn test_ids_past_a_stale_counter() {
+        let mut moneybag = fixtures::moneybag(
+            r#"{
+                "costs": [{"id": 5, "date": "2025-01", "amount": {"amount": 100}, "name": "rent"}],
+                "next_cost_id": 2
+            }"#,
+        );
+        let mut cost = moneybag.costs[0].clone();
+        cost.id = 0;
+        moneybag.change(Event::CostAdded { cost });
+        assert_eq!(moneybag.costs[1].id, 6);
+    }
+}
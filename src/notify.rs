@@ -0,0 +1,272 @@
+//! Notifications of what needs attention: invoices that became overdue, budgets exceeded this
+//! period, and subscriptions about to renew. Each is printed, and posted to a webhook or shown on
+//! the desktop as the `[notify]` table of the config says. What was notified of is kept in a file
+//! next to the moneybag, so each is only notified of once.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{error::MoneybagsError, moneybag::Moneybag};
+
+/// Where to send notifications, from the `[notify]` table of the config
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct Notify {
+    /// URL to post each notification to, as JSON with the text in `text`
+    pub(crate) webhook: Option<String>,
+    /// Show notifications on the desktop, with notify-send, or osascript on macOS
+    pub(crate) desktop: bool,
+    /// Days ahead to notify of subscriptions renewing
+    pub(crate) days: u32,
+    /// Notify when the shell starts
+    pub(crate) on_start: bool,
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Notify {
+            webhook: None,
+            desktop: false,
+            days: 7,
+            on_start: false,
+        }
+    }
+}
+
+/// Something that needs attention
+struct Notification {
+    /// Identifies what it's about, to notify of it only once
+    key: String,
+    event: &'static str,
+    text: String,
+}
+
+/// The file of what was notified of for a moneybag file: moneybag.json has moneybag.notified
+fn notified_path(filepath: &str) -> PathBuf {
+    Path::new(filepath).with_extension("notified")
+}
+
+/// What needs attention today
+fn notifications(moneybag: &Moneybag, days: u32, today: NaiveDate) -> Vec<Notification> {
+    let mut notifications = vec![];
+    for invoice in &moneybag.invoices {
+        let Some(due) = moneybag
+            .due_date(invoice)
+            .filter(|_| moneybag.is_overdue(invoice, today))
+        else {
+            continue;
+        };
+        notifications.push(Notification {
+            key: format!("overdue {}", invoice.id),
+            event: "invoice_overdue",
+            text: format!(
+                "Invoice {} to {}, {}{}, is overdue since {due}",
+                invoice.id,
+                invoice.customer.as_deref().unwrap_or_default(),
+                invoice.total(),
+                invoice.currency_suffix()
+            ),
+        });
+    }
+    let today_text = today.to_string();
+    for status in moneybag.exceeded_budgets() {
+        if status.period != status.budget.interval.period_of(&today_text) {
+            continue;
+        }
+        notifications.push(Notification {
+            key: format!("budget {} {}", status.category, status.period),
+            event: "budget_exceeded",
+            text: format!(
+                "Budget for {} is exceeded by {} in {}",
+                status.category,
+                -status.remaining(),
+                status.period
+            ),
+        });
+    }
+    for (cost, subscription) in moneybag.subscriptions() {
+        let renews = subscription.next_renewal(today);
+        let left = (renews - today).num_days();
+        if left > i64::from(days) {
+            continue;
+        }
+        notifications.push(Notification {
+            key: format!("renewal {} {renews}", cost.name),
+            event: "subscription_renewing",
+            text: format!(
+                "{} renews {renews} for {}, in {left} days",
+                cost.name, cost.amount
+            ),
+        });
+    }
+    notifications
+}
+
+#[cfg(feature = "http")]
+fn post(webhook: &str, notification: &Notification) -> Result<(), String> {
+    let body = serde_json::json!({
+        "event": notification.event,
+        "text": notification.text,
+    });
+    ureq::post(webhook)
+        .header("Content-Type", "application/json")
+        .send(body.to_string())
+        .map(|_| ())
+        .map_err(|e| format!("Could not post to {webhook}: {e}"))
+}
+
+fn show_on_desktop(notification: &Notification) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("osascript");
+        command.args([
+            "-e",
+            &format!(
+                "display notification {:?} with title \"moneybags\"",
+                notification.text
+            ),
+        ]);
+        command
+    } else {
+        let mut command = std::process::Command::new("notify-send");
+        command.args(["moneybags", &notification.text]);
+        command
+    };
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("Desktop notification failed with {status}")),
+        Err(e) => Err(format!("Could not show a desktop notification: {e}")),
+    }
+}
+
+/// Send what needs attention and hasn't been notified of, or all of it, and remember it as
+/// notified of. Only lists it, sending nothing and remembering nothing, with list. Fails, after
+/// sending the rest, if any couldn't be sent.
+pub(crate) fn handle_notify(
+    moneybag: &Moneybag,
+    notify: &Notify,
+    location: &str,
+    all: bool,
+    list: bool,
+    today: NaiveDate,
+) -> Result<(), MoneybagsError> {
+    let path = notified_path(location);
+    let notified: BTreeSet<String> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let notifications = notifications(moneybag, notify.days, today);
+    let mut still = BTreeSet::new();
+    let mut failed = vec![];
+    for notification in notifications {
+        if !all && notified.contains(&notification.key) {
+            still.insert(notification.key);
+            continue;
+        }
+        println!("{}", notification.text);
+        if list {
+            continue;
+        }
+        info!(
+            event = notification.event,
+            key = notification.key,
+            "Notifying"
+        );
+        let mut sent = Ok(());
+        if let Some(webhook) = &notify.webhook {
+            #[cfg(feature = "http")]
+            {
+                sent = sent.and(post(webhook, &notification));
+            }
+            #[cfg(not(feature = "http"))]
+            {
+                let _ = webhook;
+                return Err(crate::without("http"));
+            }
+        }
+        if notify.desktop {
+            sent = sent.and(show_on_desktop(&notification));
+        }
+        match sent {
+            Ok(()) => {
+                still.insert(notification.key);
+            }
+            // Not remembered, so it's sent again next time
+            Err(e) => failed.push(e),
+        }
+    }
+    if list {
+        return Ok(());
+    }
+    // Only what still needs attention is kept, so an invoice overdue again is notified of again
+    let written = serde_json::to_string(&still)
+        .map_err(std::io::Error::from)
+        .and_then(|json| std::fs::write(&path, json));
+    if let Err(e) = written {
+        warn!(path = %path.display(), error = %e, "Could not remember notifications");
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(MoneybagsError::Io(failed.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_notifications() {
+        let moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [
+                    {"id": 1, "date": "2025-04-01", "amount": "1000", "customer": "Acme"},
+                    {"id": 2, "date": "2025-05-01", "amount": "500", "customer": "Acme"}
+                ],
+                "costs": [
+                    {"date": "2025-05-02", "amount": "600", "name": "AWS", "category": "hosting"},
+                    {"date": "2024-05-20", "amount": "120", "name": "Domain",
+                     "subscription": {"interval": "Yearly", "renews": "2024-05-20"}}
+                ],
+                "customers": {"Acme": {"payment_terms": 30}},
+                "budgets": {"hosting": {"amount": "500", "interval": "Monthly"}}
+            }"#,
+        );
+        let today = NaiveDate::from_ymd_opt(2025, 5, 15).unwrap();
+        let keys: Vec<_> = notifications(&moneybag, 7, today)
+            .into_iter()
+            .map(|notification| notification.key)
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "overdue 1",
+                "budget hosting 2025-05",
+                "renewal Domain 2025-05-20"
+            ]
+        );
+        assert_eq!(notifications(&moneybag, 3, today).len(), 2);
+        assert!(notifications(&Moneybag::default(), 7, today).is_empty());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_post() {
+        let port = fixtures::closed_port();
+        let notification = Notification {
+            key: "overdue 1".to_string(),
+            event: "overdue",
+            text: "Invoice 1 is overdue".to_string(),
+        };
+        let webhook = format!("http://127.0.0.1:{port}/hook");
+        let error = post(&webhook, &notification).unwrap_err();
+        assert!(error.starts_with("Could not post to"), "{error}");
+    }
+}
@@ -0,0 +1,395 @@
+use std::{
+    fmt::Display,
+    process::ExitCode,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+use serde::{ser::SerializeStruct, Serialize};
+
+use crate::{
+    i18n::translate,
+    moneybag::{Cost, Invoice, Moneybag, Rate},
+};
+
+/// A list entry along with its index, for the indices commands take
+#[derive(Serialize)]
+pub(crate) struct Indexed<'a, T> {
+    pub(crate) index: usize,
+    #[serde(flatten)]
+    pub(crate) entry: &'a T,
+}
+
+/// A map entry along with its name
+#[derive(Serialize)]
+pub(crate) struct Named<'a, T> {
+    pub(crate) name: &'a str,
+    #[serde(flatten)]
+    pub(crate) entry: &'a T,
+}
+
+pub(crate) fn print_json(value: &impl Serialize) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("Could not serialize output")
+    );
+}
+
+/// Print a value with its label, translated
+pub(crate) fn print_field(label: &'static str, value: impl Display) {
+    println!("{}: {value}", translate(label));
+}
+
+/// Ways a command can fail, each with its own exit code, and an identifier printed with the error
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Failure {
+    /// The command couldn't be parsed
+    Parse = 2,
+    /// An entry it refers to doesn't exist
+    NotFound = 3,
+    /// A file couldn't be read or written, or another system reached
+    Io = 4,
+    /// It would make the moneybag inconsistent, or needs something set up first
+    Invalid = 5,
+}
+
+impl Failure {
+    /// Identifier printed with the error, e.g. "not-found"
+    #[must_use]
+    pub fn id(self) -> &'static str {
+        match self {
+            Failure::Parse => "parse",
+            Failure::NotFound => "not-found",
+            Failure::Io => "io",
+            Failure::Invalid => "invalid",
+        }
+    }
+
+    pub(crate) fn exit_code(self) -> ExitCode {
+        ExitCode::from(self as u8)
+    }
+}
+
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Turn ANSI colors on or off for everything printed from now on. Off by default.
+pub(crate) fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+/// How much commands say about what they did
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) enum Verbosity {
+    /// Only output that was asked for, like listings, and errors
+    Quiet,
+    Normal,
+    /// Also every change a command made
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+pub(crate) fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub(crate) fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+/// Print a message about what a command did, unless quiet
+pub(crate) fn note(message: &str) {
+    if verbosity() > Verbosity::Quiet {
+        println!("{message}");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Style {
+    Plain,
+    Bold,
+    Red,
+    Yellow,
+}
+
+impl Style {
+    /// Text wrapped in the style's ANSI codes, if colors are on
+    pub(crate) fn paint(self, text: &str) -> String {
+        let code = match self {
+            Style::Plain => return text.to_string(),
+            Style::Bold => "1",
+            Style::Red => "31",
+            Style::Yellow => "33",
+        };
+        if COLOR.load(Ordering::Relaxed) {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Align {
+    Left,
+    Right,
+}
+
+/// Rows printed as aligned columns under a header. Columns that are empty in every row are left out.
+#[derive(Debug)]
+pub(crate) struct Table {
+    columns: Vec<(&'static str, Align)>,
+    rows: Vec<Vec<String>>,
+    styles: Vec<Style>,
+}
+
+impl Table {
+    /// A table with the given column headers, translated
+    pub(crate) fn new(columns: &[(&'static str, Align)]) -> Self {
+        Table {
+            columns: columns
+                .iter()
+                .map(|&(header, align)| (translate(header), align))
+                .collect(),
+            rows: vec![],
+            styles: vec![],
+        }
+    }
+
+    pub(crate) fn add_row(&mut self, row: Vec<String>) {
+        self.add_styled_row(row, Style::Plain);
+    }
+
+    pub(crate) fn add_styled_row(&mut self, row: Vec<String>, style: Style) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+        self.styles.push(style);
+    }
+
+    pub(crate) fn print(&self) {
+        print!("{self}");
+    }
+}
+
+impl Table {
+    /// Headers and rows of the columns that aren't empty in every row
+    pub(crate) fn visible(&self) -> (Vec<(&'static str, Align)>, Vec<Vec<String>>) {
+        let shown: Vec<_> = (0..self.columns.len())
+            .filter(|&i| self.rows.iter().any(|row| !row[i].is_empty()))
+            .collect();
+        let columns = shown.iter().map(|&i| self.columns[i]).collect();
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| shown.iter().map(|&i| row[i].clone()).collect())
+            .collect();
+        (columns, rows)
+    }
+
+    /// The table as text without colors
+    pub(crate) fn plain(&self) -> String {
+        let mut text = String::new();
+        self.render(&mut text, false)
+            .expect("Writing to a string can't fail");
+        text
+    }
+
+    fn render(&self, f: &mut impl std::fmt::Write, color: bool) -> std::fmt::Result {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let (columns, rows) = self.visible();
+        let widths: Vec<_> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, (header, _))| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([header.chars().count()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let header: Vec<_> = columns
+            .iter()
+            .map(|(header, _)| (*header).to_string())
+            .collect();
+        let rows = std::iter::once((&header, Style::Bold))
+            .chain(rows.iter().zip(self.styles.iter().copied()));
+        for (row, style) in rows {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(&columns)
+                .zip(&widths)
+                .map(|((cell, (_, align)), &width)| match align {
+                    Align::Left => format!("{cell:<width$}"),
+                    Align::Right => format!("{cell:>width$}"),
+                })
+                .collect();
+            let line = cells.join("  ");
+            let line = line.trim_end();
+            if color {
+                writeln!(f, "{}", style.paint(line))?;
+            } else {
+                writeln!(f, "{line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(f, true)
+    }
+}
+
+/// Headers and rows of the visible columns
+impl Serialize for Table {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (columns, rows) = self.visible();
+        let headers: Vec<_> = columns.into_iter().map(|(header, _)| header).collect();
+        let mut table = serializer.serialize_struct("Table", 2)?;
+        table.serialize_field("columns", &headers)?;
+        table.serialize_field("rows", &rows)?;
+        table.end()
+    }
+}
+
+pub(crate) fn csv_writer() -> csv::Writer<std::io::Stdout> {
+    csv::Writer::from_writer(std::io::stdout())
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+pub(crate) fn print_rates_csv<'a>(rates: impl Iterator<Item = (&'a String, &'a Rate)>) {
+    let mut writer = csv_writer();
+    writer
+        .write_record(["name", "rate", "currency", "increment"])
+        .expect("Could not write CSV");
+    for (name, rate) in rates {
+        writer
+            .write_record([
+                name.clone(),
+                rate.rate.to_string(),
+                optional(rate.currency.as_ref()),
+                optional(rate.increment),
+            ])
+            .expect("Could not write CSV");
+    }
+    writer.flush().expect("Could not write CSV");
+}
+
+pub(crate) fn print_invoices_csv<'a>(
+    invoices: impl Iterator<Item = &'a Invoice>,
+    moneybag: &Moneybag,
+) {
+    let mut writer = csv_writer();
+    writer
+        .write_record([
+            "id",
+            "number",
+            "date",
+            "customer",
+            "amount",
+            "rate",
+            "currency",
+            "expenses",
+            "total",
+            "base_total",
+            "due_date",
+            "scope",
+        ])
+        .expect("Could not write CSV");
+    for invoice in invoices {
+        writer
+            .write_record([
+                invoice.id.to_string(),
+                optional(invoice.number.as_ref()),
+                invoice.date.clone(),
+                optional(invoice.customer.as_ref()),
+                invoice.amount.to_string(),
+                optional(invoice.rate.as_ref().map(|rate| rate.rate)),
+                optional(invoice.currency()),
+                invoice.sum_expenses().to_string(),
+                invoice.total().to_string(),
+                invoice.base_total().to_string(),
+                optional(moneybag.due_date(invoice)),
+                invoice.scope.to_string(),
+            ])
+            .expect("Could not write CSV");
+    }
+    writer.flush().expect("Could not write CSV");
+}
+
+pub(crate) fn print_costs_csv<'a>(costs: impl Iterator<Item = &'a Cost>) {
+    let mut writer = csv_writer();
+    writer
+        .write_record([
+            "id",
+            "date",
+            "amount",
+            "vat",
+            "name",
+            "category",
+            "subscription",
+            "billable",
+            "note",
+            "scope",
+        ])
+        .expect("Could not write CSV");
+    for cost in costs {
+        writer
+            .write_record([
+                cost.id.to_string(),
+                cost.date.clone(),
+                cost.amount.to_string(),
+                cost.vat().to_string(),
+                cost.name.clone(),
+                optional(cost.category.as_ref()),
+                optional(cost.subscription.map(|subscription| subscription.interval)),
+                optional(cost.billable.as_ref().map(|billable| &billable.customer)),
+                optional(cost.note.as_ref()),
+                cost.scope.to_string(),
+            ])
+            .expect("Could not write CSV");
+    }
+    writer.flush().expect("Could not write CSV");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new(&[
+            ("#", Align::Right),
+            ("name", Align::Left),
+            ("note", Align::Left),
+            ("amount", Align::Right),
+        ]);
+        table.add_row(vec![
+            "0".into(),
+            "rent".into(),
+            String::new(),
+            "12000.00".into(),
+        ]);
+        table.add_row(vec![
+            "1".into(),
+            "vpn".into(),
+            String::new(),
+            "129.00".into(),
+        ]);
+        assert_eq!(
+            table.to_string(),
+            "#  name    amount\n0  rent  12000.00\n1  vpn     129.00\n"
+        );
+        assert_eq!(Table::new(&[("#", Align::Right)]).to_string(), "");
+    }
+}
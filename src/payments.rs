@@ -0,0 +1,334 @@
+//! Importing what Stripe and PayPal paid out, from their CSV exports. Payments received mark
+//! a matching unpaid invoice paid, or become paid invoices of their own, and the fees taken become
+//! costs. Entries keep the transaction ID as their reference, so an export can be imported again.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::{
+    error::MoneybagsError,
+    events::Event,
+    import::parse_bank_amount,
+    moneybag::{Cost, Invoice, Moneybag, Scope},
+    Money,
+};
+
+/// Where payments were taken
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Provider {
+    Stripe,
+    Paypal,
+}
+
+impl Provider {
+    fn name(self) -> &'static str {
+        match self {
+            Provider::Stripe => "Stripe",
+            Provider::Paypal => "PayPal",
+        }
+    }
+}
+
+/// One row of a CSV export, by the names of its columns
+pub(crate) struct Row<'a> {
+    pub(crate) columns: &'a HashMap<String, usize>,
+    pub(crate) record: &'a csv::StringRecord,
+}
+
+impl Row<'_> {
+    pub(crate) fn get(&self, column: &str) -> &str {
+        self.columns
+            .get(column)
+            .and_then(|&i| self.record.get(i))
+            .unwrap_or_default()
+            .trim()
+    }
+
+    fn amount(&self, column: &str) -> Money {
+        parse_bank_amount(self.get(column)).unwrap_or_default()
+    }
+}
+
+/// A payment received, or a fee taken on its own
+struct Payment {
+    id: String,
+    date: String,
+    /// Paid by the customer, less refunded if negative
+    gross: Money,
+    fee: Money,
+    /// Name or email of who paid
+    payers: Vec<String>,
+}
+
+/// The payment in a row of a Stripe balance transactions export, or None if it's not one,
+/// like a payout to the bank
+fn stripe_payment(row: &Row) -> Option<Payment> {
+    let (gross, fee) = match row.get("Type") {
+        "charge" | "payment" | "refund" | "payment_refund" => {
+            (row.amount("Amount"), row.amount("Fee"))
+        }
+        // Stripe's own fees, e.g. for Billing, charged to the balance
+        "stripe_fee" | "tax_fee" => (Money::default(), -row.amount("Amount")),
+        _ => return None,
+    };
+    Some(Payment {
+        id: row.get("id").to_string(),
+        date: row.get("Created (UTC)").chars().take(10).collect(),
+        gross,
+        fee,
+        payers: vec![row.get("Customer Email").to_string()],
+    })
+}
+
+/// The payment in a row of a PayPal activity export, or None if it's not one. Only completed
+/// payments received count; money sent and transfers to the bank are left out.
+fn paypal_payment(row: &Row) -> Option<Payment> {
+    let gross = row.amount("Gross");
+    let kind = row.get("Type").to_lowercase();
+    if row.get("Status") != "Completed"
+        || ["transfer", "withdraw", "conversion", "hold"]
+            .iter()
+            .any(|word| kind.contains(word))
+        || (gross.is_negative() && !kind.contains("refund"))
+    {
+        return None;
+    }
+    Some(Payment {
+        id: row.get("Transaction ID").to_string(),
+        date: paypal_date(row.get("Date"))?,
+        gross,
+        fee: -row.amount("Fee"),
+        payers: vec![
+            row.get("Name").to_string(),
+            row.get("From Email Address").to_string(),
+        ],
+    })
+}
+
+/// PayPal writes dates as set for the account, e.g. 2025-05-12, 05/12/2025, or 12.05.2025
+fn paypal_date(date: &str) -> Option<String> {
+    ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+        .map(|date| date.to_string())
+}
+
+/// Import an export from a payment provider. Payments go to the customer given, or the customer
+/// whose name or email the payer has.
+pub(crate) fn import_payments(
+    provider: Provider,
+    path: &str,
+    customer: Option<&str>,
+    moneybag: &mut Moneybag,
+) -> Result<Vec<String>, MoneybagsError> {
+    let import_error = |source| MoneybagsError::Import {
+        path: path.to_string(),
+        source,
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(import_error)?;
+    let columns: HashMap<_, _> = reader
+        .headers()
+        .map_err(import_error)?
+        .iter()
+        .enumerate()
+        .map(|(i, header)| (header.trim().to_string(), i))
+        .collect();
+    let (mut paid, mut added, mut fees, mut skipped) = (0, 0, 0, 0);
+    for record in reader.records() {
+        let record = record.map_err(import_error)?;
+        let row = Row {
+            columns: &columns,
+            record: &record,
+        };
+        let payment = match provider {
+            Provider::Stripe => stripe_payment(&row),
+            Provider::Paypal => paypal_payment(&row),
+        };
+        let Some(payment) = payment.filter(|payment| !payment.id.is_empty()) else {
+            continue;
+        };
+        let reference = Some(payment.id.clone());
+        if moneybag
+            .invoices
+            .iter()
+            .any(|invoice| invoice.reference == reference)
+            || moneybag
+                .costs
+                .iter()
+                .any(|cost| cost.reference == reference)
+        {
+            skipped += 1;
+            continue;
+        }
+        if !payment.gross.is_zero() {
+            let customer = customer
+                .map(str::to_string)
+                .or_else(|| payer(&payment, moneybag));
+            if record_payment(&payment, customer, moneybag) {
+                paid += 1;
+            } else {
+                added += 1;
+            }
+        }
+        if !payment.fee.is_zero() {
+            moneybag.change(Event::CostAdded {
+                cost: fee(&payment, provider),
+            });
+            fees += 1;
+        }
+    }
+    moneybag.add_missing_customers();
+    Ok(vec![format!(
+        "Imported {} payments ({paid} marking invoices paid) and {fees} fees, skipped {skipped} \
+         imported before",
+        paid + added
+    )])
+}
+
+/// The customer whose name or email is one of the payment's payers
+fn payer(payment: &Payment, moneybag: &Moneybag) -> Option<String> {
+    let is_payer = |text: &str| {
+        payment
+            .payers
+            .iter()
+            .any(|payer| !payer.is_empty() && payer.eq_ignore_ascii_case(text))
+    };
+    moneybag
+        .customers
+        .iter()
+        .find(|(name, customer)| is_payer(name) || customer.email.as_deref().is_some_and(is_payer))
+        .map(|(name, _)| name.clone())
+}
+
+/// Mark the oldest unpaid invoice for the amount to the customer paid, and return true, or add a
+/// paid invoice for it and return false
+fn record_payment(payment: &Payment, customer: Option<String>, moneybag: &mut Moneybag) -> bool {
+    let date = NaiveDate::parse_from_str(&payment.date, "%Y-%m-%d").ok();
+    let unpaid = moneybag.invoices.iter().find(|invoice| {
+        invoice.paid.is_none()
+            && invoice.base_total() + invoice.vat() == payment.gross
+            && (customer.is_none() || invoice.customer == customer)
+    });
+    if let Some(invoice) = unpaid {
+        let invoice = Invoice {
+            paid: date,
+            reference: Some(payment.id.clone()),
+            ..invoice.clone()
+        };
+        moneybag.change(Event::InvoiceEdited { invoice });
+        return true;
+    }
+    let invoice = Invoice {
+        id: 0,
+        date: payment.date.clone(),
+        amount: payment.gross,
+        rate: None,
+        rate_name: None,
+        customer,
+        expenses: vec![],
+        scope: Scope::Business,
+        exchange_rate: None,
+        tier_hours: std::collections::BTreeMap::new(),
+        number: None,
+        paid: date,
+        vat: None,
+        reference: Some(payment.id.clone()),
+        reminded: None,
+    };
+    moneybag.change(Event::InvoiceAdded { invoice });
+    false
+}
+
+fn fee(payment: &Payment, provider: Provider) -> Cost {
+    Cost {
+        id: 0,
+        date: payment.date.clone(),
+        amount: payment.fee,
+        name: format!("{} fee", provider.name()),
+        recurring: false,
+        vat: None,
+        subscription: None,
+        category: Some("fees".to_string()),
+        billable: None,
+        customer: None,
+        note: None,
+        scope: Scope::Business,
+        reference: Some(payment.id.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_import_payments() {
+        let path = std::env::temp_dir().join("moneybags-test-stripe.csv");
+        std::fs::write(
+            &path,
+            "id,Type,Source,Amount,Fee,Net,Currency,Created (UTC),Description,Customer Email\n\
+             txn_1,charge,ch_1,1250.00,36.50,1213.50,sek,2025-05-02 10:00,Invoice 1,ap@acme.com\n\
+             txn_2,charge,ch_2,300.00,9.00,291.00,sek,2025-05-03 10:00,Workshop,\n\
+             txn_3,payout,po_1,-1504.50,0.00,-1504.50,sek,2025-05-05 10:00,Payout,\n",
+        )
+        .unwrap();
+        let mut moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [{"id": 1, "date": "2025-04-30", "amount": "1000", "customer": "Acme",
+                              "vat": "25"}],
+                "customers": {"Acme": {"email": "ap@acme.com"}}
+            }"#,
+        );
+        let path = path.to_str().unwrap();
+        import_payments(Provider::Stripe, path, None, &mut moneybag).unwrap();
+        import_payments(Provider::Stripe, path, None, &mut moneybag).unwrap();
+        assert_eq!(moneybag.invoices.len(), 2);
+        assert_eq!(moneybag.invoices[0].reference.as_deref(), Some("txn_1"));
+        assert!(moneybag.invoices[0].paid.is_some());
+        assert_eq!(moneybag.invoices[1].amount, "300".parse().unwrap());
+        assert_eq!(moneybag.invoices[1].customer, None);
+        let fees: Money = moneybag.costs.iter().map(|cost| cost.amount).sum();
+        assert_eq!(fees, "45.50".parse().unwrap());
+        assert_eq!(paypal_date("05/12/2025").as_deref(), Some("2025-05-12"));
+    }
+
+    #[test]
+    fn test_unusable_exports() {
+        let mut moneybag = Moneybag::default();
+        let missing = std::env::temp_dir().join("moneybags-test-missing.csv");
+        assert!(matches!(
+            import_payments(
+                Provider::Paypal,
+                missing.to_str().unwrap(),
+                None,
+                &mut moneybag
+            ),
+            Err(MoneybagsError::Import { .. })
+        ));
+        let path = std::env::temp_dir().join("moneybags-test-paypal.csv");
+        std::fs::write(
+            &path,
+            "Date,Name,Type,Status,Gross,Fee,Transaction ID,From Email Address
+             someday,Acme,Payment,Completed,100.00,-3.00,TX1,ap@acme.com
+             2025-05-02,Acme,Payment,Pending,100.00,-3.00,TX2,ap@acme.com
+             2025-05-03,Acme,Payment,Completed,100.00,-3.00,,ap@acme.com
+             2025-05-04,Bank,General Withdrawal,Completed,-500.00,0.00,TX3,
+",
+        )
+        .unwrap();
+        import_payments(
+            Provider::Paypal,
+            path.to_str().unwrap(),
+            None,
+            &mut moneybag,
+        )
+        .unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(moneybag.invoices.is_empty() && moneybag.costs.is_empty());
+    }
+}
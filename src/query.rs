@@ -0,0 +1,431 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    error::MoneybagsError,
+    money::Money,
+    moneybag::{Cost, Invoice, Moneybag},
+    output::{Align, Table},
+};
+
+/// Fields of invoices, in the order they're listed in errors. Amounts are in the base currency.
+const INVOICE_FIELDS: &[&str] = &[
+    "date", "month", "year", "customer", "number", "rate", "currency", "scope", "status", "amount",
+    "hours", "vat",
+];
+const COST_FIELDS: &[&str] = &[
+    "date", "month", "year", "name", "category", "customer", "scope", "amount", "vat",
+];
+/// Fields that hold an amount, which the aggregates other than count work on
+const AMOUNT_FIELDS: &[&str] = &["amount", "hours", "vat"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn name(self) -> &'static str {
+        match self {
+            Aggregate::Count => "count",
+            Aggregate::Sum => "sum",
+            Aggregate::Avg => "avg",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    Invoices,
+    Costs,
+}
+
+impl Source {
+    fn fields(self) -> &'static [&'static str] {
+        match self {
+            Source::Invoices => INVOICE_FIELDS,
+            Source::Costs => COST_FIELDS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    /// Contains, ignoring case
+    Contains,
+}
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    Text(String),
+    Amount(Money),
+}
+
+#[derive(Debug, PartialEq)]
+struct Condition {
+    field: &'static str,
+    operator: Operator,
+    value: Value,
+}
+
+/// A query like `sum amount from costs where date >= 2025-01 group by category`
+#[derive(Debug, PartialEq)]
+struct Query {
+    aggregate: Aggregate,
+    field: Option<&'static str>,
+    source: Source,
+    conditions: Vec<Condition>,
+    group_by: Option<&'static str>,
+}
+
+/// What a query found: one value, or one for each group
+#[derive(Debug, Serialize)]
+pub enum QueryResult {
+    Value(String),
+    Groups {
+        group_by: &'static str,
+        aggregate: &'static str,
+        values: BTreeMap<String, String>,
+    },
+}
+
+impl QueryResult {
+    pub(crate) fn print(&self) {
+        match self {
+            QueryResult::Value(value) => println!("{value}"),
+            QueryResult::Groups {
+                group_by,
+                aggregate,
+                values,
+            } => {
+                let mut table = Table::new(&[(group_by, Align::Left), (aggregate, Align::Right)]);
+                for (group, value) in values {
+                    table.add_row(vec![group.clone(), value.clone()]);
+                }
+                table.print();
+            }
+        }
+    }
+}
+
+/// The field of the source a word names
+fn named_field(source: Source, word: &str) -> Result<&'static str, String> {
+    source
+        .fields()
+        .iter()
+        .find(|field| **field == word)
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "Unknown field {word}, expected one of {}",
+                source.fields().join(", ")
+            )
+        })
+}
+
+fn parse(words: &[String]) -> Result<Query, String> {
+    let mut words = words.iter().map(|word| word.to_lowercase()).peekable();
+    let aggregate = match words.next().as_deref() {
+        Some("count") => Aggregate::Count,
+        Some("sum") => Aggregate::Sum,
+        Some("avg") => Aggregate::Avg,
+        Some("min") => Aggregate::Min,
+        Some("max") => Aggregate::Max,
+        _ => return Err("Expected count, sum, avg, min, or max".to_string()),
+    };
+    let named = words.next_if(|word| word != "from");
+    if words.next().as_deref() != Some("from") {
+        return Err("Expected from after what to aggregate".to_string());
+    }
+    let source = match words.next().as_deref() {
+        Some("invoices") => Source::Invoices,
+        Some("costs") => Source::Costs,
+        _ => return Err("Expected invoices or costs after from".to_string()),
+    };
+    let field = named.map(|word| named_field(source, &word)).transpose()?;
+    match field {
+        Some(field) if !AMOUNT_FIELDS.contains(&field) => {
+            let aggregate = aggregate.name();
+            return Err(format!(
+                "Can't {aggregate} {field}, only amount, hours, or vat"
+            ));
+        }
+        None if aggregate != Aggregate::Count => {
+            return Err("Expected a field to aggregate, e.g. sum amount".to_string())
+        }
+        _ => {}
+    }
+    let mut conditions = vec![];
+    let mut group_by = None;
+    while let Some(word) = words.next() {
+        match word.as_str() {
+            "where" | "and" => {
+                let field = named_field(source, &words.next().unwrap_or_default())?;
+                let operator = match words.next().as_deref() {
+                    Some("=") => Operator::Equal,
+                    Some("!=") => Operator::NotEqual,
+                    Some("<") => Operator::Less,
+                    Some("<=") => Operator::LessOrEqual,
+                    Some(">") => Operator::Greater,
+                    Some(">=") => Operator::GreaterOrEqual,
+                    Some("~") => Operator::Contains,
+                    _ => return Err(format!("Expected =, !=, <, <=, >, >=, or ~ after {field}")),
+                };
+                let value = words
+                    .next()
+                    .ok_or_else(|| format!("Expected a value to compare {field} to"))?;
+                let value = if AMOUNT_FIELDS.contains(&field) {
+                    Value::Amount(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Could not parse {value} as an amount"))?,
+                    )
+                } else {
+                    Value::Text(value)
+                };
+                conditions.push(Condition {
+                    field,
+                    operator,
+                    value,
+                });
+            }
+            "group" => {
+                if words.next().as_deref() != Some("by") {
+                    return Err("Expected by after group".to_string());
+                }
+                group_by = Some(named_field(source, &words.next().unwrap_or_default())?);
+            }
+            word => return Err(format!("Expected where, and, or group by, got {word}")),
+        }
+    }
+    Ok(Query {
+        aggregate,
+        field,
+        source,
+        conditions,
+        group_by,
+    })
+}
+
+enum Entry<'a> {
+    Invoice(&'a Invoice),
+    Cost(&'a Cost),
+}
+
+impl Entry<'_> {
+    /// A field of the entry, or None if it has none
+    fn field(&self, field: &str) -> Option<Value> {
+        match *self {
+            Entry::Invoice(invoice) => invoice_field(invoice, field),
+            Entry::Cost(cost) => cost_field(cost, field),
+        }
+    }
+}
+
+fn invoice_field(invoice: &Invoice, field: &str) -> Option<Value> {
+    let text = |text: Option<&str>| text.map(|text| Value::Text(text.to_string()));
+    match field {
+        "date" => text(Some(&invoice.date)),
+        "month" => text(invoice.date.get(0..7)),
+        "year" => text(invoice.date.get(0..4)),
+        "customer" => text(invoice.customer.as_deref()),
+        "number" => text(invoice.number.as_deref()),
+        "rate" => text(invoice.rate_name.as_deref()),
+        "currency" => text(invoice.currency()),
+        "scope" => text(Some(&invoice.scope.to_string())),
+        "status" => text(Some(if invoice.paid.is_some() {
+            "paid"
+        } else {
+            "unpaid"
+        })),
+        "amount" => Some(Value::Amount(invoice.base_total())),
+        "hours" => invoice.hours().map(Value::Amount),
+        "vat" => invoice.vat.map(|_| Value::Amount(invoice.vat())),
+        _ => None,
+    }
+}
+
+fn cost_field(cost: &Cost, field: &str) -> Option<Value> {
+    let text = |text: Option<&str>| text.map(|text| Value::Text(text.to_string()));
+    match field {
+        "date" => text(Some(&cost.date)),
+        "month" => text(cost.date.get(0..7)),
+        "year" => text(cost.date.get(0..4)),
+        "name" => text(Some(&cost.name)),
+        "category" => text(cost.category.as_deref()),
+        "customer" => text(cost.attributed_to()),
+        "scope" => text(Some(&cost.scope.to_string())),
+        "amount" => Some(Value::Amount(cost.amount)),
+        "vat" => cost.vat.map(|_| Value::Amount(cost.vat())),
+        _ => None,
+    }
+}
+
+impl Condition {
+    /// Whether a field value meets the condition. Entries without the field don't.
+    fn matches(&self, value: Option<&Value>) -> bool {
+        let ordering = match (value, &self.value) {
+            (Some(Value::Amount(value)), Value::Amount(wanted)) => value.cmp(wanted),
+            (Some(Value::Text(value)), Value::Text(wanted)) => {
+                if self.operator == Operator::Contains {
+                    return value.to_lowercase().contains(wanted);
+                }
+                value.to_lowercase().cmp(wanted)
+            }
+            _ => return false,
+        };
+        match self.operator {
+            Operator::Equal => ordering.is_eq(),
+            Operator::NotEqual => ordering.is_ne(),
+            Operator::Less => ordering.is_lt(),
+            Operator::LessOrEqual => ordering.is_le(),
+            Operator::Greater => ordering.is_gt(),
+            Operator::GreaterOrEqual => ordering.is_ge(),
+            Operator::Contains => false,
+        }
+    }
+}
+
+impl Query {
+    /// The aggregated value of each group, under an empty key without group by. Entries without
+    /// the aggregated field are left out, and those without the grouped field group under "-".
+    fn run(&self, moneybag: &Moneybag) -> BTreeMap<String, String> {
+        let entries: Vec<Entry> = match self.source {
+            Source::Invoices => moneybag.invoices.iter().map(Entry::Invoice).collect(),
+            Source::Costs => moneybag.costs.iter().map(Entry::Cost).collect(),
+        };
+        let mut groups: BTreeMap<String, Vec<Money>> = BTreeMap::new();
+        for entry in entries {
+            let matches = self
+                .conditions
+                .iter()
+                .all(|condition| condition.matches(entry.field(condition.field).as_ref()));
+            if !matches {
+                continue;
+            }
+            let amount = match self.field.map(|field| entry.field(field)) {
+                None => Money::default(),
+                Some(Some(Value::Amount(amount))) => amount,
+                Some(_) => continue,
+            };
+            let group = match self.group_by.map(|field| entry.field(field)) {
+                None => String::new(),
+                Some(Some(Value::Text(text))) => text,
+                Some(Some(Value::Amount(amount))) => amount.to_string(),
+                Some(None) => "-".to_string(),
+            };
+            groups.entry(group).or_default().push(amount);
+        }
+        groups
+            .into_iter()
+            .map(|(group, amounts)| (group, self.aggregate(&amounts)))
+            .collect()
+    }
+
+    fn aggregate(&self, amounts: &[Money]) -> String {
+        let sum: Money = amounts.iter().copied().sum();
+        let value = match self.aggregate {
+            Aggregate::Count => return amounts.len().to_string(),
+            Aggregate::Sum => sum,
+            Aggregate::Avg if amounts.is_empty() => Money::default(),
+            Aggregate::Avg => sum / i64::try_from(amounts.len()).unwrap_or(i64::MAX),
+            Aggregate::Min => amounts.iter().copied().min().unwrap_or_default(),
+            Aggregate::Max => amounts.iter().copied().max().unwrap_or_default(),
+        };
+        value.to_string()
+    }
+}
+
+/// Run a query given as words
+pub(crate) fn handle_query(
+    words: &[String],
+    moneybag: &Moneybag,
+) -> Result<QueryResult, MoneybagsError> {
+    let query = parse(words).map_err(MoneybagsError::Usage)?;
+    let values = query.run(moneybag);
+    let Some(group_by) = query.group_by else {
+        let value = values.into_values().next();
+        return Ok(QueryResult::Value(
+            value.unwrap_or_else(|| query.aggregate(&[])),
+        ));
+    };
+    Ok(QueryResult::Groups {
+        group_by,
+        aggregate: query.aggregate.name(),
+        values,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn words(query: &str) -> Vec<String> {
+        query.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_query() {
+        let moneybag = fixtures::moneybag(
+            r#"{
+                "costs": [
+                    {"date": "2024-12", "amount": {"amount": 40000}, "name": "rent", "category": "office"},
+                    {"date": "2025-01", "amount": {"amount": 30000}, "name": "rent", "category": "office"},
+                    {"date": "2025-02", "amount": {"amount": 10000}, "name": "Rent", "category": "office"},
+                    {"date": "2025-03-02", "amount": {"amount": 5000}, "name": "pens"}
+                ]
+            }"#,
+        );
+        let query = parse(&words(
+            "SUM amount from costs where date >= 2025-01 group by category",
+        ))
+        .unwrap();
+        let results = query.run(&moneybag);
+        assert_eq!(results["office"], "400.00");
+        assert_eq!(results["-"], "50.00");
+        let query = parse(&words(
+            "count from costs where name = rent and amount < 350",
+        ))
+        .unwrap();
+        assert_eq!(query.run(&moneybag)[""], "2");
+        assert!(parse(&words("sum name from costs")).is_err());
+        assert!(parse(&words("sum amount from costs where colour = red")).is_err());
+        // There are no invoices to average the hours of
+        let query = parse(&words("avg hours from invoices")).unwrap();
+        assert!(query.run(&moneybag).is_empty());
+        assert_eq!(query.aggregate(&[]), "0.00");
+    }
+
+    #[test]
+    fn test_bad_queries() {
+        for query in [
+            "",
+            "median amount from costs",
+            "sum amount costs",
+            "sum amount from assets",
+            "sum from costs",
+            "count from invoices where status",
+            "count from invoices where status is paid",
+            "count from invoices where amount > lots",
+            "count from invoices where hours >",
+            "count from invoices group customer",
+            "count from invoices order by date",
+        ] {
+            assert!(parse(&words(query)).is_err(), "{query}");
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! A way back to changes that weren't saved when moneybags panicked. The moneybag is kept as it
+//! was before each command, written next to its file if the command panics, and offered back
+//! on the next start.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tracing::{info, warn};
+
+use crate::{
+    confirm, error::MoneybagsError, events, i18n::tr, moneybag::Moneybag, output::note, serialize,
+};
+
+/// The moneybag as it was last saved and as it was before the latest command, and where to
+/// write it if they differ when a command panics
+struct Kept {
+    path: PathBuf,
+    saved: String,
+    latest: String,
+}
+
+static KEPT: Mutex<Option<Kept>> = Mutex::new(None);
+
+/// The recovery file for a moneybag file: moneybag.json has moneybag.recovery.json
+pub(crate) fn recovery_path(filepath: &str) -> PathBuf {
+    Path::new(filepath).with_extension("recovery.json")
+}
+
+/// Note the moneybag as it was loaded or saved, which there's nothing to recover from
+pub(crate) fn saved(moneybag: &Moneybag, location: &str) {
+    let json = serialize(moneybag);
+    if let Ok(mut kept) = KEPT.lock() {
+        *kept = Some(Kept {
+            path: recovery_path(location),
+            saved: json.clone(),
+            latest: json,
+        });
+    }
+}
+
+/// Keep the moneybag as it is before a command, to write if the command panics. Does nothing
+/// unless it was loaded by [`saved`], as by the command line program.
+pub(crate) fn keep(moneybag: &Moneybag) {
+    if let Ok(mut kept) = KEPT.lock() {
+        if let Some(kept) = kept.as_mut() {
+            kept.latest = serialize(moneybag);
+        }
+    }
+}
+
+/// Write the moneybag last kept, from the panic hook, if it has unsaved changes. Nothing is
+/// written if the panic happened while keeping it, and a recovery file not yet loaded is left
+/// as it is.
+pub(crate) fn write() {
+    let Ok(kept) = KEPT.try_lock() else {
+        return;
+    };
+    let Some(Kept {
+        path,
+        saved,
+        latest,
+    }) = kept.as_ref()
+    else {
+        return;
+    };
+    if latest == saved {
+        return;
+    }
+    let written = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(latest.as_bytes()));
+    match written {
+        Ok(()) => eprintln!(
+            "{}",
+            tr!(
+                "Unsaved changes were written to {file}, start moneybags to load them",
+                file = path.display()
+            )
+        ),
+        Err(e) => eprintln!("Could not write unsaved changes to {}: {e}", path.display()),
+    }
+}
+
+/// Offer to load what a panic left in the recovery file, in place of the moneybag as it was
+/// loaded, and remove the file. The changes are logged and left unsaved. Nothing is asked if the
+/// file holds the moneybag as it already is. A file that isn't a moneybag is left.
+pub(crate) fn offer(moneybag: &mut Moneybag, location: &str) -> Result<(), MoneybagsError> {
+    let path = recovery_path(location);
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    match serde_json::from_str::<Moneybag>(&json) {
+        Ok(recovered) if serialize(&recovered) == serialize(moneybag) => {}
+        Ok(recovered) => {
+            let question = tr!(
+                "{file} has changes that weren't saved before moneybags stopped. Load them? \
+                 Otherwise they're discarded.",
+                file = path.display()
+            );
+            if confirm(&question)? {
+                info!(path = %path.display(), "Loading recovered changes");
+                // The events of the commands were lost with them, so they're found again
+                let mut changed = moneybag.clone();
+                for event in events::between(moneybag, &recovered) {
+                    changed.change(event);
+                }
+                events::record(moneybag, changed)?;
+                note(tr!("Loaded, save to keep them"));
+            } else {
+                warn!(path = %path.display(), "Discarding recovered changes");
+            }
+        }
+        Err(source) => {
+            return Err(MoneybagsError::Parse {
+                path: path.display().to_string(),
+                source,
+            })
+        }
+    }
+    std::fs::remove_file(&path)
+        .map_err(|e| MoneybagsError::Io(format!("Could not remove {}: {e}", path.display())))
+}
+
+/// Point out a recovery file when there's no shell to offer it in
+pub(crate) fn remind(location: &str) {
+    let path = recovery_path(location);
+    if path.exists() {
+        note(&tr!(
+            "Unsaved changes were written to {file}, start moneybags to load them",
+            file = path.display()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_leaves_a_recovery_file_as_it_is() {
+        let location = std::env::temp_dir()
+            .join(format!("moneybags-recovery-{}.json", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let path = recovery_path(&location);
+        let moneybag = Moneybag::default();
+        saved(&moneybag, &location);
+        write();
+        assert!(!path.exists());
+        std::fs::write(&path, "changes from before").unwrap();
+        let mut changed = moneybag.clone();
+        changed.settings.tax_rate = "30".parse().unwrap();
+        keep(&changed);
+        write();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "changes from before"
+        );
+        std::fs::remove_file(&path).unwrap();
+        write();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), serialize(&changed));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,341 @@
+//! Emailing customers reminders of their overdue invoices, over the SMTP server set in the
+//! `[email]` table of the config, and noting on the invoices when they were reminded
+
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::MoneybagsError,
+    moneybag::{Invoice, Moneybag},
+    CommandOutput,
+};
+
+/// How to reach the SMTP server, and what reminders say. In the subject and body, `{customer}`,
+/// `{invoice}`, `{amount}`, `{date}`, `{due}`, and `{days}` (overdue) are replaced by the
+/// invoice's.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct Email {
+    /// Host name of the SMTP server
+    pub(crate) smtp: Option<String>,
+    /// Port, if not the usual one for the security
+    pub(crate) port: Option<u16>,
+    pub(crate) security: Security,
+    pub(crate) username: Option<String>,
+    /// Password for username, unless set in `MONEYBAGS_SMTP_PASSWORD`
+    pub(crate) password: Option<String>,
+    /// Who reminders are from, e.g. "Acme Consulting <billing@acme.se>"
+    pub(crate) from: Option<String>,
+    pub(crate) subject: String,
+    pub(crate) body: String,
+}
+
+impl Default for Email {
+    fn default() -> Self {
+        Email {
+            smtp: None,
+            port: None,
+            security: Security::default(),
+            username: None,
+            password: None,
+            from: None,
+            subject: "Reminder: invoice {invoice} is overdue".to_string(),
+            body: "Hello {customer},\n\n\
+                   Invoice {invoice} of {amount}, sent {date}, was due {due} and is still unpaid. \
+                   Please pay it at your earliest convenience, or let us know if you already have.\n\n\
+                   Best regards\n"
+                .to_string(),
+        }
+    }
+}
+
+/// How the connection to the SMTP server is encrypted
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Security {
+    /// Upgraded to TLS, on port 587 by default
+    #[default]
+    Starttls,
+    /// TLS from the start, on port 465 by default
+    Tls,
+    /// Not at all, on port 25 by default, e.g. for a relay on localhost
+    Plain,
+}
+
+/// A reminder to send
+#[derive(Debug, Serialize)]
+pub struct Reminder {
+    /// ID of the invoice
+    pub invoice: usize,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// The template with the invoice's details filled in
+fn fill(template: &str, invoice: &Invoice, due: NaiveDate, today: NaiveDate) -> String {
+    let total = invoice.total();
+    let vat = invoice
+        .vat
+        .map(|rate| total * rate / 100)
+        .unwrap_or_default();
+    template
+        .replace(
+            "{customer}",
+            invoice.customer.as_deref().unwrap_or_default(),
+        )
+        .replace(
+            "{invoice}",
+            &invoice
+                .number
+                .clone()
+                .unwrap_or_else(|| invoice.id.to_string()),
+        )
+        .replace(
+            "{amount}",
+            &format!("{}{}", total + vat, invoice.currency_suffix()),
+        )
+        .replace("{date}", &invoice.date)
+        .replace("{due}", &due.to_string())
+        .replace("{days}", &(today - due).num_days().to_string())
+}
+
+/// Reminders of the invoices overdue today, to customers with an email address, unless they were
+/// reminded less than `every` days ago, and of the overdue invoices no one can be reminded of
+fn reminders(
+    moneybag: &Moneybag,
+    email: &Email,
+    every: u32,
+    today: NaiveDate,
+) -> (Vec<Reminder>, Vec<String>) {
+    let (mut reminders, mut unreachable) = (vec![], vec![]);
+    for invoice in &moneybag.invoices {
+        let Some(due) = moneybag
+            .due_date(invoice)
+            .filter(|_| moneybag.is_overdue(invoice, today))
+        else {
+            continue;
+        };
+        let recently = invoice.reminded.is_some_and(|reminded| {
+            reminded.checked_add_days(Days::new(u64::from(every))) > Some(today)
+        });
+        if recently {
+            continue;
+        }
+        let customer = invoice.customer.as_deref().unwrap_or_default();
+        let Some(to) = moneybag
+            .customers
+            .get(customer)
+            .and_then(|customer| customer.email.clone())
+        else {
+            unreachable.push(format!(
+                "Invoice {} is overdue, but {customer} has no email to remind",
+                invoice.id
+            ));
+            continue;
+        };
+        reminders.push(Reminder {
+            invoice: invoice.id,
+            to,
+            subject: fill(&email.subject, invoice, due, today),
+            body: fill(&email.body, invoice, due, today),
+        });
+    }
+    (reminders, unreachable)
+}
+
+/// Send the reminders in order, calling sent after each, and stopping at the first that can't be
+/// sent. None is sent if the config or an address is wrong.
+#[cfg(feature = "email")]
+fn send(
+    email: &Email,
+    reminders: &[Reminder],
+    mut sent: impl FnMut(&Reminder),
+) -> Result<(), MoneybagsError> {
+    use lettre::{
+        message::Mailbox, transport::smtp::authentication::Credentials, Message, SmtpTransport,
+        Transport,
+    };
+
+    let invalid = MoneybagsError::Invalid;
+    let (Some(smtp), Some(from)) = (&email.smtp, &email.from) else {
+        return Err(invalid(
+            "Set smtp and from in the [email] table of the config to send reminders".to_string(),
+        ));
+    };
+    let from: Mailbox = from
+        .parse()
+        .map_err(|e| invalid(format!("Could not use {from} as sender: {e}")))?;
+    let mut builder = match email.security {
+        Security::Starttls => SmtpTransport::starttls_relay(smtp),
+        Security::Tls => SmtpTransport::relay(smtp),
+        Security::Plain => Ok(SmtpTransport::builder_dangerous(smtp)),
+    }
+    .map_err(|e| invalid(format!("Could not connect to {smtp}: {e}")))?;
+    if let Some(port) = email.port {
+        builder = builder.port(port);
+    }
+    if let Some(username) = &email.username {
+        let password = std::env::var("MONEYBAGS_SMTP_PASSWORD")
+            .ok()
+            .or_else(|| email.password.clone())
+            .unwrap_or_default();
+        builder = builder.credentials(Credentials::new(username.clone(), password));
+    }
+    let mailer = builder.build();
+    let messages = reminders
+        .iter()
+        .map(|reminder| {
+            let could_not = |e: &dyn std::fmt::Display| {
+                invalid(format!("Could not remind {}: {e}", reminder.to))
+            };
+            Message::builder()
+                .from(from.clone())
+                .to(reminder.to.parse().map_err(|e| could_not(&e))?)
+                .subject(&reminder.subject)
+                .body(reminder.body.clone())
+                .map_err(|e| could_not(&e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for (reminder, message) in reminders.iter().zip(messages) {
+        mailer
+            .send(&message)
+            .map_err(|e| MoneybagsError::Io(format!("Could not remind {}: {e}", reminder.to)))?;
+        sent(reminder);
+    }
+    Ok(())
+}
+
+/// Remind customers of their overdue invoices, or only list the reminders, and note the date on
+/// the invoices reminded. Stops at a reminder that can't be sent, saying which were sent before
+/// it, as they aren't noted.
+pub(crate) fn handle_remind(
+    every: u32,
+    list: bool,
+    moneybag: &mut Moneybag,
+    email: &Email,
+    today: NaiveDate,
+) -> Result<CommandOutput, MoneybagsError> {
+    let (reminders, mut messages) = reminders(moneybag, email, every, today);
+    if list {
+        return Ok(CommandOutput::Reminders {
+            reminders,
+            unreachable: messages,
+        });
+    }
+    if reminders.is_empty() {
+        messages.push("No overdue invoices to remind of".to_string());
+        return Ok(CommandOutput::Messages(messages));
+    }
+    #[cfg(feature = "email")]
+    {
+        let mut reminded = vec![];
+        let sent = send(email, &reminders, |reminder| {
+            let Some(invoice) = moneybag
+                .invoices
+                .iter()
+                .find(|invoice| invoice.id == reminder.invoice)
+            else {
+                return;
+            };
+            let mut invoice = invoice.clone();
+            invoice.reminded = Some(today);
+            messages.push(format!(
+                "Reminded {} of invoice {}",
+                reminder.to, invoice.id
+            ));
+            reminded.push(invoice.id.to_string());
+            moneybag.change(crate::events::Event::InvoiceEdited { invoice });
+        });
+        match sent {
+            Ok(()) => Ok(CommandOutput::Messages(messages)),
+            Err(MoneybagsError::Io(e)) if !reminded.is_empty() => Err(MoneybagsError::Io(format!(
+                "{e}. Reminders of invoices {} were sent, but not noted on them",
+                reminded.join(", ")
+            ))),
+            Err(e) => Err(e),
+        }
+    }
+    #[cfg(not(feature = "email"))]
+    Err(crate::without("email"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_reminders() {
+        let mut moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [
+                    {"id": 1, "date": "2025-04-01", "amount": "1000", "customer": "Acme",
+                     "number": "INV-2025-001", "vat": "25"},
+                    {"id": 2, "date": "2025-04-01", "amount": "500", "customer": "Acme",
+                     "reminded": "2025-05-10"},
+                    {"id": 3, "date": "2025-05-10", "amount": "500", "customer": "Acme"}
+                ],
+                "customers": {"Acme": {"email": "ap@acme.com", "payment_terms": 30}}
+            }"#,
+        );
+        let email = Email {
+            subject: "Invoice {invoice}, {days} days overdue".to_string(),
+            body: "{customer}: {amount}, due {due}".to_string(),
+            ..Email::default()
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 5, 15).unwrap();
+        let (due, _) = reminders(&moneybag, &email, 7, today);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].to, "ap@acme.com");
+        assert_eq!(due[0].subject, "Invoice INV-2025-001, 14 days overdue");
+        assert_eq!(due[0].body, "Acme: 1250.00, due 2025-05-01");
+        moneybag.invoices[1].reminded = NaiveDate::from_ymd_opt(2025, 5, 8);
+        assert_eq!(reminders(&moneybag, &email, 7, today).0.len(), 2);
+        // Nobody to send them to
+        moneybag.customers.get_mut("Acme").unwrap().email = None;
+        let (due, unreachable) = reminders(&moneybag, &email, 7, today);
+        assert!(due.is_empty());
+        assert_eq!(unreachable.len(), 2);
+    }
+
+    #[cfg(feature = "email")]
+    #[test]
+    fn test_send() {
+        let reminder = |to: &str| Reminder {
+            invoice: 1,
+            to: to.to_string(),
+            subject: "Reminder".to_string(),
+            body: String::new(),
+        };
+        let mut reminders = vec![reminder("ap@acme.com"), reminder("accounts payable")];
+        let unsent = |_: &Reminder| panic!("No reminder should be sent");
+        assert!(matches!(
+            send(&Email::default(), &reminders, unsent),
+            Err(MoneybagsError::Invalid(_))
+        ));
+        let port = fixtures::closed_port();
+        let mut email = Email {
+            smtp: Some("127.0.0.1".to_string()),
+            port: Some(port),
+            security: Security::Plain,
+            from: Some("billing".to_string()),
+            ..Email::default()
+        };
+        assert!(matches!(
+            send(&email, &reminders, unsent),
+            Err(MoneybagsError::Invalid(_))
+        ));
+        email.from = Some("billing@example.com".to_string());
+        // Nothing is sent with an address that isn't one
+        assert!(matches!(
+            send(&email, &reminders, unsent),
+            Err(MoneybagsError::Invalid(_))
+        ));
+        reminders.pop();
+        assert!(matches!(
+            send(&email, &reminders, unsent),
+            Err(MoneybagsError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,1408 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use chrono::{Datelike, Local, Months, NaiveDate};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    args::{ListFormat, ReportArgs, ReportCommand, TopKind},
+    chart,
+    command_output::CommandOutput,
+    error::MoneybagsError,
+    export,
+    i18n::{tr, translate},
+    money::Money,
+    moneybag::{
+        average_invoice, sum_costs, sum_input_vat, sum_invoices, Interval, Invoice, Moneybag, Scope,
+    },
+    output::{csv_writer, note, print_json, Align, Style, Table},
+};
+
+/// Characters in the longest bar of a chart
+pub(crate) const CHART_WIDTH: usize = 30;
+
+/// A report, to print or export as a table. Serialized as its table.
+#[derive(Debug)]
+pub struct Report(Kind);
+
+#[derive(Debug)]
+enum Kind {
+    Table(Table),
+    /// A table with a line of chart below it
+    Charted(Table, String),
+    Pnl(Pnl, ListFormat),
+    Vat(VatReturn),
+    Tax(TaxEstimate),
+}
+
+impl Report {
+    pub(crate) fn print(&self) {
+        match &self.0 {
+            Kind::Table(table) => table.print(),
+            Kind::Charted(table, line) => {
+                table.print();
+                println!("{line}");
+            }
+            Kind::Pnl(pnl, ListFormat::Text) => pnl.print(),
+            Kind::Pnl(pnl, ListFormat::Json) => print_json(pnl),
+            Kind::Pnl(pnl, ListFormat::Csv) => pnl.print_csv(),
+            Kind::Vat(vat_return) => vat_return.print(),
+            Kind::Tax(estimate) => estimate.print(),
+        }
+    }
+
+    fn into_table(self) -> Table {
+        match self.0 {
+            Kind::Table(table) | Kind::Charted(table, _) => table,
+            Kind::Pnl(pnl, _) => pnl.table(),
+            Kind::Vat(vat_return) => vat_return.table(),
+            Kind::Tax(estimate) => estimate.table(),
+        }
+    }
+}
+
+impl Serialize for Report {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.0 {
+            Kind::Table(table) | Kind::Charted(table, _) => table.serialize(serializer),
+            Kind::Pnl(pnl, _) => pnl.table().serialize(serializer),
+            Kind::Vat(vat_return) => vat_return.table().serialize(serializer),
+            Kind::Tax(estimate) => estimate.table().serialize(serializer),
+        }
+    }
+}
+
+/// The report asked for, or with `--out`, a note that it was saved there
+pub(crate) fn handle_report(
+    args: &ReportArgs,
+    moneybag: &Moneybag,
+) -> Result<CommandOutput, MoneybagsError> {
+    let report = report(&args.command, moneybag)?;
+    match &args.out {
+        Some(path) => {
+            let path = shellexpand::tilde(path).to_string();
+            export::export(&report.into_table(), &path).map_err(|source| {
+                MoneybagsError::Write {
+                    path: path.clone(),
+                    source,
+                }
+            })?;
+            Ok(CommandOutput::Messages(vec![format!(
+                "Saved report to {path}"
+            )]))
+        }
+        None => Ok(CommandOutput::Report(report)),
+    }
+}
+
+/// A report as a table, for the server to send
+#[cfg(feature = "server")]
+pub(crate) fn report_table(
+    command: &ReportCommand,
+    moneybag: &Moneybag,
+) -> Result<Table, MoneybagsError> {
+    report(command, moneybag).map(Report::into_table)
+}
+
+fn report(command: &ReportCommand, moneybag: &Moneybag) -> Result<Report, MoneybagsError> {
+    let this_year = || Local::now().year();
+    let report = match *command {
+        ReportCommand::Monthly { year, scope, chart } => {
+            let year = year.unwrap_or_else(this_year);
+            let table = monthly(year, scope, chart, moneybag);
+            if chart {
+                Kind::Charted(table, balance_line(year, scope, moneybag))
+            } else {
+                Kind::Table(table)
+            }
+        }
+        ReportCommand::Yearly { scope } => Kind::Table(yearly(scope, moneybag)),
+        ReportCommand::Categories {
+            year,
+            monthly,
+            scope,
+        } => Kind::Table(if monthly {
+            categories_by_month(year.unwrap_or_else(this_year), scope, moneybag)
+        } else {
+            let period = year.map(|year| year.to_string()).unwrap_or_default();
+            categories(&period, scope, moneybag)
+        }),
+        ReportCommand::Trend { months, scope } => {
+            Kind::Table(trend(months, scope, moneybag, Local::now().date_naive()))
+        }
+        ReportCommand::Profitability { ref period } => Kind::Table(profitability(
+            period.as_deref().unwrap_or_default(),
+            moneybag,
+        )),
+        ReportCommand::Aging => Kind::Table(aging(moneybag, Local::now().date_naive())),
+        ReportCommand::Top {
+            kind,
+            n,
+            ref period,
+            category,
+        } => Kind::Table(top(
+            kind,
+            n,
+            period.as_deref().unwrap_or_default(),
+            category,
+            moneybag,
+        )),
+        ReportCommand::Pnl {
+            ref period,
+            scope,
+            format,
+        } => {
+            let period = period.clone().unwrap_or_else(|| this_year().to_string());
+            Kind::Pnl(Pnl::of(&period, scope, moneybag), format)
+        }
+        ReportCommand::Vat { ref quarter } => {
+            let quarter = quarter.clone().unwrap_or_else(|| {
+                Interval::Quarterly.period_of(&Local::now().format("%Y-%m").to_string())
+            });
+            if quarter_of(&quarter).is_none() {
+                return Err(MoneybagsError::Invalid(format!(
+                    "Expected a quarter like 2025-Q2, got {quarter}"
+                )));
+            }
+            Kind::Vat(VatReturn::of(&quarter, moneybag))
+        }
+        ReportCommand::Breakeven { year } => {
+            Kind::Table(breakeven(year.unwrap_or_else(this_year), moneybag))
+        }
+        ReportCommand::Utilization { year, target } => {
+            let Some(target) = target.or(moneybag.settings.hours_target) else {
+                return Err(MoneybagsError::Invalid(
+                    "No hours target, set one with set hours-target or give --target".to_string(),
+                ));
+            };
+            Kind::Table(utilization(
+                year.unwrap_or_else(this_year),
+                target,
+                moneybag,
+            ))
+        }
+        ReportCommand::Tax { year } => {
+            let settings = &moneybag.settings;
+            if settings.tax_rate.is_zero() && settings.social_fees.is_zero() {
+                note("No tax rates set, see set tax-rate and set social-fees");
+            }
+            Kind::Tax(TaxEstimate::of(year.unwrap_or_else(this_year), moneybag))
+        }
+    };
+    Ok(Report(report))
+}
+
+/// Invoiced, costs, depreciation, and net of entries dated in a period, e.g. "2025-05" or "2025".
+/// An empty period is all of them.
+pub(crate) struct Totals {
+    invoice_count: usize,
+    average_invoice: Money,
+    pub(crate) invoiced: Money,
+    pub(crate) costs: Money,
+    pub(crate) depreciation: Money,
+}
+
+impl Totals {
+    pub(crate) fn of(period: &str, scope: Option<Scope>, moneybag: &Moneybag) -> Self {
+        let in_scope = |entry_scope: Scope| scope.is_none_or(|scope| scope == entry_scope);
+        let invoices: Vec<_> = moneybag
+            .invoices
+            .iter()
+            .filter(|invoice| in_scope(invoice.scope) && in_period(&invoice.date, period))
+            .collect();
+        let costs = moneybag
+            .costs
+            .iter()
+            .filter(|cost| in_scope(cost.scope) && in_period(&cost.date, period));
+        // Assets are always business purchases
+        let depreciation = if in_scope(Scope::Business) {
+            months_of(period, moneybag)
+                .iter()
+                .flat_map(|month| {
+                    moneybag
+                        .assets
+                        .iter()
+                        .map(|asset| asset.depreciation_in(month))
+                })
+                .sum()
+        } else {
+            Money::default()
+        };
+        Totals {
+            invoice_count: invoices.len(),
+            average_invoice: average_invoice(invoices.iter().copied()),
+            invoiced: sum_invoices(invoices),
+            costs: sum_costs(costs),
+            depreciation,
+        }
+    }
+
+    pub(crate) fn net(&self) -> Money {
+        self.invoiced - self.costs - self.depreciation
+    }
+
+    fn row(&self, period: String) -> Vec<String> {
+        vec![
+            period,
+            self.invoiced.to_string(),
+            self.costs.to_string(),
+            self.depreciation.to_string(),
+            self.net().to_string(),
+        ]
+    }
+}
+
+/// The months of a period, which is a month, a quarter, a year, or all years with entries
+pub(crate) fn months_of(period: &str, moneybag: &Moneybag) -> Vec<String> {
+    if period.is_empty() {
+        years(moneybag)
+            .iter()
+            .flat_map(|year| months_of(year, moneybag))
+            .collect()
+    } else if let Some((year, quarter)) = quarter_of(period) {
+        (quarter * 3 - 2..=quarter * 3)
+            .map(|month| format!("{year}-{month:02}"))
+            .collect()
+    } else if period.len() == 4 {
+        (1..=12)
+            .map(|month| format!("{period}-{month:02}"))
+            .collect()
+    } else {
+        vec![period.to_string()]
+    }
+}
+
+/// Year and quarter of a period like "2025-Q2"
+fn quarter_of(period: &str) -> Option<(&str, u32)> {
+    let (year, quarter) = period.split_once("-Q")?;
+    let quarter = quarter
+        .parse()
+        .ok()
+        .filter(|quarter| (1..=4).contains(quarter))?;
+    Some((year, quarter))
+}
+
+/// Whether a date starting with YYYY-MM is in a period, e.g. "2025-05", "2025-Q2", or "2025"
+pub(crate) fn in_period(date: &str, period: &str) -> bool {
+    if quarter_of(period).is_some() {
+        Interval::Quarterly.period_of(date) == period
+    } else {
+        date.starts_with(period)
+    }
+}
+
+/// Totals per month of a year, with bars of what was invoiced if chart is set
+fn monthly(year: i32, scope: Option<Scope>, chart: bool, moneybag: &Moneybag) -> Table {
+    let mut table = Table::new(&[
+        ("month", Align::Left),
+        ("invoiced", Align::Right),
+        ("costs", Align::Right),
+        ("depreciation", Align::Right),
+        ("net", Align::Right),
+        ("", Align::Left),
+    ]);
+    let months: Vec<_> = months_of(&year.to_string(), moneybag)
+        .into_iter()
+        .map(|month| (Totals::of(&month, scope, moneybag), month))
+        .collect();
+    let most = months
+        .iter()
+        .map(|(totals, _)| totals.invoiced)
+        .max()
+        .unwrap_or_default();
+    for (totals, month) in months {
+        let style = if totals.net().is_negative() {
+            Style::Red
+        } else {
+            Style::Plain
+        };
+        let mut row = totals.row(month);
+        row.push(if chart {
+            chart::bar(totals.invoiced, most, CHART_WIDTH)
+        } else {
+            String::new()
+        });
+        table.add_styled_row(row, style);
+    }
+    let totals = Totals::of(&year.to_string(), scope, moneybag);
+    let mut row = totals.row(tr!("Total").to_string());
+    row.push(String::new());
+    table.add_styled_row(row, Style::Bold);
+    table
+}
+
+/// The net of the year so far at the end of each month, as a sparkline
+fn balance_line(year: i32, scope: Option<Scope>, moneybag: &Moneybag) -> String {
+    let mut balance = Money::default();
+    let balances: Vec<_> = months_of(&year.to_string(), moneybag)
+        .iter()
+        .map(|month| {
+            balance = balance + Totals::of(month, scope, moneybag).net();
+            balance
+        })
+        .collect();
+    format!(
+        "{}: {} {balance}",
+        translate("balance"),
+        chart::sparkline(&balances)
+    )
+}
+
+/// The years that invoices or costs are dated in, or assets depreciate in up to this year, in order
+fn years(moneybag: &Moneybag) -> Vec<String> {
+    let this_year = Local::now().year();
+    let dates = moneybag
+        .invoices
+        .iter()
+        .map(|invoice| &invoice.date)
+        .chain(moneybag.costs.iter().map(|cost| &cost.date));
+    let mut years: BTreeSet<i32> = dates
+        .filter_map(|date| date.get(0..4)?.parse().ok())
+        .collect();
+    for asset in &moneybag.assets {
+        if let Some(bought) = asset
+            .date
+            .get(0..4)
+            .and_then(|year| year.parse::<i32>().ok())
+        {
+            let last = bought.saturating_add_unsigned(asset.years).min(this_year);
+            years.extend(bought..=last.max(bought));
+        }
+    }
+    years.into_iter().map(|year| year.to_string()).collect()
+}
+
+fn yearly(scope: Option<Scope>, moneybag: &Moneybag) -> Table {
+    let mut table = Table::new(&[
+        ("year", Align::Left),
+        ("invoiced", Align::Right),
+        ("costs", Align::Right),
+        ("depreciation", Align::Right),
+        ("net", Align::Right),
+        ("invoices", Align::Right),
+        ("average invoice", Align::Right),
+    ]);
+    let rows = years(moneybag)
+        .into_iter()
+        .map(|year| (Totals::of(&year, scope, moneybag), year, Style::Plain))
+        .chain([(
+            Totals::of("", scope, moneybag),
+            tr!("Total").to_string(),
+            Style::Bold,
+        )]);
+    for (totals, year, style) in rows {
+        let mut row = totals.row(year);
+        row.push(totals.invoice_count.to_string());
+        row.push(totals.average_invoice.to_string());
+        table.add_styled_row(row, style);
+    }
+    table
+}
+
+const MONTHS: [&str; 12] = [
+    "01", "02", "03", "04", "05", "06", "07", "08", "09", "10", "11", "12",
+];
+
+/// Costs dated in a period, excluding deductible VAT, by category. Costs without one are under
+/// "uncategorized".
+fn costs_by_category(
+    period: &str,
+    scope: Option<Scope>,
+    moneybag: &Moneybag,
+) -> BTreeMap<String, Money> {
+    let mut categories = BTreeMap::new();
+    let costs = moneybag.costs.iter().filter(|cost| {
+        scope.is_none_or(|scope| scope == cost.scope) && in_period(&cost.date, period)
+    });
+    for cost in costs {
+        let category = cost
+            .category
+            .clone()
+            .unwrap_or_else(|| tr!("uncategorized").to_string());
+        let sum: &mut Money = categories.entry(category).or_default();
+        *sum = *sum + cost.net();
+    }
+    categories
+}
+
+/// The n largest customers by invoiced, or costs by name or category, of business entries in a
+/// period, with the share of the total each has and the running share down to it
+fn top(kind: TopKind, n: usize, period: &str, category: bool, moneybag: &Moneybag) -> Table {
+    let mut sums: BTreeMap<String, Money> = BTreeMap::new();
+    let mut add = |name: String, amount: Money| {
+        let sum = sums.entry(name).or_default();
+        *sum = *sum + amount;
+    };
+    match kind {
+        TopKind::Invoices => {
+            let invoices = moneybag.invoices.iter().filter(|invoice| {
+                invoice.scope == Scope::Business && in_period(&invoice.date, period)
+            });
+            for invoice in invoices {
+                let customer = invoice
+                    .customer
+                    .clone()
+                    .unwrap_or_else(|| tr!("no customer").to_string());
+                add(customer, invoice.base_total());
+            }
+        }
+        TopKind::Costs if category => {
+            for (category, sum) in costs_by_category(period, Some(Scope::Business), moneybag) {
+                add(category, sum);
+            }
+        }
+        TopKind::Costs => {
+            let costs = moneybag
+                .costs
+                .iter()
+                .filter(|cost| cost.scope == Scope::Business && in_period(&cost.date, period));
+            for cost in costs {
+                add(cost.name.clone(), cost.net());
+            }
+        }
+    }
+    let total: Money = sums.values().copied().sum();
+    let mut ranked: Vec<_> = sums.into_iter().collect();
+    ranked.sort_by_key(|(_, sum)| -*sum);
+    let mut table = Table::new(&[
+        ("#", Align::Right),
+        ("name", Align::Left),
+        ("amount", Align::Right),
+        ("share", Align::Right),
+        ("running share", Align::Right),
+    ]);
+    let percent = |amount: Money| {
+        if total.is_zero() {
+            String::new()
+        } else {
+            format!("{}%", amount * 100 / total)
+        }
+    };
+    let mut running = Money::default();
+    for (i, (name, sum)) in ranked.into_iter().take(n).enumerate() {
+        running = running + sum;
+        table.add_row(vec![
+            (i + 1).to_string(),
+            name,
+            sum.to_string(),
+            percent(sum),
+            percent(running),
+        ]);
+    }
+    table
+}
+
+/// Spend per category in a period, most first, with each one's share of the total
+fn categories(period: &str, scope: Option<Scope>, moneybag: &Moneybag) -> Table {
+    let mut table = Table::new(&[
+        ("category", Align::Left),
+        ("costs", Align::Right),
+        ("share", Align::Right),
+    ]);
+    let mut categories: Vec<_> = costs_by_category(period, scope, moneybag)
+        .into_iter()
+        .collect();
+    categories.sort_by_key(|(_, sum)| -*sum);
+    let total: Money = categories.iter().map(|(_, sum)| *sum).sum();
+    for (category, sum) in categories {
+        let share = if total.is_zero() {
+            String::new()
+        } else {
+            format!("{}%", sum * 100 / total)
+        };
+        table.add_row(vec![category, sum.to_string(), share]);
+    }
+    table.add_styled_row(
+        vec![tr!("Total").to_string(), total.to_string(), String::new()],
+        Style::Bold,
+    );
+    table
+}
+
+/// Spend per category in each month of a year
+fn categories_by_month(year: i32, scope: Option<Scope>, moneybag: &Moneybag) -> Table {
+    let mut columns = vec![("category", Align::Left)];
+    columns.extend(MONTHS.map(|month| (month, Align::Right)));
+    columns.push(("total", Align::Right));
+    let mut table = Table::new(&columns);
+    let months = MONTHS.map(|month| costs_by_category(&format!("{year}-{month}"), scope, moneybag));
+    for (category, total) in costs_by_category(&year.to_string(), scope, moneybag) {
+        let mut row = vec![category.clone()];
+        row.extend(months.iter().map(|month| {
+            month
+                .get(&category)
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        }));
+        row.push(total.to_string());
+        table.add_row(row);
+    }
+    let sum = |categories: &BTreeMap<String, Money>| categories.values().copied().sum::<Money>();
+    let mut row = vec![tr!("Total").to_string()];
+    row.extend(months.iter().map(|month| {
+        let sum = sum(month);
+        if sum.is_zero() {
+            String::new()
+        } else {
+            sum.to_string()
+        }
+    }));
+    row.push(sum(&costs_by_category(&year.to_string(), scope, moneybag)).to_string());
+    table.add_styled_row(row, Style::Bold);
+    table
+}
+
+/// Profit and loss of a period. Amounts are in the base currency, and costs exclude deductible
+/// VAT, which is reported on its own.
+#[derive(Debug, Serialize)]
+struct Pnl {
+    period: String,
+    work: Money,
+    billed_expenses: Money,
+    revenue: Money,
+    /// Costs by category
+    costs: BTreeMap<String, Money>,
+    depreciation: Money,
+    total_costs: Money,
+    operating_result: Money,
+    input_vat: Money,
+}
+
+impl Pnl {
+    fn of(period: &str, scope: Option<Scope>, moneybag: &Moneybag) -> Self {
+        let in_scope = |entry_scope: Scope| scope.is_none_or(|scope| scope == entry_scope);
+        let invoices: Vec<_> = moneybag
+            .invoices
+            .iter()
+            .filter(|invoice| in_scope(invoice.scope) && in_period(&invoice.date, period))
+            .collect();
+        let revenue = sum_invoices(invoices.iter().copied());
+        let work = invoices.iter().map(|invoice| invoice.base_work()).sum();
+        let totals = Totals::of(period, scope, moneybag);
+        Pnl {
+            period: period.to_string(),
+            work,
+            billed_expenses: revenue - work,
+            revenue,
+            costs: costs_by_category(period, scope, moneybag),
+            depreciation: totals.depreciation,
+            total_costs: totals.costs + totals.depreciation,
+            operating_result: totals.net(),
+            input_vat: sum_input_vat(
+                moneybag
+                    .costs
+                    .iter()
+                    .filter(|cost| in_scope(cost.scope) && in_period(&cost.date, period)),
+            ),
+        }
+    }
+
+    /// Section, item, and amount of each line of the statement
+    fn lines(&self) -> Vec<(&'static str, String, Money)> {
+        let mut lines = vec![
+            ("Revenue", tr!("Work").to_string(), self.work),
+            (
+                "Revenue",
+                tr!("Billed expenses").to_string(),
+                self.billed_expenses,
+            ),
+            ("Revenue", tr!("Total revenue").to_string(), self.revenue),
+        ];
+        for (category, amount) in &self.costs {
+            lines.push(("Costs", category.clone(), *amount));
+        }
+        lines.push(("Costs", tr!("Depreciation").to_string(), self.depreciation));
+        lines.push(("Costs", tr!("Total costs").to_string(), self.total_costs));
+        lines.push((
+            "Result",
+            tr!("Operating result").to_string(),
+            self.operating_result,
+        ));
+        lines.push(("VAT", tr!("Input VAT").to_string(), self.input_vat));
+        lines
+    }
+
+    fn print(&self) {
+        let lines = self.lines();
+        let width = lines
+            .iter()
+            .map(|(_, item, amount)| item.chars().count() + amount.to_string().len())
+            .max()
+            .unwrap_or_default()
+            + 2;
+        println!("{}", Style::Bold.paint(&self.period));
+        let mut section = "";
+        for (line_section, item, amount) in lines {
+            if line_section != section {
+                section = line_section;
+                println!("{}", Style::Bold.paint(translate(section)));
+            }
+            let padding = width - item.chars().count();
+            println!("  {item}{:>padding$}", amount.to_string());
+        }
+    }
+
+    fn table(&self) -> Table {
+        let mut table = Table::new(&[
+            ("section", Align::Left),
+            ("item", Align::Left),
+            ("amount", Align::Right),
+        ]);
+        for (section, item, amount) in self.lines() {
+            table.add_row(vec![
+                translate(section).to_string(),
+                item,
+                amount.to_string(),
+            ]);
+        }
+        table
+    }
+
+    fn print_csv(&self) {
+        let mut writer = csv_writer();
+        writer
+            .write_record(["section", "item", "amount"])
+            .expect("Could not write CSV");
+        for (section, item, amount) in self.lines() {
+            writer
+                .write_record([section, &item, &amount.to_string()])
+                .expect("Could not write CSV");
+        }
+        writer.flush().expect("Could not write CSV");
+    }
+}
+
+/// Costs against income per month of a year, business entries only, and how many average invoices
+/// and hours at the average hourly rate it took to cover the costs, against how many there were
+fn breakeven(year: i32, moneybag: &Moneybag) -> Table {
+    let business: Vec<_> = moneybag
+        .invoices
+        .iter()
+        .filter(|invoice| invoice.scope == Scope::Business)
+        .collect();
+    let average = average_invoice(business.iter().copied());
+    let (work, hours) = business
+        .iter()
+        .filter_map(|invoice| Some((invoice.base_work(), invoice.hours()?)))
+        .fold(
+            (Money::default(), Money::default()),
+            |(work, hours), invoice| (work + invoice.0, hours + invoice.1),
+        );
+    let hourly = (!hours.is_zero()).then(|| work / hours);
+    let mut table = Table::new(&[
+        ("month", Align::Left),
+        ("costs", Align::Right),
+        ("invoiced", Align::Right),
+        ("invoices needed", Align::Right),
+        ("invoices", Align::Right),
+        ("hours needed", Align::Right),
+        ("hours", Align::Right),
+    ]);
+    for month in months_of(&year.to_string(), moneybag) {
+        let totals = Totals::of(&month, Some(Scope::Business), moneybag);
+        let costs = totals.costs + totals.depreciation;
+        let billed: Money = business
+            .iter()
+            .filter(|invoice| in_period(&invoice.date, &month))
+            .filter_map(|invoice| invoice.hours())
+            .sum();
+        let needed = |per_unit: Money| {
+            if per_unit.is_zero() {
+                String::new()
+            } else {
+                (costs / per_unit).to_string()
+            }
+        };
+        let style = if totals.invoiced < costs {
+            Style::Red
+        } else {
+            Style::Plain
+        };
+        table.add_styled_row(
+            vec![
+                month,
+                costs.to_string(),
+                totals.invoiced.to_string(),
+                needed(average),
+                totals.invoice_count.to_string(),
+                hourly.map(needed).unwrap_or_default(),
+                hourly.map(|_| billed.to_string()).unwrap_or_default(),
+            ],
+            style,
+        );
+    }
+    table
+}
+
+/// Hours billed on invoices with a rate per month of a year, and as a percentage of the target
+fn utilization(year: i32, target: Money, moneybag: &Moneybag) -> Table {
+    let mut table = Table::new(&[
+        ("month", Align::Left),
+        ("hours", Align::Right),
+        ("target", Align::Right),
+        ("utilization", Align::Right),
+    ]);
+    let percent = |hours: Money, target: Money| {
+        if target.is_zero() {
+            String::new()
+        } else {
+            format!("{}%", hours * 100 / target)
+        }
+    };
+    let mut total = Money::default();
+    let months = months_of(&year.to_string(), moneybag);
+    for month in &months {
+        let hours: Money = moneybag
+            .invoices
+            .iter()
+            .filter(|invoice| in_period(&invoice.date, month))
+            .filter_map(Invoice::hours)
+            .sum();
+        total = total + hours;
+        let style = if hours < target {
+            Style::Plain
+        } else {
+            Style::Bold
+        };
+        table.add_styled_row(
+            vec![
+                month.clone(),
+                hours.to_string(),
+                target.to_string(),
+                percent(hours, target),
+            ],
+            style,
+        );
+    }
+    let year_target = target * i64::try_from(months.len()).unwrap_or_default();
+    table.add_styled_row(
+        vec![
+            tr!("Total").to_string(),
+            total.to_string(),
+            year_target.to_string(),
+            percent(total, year_target),
+        ],
+        Style::Bold,
+    );
+    table
+}
+
+/// Invoiced, and costs with depreciation, per month for the months up to today's, each with the
+/// averages of the 3 and 12 months up to it. The net is up when its 3-month average is above the
+/// 12-month one, and down when below.
+fn trend(months: u32, scope: Option<Scope>, moneybag: &Moneybag, today: NaiveDate) -> Table {
+    let mut table = Table::new(&[
+        ("month", Align::Left),
+        ("invoiced", Align::Right),
+        ("3-month avg", Align::Right),
+        ("12-month avg", Align::Right),
+        ("costs", Align::Right),
+        ("3-month avg", Align::Right),
+        ("12-month avg", Align::Right),
+        ("trend", Align::Left),
+    ]);
+    let this_month = today.with_day(1).expect("Every month has a first day");
+    // Eleven months before the first shown, for its 12-month average
+    let totals: Vec<_> = (0..months + 11)
+        .rev()
+        .map(|i| {
+            let month = (this_month - Months::new(i)).format("%Y-%m").to_string();
+            let totals = Totals::of(&month, scope, moneybag);
+            (month, totals.invoiced, totals.costs + totals.depreciation)
+        })
+        .collect();
+    let average = |window: &[(String, Money, Money)], n: usize| {
+        let window = &window[window.len() - n..];
+        let divisor = i64::try_from(n).unwrap_or(i64::MAX);
+        (
+            window
+                .iter()
+                .map(|(_, invoiced, _)| *invoiced)
+                .sum::<Money>()
+                / divisor,
+            window.iter().map(|(_, _, costs)| *costs).sum::<Money>() / divisor,
+        )
+    };
+    for end in 12..=totals.len() {
+        let window = &totals[end - 12..end];
+        let (month, invoiced, costs) = &window[11];
+        let (invoiced_3, costs_3) = average(window, 3);
+        let (invoiced_12, costs_12) = average(window, 12);
+        let (net_3, net_12) = (invoiced_3 - costs_3, invoiced_12 - costs_12);
+        let (direction, style) = match net_3.cmp(&net_12) {
+            Ordering::Greater => (tr!("up"), Style::Plain),
+            Ordering::Less => (tr!("down"), Style::Red),
+            Ordering::Equal => (tr!("flat"), Style::Plain),
+        };
+        table.add_styled_row(
+            vec![
+                month.clone(),
+                invoiced.to_string(),
+                invoiced_3.to_string(),
+                invoiced_12.to_string(),
+                costs.to_string(),
+                costs_3.to_string(),
+                costs_12.to_string(),
+                direction.to_string(),
+            ],
+            style,
+        );
+    }
+    table
+}
+
+/// Invoiced in the base currency, costs excluding VAT attributed or billable to each customer,
+/// and what's left, of entries in a period, most profitable first
+fn profitability(period: &str, moneybag: &Moneybag) -> Table {
+    let mut table = Table::new(&[
+        ("customer", Align::Left),
+        ("invoiced", Align::Right),
+        ("costs", Align::Right),
+        ("profit", Align::Right),
+        ("margin", Align::Right),
+        ("hours", Align::Right),
+        ("per hour", Align::Right),
+    ]);
+    // Invoiced, costs, and hours
+    let mut customers: BTreeMap<&str, (Money, Money, Money)> = BTreeMap::new();
+    for invoice in &moneybag.invoices {
+        if let Some(customer) = invoice.customer.as_deref() {
+            if in_period(&invoice.date, period) {
+                let (invoiced, _, hours) = customers.entry(customer).or_default();
+                *invoiced = *invoiced + invoice.base_total();
+                *hours = *hours + invoice.hours().unwrap_or_default();
+            }
+        }
+    }
+    for cost in &moneybag.costs {
+        if let Some(customer) = cost.attributed_to() {
+            if in_period(&cost.date, period) {
+                let (_, costs, _) = customers.entry(customer).or_default();
+                *costs = *costs + cost.net();
+            }
+        }
+    }
+    let mut customers: Vec<_> = customers.into_iter().collect();
+    customers.sort_by_key(|(_, (invoiced, costs, _))| *costs - *invoiced);
+    for (customer, (invoiced, costs, hours)) in customers {
+        let profit = invoiced - costs;
+        let style = if profit.is_negative() {
+            Style::Red
+        } else {
+            Style::Plain
+        };
+        table.add_styled_row(
+            vec![
+                customer.to_string(),
+                invoiced.to_string(),
+                costs.to_string(),
+                profit.to_string(),
+                if invoiced.is_zero() {
+                    String::new()
+                } else {
+                    format!("{}%", profit * 100 / invoiced)
+                },
+                if hours.is_zero() {
+                    String::new()
+                } else {
+                    hours.to_string()
+                },
+                if hours.is_zero() {
+                    String::new()
+                } else {
+                    (profit / hours).to_string()
+                },
+            ],
+            style,
+        );
+    }
+    table
+}
+
+/// Unpaid invoices per customer, in the base currency, bucketed by days past due: not yet due,
+/// 1-30, 31-60, and over 60. Invoices without payment terms are due on their date.
+fn aging(moneybag: &Moneybag, today: NaiveDate) -> Table {
+    let mut table = Table::new(&[
+        ("customer", Align::Left),
+        ("current", Align::Right),
+        ("1-30 days", Align::Right),
+        ("31-60 days", Align::Right),
+        ("over 60 days", Align::Right),
+        ("total", Align::Right),
+    ]);
+    let mut customers: BTreeMap<String, [Money; 4]> = BTreeMap::new();
+    let unpaid = moneybag
+        .invoices
+        .iter()
+        .filter(|invoice| invoice.paid.is_none());
+    for invoice in unpaid {
+        let Some(due) = moneybag.due_date(invoice).or_else(|| invoice.parsed_date()) else {
+            continue;
+        };
+        let bucket = match (today - due).num_days() {
+            ..=0 => 0,
+            1..=30 => 1,
+            31..=60 => 2,
+            _ => 3,
+        };
+        let customer = invoice
+            .customer
+            .clone()
+            .unwrap_or_else(|| tr!("no customer").to_string());
+        let buckets = customers.entry(customer).or_default();
+        buckets[bucket] = buckets[bucket] + invoice.base_total();
+    }
+    let row = |name: String, buckets: &[Money; 4]| {
+        let mut row = vec![name];
+        row.extend(buckets.iter().map(|amount| {
+            if amount.is_zero() {
+                String::new()
+            } else {
+                amount.to_string()
+            }
+        }));
+        row.push(buckets.iter().copied().sum::<Money>().to_string());
+        row
+    };
+    let mut totals = [Money::default(); 4];
+    for (customer, buckets) in customers {
+        for (total, amount) in totals.iter_mut().zip(buckets) {
+            *total = *total + amount;
+        }
+        let style = if buckets[3].is_zero() {
+            Style::Plain
+        } else {
+            Style::Red
+        };
+        table.add_styled_row(row(customer, &buckets), style);
+    }
+    table.add_styled_row(row(tr!("Total").to_string(), &totals), Style::Bold);
+    table
+}
+
+/// VAT to pay, or get back when negative, for a quarter like "2025-Q2"
+pub(crate) fn vat_to_pay(quarter: &str, moneybag: &Moneybag) -> Money {
+    VatReturn::of(quarter, moneybag).to_pay()
+}
+
+/// VAT charged on invoices and deducted on costs of business entries in a quarter
+#[derive(Debug)]
+struct VatReturn {
+    quarter: String,
+    /// Invoiced amount that VAT was charged on
+    taxable_sales: Money,
+    /// VAT charged, by rate
+    output_vat: BTreeMap<Money, Money>,
+    input_vat: Money,
+}
+
+impl VatReturn {
+    fn of(quarter: &str, moneybag: &Moneybag) -> Self {
+        let mut taxable_sales = Money::default();
+        let mut output_vat = BTreeMap::new();
+        let invoices = moneybag.invoices.iter().filter(|invoice| {
+            invoice.scope == Scope::Business && in_period(&invoice.date, quarter)
+        });
+        for invoice in invoices {
+            if let Some(rate) = invoice.vat {
+                taxable_sales = taxable_sales + invoice.base_total();
+                let vat: &mut Money = output_vat.entry(rate).or_default();
+                *vat = *vat + invoice.vat();
+            }
+        }
+        VatReturn {
+            quarter: quarter.to_string(),
+            taxable_sales,
+            output_vat,
+            input_vat: sum_input_vat(
+                moneybag
+                    .costs
+                    .iter()
+                    .filter(|cost| cost.scope == Scope::Business && in_period(&cost.date, quarter)),
+            ),
+        }
+    }
+
+    fn table(&self) -> Table {
+        let mut table = Table::new(&[
+            ("box", Align::Left),
+            ("item", Align::Left),
+            ("amount", Align::Right),
+        ]);
+        for (declaration_box, item, amount) in self.lines() {
+            table.add_row(vec![declaration_box.to_string(), item, amount.to_string()]);
+        }
+        table
+    }
+
+    fn to_pay(&self) -> Money {
+        self.output_vat.values().copied().sum::<Money>() - self.input_vat
+    }
+
+    /// Box on the Swedish VAT declaration, label, and amount of each line
+    fn lines(&self) -> Vec<(&'static str, String, Money)> {
+        let mut lines = vec![("05", tr!("Taxable sales").to_string(), self.taxable_sales)];
+        for (rate, vat) in &self.output_vat {
+            let declaration_box = match rate.to_string().as_str() {
+                "25.00" => "10",
+                "12.00" => "11",
+                "6.00" => "12",
+                _ => "",
+            };
+            lines.push((
+                declaration_box,
+                tr!("Output VAT {rate}%", rate = rate),
+                *vat,
+            ));
+        }
+        lines.push(("48", tr!("Input VAT").to_string(), self.input_vat));
+        lines.push(("49", tr!("VAT to pay").to_string(), self.to_pay()));
+        lines
+    }
+
+    fn print(&self) {
+        let lines = self.lines();
+        let width = lines
+            .iter()
+            .map(|(_, label, amount)| label.chars().count() + amount.to_string().len())
+            .max()
+            .unwrap_or_default()
+            + 2;
+        println!("{}", Style::Bold.paint(&self.quarter));
+        for (declaration_box, label, amount) in lines {
+            let padding = width - label.chars().count();
+            println!(
+                "{declaration_box:>2}  {label}{:>padding$}",
+                amount.to_string()
+            );
+        }
+    }
+}
+
+/// What to set aside for income tax and social fees from a year's business profit
+#[derive(Debug)]
+struct TaxEstimate {
+    year: i32,
+    profit: Money,
+    social_fees: Money,
+    income_tax: Money,
+}
+
+impl TaxEstimate {
+    fn of(year: i32, moneybag: &Moneybag) -> Self {
+        let settings = &moneybag.settings;
+        let profit = Totals::of(&year.to_string(), Some(Scope::Business), moneybag).net();
+        // A loss is taxed as nothing
+        let taxable = profit.max(Money::default());
+        let social_fees = taxable * settings.social_fees / 100;
+        let income_tax = (taxable - social_fees) * settings.tax_rate / 100;
+        TaxEstimate {
+            year,
+            profit,
+            social_fees,
+            income_tax,
+        }
+    }
+
+    fn set_aside(&self) -> Money {
+        self.social_fees + self.income_tax
+    }
+
+    fn lines(&self) -> [(&'static str, Money); 5] {
+        [
+            (tr!("Profit"), self.profit),
+            (tr!("Social fees"), self.social_fees),
+            (tr!("Income tax"), self.income_tax),
+            (tr!("To set aside"), self.set_aside()),
+            (tr!("Left after tax"), self.profit - self.set_aside()),
+        ]
+    }
+
+    fn table(&self) -> Table {
+        let mut table = Table::new(&[("item", Align::Left), ("amount", Align::Right)]);
+        for (item, amount) in self.lines() {
+            table.add_row(vec![item.to_string(), amount.to_string()]);
+        }
+        table
+    }
+
+    fn print(&self) {
+        let lines = self.lines();
+        let width = lines
+            .iter()
+            .map(|(label, amount)| label.chars().count() + amount.to_string().len())
+            .max()
+            .unwrap_or_default()
+            + 2;
+        println!("{}", Style::Bold.paint(&self.year.to_string()));
+        for (label, amount) in lines {
+            let padding = width - label.chars().count();
+            println!("  {label}{:>padding$}", amount.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn moneybag() -> Moneybag {
+        fixtures::moneybag(
+            r#"{
+                "invoices": [
+                    {"date": "2025-01-31", "amount": {"amount": 100000}},
+                    {"date": "2024-12-31", "amount": {"amount": 50000}}
+                ],
+                "costs": [
+                    {"date": "2025-01", "amount": {"amount": 30000}, "name": "rent", "category": "office"},
+                    {"date": "2025-03-02", "amount": {"amount": 10000}, "name": "pens"}
+                ]
+            }"#,
+        )
+    }
+
+    #[test]
+    fn test_monthly() {
+        let table = monthly(2025, None, false, &moneybag()).to_string();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 14);
+        assert!(lines[1].starts_with("2025-01") && lines[1].ends_with("700.00"));
+        assert!(lines[3].ends_with("-100.00"));
+        assert!(lines[13].ends_with("600.00"));
+
+        // A cost dated so it can't be placed in a month is left out rather than guessed
+        let mut moneybag = moneybag();
+        moneybag.costs[0].date = "May 2025".to_string();
+        let table = monthly(2025, None, false, &moneybag).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows[1], ["2025-01", "1000.00", "0.00", "0.00", "1000.00"]);
+        assert_eq!(rows[13], ["Total", "1000.00", "100.00", "0.00", "900.00"]);
+    }
+
+    #[test]
+    fn test_yearly() {
+        let table = yearly(None, &moneybag()).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[1],
+            ["2024", "500.00", "0.00", "0.00", "500.00", "1", "500.00"]
+        );
+        assert_eq!(
+            rows[3][1..],
+            ["1500.00", "400.00", "0.00", "1100.00", "2", "750.00"]
+        );
+
+        let table = yearly(None, &Moneybag::default()).to_string();
+        assert!(table
+            .lines()
+            .nth(1)
+            .unwrap()
+            .split_whitespace()
+            .eq(["Total", "0.00", "0.00", "0.00", "0.00", "0", "0.00"]));
+    }
+
+    #[test]
+    fn test_categories() {
+        let table = categories("2025", None, &moneybag()).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows[1], ["office", "300.00", "75.00%"]);
+        assert_eq!(rows[2], ["uncategorized", "100.00", "25.00%"]);
+
+        let table = categories("2030", None, &moneybag()).to_string();
+        assert!(table
+            .lines()
+            .nth(1)
+            .unwrap()
+            .split_whitespace()
+            .eq(["Total", "0.00"]));
+    }
+
+    #[test]
+    fn test_pnl() {
+        let pnl = Pnl::of("2025-Q1", None, &moneybag());
+        assert_eq!(pnl.revenue, "1000".parse().unwrap());
+        assert_eq!(pnl.total_costs, "400".parse().unwrap());
+        assert_eq!(pnl.operating_result, "600".parse().unwrap());
+        assert_eq!(
+            Pnl::of("2025-Q2", None, &moneybag()).revenue,
+            Money::default()
+        );
+        let pnl = Pnl::of("2025-Q5", None, &moneybag());
+        assert!(pnl.revenue.is_zero() && pnl.costs.is_empty());
+    }
+
+    #[test]
+    fn test_vat_return() {
+        let mut moneybag = moneybag();
+        moneybag.invoices[0].vat = Some("25".parse().unwrap());
+        moneybag.costs[1].vat = "25%".parse().ok();
+        let vat_return = VatReturn::of("2025-Q1", &moneybag);
+        let lines = vat_return.lines();
+        assert_eq!(lines[0].2, "1000".parse().unwrap());
+        assert_eq!(lines[1].0, "10");
+        assert_eq!(lines[1].2, "250".parse().unwrap());
+        assert_eq!(vat_return.to_pay(), "230".parse().unwrap());
+        for quarter in ["2025-Q5", "2025-Q0", "Q2", "2025-q2"] {
+            let command = ReportCommand::Vat {
+                quarter: Some(quarter.to_string()),
+            };
+            assert!(
+                matches!(report(&command, &moneybag), Err(MoneybagsError::Invalid(_))),
+                "{quarter}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_tax_estimate() {
+        let mut moneybag = moneybag();
+        moneybag.settings.social_fees = "25".parse().unwrap();
+        moneybag.settings.tax_rate = "30".parse().unwrap();
+        let estimate = TaxEstimate::of(2025, &moneybag);
+        assert_eq!(estimate.profit, "600".parse().unwrap());
+        assert_eq!(estimate.social_fees, "150".parse().unwrap());
+        assert_eq!(estimate.income_tax, "135".parse().unwrap());
+        // No fees or tax on a loss
+        moneybag.costs[0].amount = "3000".parse().unwrap();
+        let estimate = TaxEstimate::of(2025, &moneybag);
+        assert!(estimate.profit.is_negative());
+        assert!(estimate.social_fees.is_zero() && estimate.income_tax.is_zero());
+    }
+
+    #[test]
+    fn test_breakeven() {
+        let table = breakeven(2025, &moneybag()).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        // Invoices average 750, so 300 of costs take 0.40 of one
+        assert_eq!(rows[1], ["2025-01", "300.00", "1000.00", "0.40", "1"]);
+        assert_eq!(rows[2], ["2025-02", "0.00", "0.00", "0.00", "0"]);
+
+        // Without invoices there's nothing to divide the costs by, so that column is left out
+        let mut moneybag = moneybag();
+        moneybag.invoices.clear();
+        let table = breakeven(2025, &moneybag).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows[0], ["month", "costs", "invoiced", "invoices"]);
+        assert_eq!(rows[1], ["2025-01", "300.00", "0.00", "0"]);
+    }
+
+    #[test]
+    fn test_utilization() {
+        let mut moneybag = moneybag();
+        moneybag.invoices[0].rate = serde_json::from_str(r#"{"rate": {"amount": 100000}}"#).ok();
+        let table = utilization(2025, "100".parse().unwrap(), &moneybag).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows[1], ["2025-01", "1000.00", "100.00", "1000.00%"]);
+        assert_eq!(rows[13], ["Total", "1000.00", "1200.00", "83.33%"]);
+
+        let command = ReportCommand::Utilization {
+            year: Some(2025),
+            target: None,
+        };
+        assert!(matches!(
+            report(&command, &moneybag),
+            Err(MoneybagsError::Invalid(_))
+        ));
+        let table = utilization(2025, Money::default(), &moneybag).to_string();
+        assert!(table
+            .lines()
+            .nth(1)
+            .unwrap()
+            .split_whitespace()
+            .eq(["2025-01", "1000.00", "0.00"]));
+    }
+
+    #[test]
+    fn test_top() {
+        let table = top(TopKind::Costs, 1, "2025", false, &moneybag()).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], ["1", "rent", "300.00", "75.00%", "75.00%"]);
+        assert!(top(TopKind::Invoices, 10, "2030", false, &moneybag())
+            .to_string()
+            .is_empty());
+        assert!(top(TopKind::Costs, 0, "2025", false, &moneybag())
+            .to_string()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_aging() {
+        let mut moneybag = moneybag();
+        moneybag.invoices[0].paid = NaiveDate::from_ymd_opt(2025, 2, 10);
+        let today = NaiveDate::from_ymd_opt(2025, 2, 15).unwrap();
+        let table = aging(&moneybag, today).to_string();
+        let lines: Vec<_> = table.lines().collect();
+        // The unpaid invoice from December is 46 days past its date
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1]
+            .split_whitespace()
+            .eq(["no", "customer", "500.00", "500.00"]));
+
+        // An invoice dated so its age can't be told is left out
+        moneybag.invoices[0].paid = None;
+        moneybag.invoices[0].date = "someday".to_string();
+        moneybag.invoices[1].paid = NaiveDate::from_ymd_opt(2025, 1, 5);
+        let table = aging(&moneybag, today).to_string();
+        assert_eq!(table.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_trend() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let table = trend(3, None, &moneybag(), today).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows.len(), 4);
+        // January's invoice and rent, spread over three and twelve months
+        assert_eq!(
+            rows[1],
+            ["2025-01", "1000.00", "500.00", "125.00", "300.00", "100.00", "25.00", "up"]
+        );
+        assert_eq!(rows[3][0], "2025-03");
+        assert!(trend(0, None, &moneybag(), today).to_string().is_empty());
+    }
+
+    #[test]
+    fn test_profitability() {
+        let mut moneybag = moneybag();
+        moneybag.invoices[0].customer = Some("Acme".to_string());
+        moneybag.costs[1].customer = Some("Acme".to_string());
+        let table = profitability("2025", &moneybag).to_string();
+        let rows: Vec<Vec<_>> = table
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(rows[1], ["Acme", "1000.00", "100.00", "900.00", "90.00%"]);
+        assert!(profitability("2025", &fixtures::moneybag("{}"))
+            .to_string()
+            .is_empty());
+    }
+}
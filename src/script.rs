@@ -0,0 +1,155 @@
+//! Custom commands written in Rhai, one script each in the scripts directory. A script sees the
+//! moneybag as `moneybag`, with amounts as text like in the file, and what it was given as
+//! `args`. `money("12.50")` makes an amount to add up, and what the script ends with is printed.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope};
+
+use crate::{config::Config, error::MoneybagsError, money::Money, moneybag::Moneybag};
+
+/// Scripts in a directory by the command they add: travel.rhai adds `travel`. A directory that
+/// can't be read has none.
+pub(crate) fn find(dir: &Path) -> BTreeMap<String, PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "rhai")
+        })
+        .filter_map(|path| Some((path.file_stem()?.to_str()?.to_string(), path)))
+        .collect()
+}
+
+/// Run the script for a command, or list the scripts there are
+pub(crate) fn handle_script(
+    name: Option<&str>,
+    args: &[String],
+    config: &Config,
+    moneybag: &Moneybag,
+) -> Result<(), MoneybagsError> {
+    let Some(name) = name else {
+        if config.script_commands.is_empty() {
+            println!("No scripts in {}", config.scripts_dir().display());
+        }
+        for (name, path) in &config.script_commands {
+            println!("{name:<16}{}", description(path));
+        }
+        return Ok(());
+    };
+    let Some(path) = config.script_commands.get(name) else {
+        let names: Vec<_> = config.script_commands.keys().map(String::as_str).collect();
+        return Err(MoneybagsError::NotFound(format!(
+            "No script {name}, there are: {}",
+            names.join(", ")
+        )));
+    };
+    let script = std::fs::read_to_string(path).map_err(|source| MoneybagsError::Read {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let result = run(&script, args, moneybag)
+        .map_err(|e| MoneybagsError::Invalid(format!("{}: {e}", path.display())))?;
+    if !result.is_unit() {
+        println!("{}", show(result));
+    }
+    Ok(())
+}
+
+/// The first line of a script, if it's a comment, to list it by
+fn description(path: &Path) -> String {
+    let script = std::fs::read_to_string(path).unwrap_or_default();
+    let first = script.lines().next().unwrap_or_default();
+    first
+        .strip_prefix("//")
+        .map(|comment| comment.trim_start_matches('/').trim().to_string())
+        .unwrap_or_default()
+}
+
+fn run(script: &str, args: &[String], moneybag: &Moneybag) -> Result<Dynamic, Box<EvalAltResult>> {
+    let mut scope = Scope::new();
+    scope.push_constant("moneybag", rhai::serde::to_dynamic(moneybag)?);
+    let args: Array = args.iter().cloned().map(Dynamic::from).collect();
+    scope.push_constant("args", args);
+    scope.push_constant("today", Local::now().date_naive().to_string());
+    engine().eval_with_scope(&mut scope, script)
+}
+
+/// Text for a value, with amounts as they're shown elsewhere
+fn show(value: Dynamic) -> String {
+    match value.try_cast_result::<Money>() {
+        Ok(money) => money.to_string(),
+        Err(value) => value.to_string(),
+    }
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .register_type_with_name::<Money>("Money")
+        .register_fn("money", |text: &str| -> Result<Money, Box<EvalAltResult>> {
+            text.parse()
+                .map_err(|_| format!("Could not parse {text} as an amount").into())
+        })
+        .register_fn("to_string", |money: &mut Money| money.to_string())
+        .register_fn("to_debug", |money: &mut Money| money.to_string())
+        .register_fn("+", |a: Money, b: Money| a + b)
+        .register_fn("-", |a: Money, b: Money| a - b)
+        .register_fn("*", |a: Money, b: i64| a * b)
+        .register_fn("/", |a: Money, b: i64| a / b)
+        .register_fn("==", |a: Money, b: Money| a == b)
+        .register_fn("!=", |a: Money, b: Money| a != b)
+        .register_fn("<", |a: Money, b: Money| a < b)
+        .register_fn("<=", |a: Money, b: Money| a <= b)
+        .register_fn(">", |a: Money, b: Money| a > b)
+        .register_fn(">=", |a: Money, b: Money| a >= b);
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_run() {
+        let moneybag = fixtures::moneybag(
+            r#"{
+                "costs": [
+                    {"date": "2025-01", "amount": "300", "name": "train", "category": "travel"},
+                    {"date": "2025-02", "amount": "50.5", "name": "taxi", "category": "travel"},
+                    {"date": "2025-02", "amount": "1000", "name": "rent", "category": "office"}
+                ]
+            }"#,
+        );
+        let script = r#"
+            let total = money("0");
+            for cost in moneybag.costs {
+                if cost.category == args[0] { total += money(cost.amount); }
+            }
+            total
+        "#;
+        let total = run(script, &["travel".to_string()], &moneybag).unwrap();
+        assert_eq!(show(total), "350.50");
+        assert!(run("money(\"lots\")", &[], &moneybag).is_err());
+    }
+
+    #[test]
+    fn test_failing_scripts() {
+        let moneybag = Moneybag::default();
+        assert!(run("let total = ;", &[], &moneybag).is_err());
+        assert!(run("args[0]", &[], &moneybag).is_err());
+        assert!(run("moneybag.costs[0].amount", &[], &moneybag).is_err());
+        assert!(run("money(\"1\") + 1", &[], &moneybag).is_err());
+        let dir = std::env::temp_dir().join(format!("moneybags-no-scripts-{}", std::process::id()));
+        assert!(find(&dir).is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+//! The moneybag over HTTP: a small JSON API, and a page for entering costs from a phone
+
+use std::io::Read;
+
+use clap::Parser;
+use serde::{de::DeserializeOwned, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::info;
+
+use crate::{
+    args::Command,
+    balance, check_customer,
+    error::MoneybagsError,
+    events::{self, Event},
+    moneybag::{Cost, Invoice, Moneybag},
+    output::Failure,
+    report::report_table,
+    Session,
+};
+
+/// The page for entering costs, served at /
+const PAGE: &str = include_str!("server.html");
+
+/// The most bytes of a request body read, far more than an invoice or cost takes
+const LIMIT: u64 = 1024 * 1024;
+
+/// Invoices and costs, which the API lists, adds, edits, and deletes the same way
+trait Entry: Serialize + DeserializeOwned {
+    /// The name in paths, e.g. "invoices"
+    const PLURAL: &'static str;
+    fn id(&self) -> usize;
+    fn set_id(&mut self, id: usize);
+    fn all(moneybag: &Moneybag) -> &Vec<Self>;
+    fn added(self) -> Event;
+    fn edited(self) -> Event;
+    fn delete(moneybag: &mut Moneybag, index: usize);
+    /// Why the entry doesn't fit in the moneybag, if it doesn't
+    fn check(&self, moneybag: &Moneybag) -> Result<(), String>;
+}
+
+impl Entry for Invoice {
+    const PLURAL: &'static str = "invoices";
+    fn id(&self) -> usize {
+        self.id
+    }
+    fn set_id(&mut self, id: usize) {
+        self.id = id;
+    }
+    fn all(moneybag: &Moneybag) -> &Vec<Self> {
+        &moneybag.invoices
+    }
+    fn added(self) -> Event {
+        Event::InvoiceAdded { invoice: self }
+    }
+    fn edited(self) -> Event {
+        Event::InvoiceEdited { invoice: self }
+    }
+    fn delete(moneybag: &mut Moneybag, index: usize) {
+        moneybag.delete_invoice(index);
+    }
+    fn check(&self, moneybag: &Moneybag) -> Result<(), String> {
+        check_customer(self.customer.as_ref(), &moneybag.customers)
+    }
+}
+
+impl Entry for Cost {
+    const PLURAL: &'static str = "costs";
+    fn id(&self) -> usize {
+        self.id
+    }
+    fn set_id(&mut self, id: usize) {
+        self.id = id;
+    }
+    fn all(moneybag: &Moneybag) -> &Vec<Self> {
+        &moneybag.costs
+    }
+    fn added(self) -> Event {
+        Event::CostAdded { cost: self }
+    }
+    fn edited(self) -> Event {
+        Event::CostEdited { cost: self }
+    }
+    fn delete(moneybag: &mut Moneybag, index: usize) {
+        let id = moneybag.costs[index].id;
+        moneybag.change(Event::CostDeleted { id });
+    }
+    fn check(&self, moneybag: &Moneybag) -> Result<(), String> {
+        let billable = self.billable.as_ref().map(|billable| &billable.customer);
+        check_customer(billable, &moneybag.customers)?;
+        check_customer(self.customer.as_ref(), &moneybag.customers)
+    }
+}
+
+/// A status code and a JSON body
+#[derive(Debug)]
+struct Reply {
+    status: u16,
+    body: String,
+}
+
+impl Reply {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        Reply {
+            status,
+            body: serde_json::to_string_pretty(value).expect("Could not serialize reply"),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Reply::json(status, &serde_json::json!({ "error": message }))
+    }
+}
+
+/// Serve the moneybag until the process is stopped, answering requests one at a time. Changes
+/// are saved as they're made.
+pub(crate) fn serve(
+    listen: &str,
+    token: &str,
+    moneybag: &mut Moneybag,
+    session: &Session,
+) -> Result<(), MoneybagsError> {
+    let server = Server::http(listen)
+        .map_err(|e| MoneybagsError::Io(format!("Could not listen on {listen}: {e}")))?;
+    println!("Serving {} on http://{listen}", session.storage.location());
+    for mut request in server.incoming_requests() {
+        if request.method() == &Method::Get && request.url() == "/" {
+            let html = Header::from_bytes("Content-Type", "text/html; charset=utf-8")
+                .expect("Header should be valid");
+            let _ = request.respond(Response::from_string(PAGE).with_header(html));
+            continue;
+        }
+        // Nothing of a request is read before it's known to be from someone with the token
+        let reply = if authorized(&request, token) {
+            let mut body = String::new();
+            // One byte over the limit tells a body that's too large from one that fits exactly
+            match request
+                .as_reader()
+                .take(LIMIT + 1)
+                .read_to_string(&mut body)
+            {
+                Err(e) => Reply::error(400, &format!("Could not read request: {e}")),
+                Ok(_) if body.len() as u64 > LIMIT => {
+                    Reply::error(413, &format!("Request body over {LIMIT} bytes"))
+                }
+                Ok(_) => {
+                    let path = request
+                        .url()
+                        .split('?')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string();
+                    respond(request.method(), &path, &body, moneybag, session)
+                }
+            }
+        } else {
+            Reply::error(401, "Expected the header Authorization: Bearer <token>")
+        };
+        info!(method = %request.method(), url = request.url(), status = reply.status, "Request");
+        let json =
+            Header::from_bytes("Content-Type", "application/json").expect("Header should be valid");
+        let response = Response::from_string(reply.body)
+            .with_status_code(reply.status)
+            .with_header(json);
+        // A client that went away before the reply doesn't stop the server
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| same(given.as_bytes(), token.as_bytes()))
+}
+
+/// Compare without stopping at the first difference, so the time taken doesn't tell how much of
+/// a guessed token was right
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn respond(
+    method: &Method,
+    path: &str,
+    body: &str,
+    moneybag: &mut Moneybag,
+    session: &Session,
+) -> Reply {
+    let words: Vec<String> = path
+        .split('/')
+        .filter(|word| !word.is_empty())
+        .map(decode)
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    match (method, &words[..]) {
+        (Method::Get, ["balance" | "report", ..]) => read(&words, moneybag),
+        (_, ["invoices", rest @ ..]) => entries::<Invoice>(method, rest, body, moneybag, session),
+        (_, ["costs", rest @ ..]) => entries::<Cost>(method, rest, body, moneybag, session),
+        _ => Reply::error(
+            404,
+            &format!("No {method} {path}, see the README for the API"),
+        ),
+    }
+}
+
+/// A path segment with %XX escapes decoded, e.g. from spaces in a report period
+fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The balance or a report, with the rest of the path as its arguments, e.g.
+/// /report/monthly/2025 for `report monthly 2025`
+fn read(words: &[&str], moneybag: &Moneybag) -> Reply {
+    let command = match Command::try_parse_from(words) {
+        Ok(command) => command,
+        Err(e) => return Reply::error(400, &e.to_string()),
+    };
+    match command {
+        Command::Balance { filter, .. } => Reply::json(200, &balance(&filter, moneybag)),
+        Command::Report(args) => match report_table(&args.command, moneybag) {
+            Ok(table) => Reply::json(200, &table),
+            Err(e) => failed(&e),
+        },
+        _ => unreachable!("Only balance and report are read"),
+    }
+}
+
+/// The reply to a command that failed, with why, after printing it
+fn failed(e: &MoneybagsError) -> Reply {
+    e.report();
+    let status = match e.failure() {
+        Failure::NotFound => 404,
+        Failure::Parse => 400,
+        Failure::Io => 500,
+        Failure::Invalid => 422,
+    };
+    Reply::error(status, &e.to_string())
+}
+
+/// List, show, add, edit, or delete invoices or costs. Adding and editing take the entry as JSON,
+/// and reply with it as stored.
+fn entries<T: Entry>(
+    method: &Method,
+    path: &[&str],
+    body: &str,
+    moneybag: &mut Moneybag,
+    session: &Session,
+) -> Reply {
+    let id = match path {
+        [] => None,
+        [id] => match id.parse::<usize>() {
+            Ok(id) => Some(id),
+            Err(_) => return Reply::error(400, &format!("Expected an ID, got {id}")),
+        },
+        _ => return Reply::error(404, "Expected /<entries> or /<entries>/<id>"),
+    };
+    let position = id.map(|id| T::all(moneybag).iter().position(|entry| entry.id() == id));
+    if let Some(None) = position {
+        let id = id.unwrap_or_default();
+        return Reply::error(404, &format!("No {} with ID {id}", T::PLURAL));
+    }
+    let mut changed = moneybag.clone();
+    let reply = match (method, position.flatten()) {
+        (Method::Get, None) => return Reply::json(200, T::all(moneybag)),
+        (Method::Get, Some(i)) => return Reply::json(200, &T::all(moneybag)[i]),
+        (Method::Delete, Some(i)) => {
+            T::delete(&mut changed, i);
+            Reply::json(200, &serde_json::json!({ "deleted": id }))
+        }
+        (Method::Post, None) | (Method::Put, Some(_)) => {
+            let mut entry: T = match serde_json::from_str(body) {
+                Ok(entry) => entry,
+                Err(e) => return Reply::error(400, &format!("Invalid entry: {e}")),
+            };
+            if let Err(e) = entry.check(moneybag) {
+                return Reply::error(422, &e);
+            }
+            entry.set_id(id.unwrap_or_default());
+            let i = position.flatten().unwrap_or(T::all(&changed).len());
+            changed.change(if id.is_some() {
+                entry.edited()
+            } else {
+                entry.added()
+            });
+            let status = if id.is_some() { 200 } else { 201 };
+            Reply::json(status, &T::all(&changed)[i])
+        }
+        _ => return Reply::error(405, &format!("Can't {method} {}", T::PLURAL)),
+    };
+    match commit(moneybag, changed, session) {
+        Ok(()) => reply,
+        Err(e) => e,
+    }
+}
+
+/// Make a change for good: run the hooks, log it, and save unless it's a dry run
+fn commit(moneybag: &mut Moneybag, changed: Moneybag, session: &Session) -> Result<(), Reply> {
+    let before = events::record(moneybag, changed).map_err(|e| failed(&e))?;
+    if let Some(logged) = moneybag.log.get(before.log.len()) {
+        session.config.hooks.after_command(&before, &logged.events);
+    }
+    if session.dry_run {
+        return Ok(());
+    }
+    session.save(moneybag, None).map_err(|e| failed(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, storage::JsonFile};
+
+    #[test]
+    fn test_entries() {
+        let session = Session {
+            storage: Box::new(JsonFile::new("unused")),
+            autosave: false,
+            dry_run: true,
+            config: Config::default(),
+            last_added: std::cell::RefCell::default(),
+        };
+        let mut moneybag = Moneybag::default();
+        let mut request = |method: Method, path: &str, body: &str| {
+            respond(&method, path, body, &mut moneybag, &session)
+        };
+        let cost = r#"{"date": "2025-05-12", "amount": "120", "name": "lunch"}"#;
+        assert_eq!(request(Method::Post, "/costs", cost).status, 201);
+        assert_eq!(request(Method::Post, "/costs", cost).status, 201);
+        let edited = r#"{"date": "2025-05-12", "amount": "90", "name": "taxi"}"#;
+        assert_eq!(request(Method::Put, "/costs/2", edited).status, 200);
+        assert_eq!(request(Method::Delete, "/costs/1", "").status, 200);
+        assert_eq!(request(Method::Delete, "/costs/1", "").status, 404);
+        let unknown = r#"{"date": "2025-05-12", "amount": "1", "name": "x", "customer": "Acme"}"#;
+        assert_eq!(request(Method::Post, "/costs", unknown).status, 422);
+        let listed = request(Method::Get, "/costs", "").body;
+        let costs: Vec<Cost> = serde_json::from_str(&listed).unwrap();
+        assert_eq!(costs.len(), 1);
+        assert_eq!((costs[0].id, costs[0].name.as_str()), (2, "taxi"));
+        let balance = request(Method::Get, "/balance", "").body;
+        assert!(balance.contains(r#""costs": "90.00""#));
+        assert_eq!(request(Method::Get, "/report/yearly", "").status, 200);
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let session = Session {
+            storage: Box::new(JsonFile::new("unused")),
+            autosave: false,
+            dry_run: true,
+            config: Config::default(),
+            last_added: std::cell::RefCell::default(),
+        };
+        let mut moneybag = Moneybag::default();
+        let mut request = |method: Method, path: &str, body: &str| {
+            respond(&method, path, body, &mut moneybag, &session).status
+        };
+        assert_eq!(request(Method::Get, "/assets", ""), 404);
+        assert_eq!(request(Method::Get, "/costs/1/name", ""), 404);
+        assert_eq!(request(Method::Get, "/costs/first", ""), 400);
+        assert_eq!(request(Method::Get, "/invoices/1", ""), 404);
+        assert_eq!(
+            request(Method::Post, "/costs", "{\"name\": \"lunch\"}"),
+            400
+        );
+        assert_eq!(request(Method::Post, "/costs", ""), 400);
+        assert_eq!(request(Method::Delete, "/costs", ""), 405);
+        assert_eq!(request(Method::Get, "/report/weekly", ""), 400);
+        assert_eq!(
+            request(Method::Get, "/report/vat/--quarter/2025-Q5", ""),
+            422
+        );
+        // Without a target for hours set
+        assert_eq!(request(Method::Get, "/report/utilization/2025", ""), 422);
+        assert!(moneybag.costs.is_empty() && moneybag.log.is_empty());
+        assert!(!same(b"secret", b"secreT"));
+        assert!(!same(b"secret", b"secret2"));
+        assert!(same(b"secret", b"secret"));
+    }
+}
@@ -0,0 +1,678 @@
+//! The business side of the moneybag as an SIE 4 file, the format Swedish accounting programs
+//! exchange books in. Invoices are booked to receivables and sales when sent and to the bank when
+//! paid, and costs straight from the bank, on accounts of the BAS chart. Files from an accountant
+//! are read back the same way.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Local, NaiveDate};
+use serde::Deserialize;
+
+use crate::{
+    error::MoneybagsError,
+    events::Event,
+    money::{Money, PercentOrMoney},
+    moneybag::{Cost, Invoice, Moneybag, Scope},
+};
+
+/// Accounts to book on and who the books are for, from the `[sie]` table of the config. Costs
+/// are booked on the account of their category, or on `costs` if it has none.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct Sie {
+    pub(crate) company: Option<String>,
+    pub(crate) org_number: Option<String>,
+    pub(crate) bank: u32,
+    pub(crate) receivables: u32,
+    /// Sales with VAT
+    pub(crate) sales: u32,
+    pub(crate) sales_without_vat: u32,
+    pub(crate) output_vat: u32,
+    pub(crate) input_vat: u32,
+    pub(crate) costs: u32,
+    /// Account of each cost category, e.g. `travel = 5800`
+    pub(crate) categories: BTreeMap<String, u32>,
+}
+
+impl Default for Sie {
+    fn default() -> Self {
+        Sie {
+            company: None,
+            org_number: None,
+            bank: 1930,
+            receivables: 1510,
+            sales: 3001,
+            sales_without_vat: 3004,
+            output_vat: 2611,
+            input_vat: 2641,
+            costs: 6990,
+            categories: BTreeMap::new(),
+        }
+    }
+}
+
+/// Names of the BAS accounts moneybags books on by default, and some common cost accounts
+const BAS_NAMES: &[(u32, &str)] = &[
+    (1510, "Kundfordringar"),
+    (1930, "Företagskonto/checkkonto/affärskonto"),
+    (2611, "Utgående moms på försäljning inom Sverige, 25 %"),
+    (2641, "Debiterad ingående moms"),
+    (3001, "Försäljning inom Sverige, 25 % moms"),
+    (3004, "Försäljning inom Sverige, momsfri"),
+    (5410, "Förbrukningsinventarier"),
+    (5800, "Resekostnader"),
+    (6212, "Mobiltelefon"),
+    (6230, "Datakommunikation"),
+    (6540, "IT-tjänster"),
+    (6570, "Bankkostnader"),
+    (6990, "Övriga externa kostnader"),
+];
+
+/// A verification: what one event moved between accounts, in amounts that add up to zero
+struct Verification {
+    date: NaiveDate,
+    text: String,
+    transactions: Vec<(u32, Money)>,
+}
+
+/// A date like 2025-05-12, or the first of the month for one like 2025-05
+fn entry_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{date}-01"), "%Y-%m-%d"))
+        .ok()
+}
+
+/// The verifications of a year, in date order
+fn verifications(year: i32, moneybag: &Moneybag, sie: &Sie) -> Vec<Verification> {
+    let mut verifications = vec![];
+    let business = |scope| scope == Scope::Business;
+    for invoice in moneybag.invoices.iter().filter(|i| business(i.scope)) {
+        let name = match (&invoice.number, &invoice.customer) {
+            (Some(number), Some(customer)) => format!("Faktura {number} {customer}"),
+            (Some(number), None) => format!("Faktura {number}"),
+            (None, Some(customer)) => format!("Faktura {} {customer}", invoice.id),
+            (None, None) => format!("Faktura {}", invoice.id),
+        };
+        let (total, vat) = (invoice.base_total(), invoice.vat());
+        if let Some(date) = entry_date(&invoice.date).filter(|date| date.year() == year) {
+            let sales = if vat.is_zero() {
+                sie.sales_without_vat
+            } else {
+                sie.sales
+            };
+            verifications.push(Verification {
+                date,
+                text: name.clone(),
+                transactions: vec![
+                    (sie.receivables, total + vat),
+                    (sales, -total),
+                    (sie.output_vat, -vat),
+                ],
+            });
+        }
+        if let Some(paid) = invoice.paid.filter(|paid| paid.year() == year) {
+            verifications.push(Verification {
+                date: paid,
+                text: format!("Betalning {}", name.to_lowercase()),
+                transactions: vec![(sie.bank, total + vat), (sie.receivables, -(total + vat))],
+            });
+        }
+    }
+    for cost in moneybag.costs.iter().filter(|c| business(c.scope)) {
+        let Some(date) = entry_date(&cost.date).filter(|date| date.year() == year) else {
+            continue;
+        };
+        let account = cost
+            .category
+            .as_ref()
+            .and_then(|category| sie.categories.get(category))
+            .copied()
+            .unwrap_or(sie.costs);
+        verifications.push(Verification {
+            date,
+            text: cost.name.clone(),
+            transactions: vec![
+                (account, cost.net()),
+                (sie.input_vat, cost.vat()),
+                (sie.bank, -cost.amount),
+            ],
+        });
+    }
+    for verification in &mut verifications {
+        verification
+            .transactions
+            .retain(|(_, amount)| !amount.is_zero());
+    }
+    verifications.retain(|verification| !verification.transactions.is_empty());
+    verifications.sort_by_key(|verification| verification.date);
+    verifications
+}
+
+/// Name of an account: its BAS name, or the categories booked on it
+fn account_name(account: u32, sie: &Sie) -> String {
+    if let Some((_, name)) = BAS_NAMES.iter().find(|(number, _)| *number == account) {
+        return (*name).to_string();
+    }
+    let categories: Vec<_> = sie
+        .categories
+        .iter()
+        .filter(|(_, number)| **number == account)
+        .map(|(category, _)| category.as_str())
+        .collect();
+    if categories.is_empty() {
+        format!("Konto {account}")
+    } else {
+        categories.join(", ")
+    }
+}
+
+/// Text in quotes, as SIE fields with spaces are written
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The books of a year as SIE 4 text, made on the given day
+pub(crate) fn sie(year: i32, moneybag: &Moneybag, sie: &Sie, today: NaiveDate) -> String {
+    let verifications = verifications(year, moneybag, sie);
+    let mut lines = vec![
+        "#FLAGGA 0".to_string(),
+        "#FORMAT PC8".to_string(),
+        "#SIETYP 4".to_string(),
+        format!("#PROGRAM \"moneybags\" {}", env!("CARGO_PKG_VERSION")),
+        format!("#GEN {}", today.format("%Y%m%d")),
+        format!(
+            "#FNAMN {}",
+            quoted(sie.company.as_deref().unwrap_or("moneybags"))
+        ),
+    ];
+    if let Some(org_number) = &sie.org_number {
+        lines.push(format!("#ORGNR {org_number}"));
+    }
+    lines.push(format!("#RAR 0 {year}0101 {year}1231"));
+    lines.push("#KPTYP BAS2014".to_string());
+    let mut accounts: Vec<_> = verifications
+        .iter()
+        .flat_map(|verification| &verification.transactions)
+        .map(|(account, _)| *account)
+        .collect();
+    accounts.sort_unstable();
+    accounts.dedup();
+    for account in accounts {
+        lines.push(format!(
+            "#KONTO {account} {}",
+            quoted(&account_name(account, sie))
+        ));
+    }
+    for (number, verification) in (1..).zip(verifications) {
+        lines.push(format!(
+            "#VER A {number} {} {}",
+            verification.date.format("%Y%m%d"),
+            quoted(&verification.text)
+        ));
+        lines.push("{".to_string());
+        for (account, amount) in verification.transactions {
+            lines.push(format!("   #TRANS {account} {{}} {amount}"));
+        }
+        lines.push("}".to_string());
+    }
+    lines.join("\r\n") + "\r\n"
+}
+
+/// Characters 0x80 to 0xFF of PC8, the IBM PC code page SIE files are written in
+const PC8_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+                        └┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// Text in PC8. Characters it doesn't have become '?'.
+pub(crate) fn pc8(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match u8::try_from(c) {
+            Ok(byte) if byte.is_ascii() => byte,
+            _ => PC8_HIGH
+                .chars()
+                .position(|high| high == c)
+                .and_then(|i| u8::try_from(0x80 + i).ok())
+                .unwrap_or(b'?'),
+        })
+        .collect()
+}
+
+/// Text from PC8
+fn from_pc8(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0..=0x7F => char::from(byte),
+            _ => PC8_HIGH
+                .chars()
+                .nth(usize::from(byte - 0x80))
+                .unwrap_or('?'),
+        })
+        .collect()
+}
+
+/// Write the books of a year to an SIE file at path
+pub(crate) fn export_sie(
+    year: i32,
+    moneybag: &Moneybag,
+    config: &Sie,
+    path: &str,
+) -> std::io::Result<()> {
+    std::fs::write(
+        path,
+        pc8(&sie(year, moneybag, config, Local::now().date_naive())),
+    )
+}
+
+/// A verification read from an SIE file, with the reference it's kept by
+struct Read {
+    reference: String,
+    verification: Verification,
+}
+
+/// The fields of an SIE line: words, quoted text, and lists in braces, which are kept whole
+fn fields(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut field = String::new();
+        match c {
+            '"' => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => field.extend(chars.next()),
+                        '"' => break,
+                        c => field.push(c),
+                    }
+                }
+            }
+            '{' => {
+                for c in chars.by_ref() {
+                    field.push(c);
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            _ => {
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    field.push(c);
+                }
+            }
+        }
+        fields.push(field);
+    }
+    fields
+}
+
+/// The account names and verifications in SIE text
+fn parse_sie(text: &str) -> Result<(BTreeMap<u32, String>, Vec<Read>), String> {
+    let mut names = BTreeMap::new();
+    let mut read = vec![];
+    let mut current: Option<Read> = None;
+    for (i, line) in text.lines().enumerate() {
+        let fields = fields(line);
+        let field = |n: usize| fields.get(n).map_or("", String::as_str);
+        let bad = |what: &str| format!("line {}: {what} {line}", i + 1);
+        match field(0) {
+            "#KONTO" => {
+                if let Ok(account) = field(1).parse() {
+                    names.insert(account, field(2).to_string());
+                }
+            }
+            "#VER" => {
+                let date =
+                    NaiveDate::parse_from_str(field(3), "%Y%m%d").map_err(|_| bad("no date in"))?;
+                current = Some(Read {
+                    reference: format!("{} {} {date}", field(1), field(2)),
+                    verification: Verification {
+                        date,
+                        text: field(4).to_string(),
+                        transactions: vec![],
+                    },
+                });
+            }
+            "#TRANS" => {
+                let Some(current) = current.as_mut() else {
+                    return Err(bad("transaction outside a verification:"));
+                };
+                let account = field(1).parse().map_err(|_| bad("no account in"))?;
+                let amount = field(3).parse().map_err(|_| bad("no amount in"))?;
+                current.verification.transactions.push((account, amount));
+            }
+            "}" => read.extend(current.take()),
+            _ => {}
+        }
+    }
+    Ok((names, read))
+}
+
+/// What was imported, for the summary
+#[derive(Default)]
+struct Imported {
+    invoices: usize,
+    costs: usize,
+    paid: usize,
+    skipped: usize,
+}
+
+/// An invoice for income booked on a day, with the VAT booked on it
+fn income_invoice(date: NaiveDate, income: Money, output_vat: Money, paid: bool) -> Invoice {
+    // Whole percent, as VAT rates are, rounding away what was rounded to whole kronor
+    let vat = Some((output_vat.steps_of(income, 10_000) + 50) / 100)
+        .filter(|vat| *vat != 0)
+        .and_then(|vat| vat.to_string().parse().ok());
+    Invoice {
+        id: 0,
+        date: date.to_string(),
+        amount: income,
+        rate: None,
+        rate_name: None,
+        customer: None,
+        expenses: vec![],
+        scope: Scope::Business,
+        exchange_rate: None,
+        tier_hours: BTreeMap::new(),
+        number: None,
+        paid: paid.then_some(date),
+        vat,
+        reference: None,
+        reminded: None,
+    }
+}
+
+/// Add what a verification books: an invoice for income, costs for expenses, or the payment of
+/// an invoice
+fn add_verification(
+    read: Read,
+    category_of: impl Fn(u32) -> Option<String>,
+    customer: Option<&str>,
+    moneybag: &mut Moneybag,
+    imported: &mut Imported,
+) {
+    let Read {
+        reference,
+        verification,
+    } = read;
+    let sum = |accounts: std::ops::RangeInclusive<u32>| -> Money {
+        verification
+            .transactions
+            .iter()
+            .filter(|(account, _)| accounts.contains(account))
+            .map(|(_, amount)| *amount)
+            .sum()
+    };
+    let (income, output_vat, input_vat, bank) = (
+        -sum(3000..=3999),
+        -sum(2610..=2639),
+        sum(2640..=2649),
+        sum(1900..=1999),
+    );
+    let mut expenses: BTreeMap<u32, Money> = BTreeMap::new();
+    for (account, amount) in &verification.transactions {
+        if (4000..=8999).contains(account) {
+            let expense = expenses.entry(*account).or_default();
+            *expense = *expense + *amount;
+        }
+    }
+    if !income.is_zero() {
+        let duplicate = moneybag.invoices.iter().any(|invoice| {
+            entry_date(&invoice.date) == Some(verification.date) && invoice.base_total() == income
+        });
+        if duplicate {
+            imported.skipped += 1;
+        } else {
+            moneybag.change(Event::InvoiceAdded {
+                invoice: Invoice {
+                    customer: customer.map(str::to_string),
+                    reference: Some(reference.clone()),
+                    ..income_invoice(verification.date, income, output_vat, !bank.is_zero())
+                },
+            });
+            imported.invoices += 1;
+        }
+    }
+    let net: Money = expenses.values().copied().sum();
+    let mut vat_left = input_vat;
+    for (i, (account, amount)) in expenses.iter().enumerate() {
+        // Input VAT is shared between the expenses by amount, the last taking what's left
+        let vat = if i + 1 == expenses.len() {
+            vat_left
+        } else {
+            input_vat * *amount / net
+        };
+        vat_left = vat_left - vat;
+        let cost = Cost {
+            id: 0,
+            date: verification.date.to_string(),
+            amount: *amount + vat,
+            name: verification.text.clone(),
+            recurring: false,
+            vat: Some(PercentOrMoney::Fixed(vat)).filter(|_| !vat.is_zero()),
+            subscription: None,
+            category: category_of(*account),
+            billable: None,
+            customer: None,
+            note: None,
+            scope: Scope::Business,
+            reference: Some(reference.clone()),
+        };
+        let duplicate = moneybag.costs.iter().any(|other| {
+            entry_date(&other.date) == Some(verification.date)
+                && other.amount == cost.amount
+                && other.name == cost.name
+        });
+        if duplicate {
+            imported.skipped += 1;
+        } else {
+            moneybag.change(Event::CostAdded { cost });
+            imported.costs += 1;
+        }
+    }
+    if income.is_zero() && expenses.is_empty() && bank > Money::default() {
+        let unpaid = moneybag
+            .invoices
+            .iter()
+            .find(|invoice| invoice.paid.is_none() && invoice.base_total() + invoice.vat() == bank);
+        if let Some(invoice) = unpaid {
+            let invoice = Invoice {
+                paid: Some(verification.date),
+                ..invoice.clone()
+            };
+            moneybag.change(Event::InvoiceEdited { invoice });
+            imported.paid += 1;
+        }
+    }
+}
+
+/// Import the verifications of an SIE file: income as invoices, to the customer given, expenses
+/// as costs, and payments into the bank as invoices paid. Costs are put in the category the
+/// config books on their account, or one named after the account. Verifications imported before
+/// are skipped, as are entries that are already in the moneybag, like those exported from it.
+pub(crate) fn import_sie(
+    path: &str,
+    customer: Option<&str>,
+    moneybag: &mut Moneybag,
+    config: &Sie,
+) -> Result<Vec<String>, MoneybagsError> {
+    let import_error = |reason| MoneybagsError::ImportBook {
+        path: path.to_string(),
+        reason,
+    };
+    let bytes = std::fs::read(path).map_err(|e| import_error(e.to_string()))?;
+    let text = String::from_utf8(bytes).unwrap_or_else(|e| from_pc8(e.as_bytes()));
+    let (names, read) = parse_sie(&text).map_err(import_error)?;
+    let category_of = |account: u32| {
+        let configured = config
+            .categories
+            .iter()
+            .find(|(_, number)| **number == account)
+            .map(|(category, _)| category.clone());
+        if configured.is_none() && account == config.costs {
+            return None;
+        }
+        configured
+            .or_else(|| Some(names.get(&account)?.to_lowercase()))
+            .or_else(|| Some(account.to_string()))
+    };
+    let mut imported = Imported::default();
+    for read in read {
+        let reference = Some(read.reference.clone());
+        if moneybag
+            .invoices
+            .iter()
+            .any(|invoice| invoice.reference == reference)
+            || moneybag
+                .costs
+                .iter()
+                .any(|cost| cost.reference == reference)
+        {
+            imported.skipped += 1;
+            continue;
+        }
+        add_verification(read, category_of, customer, moneybag, &mut imported);
+    }
+    moneybag.add_missing_customers();
+    Ok(vec![format!(
+        "Imported {} invoices and {} costs, marked {} invoices paid, skipped {} already in the \
+         moneybag",
+        imported.invoices, imported.costs, imported.paid, imported.skipped
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_sie() {
+        let moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [
+                    {"id": 1, "date": "2025-01-10", "amount": "1000", "customer": "Acme",
+                     "vat": "25", "paid": "2025-02-09"},
+                    {"id": 2, "date": "2024-12-20", "amount": "500", "paid": "2025-01-05"}
+                ],
+                "costs": [
+                    {"date": "2025-03-01", "amount": "125", "name": "Train", "vat": {"Fixed": "25"},
+                     "category": "travel"},
+                    {"date": "2025-03", "amount": "40", "name": "Coffee", "scope": "Personal"}
+                ]
+            }"#,
+        );
+        let mut config = Sie::default();
+        config.categories.insert("travel".to_string(), 5800);
+        let sie = sie(
+            2025,
+            &moneybag,
+            &config,
+            NaiveDate::from_ymd_opt(2025, 12, 31).unwrap(),
+        );
+        assert!(sie.contains("#KONTO 5800 \"Resekostnader\"\r\n"));
+        assert!(sie.contains(
+            "#VER A 1 20250105 \"Betalning faktura 2\"\r\n{\r\n   #TRANS 1930 {} 500.00\r\n   \
+             #TRANS 1510 {} -500.00\r\n}"
+        ));
+        assert!(sie.contains(
+            "#VER A 2 20250110 \"Faktura 1 Acme\"\r\n{\r\n   #TRANS 1510 {} 1250.00\r\n   \
+             #TRANS 3001 {} -1000.00\r\n   #TRANS 2611 {} -250.00\r\n}"
+        ));
+        assert!(sie.contains("#TRANS 5800 {} 100.00\r\n   #TRANS 2641 {} 25.00"));
+        assert!(!sie.contains("Coffee"));
+        assert_eq!(sie.matches("#VER").count(), 4);
+        assert_eq!(pc8("Två öl"), b"Tv\x86 \x94l");
+        assert_eq!(PC8_HIGH.chars().count(), 128);
+        assert_eq!(from_pc8(&pc8("Två öl")), "Två öl");
+    }
+
+    #[test]
+    fn test_import_sie() {
+        let path = std::env::temp_dir().join("moneybags-test.se");
+        let text = "#FLAGGA 0\r\n#KONTO 6540 \"IT-tjänster\"\r\n\
+                    #VER A 1 20250110 \"Faktura 7\"\r\n{\r\n   #TRANS 1510 {} 1250.00\r\n   \
+                    #TRANS 3001 {} -1000.00\r\n   #TRANS 2611 {} -250.00\r\n}\r\n\
+                    #VER A 2 20250120 \"Hosting \\\"EU\\\"\"\r\n{\r\n   #TRANS 6540 {1 \"10\"} 80.00\r\n   \
+                    #TRANS 5800 {} 20.00\r\n   #TRANS 2641 {} 25.00\r\n   #TRANS 1930 {} -125.00\r\n}\r\n\
+                    #VER A 3 20250209 \"Betalning\"\r\n{\r\n   #TRANS 1930 {} 1250.00\r\n   \
+                    #TRANS 1510 {} -1250.00\r\n}\r\n";
+        std::fs::write(&path, pc8(text)).unwrap();
+        let mut moneybag = Moneybag::default();
+        let mut config = Sie::default();
+        config.categories.insert("travel".to_string(), 5800);
+        let path = path.to_str().unwrap();
+        import_sie(path, Some("Acme"), &mut moneybag, &config).unwrap();
+        import_sie(path, Some("Acme"), &mut moneybag, &config).unwrap();
+        assert_eq!(moneybag.invoices.len(), 1);
+        let invoice = &moneybag.invoices[0];
+        assert_eq!(invoice.vat, Some("25".parse().unwrap()));
+        assert_eq!(invoice.paid, NaiveDate::from_ymd_opt(2025, 2, 9));
+        assert_eq!(moneybag.costs.len(), 2);
+        assert_eq!(moneybag.costs[0].name, "Hosting \"EU\"");
+        assert_eq!(moneybag.costs[0].category.as_deref(), Some("travel"));
+        assert_eq!(moneybag.costs[1].category.as_deref(), Some("it-tjänster"));
+        let total: Money = moneybag.costs.iter().map(|cost| cost.amount).sum();
+        assert_eq!(total, "125".parse().unwrap());
+        let vat: Money = moneybag.costs.iter().map(Cost::vat).sum();
+        assert_eq!(vat, "25".parse().unwrap());
+    }
+
+    #[test]
+    fn test_export_sie() {
+        let moneybag = fixtures::moneybag(
+            r#"{"invoices": [{"id": 1, "date": "sometime", "amount": "1000"}]}"#,
+        );
+        let today = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        let sie = sie(2025, &moneybag, &Sie::default(), today);
+        // An invoice without a date to book it on is left out
+        assert!(sie.starts_with("#FLAGGA 0"));
+        assert!(!sie.contains("#VER"));
+        let dir = std::env::temp_dir().join(format!("moneybags-no-sie-{}", std::process::id()));
+        let path = dir.join("2025.se");
+        assert!(export_sie(2025, &moneybag, &Sie::default(), &path.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn test_import_bad_sie() {
+        for (text, error) in [
+            (
+                "#VER A 1 2025-01-10 \"Faktura\"\n{\n}",
+                "line 1: no date in",
+            ),
+            (
+                "#TRANS 1930 {} 100.00",
+                "line 1: transaction outside a verification:",
+            ),
+            (
+                "#VER A 1 20250110 \"\"\n{\n#TRANS bank {} 100.00\n}",
+                "line 3: no account in",
+            ),
+            (
+                "#VER A 1 20250110 \"\"\n{\n#TRANS 1930 {} 100,00\n}",
+                "line 3: no amount in",
+            ),
+        ] {
+            let result = parse_sie(text);
+            assert!(
+                result.as_ref().is_err_and(|e| e.starts_with(error)),
+                "{text}"
+            );
+        }
+        let mut moneybag = Moneybag::default();
+        let missing = std::env::temp_dir().join("moneybags-test-missing.se");
+        assert!(matches!(
+            import_sie(
+                missing.to_str().unwrap(),
+                None,
+                &mut moneybag,
+                &Sie::default()
+            ),
+            Err(MoneybagsError::ImportBook { .. })
+        ));
+        assert!(moneybag.invoices.is_empty() && moneybag.costs.is_empty());
+    }
+}
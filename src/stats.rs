@@ -0,0 +1,172 @@
+use std::{collections::BTreeSet, time::SystemTime};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    error::MoneybagsError,
+    events,
+    i18n::tr,
+    money::Money,
+    moneybag::{Invoice, Moneybag},
+    output::print_field,
+    report::in_period,
+};
+
+/// The spread of invoice amounts in the base currency
+#[derive(Debug, Serialize)]
+pub struct InvoiceStats {
+    pub count: usize,
+    pub sum: Money,
+    pub mean: Money,
+    pub median: Money,
+    pub min: Money,
+    pub max: Money,
+    /// The amount 90% of invoices are at or below
+    pub percentile_90: Money,
+}
+
+impl InvoiceStats {
+    /// Statistics of the invoices, or None if there are none
+    fn of<'a>(invoices: impl IntoIterator<Item = &'a Invoice>) -> Option<Self> {
+        let mut amounts: Vec<_> = invoices.into_iter().map(Invoice::base_total).collect();
+        amounts.sort();
+        let (&min, &max) = (amounts.first()?, amounts.last()?);
+        let count = amounts.len();
+        let sum: Money = amounts.iter().copied().sum();
+        let middle = count / 2;
+        let median = if count % 2 == 0 {
+            (amounts[middle - 1] + amounts[middle]) / 2
+        } else {
+            amounts[middle]
+        };
+        // Nearest rank: the smallest amount with at least 90% of invoices at or below it
+        let rank = (count * 9).div_ceil(10);
+        Some(InvoiceStats {
+            count,
+            sum,
+            mean: sum / i64::try_from(count).unwrap_or(i64::MAX),
+            median,
+            min,
+            max,
+            percentile_90: amounts[rank - 1],
+        })
+    }
+
+    pub(crate) fn print(&self) {
+        print_field("Invoices", self.count);
+        print_field("Total", self.sum);
+        print_field("Mean", self.mean);
+        print_field("Median", self.median);
+        print_field("Smallest", self.min);
+        print_field("Largest", self.max);
+        print_field("90th percentile", self.percentile_90);
+    }
+}
+
+/// Statistics of invoices in a period and to a customer, if given
+pub(crate) fn invoice_stats(
+    period: Option<&str>,
+    customer: Option<&str>,
+    moneybag: &Moneybag,
+) -> Result<InvoiceStats, MoneybagsError> {
+    let invoices = moneybag.invoices.iter().filter(|invoice| {
+        period.is_none_or(|period| in_period(&invoice.date, period))
+            && customer.is_none_or(|customer| invoice.customer.as_deref() == Some(customer))
+    });
+    InvoiceStats::of(invoices)
+        .ok_or_else(|| MoneybagsError::NotFound(tr!("No invoices to summarize").to_string()))
+}
+
+/// Print how much is in a moneybag and its file: entries of each kind, the dates they span, and
+/// the file's size and when it was last saved
+pub(crate) fn print_file_stats(moneybag: &Moneybag, filepath: &str) {
+    let file = std::fs::metadata(filepath).ok();
+    let log = std::fs::metadata(events::log_path(filepath)).ok();
+    print_field("File", filepath);
+    match &file {
+        Some(file) => print_field("Size", size(file.len())),
+        None => print_field("Size", tr!("not saved yet")),
+    }
+    if let Some(log) = log {
+        print_field("Log size", size(log.len()));
+    }
+    match file.and_then(|file| file.modified().ok()) {
+        Some(modified) => print_field("Last saved", saved_at(modified)),
+        None => print_field("Last saved", tr!("never")),
+    }
+    print_field("Invoices", moneybag.invoices.len());
+    print_field("Costs", moneybag.costs.len());
+    print_field("Assets", moneybag.assets.len());
+    print_field("Rates", moneybag.rates.len());
+    print_field("Budgets", moneybag.budgets.len());
+    print_field("Import rules", moneybag.import_rules.len());
+    print_field("Logged changes", moneybag.log.len());
+    print_field("Customers", moneybag.customers.len());
+    let categories: BTreeSet<_> = moneybag
+        .costs
+        .iter()
+        .filter_map(|cost| cost.category.as_deref())
+        .collect();
+    print_field("Categories", categories.len());
+    let dates: BTreeSet<_> = moneybag
+        .invoices
+        .iter()
+        .map(|invoice| &invoice.date)
+        .chain(moneybag.costs.iter().map(|cost| &cost.date))
+        .collect();
+    if let (Some(oldest), Some(newest)) = (dates.first(), dates.last()) {
+        print_field("Oldest entry", oldest);
+        print_field("Newest entry", newest);
+    }
+}
+
+/// A size in bytes as B, kB, or MB, rounded down
+fn size(bytes: u64) -> String {
+    match bytes {
+        0..1_000 => format!("{bytes} B"),
+        1_000..1_000_000 => format!("{}.{} kB", bytes / 1_000, bytes % 1_000 / 100),
+        _ => format!("{}.{} MB", bytes / 1_000_000, bytes % 1_000_000 / 100_000),
+    }
+}
+
+fn saved_at(modified: SystemTime) -> String {
+    DateTime::<Local>::from(modified)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_invoice_stats() {
+        let moneybag = fixtures::moneybag(
+            r#"{
+                "invoices": [
+                    {"date": "2025-01-31", "amount": {"amount": 100000}},
+                    {"date": "2025-02-28", "amount": {"amount": 300000}},
+                    {"date": "2025-03-31", "amount": {"amount": 200000}},
+                    {"date": "2025-04-30", "amount": {"amount": 5000000}}
+                ]
+            }"#,
+        );
+        let stats = InvoiceStats::of(&moneybag.invoices).unwrap();
+        assert_eq!(stats.count, 4);
+        // One large invoice pulls up the mean, but not the median
+        assert_eq!(stats.mean, "14000".parse().unwrap());
+        assert_eq!(stats.median, "2500".parse().unwrap());
+        assert_eq!(stats.min, "1000".parse().unwrap());
+        assert_eq!(stats.percentile_90, "50000".parse().unwrap());
+        assert!(InvoiceStats::of(&[]).is_none());
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size(999), "999 B");
+        assert_eq!(size(12_345), "12.3 kB");
+        assert_eq!(size(4_560_000), "4.5 MB");
+    }
+}
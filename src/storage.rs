@@ -0,0 +1,243 @@
+//! Where moneybags are kept between sessions. [`JsonFile`] keeps one in a JSON file with its log
+//! next to it, and anything else that implements [`Storage`] can keep one elsewhere.
+
+use std::{cell::RefCell, time::Instant};
+
+use chrono::Local;
+use tracing::{info, warn};
+
+use crate::{
+    error::MoneybagsError,
+    events::{self, Event, LogEntry},
+    moneybag::Moneybag,
+};
+
+/// A place to load a moneybag from and save it to
+pub trait Storage {
+    /// The moneybag as it was stored, with its log, or an empty one if nothing is stored yet
+    ///
+    /// # Errors
+    ///
+    /// If what's stored can't be read, or isn't a moneybag
+    fn load(&self) -> Result<Moneybag, MoneybagsError>;
+
+    /// Store a moneybag and its log, replacing what was stored
+    ///
+    /// # Errors
+    ///
+    /// If it can't be stored
+    fn save(&self, moneybag: &Moneybag) -> Result<(), MoneybagsError>;
+
+    /// Where the moneybag is stored, e.g. a path, for messages and the prompt
+    fn location(&self) -> String;
+}
+
+/// A moneybag in a JSON file, with its log next to it
+pub struct JsonFile {
+    path: String,
+    /// How many entries of the log its file has, and the last of them as JSON. Saving a log that
+    /// goes on from there appends what's new, and any other log replaces the file.
+    written: RefCell<(usize, Option<String>)>,
+}
+
+impl JsonFile {
+    #[must_use]
+    pub fn new(path: impl Into<String>) -> Self {
+        JsonFile {
+            path: path.into(),
+            written: RefCell::new((0, None)),
+        }
+    }
+
+    fn wrote(&self, log: &[LogEntry]) {
+        *self.written.borrow_mut() = (log.len(), log.last().map(entry_json));
+    }
+}
+
+fn entry_json(entry: &LogEntry) -> String {
+    serde_json::to_string(entry).unwrap_or_default()
+}
+
+impl Storage for JsonFile {
+    fn load(&self) -> Result<Moneybag, MoneybagsError> {
+        let start = Instant::now();
+        let log = events::read_log(&events::log_path(&self.path))?;
+        let json = match std::fs::read_to_string(&self.path) {
+            Ok(json) => json,
+            // The log is all that's left, so the moneybag is what it builds
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let moneybag = log.as_deref().map(events::replay).unwrap_or_default();
+                self.wrote(&moneybag.log);
+                return Ok(moneybag);
+            }
+            Err(source) => {
+                return Err(MoneybagsError::Read {
+                    path: self.path.clone(),
+                    source,
+                })
+            }
+        };
+        let mut moneybag: Moneybag =
+            serde_json::from_str(&json).map_err(|source| MoneybagsError::Parse {
+                path: self.path.clone(),
+                source,
+            })?;
+        moneybag.log = log.unwrap_or_default();
+        self.wrote(&moneybag.log);
+        info!(
+            path = self.path,
+            invoices = moneybag.invoices.len(),
+            costs = moneybag.costs.len(),
+            logged = moneybag.log.len(),
+            elapsed = ?start.elapsed(),
+            "Loaded moneybag"
+        );
+        Ok(moneybag)
+    }
+
+    fn save(&self, moneybag: &Moneybag) -> Result<(), MoneybagsError> {
+        let start = Instant::now();
+        let write_error = |source| MoneybagsError::Write {
+            path: self.path.clone(),
+            source,
+        };
+        let (written, last) = self.written.borrow().clone();
+        let log_path = events::log_path(&self.path);
+        // A log that was never loaded isn't in this moneybag, so replacing it would lose it
+        if written == 0 && std::fs::metadata(&log_path).is_ok_and(|log| log.len() > 0) {
+            return Err(MoneybagsError::Write {
+                path: log_path.display().to_string(),
+                source: std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    "there is a log that wasn't loaded",
+                ),
+            });
+        }
+        let json = serde_json::to_string_pretty(&moneybag)
+            .map_err(|e| write_error(std::io::Error::other(e)))?;
+        std::fs::write(&self.path, json).map_err(write_error)?;
+        if !moneybag.log.is_empty() {
+            let goes_on = written > 0 && moneybag.log.get(written - 1).map(entry_json) == last;
+            let (new, append) = if goes_on {
+                (&moneybag.log[written..], true)
+            } else {
+                (&moneybag.log[..], false)
+            };
+            events::write_log(new, &log_path, append)?;
+            self.wrote(&moneybag.log);
+        }
+        info!(path = self.path, elapsed = ?start.elapsed(), "Saved moneybag");
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        self.path.clone()
+    }
+}
+
+/// Load a moneybag and bring it up to date: customers only named by entries get added, and
+/// entries without IDs get them. A moneybag that isn't what its log builds, because it's from
+/// before there was a log or was changed outside moneybags, starts the log over from it. That's
+/// told by the checksum of the last entry, or for logs from before there were checksums, by
+/// replaying the log.
+///
+/// # Errors
+///
+/// If the storage can't load it
+pub fn open(storage: &dyn Storage) -> Result<Moneybag, MoneybagsError> {
+    let mut moneybag = storage.load()?;
+    moneybag.add_missing_customers();
+    moneybag.assign_ids();
+    // What loading changes is in the moneybag the log starts over from, if it changes anything
+    moneybag.pending.clear();
+    let json = serde_json::to_string(&moneybag).map_err(|source| MoneybagsError::Parse {
+        path: storage.location(),
+        source,
+    })?;
+    let logged = match moneybag.log.last() {
+        Some(LogEntry {
+            checksum: Some(checksum),
+            ..
+        }) => *checksum == events::checksum(&json),
+        _ => serde_json::to_string(&events::replay(&moneybag.log)).ok() == Some(json.clone()),
+    };
+    if !logged {
+        warn!(
+            location = storage.location(),
+            "Moneybag isn't what its log builds, starting the log over"
+        );
+        let started = Event::Started {
+            moneybag: Box::new(moneybag.clone()),
+        };
+        moneybag.log.push(LogEntry {
+            at: Local::now(),
+            events: vec![started],
+            checksum: Some(events::checksum(&json)),
+        });
+    }
+    Ok(moneybag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unreadable_files() {
+        let dir = std::env::temp_dir().join(format!("moneybags-storage-{}", std::process::id()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("moneybag.json").to_string_lossy().into_owned();
+        let file = JsonFile::new(&path);
+        std::fs::write(&path, r#"{"invoices": ["#).unwrap();
+        assert!(matches!(open(&file), Err(MoneybagsError::Parse { .. })));
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::write(events::log_path(&path), "not a log entry\n").unwrap();
+        assert!(matches!(
+            open(&file),
+            Err(MoneybagsError::Log { line: 1, .. })
+        ));
+        // A directory in place of the file
+        let file = JsonFile::new(dir.to_string_lossy());
+        assert!(matches!(file.load(), Err(MoneybagsError::Read { .. })));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let file = JsonFile::new(&path);
+        assert!(matches!(
+            file.save(&Moneybag::default()),
+            Err(MoneybagsError::Write { .. })
+        ));
+    }
+
+    #[test]
+    fn test_log_without_its_moneybag() {
+        let dir = std::env::temp_dir().join(format!("moneybags-log-only-{}", std::process::id()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("books.log").to_string_lossy().into_owned();
+        assert_eq!(events::log_path(&path), dir.join("books.log.log"));
+        let mut moneybag = Moneybag::default();
+        let mut changed = moneybag.clone();
+        let mut settings = changed.settings.clone();
+        settings.tax_rate = "30".parse().unwrap();
+        changed.change(Event::SettingsChanged { settings });
+        events::record(&mut moneybag, changed).unwrap();
+        JsonFile::new(&path).save(&moneybag).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // Saving over the log before loading it would lose it
+        assert!(matches!(
+            JsonFile::new(&path).save(&Moneybag::default()),
+            Err(MoneybagsError::Write { .. })
+        ));
+        let file = JsonFile::new(&path);
+        let loaded = open(&file).unwrap();
+        assert_eq!(loaded.settings.tax_rate, "30".parse().unwrap());
+        assert_eq!(loaded.log.len(), 1);
+        file.save(&loaded).unwrap();
+        assert_eq!(
+            events::read_log(&events::log_path(&path))
+                .unwrap()
+                .unwrap()
+                .len(),
+            1
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,426 @@
+//! Syncing a moneybag with another copy of it, e.g. in a shared folder, to keep one set of books
+//! on more than one machine. The other copy's log is the main line: what changed here since the
+//! last sync is merged onto it, and this moneybag takes on its log, so the next sync starts from
+//! there. Entries changed differently on both sides are asked about, not overwritten.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::IsTerminal,
+    path::Path,
+};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    args::Keep,
+    cancellable,
+    dry_run::print_changes,
+    error::MoneybagsError,
+    events::{self, Event, LogEntry},
+    moneybag::Moneybag,
+    output::note,
+    prompt, recovery,
+    storage::{self, JsonFile, Storage},
+    Session,
+};
+
+/// An entry changed differently on both sides since the last sync
+struct Conflict {
+    /// What was changed, e.g. "invoice 3"
+    entry: String,
+    mine: Event,
+    theirs: Event,
+    /// When it was last changed here, and in the other copy, from the logs
+    mine_at: Option<DateTime<Local>>,
+    theirs_at: Option<DateTime<Local>>,
+}
+
+/// The entry an event changes, the same for every change to it
+fn entry(event: &Event) -> String {
+    match event {
+        Event::Started { .. } => "everything".to_string(),
+        Event::InvoiceAdded { invoice } | Event::InvoiceEdited { invoice } => {
+            format!("invoice {}", invoice.id)
+        }
+        Event::InvoiceDeleted { id } => format!("invoice {id}"),
+        Event::CostAdded { cost } | Event::CostEdited { cost } => format!("cost {}", cost.id),
+        Event::CostDeleted { id } => format!("cost {id}"),
+        Event::AssetsChanged { .. } => "assets".to_string(),
+        Event::ImportRulesChanged { .. } => "import rules".to_string(),
+        Event::RateSet { name, .. } => format!("rate {name}"),
+        Event::CustomerSet { name, .. } => format!("customer {name}"),
+        Event::BudgetSet { category, .. } => format!("budget {category}"),
+        Event::SettingsChanged { .. } => "settings".to_string(),
+    }
+}
+
+/// The entry as an event leaves it, or None if it's deleted
+fn value(event: &Event) -> Option<Value> {
+    let value = match event {
+        Event::Started { .. } | Event::InvoiceDeleted { .. } | Event::CostDeleted { .. } => {
+            return None
+        }
+        Event::InvoiceAdded { invoice } | Event::InvoiceEdited { invoice } => {
+            serde_json::to_value(invoice)
+        }
+        Event::CostAdded { cost } | Event::CostEdited { cost } => serde_json::to_value(cost),
+        Event::AssetsChanged { assets } => serde_json::to_value(assets),
+        Event::ImportRulesChanged { import_rules } => serde_json::to_value(import_rules),
+        Event::RateSet { rate, .. } => serde_json::to_value(rate.as_ref()?),
+        Event::CustomerSet { customer, .. } => serde_json::to_value(customer.as_ref()?),
+        Event::BudgetSet { budget, .. } => serde_json::to_value(budget.as_ref()?),
+        Event::SettingsChanged { settings } => serde_json::to_value(settings),
+    };
+    Some(value.expect("Could not serialize entry"))
+}
+
+/// When an entry was last changed, in the part of a log since the last sync
+fn changed_at(log: &[LogEntry], name: &str) -> Option<DateTime<Local>> {
+    log.iter()
+        .rev()
+        .find(|logged| {
+            logged
+                .events
+                .iter()
+                .any(|event| matches!(event, Event::Started { .. }) || entry(event) == name)
+        })
+        .map(|logged| logged.at)
+}
+
+/// How the two versions of an entry differ, a line per field
+fn differences(conflict: &Conflict) -> Vec<String> {
+    let show =
+        |value: Option<&Value>, missing: &str| value.map_or(missing.to_string(), Value::to_string);
+    match (value(&conflict.mine), value(&conflict.theirs)) {
+        (Some(Value::Object(mine)), Some(Value::Object(theirs))) => {
+            let fields: BTreeSet<&String> = mine.keys().chain(theirs.keys()).collect();
+            fields
+                .into_iter()
+                .filter(|field| mine.get(*field) != theirs.get(*field))
+                .map(|field| {
+                    format!(
+                        "{field}: {} here, {} there",
+                        show(mine.get(field), "none"),
+                        show(theirs.get(field), "none")
+                    )
+                })
+                .collect()
+        }
+        (mine, theirs) => vec![
+            format!("here: {}", show(mine.as_ref(), "deleted")),
+            format!("there: {}", show(theirs.as_ref(), "deleted")),
+        ],
+    }
+}
+
+fn json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("Could not serialize entry")
+}
+
+/// Give invoices and costs added here the next free IDs where the other copy added others under
+/// the same IDs since the last sync, and point expenses billed here at the costs' new IDs. IDs
+/// in the other copy are kept, so once synced, they stay.
+fn renumber(changes: &mut [Event], mine: &Moneybag, theirs: &Moneybag) {
+    let mut next_invoice_id = mine.next_invoice_id.max(theirs.next_invoice_id);
+    let mut next_cost_id = mine.next_cost_id.max(theirs.next_cost_id);
+    let mut cost_ids = BTreeMap::new();
+    for change in changes.iter_mut() {
+        match change {
+            Event::InvoiceAdded { invoice } => {
+                let taken = theirs.invoices.iter().find(|their| their.id == invoice.id);
+                if taken.is_some_and(|their| json(their) != json(invoice)) {
+                    invoice.id = next_invoice_id;
+                    next_invoice_id += 1;
+                }
+            }
+            Event::CostAdded { cost } => {
+                let taken = theirs.costs.iter().find(|their| their.id == cost.id);
+                if taken.is_some_and(|their| json(their) != json(cost)) {
+                    cost_ids.insert(cost.id, next_cost_id);
+                    cost.id = next_cost_id;
+                    next_cost_id += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    for change in changes {
+        if let Event::InvoiceAdded { invoice } | Event::InvoiceEdited { invoice } = change {
+            for expense in &mut invoice.expenses {
+                if let Some(id) = expense.cost.and_then(|id| cost_ids.get(&id)) {
+                    expense.cost = Some(*id);
+                }
+            }
+        }
+    }
+}
+
+/// Apply a change made here to the other copy, where the entry may since have been added or
+/// deleted
+fn apply(change: Event, moneybag: &mut Moneybag) {
+    let change = match change {
+        Event::InvoiceAdded { invoice } | Event::InvoiceEdited { invoice } => {
+            if moneybag.invoices.iter().any(|their| their.id == invoice.id) {
+                Event::InvoiceEdited { invoice }
+            } else {
+                Event::InvoiceAdded { invoice }
+            }
+        }
+        Event::CostAdded { cost } | Event::CostEdited { cost } => {
+            if moneybag.costs.iter().any(|their| their.id == cost.id) {
+                Event::CostEdited { cost }
+            } else {
+                Event::CostAdded { cost }
+            }
+        }
+        change => change,
+    };
+    moneybag.change(change);
+}
+
+/// Merge the changes from base to mine onto theirs. Of an entry changed differently on both
+/// sides, the version resolve says is kept. Fails with resolve's error if it gives up.
+fn merge(
+    base: &Moneybag,
+    mine: (&Moneybag, &[LogEntry]),
+    theirs: (&Moneybag, &[LogEntry]),
+    mut resolve: impl FnMut(&Conflict) -> Result<Keep, MoneybagsError>,
+) -> Result<Moneybag, MoneybagsError> {
+    let original = events::between(base, mine.0);
+    let mut changes = original.clone();
+    renumber(&mut changes, mine.0, theirs.0);
+    let their_changes = events::between(base, theirs.0);
+    let mut merged = theirs.0.clone();
+    for (change, original) in changes.into_iter().zip(&original) {
+        let name = entry(&change);
+        if let Some(their_change) = their_changes.iter().find(|their| entry(their) == name) {
+            if value(their_change) == value(&change) {
+                continue;
+            }
+            let conflict = Conflict {
+                // The log here has the entry under the ID it had before renumbering
+                mine_at: changed_at(mine.1, &entry(original)),
+                theirs_at: changed_at(theirs.1, &name),
+                entry: name,
+                mine: change.clone(),
+                theirs: their_change.clone(),
+            };
+            if resolve(&conflict)? == Keep::Theirs {
+                continue;
+            }
+        }
+        apply(change, &mut merged);
+    }
+    Ok(merged)
+}
+
+/// Ask whether to keep mine or theirs. Fails at the end of input, or if cancelled.
+fn ask() -> Result<Keep, MoneybagsError> {
+    let stopped = || {
+        MoneybagsError::Invalid(
+            "Stopped at a conflict, nothing synced. Give --keep to resolve conflicts without \
+             asking."
+                .to_string(),
+        )
+    };
+    loop {
+        match prompt("Keep mine or theirs? [m/t] ").as_deref() {
+            Ok("m" | "mine") => return Ok(Keep::Mine),
+            Ok("t" | "theirs") => return Ok(Keep::Theirs),
+            Err(MoneybagsError::Cancelled) => return Err(MoneybagsError::Cancelled),
+            Err(_) => return Err(stopped()),
+            _ if !std::io::stdin().is_terminal() => return Err(stopped()),
+            _ => {}
+        }
+    }
+}
+
+/// Sync the moneybag with the copy at `other`, saving the merge both there and here. A missing
+/// copy, without even a log, is made from the moneybag. Conflicts are resolved as keep says, or
+/// asked about. A dry run prints what would change here, keeping mine.
+pub(crate) fn handle_sync(
+    other: &str,
+    keep: Option<Keep>,
+    moneybag: &mut Moneybag,
+    session: &Session,
+    dry_run: bool,
+) -> Result<(), MoneybagsError> {
+    if other == session.storage.location() {
+        return Err(MoneybagsError::Invalid(
+            "Can't sync a moneybag with itself".to_string(),
+        ));
+    }
+    let storage = JsonFile::new(other);
+    if !Path::new(other).exists() && !events::log_path(other).exists() {
+        if dry_run {
+            println!("Dry run, would copy the moneybag to {other}");
+        } else {
+            storage.save(moneybag)?;
+            note(&format!("Copied the moneybag to {other}"));
+        }
+        return Ok(());
+    }
+    let mut theirs = storage::open(&storage)?;
+    let json = |logged: &LogEntry| serde_json::to_string(logged).ok();
+    let synced = moneybag
+        .log
+        .iter()
+        .zip(&theirs.log)
+        .take_while(|(mine, their)| json(mine) == json(their))
+        .count();
+    let base = events::replay(&moneybag.log[..synced]);
+    let resolve = |conflict: &Conflict| {
+        let at = |at: Option<DateTime<Local>>| {
+            at.map(|at| format!(" ({})", at.format("%Y-%m-%d %H:%M")))
+                .unwrap_or_default()
+        };
+        println!(
+            "{} was changed both here{} and in {other}{}:",
+            conflict.entry,
+            at(conflict.mine_at),
+            at(conflict.theirs_at)
+        );
+        for line in differences(conflict) {
+            println!("    {line}");
+        }
+        keep.or(dry_run.then_some(Keep::Mine)).map_or_else(ask, Ok)
+    };
+    let merged = merge(
+        &base,
+        (moneybag, &moneybag.log[synced..]),
+        (&theirs, &theirs.log[synced..]),
+        resolve,
+    );
+    let Some(merged) = cancellable(merged)? else {
+        return Ok(());
+    };
+    if dry_run {
+        print_changes(moneybag, &merged);
+        return Ok(());
+    }
+    recovery::keep(moneybag);
+    events::record(&mut theirs, merged)?;
+    storage.save(&theirs)?;
+    let synced = events::between(moneybag, &theirs);
+    session.config.hooks.after_command(moneybag, &synced);
+    // The other copy's log, with the merge at its end, is where the next sync starts from. It's
+    // saved here at once, as the log changes even when nothing else does.
+    *moneybag = theirs;
+    session.save(moneybag, None)?;
+    note(&format!("Synced with {other}"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_merge() {
+        let base = fixtures::moneybag(
+            r#"{
+                "invoices": [
+                    {"id": 1, "date": "2025-04-01", "amount": "1000", "customer": "Acme"},
+                    {"id": 2, "date": "2025-04-15", "amount": "500", "customer": "Acme"}
+                ],
+                "next_invoice_id": 3
+            }"#,
+        );
+        let mut mine = base.clone();
+        mine.invoices[0].paid = chrono::NaiveDate::from_ymd_opt(2025, 5, 1);
+        mine.invoices[1].amount = "600".parse().unwrap();
+        let mut added = base.invoices[0].clone();
+        added.id = 3;
+        added.date = "2025-05-01".to_string();
+        mine.invoices.push(added.clone());
+        let mut theirs = base.clone();
+        theirs.invoices[1].amount = "700".parse().unwrap();
+        added.date = "2025-05-02".to_string();
+        theirs.invoices.push(added);
+        theirs.next_invoice_id = 4;
+        mine.next_invoice_id = 4;
+
+        let mut conflicts = vec![];
+        let merged = merge(&base, (&mine, &[]), (&theirs, &[]), |conflict| {
+            conflicts.push((conflict.entry.clone(), differences(conflict)));
+            Ok(Keep::Mine)
+        })
+        .unwrap();
+        assert_eq!(
+            conflicts,
+            [(
+                "invoice 2".to_string(),
+                vec![r#"amount: "600.00" here, "700.00" there"#.to_string()]
+            )]
+        );
+        let invoices: Vec<_> = merged
+            .invoices
+            .iter()
+            .map(|invoice| {
+                (
+                    invoice.id,
+                    invoice.date.as_str(),
+                    invoice.amount.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            invoices,
+            [
+                (1, "2025-04-01", "1000.00".to_string()),
+                (2, "2025-04-15", "600.00".to_string()),
+                (3, "2025-05-02", "1000.00".to_string()),
+                (4, "2025-05-01", "1000.00".to_string())
+            ]
+        );
+        assert!(merged.invoices[0].paid.is_some());
+        assert_eq!(merged.next_invoice_id, 5);
+
+        let kept = merge(&base, (&mine, &[]), (&theirs, &[]), |_| Ok(Keep::Theirs)).unwrap();
+        assert_eq!(kept.invoices[1].amount.to_string(), "700.00");
+        let stopped = merge(&base, (&mine, &[]), (&theirs, &[]), |_| {
+            Err(MoneybagsError::Cancelled)
+        });
+        assert!(matches!(stopped, Err(MoneybagsError::Cancelled)));
+    }
+
+    #[test]
+    fn test_merge_renumbers_what_refers_to_renumbered_costs() {
+        let base = fixtures::moneybag(
+            r#"{
+                "invoices": [{"id": 1, "date": "2025-05-31", "amount": "1000", "customer": "Acme"}],
+                "next_invoice_id": 2
+            }"#,
+        );
+        let cost = |json: &str| serde_json::from_str(json).unwrap();
+        // Cost 1 is added here, and then billed, and the other copy added another cost 1
+        let mut mine = base.clone();
+        mine.change(Event::CostAdded {
+            cost: cost(
+                r#"{"date": "2025-05-12", "amount": "450", "name": "train",
+                    "billable": {"customer": "Acme", "billed": false}}"#,
+            ),
+        });
+        crate::bill_expenses(0, &[1], &mut mine).unwrap();
+        let mut theirs = base.clone();
+        theirs.change(Event::CostAdded {
+            cost: cost(r#"{"date": "2025-05-13", "amount": "90", "name": "taxi"}"#),
+        });
+
+        let merged = merge(&base, (&mine, &[]), (&theirs, &[]), |_| {
+            Err(MoneybagsError::Cancelled)
+        })
+        .unwrap();
+        let costs: Vec<_> = merged
+            .costs
+            .iter()
+            .map(|cost| (cost.id, cost.name.as_str()))
+            .collect();
+        assert_eq!(costs, [(1, "taxi"), (2, "train")]);
+        assert!(merged.costs[1].billable.as_ref().unwrap().billed);
+        assert_eq!(merged.invoices[0].expenses[0].cost, Some(2));
+    }
+}
@@ -0,0 +1,578 @@
+//! Importing hours tracked in Toggl Track or Clockify, from a detailed report CSV export or their
+//! APIs. The hours of a period become an invoice per customer and rate, which projects are mapped
+//! to in the `[time]` table of the config. Invoices keep the period, customer, and rate as their
+//! reference, so a period can be imported again.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::{
+    args::TrackedArgs,
+    error::MoneybagsError,
+    events::Event,
+    moneybag::{Invoice, Moneybag, Scope},
+    payments::Row,
+    report::in_period,
+    Money,
+};
+
+/// Where hours were tracked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Tracker {
+    Toggl,
+    Clockify,
+}
+
+impl Tracker {
+    fn name(self) -> &'static str {
+        match self {
+            Tracker::Toggl => "Toggl",
+            Tracker::Clockify => "Clockify",
+        }
+    }
+}
+
+/// The time trackers' APIs, and what their projects are billed as, from the `[time]` table of the
+/// config
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct TimeTracking {
+    /// API token of Toggl Track, unless set in `MONEYBAGS_TOGGL_TOKEN`
+    pub(crate) toggl_token: Option<String>,
+    /// API key of Clockify, unless set in `MONEYBAGS_CLOCKIFY_TOKEN`
+    pub(crate) clockify_token: Option<String>,
+    /// Clockify workspace ID, if not the active one
+    pub(crate) clockify_workspace: Option<String>,
+    /// Clockify's API, e.g. a regional one like <https://euc1.clockify.me/api>
+    pub(crate) clockify_url: String,
+    /// Customer and rate of each project, by its name
+    pub(crate) projects: BTreeMap<String, Project>,
+}
+
+impl Default for TimeTracking {
+    fn default() -> Self {
+        TimeTracking {
+            toggl_token: None,
+            clockify_token: None,
+            clockify_workspace: None,
+            clockify_url: "https://api.clockify.me/api".to_string(),
+            projects: BTreeMap::new(),
+        }
+    }
+}
+
+/// What the hours of a project are invoiced as
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Project {
+    /// Customer, if not the project's client
+    pub(crate) customer: Option<String>,
+    /// Rate, if not the one given to the import
+    pub(crate) rate: Option<String>,
+}
+
+/// Time spent on a project
+#[derive(Debug, PartialEq)]
+struct Entry {
+    date: NaiveDate,
+    project: String,
+    client: String,
+    seconds: i64,
+    billable: bool,
+}
+
+/// HH:MM:SS, with any number of hours
+fn seconds(duration: &str) -> Option<i64> {
+    let mut parts = duration.split(':').map(|part| part.parse::<i64>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// The entry in a row of a detailed report export
+fn entry(tracker: Tracker, row: &Row) -> Option<Entry> {
+    let (date, duration) = match tracker {
+        Tracker::Toggl => (row.get("Start date"), row.get("Duration")),
+        // Dates are written as set for the account
+        Tracker::Clockify => (row.get("Start Date"), row.get("Duration (h)")),
+    };
+    Some(Entry {
+        date: ["%Y-%m-%d", "%m/%d/%Y", "%d/%m/%Y", "%d.%m.%Y"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(date, format).ok())?,
+        project: row.get("Project").to_string(),
+        client: row.get("Client").to_string(),
+        seconds: seconds(duration)?,
+        billable: row.get("Billable") == "Yes",
+    })
+}
+
+fn read_export(tracker: Tracker, path: &str) -> Result<Vec<Entry>, MoneybagsError> {
+    let import_error = |source| MoneybagsError::Import {
+        path: path.to_string(),
+        source,
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(import_error)?;
+    let columns: HashMap<_, _> = reader
+        .headers()
+        .map_err(import_error)?
+        .iter()
+        .enumerate()
+        // Toggl starts its exports with a byte order mark
+        .map(|(i, header)| (header.trim_start_matches('\u{feff}').trim().to_string(), i))
+        .collect();
+    let mut entries = vec![];
+    for record in reader.records() {
+        let record = record.map_err(import_error)?;
+        let row = Row {
+            columns: &columns,
+            record: &record,
+        };
+        entries.extend(entry(tracker, &row));
+    }
+    Ok(entries)
+}
+
+/// The first day of a period like 2025, 2025-Q2, or 2025-05, and the day after its last
+fn bounds(period: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let (year, months) = match period.split_once('-') {
+        None => (period, 1..=12),
+        Some((year, quarter)) if quarter.starts_with('Q') => {
+            let quarter: u32 = quarter[1..].parse().ok().filter(|q| (1..=4).contains(q))?;
+            (year, quarter * 3 - 2..=quarter * 3)
+        }
+        Some((year, month)) => {
+            let month: u32 = month.parse().ok()?;
+            (year, month..=month)
+        }
+    };
+    let year: i32 = year.parse().ok()?;
+    let start = NaiveDate::from_ymd_opt(year, *months.start(), 1)?;
+    let end = match months.end() {
+        12 => NaiveDate::from_ymd_opt(year + 1, 1, 1)?,
+        month => NaiveDate::from_ymd_opt(year, month + 1, 1)?,
+    };
+    Some((start, end))
+}
+
+/// The entries of the period from the tracker's API
+#[cfg(feature = "http")]
+mod api {
+    use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+    use serde::{de::DeserializeOwned, Deserialize};
+
+    use super::{Entry, TimeTracking};
+
+    fn get<T: DeserializeOwned>(url: &str, header: (&str, &str)) -> Result<T, String> {
+        let body = ureq::get(url)
+            .header(header.0, header.1)
+            .call()
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(|e| format!("Could not fetch {url}: {e}"))?;
+        serde_json::from_str(&body).map_err(|e| format!("Could not read what {url} returned: {e}"))
+    }
+
+    fn token(config: Option<&String>, variable: &str, table_key: &str) -> Result<String, String> {
+        std::env::var(variable)
+            .ok()
+            .or_else(|| config.cloned())
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| {
+                format!("Set {table_key} in the [time] table of the config, or {variable}")
+            })
+    }
+
+    fn local_date(at: DateTime<FixedOffset>) -> NaiveDate {
+        at.with_timezone(&Local).date_naive()
+    }
+
+    #[derive(Deserialize)]
+    struct TogglEntry {
+        start: DateTime<FixedOffset>,
+        /// Negative while running
+        duration: i64,
+        #[serde(default)]
+        billable: bool,
+        project_name: Option<String>,
+        client_name: Option<String>,
+    }
+
+    pub(super) fn toggl(
+        config: &TimeTracking,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<Entry>, String> {
+        use base64::Engine;
+
+        let token = token(
+            config.toggl_token.as_ref(),
+            "MONEYBAGS_TOGGL_TOKEN",
+            "toggl_token",
+        )?;
+        let credentials =
+            base64::engine::general_purpose::STANDARD.encode(format!("{token}:api_token"));
+        let entries: Vec<TogglEntry> = get(
+            &format!(
+                "https://api.track.toggl.com/api/v9/me/time_entries\
+                 ?start_date={start}&end_date={end}&meta=true"
+            ),
+            ("Authorization", &format!("Basic {credentials}")),
+        )?;
+        Ok(entries
+            .into_iter()
+            .filter(|entry| entry.duration >= 0)
+            .map(|entry| Entry {
+                date: local_date(entry.start),
+                project: entry.project_name.unwrap_or_default(),
+                client: entry.client_name.unwrap_or_default(),
+                seconds: entry.duration,
+                billable: entry.billable,
+            })
+            .collect())
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ClockifyUser {
+        id: String,
+        active_workspace: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ClockifyEntry {
+        #[serde(default)]
+        billable: bool,
+        time_interval: ClockifyInterval,
+        project: Option<ClockifyProject>,
+    }
+
+    #[derive(Deserialize)]
+    struct ClockifyInterval {
+        start: DateTime<FixedOffset>,
+        /// None while running
+        end: Option<DateTime<FixedOffset>>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ClockifyProject {
+        name: String,
+        client_name: Option<String>,
+    }
+
+    const PAGE_SIZE: usize = 1000;
+
+    pub(super) fn clockify(
+        config: &TimeTracking,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<Entry>, String> {
+        let token = token(
+            config.clockify_token.as_ref(),
+            "MONEYBAGS_CLOCKIFY_TOKEN",
+            "clockify_token",
+        )?;
+        let url = config.clockify_url.trim_end_matches('/');
+        let header = ("X-Api-Key", token.as_str());
+        let user: ClockifyUser = get(&format!("{url}/v1/user"), header)?;
+        let workspace = config
+            .clockify_workspace
+            .as_ref()
+            .unwrap_or(&user.active_workspace);
+        let mut entries = vec![];
+        for page in 1.. {
+            let fetched: Vec<ClockifyEntry> = get(
+                &format!(
+                    "{url}/v1/workspaces/{workspace}/user/{}/time-entries?hydrated=true\
+                     &start={start}T00:00:00Z&end={end}T00:00:00Z&page={page}&page-size={PAGE_SIZE}",
+                    user.id
+                ),
+                header,
+            )?;
+            let last = fetched.len() < PAGE_SIZE;
+            entries.extend(fetched.into_iter().filter_map(|entry| {
+                let interval = entry.time_interval;
+                let project = entry.project;
+                Some(Entry {
+                    date: local_date(interval.start),
+                    project: project
+                        .as_ref()
+                        .map(|project| project.name.clone())
+                        .unwrap_or_default(),
+                    client: project
+                        .and_then(|project| project.client_name)
+                        .unwrap_or_default(),
+                    seconds: (interval.end? - interval.start).num_seconds(),
+                    billable: entry.billable,
+                })
+            }));
+            if last {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// The tracked entries of the period, from an export if a file is given, or else the API
+fn tracked(
+    tracker: Tracker,
+    file: Option<&str>,
+    period: Option<&str>,
+    config: &TimeTracking,
+) -> Result<Vec<Entry>, MoneybagsError> {
+    if let Some(file) = file {
+        return read_export(tracker, file);
+    }
+    let Some((start, end)) = period.and_then(bounds) else {
+        return Err(MoneybagsError::Invalid(
+            "Give the period to fetch, e.g. --period 2025-05, or an exported file".to_string(),
+        ));
+    };
+    #[cfg(feature = "http")]
+    {
+        match tracker {
+            Tracker::Toggl => api::toggl(config, start, end),
+            Tracker::Clockify => api::clockify(config, start, end),
+        }
+        .map_err(MoneybagsError::Invalid)
+    }
+    #[cfg(not(feature = "http"))]
+    {
+        let _ = (config, start, end);
+        Err(MoneybagsError::Invalid(
+            "moneybags was built without http, build it with --features http".to_string(),
+        ))
+    }
+}
+
+/// Hours of the invoice to a customer at a rate for a period
+#[derive(Default)]
+struct Hours {
+    seconds: i64,
+    /// Date of the last entry
+    last: Option<NaiveDate>,
+}
+
+/// The hours of each invoice, by period, customer, and rate, and the seconds of each project left
+/// out for having no rate
+type Grouped = (
+    BTreeMap<(String, Option<String>, String), Hours>,
+    BTreeMap<String, i64>,
+);
+
+/// The entries in the period, or each month, by the invoice they go on
+fn group(entries: Vec<Entry>, args: &TrackedArgs, config: &TimeTracking) -> Grouped {
+    let mut invoices: BTreeMap<_, Hours> = BTreeMap::new();
+    let mut unrated = BTreeMap::new();
+    for entry in entries {
+        let date = entry.date.to_string();
+        let period = match &args.period {
+            Some(period) if in_period(&date, period) => period.clone(),
+            Some(_) => continue,
+            None => date[..7].to_string(),
+        };
+        if args.billable && !entry.billable {
+            continue;
+        }
+        let project = config.projects.get(&entry.project);
+        let customer = project
+            .and_then(|project| project.customer.clone())
+            .or_else(|| (!entry.client.is_empty()).then_some(entry.client));
+        let Some(rate) = project
+            .and_then(|project| project.rate.clone())
+            .or_else(|| args.rate.clone())
+        else {
+            *unrated.entry(entry.project).or_default() += entry.seconds;
+            continue;
+        };
+        let hours = invoices.entry((period, customer, rate)).or_default();
+        hours.seconds += entry.seconds;
+        hours.last = hours.last.max(Some(entry.date));
+    }
+    (invoices, unrated)
+}
+
+/// Import the hours of a period tracked in Toggl or Clockify as an invoice per customer and rate,
+/// or one per month of an export if no period is given. Projects are invoiced to their client at
+/// the rate given, unless mapped otherwise in the config. Hours without a rate are left out.
+pub(crate) fn import_tracked(
+    tracker: Tracker,
+    args: &TrackedArgs,
+    moneybag: &mut Moneybag,
+    config: &TimeTracking,
+) -> Result<Vec<String>, MoneybagsError> {
+    let file = args
+        .file
+        .as_ref()
+        .map(|file| shellexpand::tilde(file).to_string());
+    let entries = tracked(tracker, file.as_deref(), args.period.as_deref(), config)?;
+    let (invoices, unrated) = group(entries, args, config);
+    let (mut imported, mut skipped) = (0, 0);
+    let mut total = Money::default();
+    for ((period, customer, rate_name), hours) in invoices {
+        let reference = Some(format!(
+            "{} {period} {} {rate_name}",
+            tracker.name(),
+            customer.as_deref().unwrap_or_default()
+        ));
+        if moneybag
+            .invoices
+            .iter()
+            .any(|invoice| invoice.reference == reference)
+        {
+            skipped += 1;
+            continue;
+        }
+        let Some(rate) = moneybag.rates.get(&rate_name).cloned() else {
+            return Err(MoneybagsError::Invalid(format!(
+                "Rate {rate_name} not found in rates"
+            )));
+        };
+        let exchange_rate = moneybag
+            .settings
+            .exchange_rate_for(rate.currency.as_deref())
+            .map_err(MoneybagsError::Invalid)?;
+        let seconds: Money = hours.seconds.to_string().parse().unwrap_or_default();
+        let billed = rate.billable_hours(seconds / 3600);
+        let date = hours.last.unwrap_or_default().to_string();
+        let number = moneybag.next_invoice_number(customer.as_deref(), &date);
+        let invoice = Invoice {
+            id: 0,
+            date,
+            amount: billed,
+            rate: Some(rate),
+            rate_name: Some(rate_name),
+            customer,
+            expenses: vec![],
+            scope: Scope::Business,
+            exchange_rate,
+            tier_hours: BTreeMap::new(),
+            number,
+            paid: None,
+            vat: None,
+            reference,
+            reminded: None,
+        };
+        moneybag.change(Event::InvoiceAdded { invoice });
+        imported += 1;
+        total = total + billed;
+    }
+    moneybag.add_missing_customers();
+    let mut messages = vec![format!(
+        "Imported {imported} invoices of {total} hours from {}, skipped {skipped} imported before",
+        tracker.name()
+    )];
+    if !unrated.is_empty() {
+        let projects: Vec<_> = unrated
+            .iter()
+            .map(|(project, seconds)| {
+                format!(
+                    "{} ({}:{:02})",
+                    if project.is_empty() {
+                        "no project"
+                    } else {
+                        project
+                    },
+                    seconds / 3600,
+                    seconds % 3600 / 60
+                )
+            })
+            .collect();
+        messages.push(format!(
+            "Left out hours without a rate, give --rate or map their projects in [time.projects]: {}",
+            projects.join(", ")
+        ));
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_bounds() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(
+            bounds("2025-05"),
+            Some((date(2025, 5, 1), date(2025, 6, 1)))
+        );
+        assert_eq!(
+            bounds("2025-Q4"),
+            Some((date(2025, 10, 1), date(2026, 1, 1)))
+        );
+        assert_eq!(bounds("2025"), Some((date(2025, 1, 1), date(2026, 1, 1))));
+        assert_eq!(bounds("2025-Q5"), None);
+        assert_eq!(seconds("10:30:15"), Some(37815));
+    }
+
+    #[test]
+    fn test_import_tracked() {
+        let args = |file: Option<&str>, period: Option<&str>| TrackedArgs {
+            file: file.map(str::to_string),
+            period: period.map(str::to_string),
+            rate: Some("standard".to_string()),
+            billable: false,
+        };
+        let port = fixtures::closed_port();
+        let config = TimeTracking {
+            clockify_token: Some("key".to_string()),
+            clockify_url: format!("http://127.0.0.1:{port}/api"),
+            ..TimeTracking::default()
+        };
+        let mut moneybag = Moneybag::default();
+        for period in [None, Some("2025-13")] {
+            assert!(matches!(
+                import_tracked(
+                    Tracker::Clockify,
+                    &args(None, period),
+                    &mut moneybag,
+                    &config
+                ),
+                Err(MoneybagsError::Invalid(_))
+            ));
+        }
+        let result = import_tracked(
+            Tracker::Clockify,
+            &args(None, Some("2025-05")),
+            &mut moneybag,
+            &config,
+        );
+        let Err(MoneybagsError::Invalid(error)) = result else {
+            panic!("Expected the import to fail");
+        };
+        if cfg!(feature = "http") {
+            assert!(error.starts_with("Could not fetch"), "{error}");
+        }
+
+        let path = std::env::temp_dir().join("moneybags-test-toggl.csv");
+        std::fs::write(
+            &path,
+            "\u{feff}Project,Client,Start date,Duration,Billable\n             Website,Acme,2025-05-02,02:30:00,Yes\n             Website,Acme,soon,01:00:00,Yes\n             Website,Acme,2025-05-03,an hour,Yes\n",
+        )
+        .unwrap();
+        let file = path.to_str().unwrap();
+        assert_eq!(read_export(Tracker::Toggl, file).unwrap().len(), 1);
+        // The rate isn't in the moneybag
+        let result = import_tracked(
+            Tracker::Toggl,
+            &args(Some(file), None),
+            &mut moneybag,
+            &config,
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(MoneybagsError::Invalid(_))));
+        assert!(matches!(
+            read_export(Tracker::Toggl, file),
+            Err(MoneybagsError::Import { .. })
+        ));
+        assert!(moneybag.invoices.is_empty());
+    }
+}
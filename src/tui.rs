@@ -0,0 +1,426 @@
+use std::io::IsTerminal;
+
+use chrono::Local;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Paragraph, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    error::MoneybagsError,
+    events,
+    money::Money,
+    moneybag::{sum_costs, sum_depreciation, sum_input_vat, sum_invoices, Moneybag},
+};
+
+/// Which list keys act on
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Invoices,
+    Costs,
+}
+
+/// What typed keys do
+#[derive(PartialEq)]
+enum Mode {
+    Browse,
+    /// Typing the filter, which lists are narrowed down by as it's typed
+    Filter,
+    /// Typing a new amount for the selected entry
+    Edit(String),
+    /// Asking whether to delete the selected entry
+    Delete,
+}
+
+struct App<'a> {
+    moneybag: &'a mut Moneybag,
+    pane: Pane,
+    invoices: TableState,
+    costs: TableState,
+    filter: String,
+    mode: Mode,
+    message: String,
+}
+
+const HELP: &str =
+    "tab: switch list  ↑↓: move  /: filter  e: edit amount  p: mark paid  d: delete  q: quit";
+
+/// Run the interface, if there's a terminal to show it in
+pub(crate) fn handle_tui(moneybag: &mut Moneybag) -> Result<(), MoneybagsError> {
+    if !std::io::stdout().is_terminal() {
+        return Err(MoneybagsError::Invalid("tui needs a terminal".to_string()));
+    }
+    run(moneybag).map_err(|e| MoneybagsError::Io(format!("Could not run tui: {e}")))
+}
+
+/// Show invoices, costs, and the balance in full screen, until quit. Changes are made to the
+/// moneybag directly.
+fn run(moneybag: &mut Moneybag) -> std::io::Result<()> {
+    let mut app = App {
+        moneybag,
+        pane: Pane::Invoices,
+        invoices: TableState::default().with_selected(0),
+        costs: TableState::default().with_selected(0),
+        filter: String::new(),
+        mode: Mode::Browse,
+        message: HELP.to_string(),
+    };
+    ratatui::run(|terminal| app.run(terminal))
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Indices of the entries in a list that match the filter
+    fn visible(&self, pane: Pane) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        let matches = |entry: String| entry.to_lowercase().contains(&filter);
+        match pane {
+            Pane::Invoices => (0..self.moneybag.invoices.len())
+                .filter(|&i| matches(self.moneybag.invoices[i].to_string()))
+                .collect(),
+            Pane::Costs => (0..self.moneybag.costs.len())
+                .filter(|&i| matches(self.moneybag.costs[i].to_string()))
+                .collect(),
+        }
+    }
+
+    fn state(&mut self, pane: Pane) -> &mut TableState {
+        match pane {
+            Pane::Invoices => &mut self.invoices,
+            Pane::Costs => &mut self.costs,
+        }
+    }
+
+    /// Index of the selected entry in the focused list
+    fn selected(&self) -> Option<usize> {
+        let state = match self.pane {
+            Pane::Invoices => &self.invoices,
+            Pane::Costs => &self.costs,
+        };
+        state
+            .selected()
+            .and_then(|row| self.visible(self.pane).get(row).copied())
+    }
+
+    /// Keep selections within lists that may have shrunk
+    fn clamp_selections(&mut self) {
+        for pane in [Pane::Invoices, Pane::Costs] {
+            let len = self.visible(pane).len();
+            let state = self.state(pane);
+            state.select(Some(
+                state.selected().unwrap_or(0).min(len.saturating_sub(1)),
+            ));
+        }
+    }
+
+    /// Handle a key. Returns false to quit.
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => return self.browse(key),
+            Mode::Filter => match key {
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.mode = Mode::Filter;
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.mode = Mode::Filter;
+                }
+                KeyCode::Esc => self.filter.clear(),
+                _ => {}
+            },
+            Mode::Edit(mut input) => match key {
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.mode = Mode::Edit(input);
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.mode = Mode::Edit(input);
+                }
+                KeyCode::Enter => match input.parse() {
+                    Ok(amount) => self.set_amount(amount),
+                    Err(_) => self.message = format!("Could not parse amount {input}"),
+                },
+                _ => {}
+            },
+            Mode::Delete => {
+                if key == KeyCode::Char('y') {
+                    self.delete();
+                }
+            }
+        }
+        self.clamp_selections();
+        true
+    }
+
+    fn browse(&mut self, key: KeyCode) -> bool {
+        self.message = HELP.to_string();
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.pane = match self.pane {
+                    Pane::Invoices => Pane::Costs,
+                    Pane::Costs => Pane::Invoices,
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = self.visible(self.pane).len().saturating_sub(1);
+                let state = self.state(self.pane);
+                state.select(Some(state.selected().map_or(0, |row| (row + 1).min(last))));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let state = self.state(self.pane);
+                state.select(Some(state.selected().unwrap_or(0).saturating_sub(1)));
+            }
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some(index) = self.selected() {
+                    let amount = match self.pane {
+                        Pane::Invoices => self.moneybag.invoices[index].amount,
+                        Pane::Costs => self.moneybag.costs[index].amount,
+                    };
+                    self.mode = Mode::Edit(amount.to_string());
+                }
+            }
+            KeyCode::Char('d') if self.selected().is_some() => self.mode = Mode::Delete,
+            KeyCode::Char('p') if self.pane == Pane::Invoices => {
+                if let Some(index) = self.selected() {
+                    let today = Local::now().date_naive();
+                    let mut invoice = self.moneybag.invoices[index].clone();
+                    invoice.paid = Some(today);
+                    let id = invoice.id;
+                    self.moneybag
+                        .change(events::Event::InvoiceEdited { invoice });
+                    self.message = format!("Marked invoice {id} paid {today}");
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn set_amount(&mut self, amount: Money) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        let event = match self.pane {
+            Pane::Invoices => {
+                let mut invoice = self.moneybag.invoices[index].clone();
+                invoice.amount = amount;
+                events::Event::InvoiceEdited { invoice }
+            }
+            Pane::Costs => {
+                let mut cost = self.moneybag.costs[index].clone();
+                cost.amount = amount;
+                events::Event::CostEdited { cost }
+            }
+        };
+        self.moneybag.change(event);
+        self.message = format!("Changed amount to {amount}");
+    }
+
+    fn delete(&mut self) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        self.message = match self.pane {
+            Pane::Invoices => format!("Deleted invoice {}", self.moneybag.delete_invoice(index)),
+            Pane::Costs => {
+                let cost = self.moneybag.costs[index].clone();
+                self.moneybag
+                    .change(events::Event::CostDeleted { id: cost.id });
+                format!("Deleted cost {cost}")
+            }
+        };
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+        let [lists, summary] =
+            Layout::horizontal([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .areas(main);
+        let [invoices, costs] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(lists);
+        self.draw_invoices(frame, invoices);
+        self.draw_costs(frame, costs);
+        frame.render_widget(
+            Paragraph::new(self.balance()).block(Block::bordered().title("Balance")),
+            summary,
+        );
+        let (title, text) = match &self.mode {
+            Mode::Browse if self.filter.is_empty() => ("Help", self.message.clone()),
+            Mode::Browse => (
+                "Help",
+                format!("{} (filter: {})", self.message, self.filter),
+            ),
+            Mode::Filter => ("Filter, enter to keep, esc to clear", self.filter.clone()),
+            Mode::Edit(input) => ("New amount, enter to save, esc to cancel", input.clone()),
+            Mode::Delete => ("Delete the selected entry? y/n", String::new()),
+        };
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title(title)),
+            status,
+        );
+    }
+
+    fn block(&self, pane: Pane, title: &'static str) -> Block<'static> {
+        let block = Block::bordered().title(title);
+        if self.pane == pane {
+            block.border_style(Style::new().fg(Color::Yellow))
+        } else {
+            block
+        }
+    }
+
+    fn draw_invoices(&mut self, frame: &mut Frame, area: Rect) {
+        let today = Local::now().date_naive();
+        let rows = self.visible(Pane::Invoices).into_iter().map(|i| {
+            let invoice = &self.moneybag.invoices[i];
+            let status = match invoice.paid {
+                Some(paid) => format!("paid {paid}"),
+                None if self.moneybag.is_overdue(invoice, today) => "overdue".to_string(),
+                None => String::new(),
+            };
+            let row = Row::new([
+                invoice.id.to_string(),
+                invoice.number.clone().unwrap_or_default(),
+                invoice.date.clone(),
+                invoice.customer.clone().unwrap_or_default(),
+                invoice.base_total().to_string(),
+                status,
+            ]);
+            if invoice.paid.is_none() && self.moneybag.is_overdue(invoice, today) {
+                row.style(Style::new().fg(Color::Red))
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(4),
+                Constraint::Length(14),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+                Constraint::Length(12),
+                Constraint::Length(15),
+            ],
+        )
+        .header(header(&[
+            "#", "number", "date", "customer", "total", "status",
+        ]))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(self.block(Pane::Invoices, "Invoices"));
+        frame.render_stateful_widget(table, area, &mut self.invoices);
+    }
+
+    fn draw_costs(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.visible(Pane::Costs).into_iter().map(|i| {
+            let cost = &self.moneybag.costs[i];
+            Row::new([
+                cost.id.to_string(),
+                cost.date.clone(),
+                cost.name.clone(),
+                cost.category.clone().unwrap_or_default(),
+                cost.amount.to_string(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(4),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+                Constraint::Length(14),
+                Constraint::Length(12),
+            ],
+        )
+        .header(header(&["#", "date", "name", "category", "amount"]))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(self.block(Pane::Costs, "Costs"));
+        frame.render_stateful_widget(table, area, &mut self.costs);
+    }
+
+    fn balance(&self) -> String {
+        let costs = sum_costs(&self.moneybag.costs);
+        let input_vat = sum_input_vat(&self.moneybag.costs);
+        let this_month = Local::now().format("%Y-%m").to_string();
+        let depreciation = sum_depreciation(&self.moneybag.assets, &this_month);
+        let invoices = sum_invoices(&self.moneybag.invoices);
+        let today = Local::now().date_naive();
+        let overdue = self
+            .moneybag
+            .invoices
+            .iter()
+            .filter(|invoice| self.moneybag.is_overdue(invoice, today))
+            .count();
+        format!(
+            "Costs: {costs}\nInput VAT: {input_vat}\nDepreciation: {depreciation}\nInvoices: {invoices}\nTotal: {}\n\nOverdue invoices: {overdue}",
+            invoices - costs - depreciation
+        )
+    }
+}
+
+fn header(columns: &[&'static str]) -> Row<'static> {
+    Row::new(columns.to_vec()).style(Style::new().add_modifier(Modifier::BOLD))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_keys() {
+        let mut moneybag = fixtures::moneybag(
+            r#"{
+                "costs": [
+                    { "id": 1, "date": "2025-02", "amount": { "amount": 10000 }, "name": "vpn", "recurring": false },
+                    { "id": 2, "date": "2025-03", "amount": { "amount": 1200000 }, "name": "rent", "recurring": false }
+                ],
+                "customers": {}
+            }"#,
+        );
+        let mut app = App {
+            moneybag: &mut moneybag,
+            pane: Pane::Invoices,
+            invoices: TableState::default().with_selected(0),
+            costs: TableState::default().with_selected(0),
+            filter: String::new(),
+            mode: Mode::Browse,
+            message: String::new(),
+        };
+        let keys = "\t/rent\ne";
+        for key in keys.chars() {
+            let key = match key {
+                '\t' => KeyCode::Tab,
+                '\n' => KeyCode::Enter,
+                c => KeyCode::Char(c),
+            };
+            assert!(app.handle_key(key));
+        }
+        assert_eq!(app.selected(), Some(1));
+        app.mode = Mode::Edit("13000".to_string());
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('d'));
+        app.handle_key(KeyCode::Char('n'));
+        assert!(!app.handle_key(KeyCode::Char('q')));
+        assert_eq!(moneybag.costs.len(), 2);
+        assert_eq!(moneybag.costs[1].amount, "13000".parse().unwrap());
+    }
+}
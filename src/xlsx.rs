@@ -0,0 +1,244 @@
+//! The moneybag as an Excel workbook: invoices, costs, rates, and a monthly summary on sheets of
+//! their own, with amounts as numbers and dates as dates
+
+use chrono::{Datelike, NaiveDate};
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, Worksheet, XlsxError};
+
+use crate::{
+    money::Money,
+    moneybag::{Invoice, Moneybag},
+    report::{months_of, Totals},
+};
+
+/// A typed cell. Dates are kept as text, and written as dates if they parse as one.
+enum Cell {
+    Id(usize),
+    Text(String),
+    Amount(Money),
+    Date(String),
+    Yes(bool),
+    Empty,
+}
+
+impl From<Option<String>> for Cell {
+    fn from(text: Option<String>) -> Self {
+        text.map_or(Cell::Empty, Cell::Text)
+    }
+}
+
+impl From<Option<Money>> for Cell {
+    fn from(amount: Option<Money>) -> Self {
+        amount.map_or(Cell::Empty, Cell::Amount)
+    }
+}
+
+/// A date like 2025-05-12, or a month like 2025-05, with the format to show it in
+fn excel_date(date: &str) -> Option<(ExcelDateTime, &'static str)> {
+    let (day, format) = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(day) => (day, "yyyy-mm-dd"),
+        Err(_) => (
+            NaiveDate::parse_from_str(&format!("{date}-01"), "%Y-%m-%d").ok()?,
+            "yyyy-mm",
+        ),
+    };
+    let datetime = ExcelDateTime::from_ymd(
+        u16::try_from(day.year()).ok()?,
+        u8::try_from(day.month()).ok()?,
+        u8::try_from(day.day()).ok()?,
+    );
+    Some((datetime.ok()?, format))
+}
+
+/// Add a sheet with a bold header row, frozen in place, and a row for each of rows
+fn add_sheet(
+    workbook: &mut Workbook,
+    name: &str,
+    headers: &[&str],
+    rows: impl IntoIterator<Item = Vec<Cell>>,
+) -> Result<(), XlsxError> {
+    let sheet = workbook.add_worksheet().set_name(name)?;
+    let bold = Format::new().set_bold();
+    for (column, header) in (0..).zip(headers) {
+        sheet.write_string_with_format(0, column, *header, &bold)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    for (row, cells) in (1..).zip(rows) {
+        for (column, cell) in (0..).zip(cells) {
+            write_cell(sheet, row, column, cell)?;
+        }
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+fn write_cell(sheet: &mut Worksheet, row: u32, column: u16, cell: Cell) -> Result<(), XlsxError> {
+    match cell {
+        Cell::Id(id) => {
+            sheet.write_number(
+                row,
+                column,
+                f64::from(u32::try_from(id).unwrap_or(u32::MAX)),
+            )?;
+        }
+        Cell::Text(text) => {
+            sheet.write_string(row, column, text)?;
+        }
+        Cell::Amount(amount) => {
+            let format = Format::new().set_num_format("#,##0.00");
+            sheet.write_number_with_format(row, column, amount.to_f64(), &format)?;
+        }
+        Cell::Date(date) => match excel_date(&date) {
+            Some((datetime, format)) => {
+                let format = Format::new().set_num_format(format);
+                sheet.write_datetime_with_format(row, column, datetime, &format)?;
+            }
+            None => {
+                sheet.write_string(row, column, date)?;
+            }
+        },
+        Cell::Yes(yes) => {
+            sheet.write_boolean(row, column, yes)?;
+        }
+        Cell::Empty => {}
+    }
+    Ok(())
+}
+
+fn invoice_row(invoice: &Invoice) -> Vec<Cell> {
+    vec![
+        Cell::Id(invoice.id),
+        invoice.number.clone().into(),
+        Cell::Date(invoice.date.clone()),
+        invoice.customer.clone().into(),
+        invoice.rate_name.clone().into(),
+        invoice.hours().into(),
+        Cell::Amount(invoice.total()),
+        invoice.currency().map(str::to_string).into(),
+        Cell::Amount(invoice.base_total()),
+        Cell::Amount(invoice.vat()),
+        invoice
+            .paid
+            .map(|paid| paid.to_string())
+            .map_or(Cell::Empty, Cell::Date),
+        Cell::Text(invoice.scope.to_string()),
+    ]
+}
+
+/// Write the moneybag to an Excel workbook at path
+pub(crate) fn export_xlsx(moneybag: &Moneybag, path: &str) -> Result<(), XlsxError> {
+    let mut workbook = Workbook::new();
+    add_sheet(
+        &mut workbook,
+        "Invoices",
+        &[
+            "ID",
+            "Number",
+            "Date",
+            "Customer",
+            "Rate",
+            "Hours",
+            "Total",
+            "Currency",
+            "Base total",
+            "VAT",
+            "Paid",
+            "Scope",
+        ],
+        moneybag.invoices.iter().map(invoice_row),
+    )?;
+    add_sheet(
+        &mut workbook,
+        "Costs",
+        &[
+            "ID",
+            "Date",
+            "Name",
+            "Category",
+            "Amount",
+            "VAT",
+            "Recurring",
+            "Customer",
+            "Billable to",
+            "Scope",
+            "Note",
+        ],
+        moneybag.costs.iter().map(|cost| {
+            vec![
+                Cell::Id(cost.id),
+                Cell::Date(cost.date.clone()),
+                Cell::Text(cost.name.clone()),
+                cost.category.clone().into(),
+                Cell::Amount(cost.amount),
+                Cell::Amount(cost.vat()),
+                Cell::Yes(cost.recurring),
+                cost.customer.clone().into(),
+                cost.billable
+                    .as_ref()
+                    .map(|billable| billable.customer.clone())
+                    .into(),
+                Cell::Text(cost.scope.to_string()),
+                cost.note.clone().into(),
+            ]
+        }),
+    )?;
+    add_sheet(
+        &mut workbook,
+        "Rates",
+        &["Name", "Rate", "Currency", "Increment", "Tiers"],
+        moneybag.rates.iter().map(|(name, rate)| {
+            let tiers: Vec<_> = rate
+                .tiers
+                .iter()
+                .map(|(tier, multiplier)| format!("{tier} {multiplier}"))
+                .collect();
+            vec![
+                Cell::Text(name.clone()),
+                Cell::Amount(rate.rate),
+                rate.currency.clone().into(),
+                rate.increment.into(),
+                Some(tiers.join(", "))
+                    .filter(|tiers| !tiers.is_empty())
+                    .into(),
+            ]
+        }),
+    )?;
+    add_sheet(
+        &mut workbook,
+        "Monthly",
+        &["Month", "Invoiced", "Costs", "Depreciation", "Net"],
+        months_of("", moneybag).into_iter().map(|month| {
+            let totals = Totals::of(&month, None, moneybag);
+            vec![
+                Cell::Date(month),
+                Cell::Amount(totals.invoiced),
+                Cell::Amount(totals.costs),
+                Cell::Amount(totals.depreciation),
+                Cell::Amount(totals.net()),
+            ]
+        }),
+    )?;
+    workbook.save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_excel_date() {
+        let (_, format) = excel_date("2025-05-12").unwrap();
+        assert_eq!(format, "yyyy-mm-dd");
+        let (_, format) = excel_date("2025-05").unwrap();
+        assert_eq!(format, "yyyy-mm");
+        assert!(excel_date("2025-13").is_none());
+        assert!(excel_date("soon").is_none());
+    }
+
+    #[test]
+    fn test_export_xlsx() {
+        let dir = std::env::temp_dir().join(format!("moneybags-no-xlsx-{}", std::process::id()));
+        let path = dir.join("moneybag.xlsx");
+        assert!(export_xlsx(&Moneybag::default(), &path.to_string_lossy()).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,621 @@
+//! Commands run the way the shell runs them, on a moneybag in memory
+
+use moneybags::{
+    events::{Event, LogEntry},
+    money::Money,
+    moneybag::Moneybag,
+    run_command,
+    storage::{self, Storage},
+    CommandOutput, MoneybagsError, QueryResult,
+};
+
+fn try_run(moneybag: &mut Moneybag, line: &str) -> Result<CommandOutput, MoneybagsError> {
+    run_command(shlex::split(line).unwrap(), moneybag)
+}
+
+fn run(moneybag: &mut Moneybag, line: &str) -> CommandOutput {
+    try_run(moneybag, line).unwrap_or_else(|e| panic!("{line}: {e}"))
+}
+
+fn moneybag(lines: &[&str]) -> Moneybag {
+    let mut moneybag = Moneybag::default();
+    for line in lines {
+        run(&mut moneybag, line);
+    }
+    moneybag
+}
+
+/// Run the program on a moneybag file of its own, with `input` for prompts, returning its exit
+/// code and output
+fn run_program(name: &str, lines: &[&str], input: &str) -> (Option<i32>, String) {
+    use std::io::Write;
+
+    let path = std::env::temp_dir().join(format!("moneybags-{name}-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut result = (None, String::new());
+    for (i, line) in lines.iter().enumerate() {
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_moneybags"))
+            .args(["--config", "/dev/null", "-f", path.to_str().unwrap()])
+            .args(shlex::split(line).unwrap())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let last = i + 1 == lines.len();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(if last { input.as_bytes() } else { b"" })
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        result = (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).into_owned()
+                + &*String::from_utf8_lossy(&output.stderr),
+        );
+    }
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(moneybags::events::log_path(path.to_str().unwrap()));
+    result
+}
+
+#[test]
+fn test_add_and_list() {
+    let mut moneybag = moneybag(&[
+        "add rate 950 standard",
+        "add customer Acme --terms 30",
+        "add invoice 2025-05-31 10 --rate standard --customer Acme",
+        "add cost 2025-05 100 vpn --category net",
+    ]);
+    assert_eq!(moneybag.invoices[0].total(), "9500".parse().unwrap());
+    let CommandOutput::Invoices { invoices, .. } = run(&mut moneybag, "list invoices") else {
+        panic!("Expected invoices");
+    };
+    assert_eq!(invoices.len(), 1);
+    assert_eq!(invoices[0].customer.as_deref(), Some("Acme"));
+    let CommandOutput::Costs { costs, .. } = run(&mut moneybag, "list costs --name-contains vp")
+    else {
+        panic!("Expected costs");
+    };
+    assert_eq!(costs[0].category.as_deref(), Some("net"));
+}
+
+#[test]
+fn test_balance() {
+    let mut moneybag = moneybag(&[
+        "add invoice 2025-05-31 1000",
+        "add cost 2025-05 250 rent",
+        "add cost 2025-05 50 coffee --scope personal",
+    ]);
+    let CommandOutput::Balance(balance) = run(&mut moneybag, "balance --scope business") else {
+        panic!("Expected a balance");
+    };
+    assert_eq!(balance.invoices, "1000".parse().unwrap());
+    assert_eq!(balance.costs, "250".parse().unwrap());
+    assert_eq!(balance.total, "750".parse().unwrap());
+}
+
+#[test]
+fn test_transactions_end_at_the_balance() {
+    let mut moneybag = moneybag(&[
+        "add invoice 2025-05-31 1000",
+        "add cost 2025-05 250 rent --vat 25%",
+        "add cost 2025-06-02 100 vpn --vat 20",
+    ]);
+    let CommandOutput::Transactions(transactions) = run(&mut moneybag, "list transactions") else {
+        panic!("Expected the transactions");
+    };
+    let CommandOutput::Balance(balance) = run(&mut moneybag, "balance") else {
+        panic!("Expected a balance");
+    };
+    assert_eq!(transactions.last().unwrap().balance, balance.total);
+    assert_eq!(balance.total, "720".parse().unwrap());
+}
+
+#[test]
+fn test_ids_stay_when_deleting() {
+    let mut moneybag = moneybag(&[
+        "add cost 2025-05 1 a",
+        "add cost 2025-05 2 b",
+        "add cost 2025-05 3 c",
+    ]);
+    run(&mut moneybag, "delete -y cost 2");
+    run(&mut moneybag, "add cost 2025-05 4 d");
+    let ids: Vec<_> = moneybag.costs.iter().map(|cost| cost.id).collect();
+    assert_eq!(ids, [1, 3, 4]);
+    run(&mut moneybag, "delete -y cost 4");
+    run(&mut moneybag, "delete -y cost 3");
+    assert_eq!(moneybag.costs[0].name, "a");
+}
+
+#[test]
+fn test_last_is_what_was_added_this_session() {
+    let (code, output) = run_program(
+        "last",
+        &["add cost 2025-05 1 a", "--keep-going"],
+        "delete -y cost last\nadd cost 2025-05 2 b\nadd invoice 2025-05-31 100\n\
+         delete -y cost last\n",
+    );
+    assert!(output.contains("No cost added this session"), "{output}");
+    assert!(
+        output.contains("Deleted cost 2: 2025-05 2.00 b"),
+        "{output}"
+    );
+    assert_eq!(code, Some(3));
+    let mut moneybag = moneybag(&["add cost 2025-05 1 a"]);
+    assert!(matches!(
+        try_run(&mut moneybag, "edit cost last"),
+        Err(MoneybagsError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_failing_command_changes_nothing() {
+    let mut moneybag = moneybag(&["add invoice 2025-05-31 1000"]);
+    let result = try_run(&mut moneybag, "pay 9");
+    assert!(matches!(result, Err(MoneybagsError::NotFound(_))));
+    assert!(matches!(
+        try_run(&mut moneybag, "pay yesterday"),
+        Err(MoneybagsError::Usage(_))
+    ));
+    assert!(moneybag.invoices[0].paid.is_none());
+    run(&mut moneybag, "pay 1 2025-06-30");
+    assert!(moneybag.invoices[0].paid.is_some());
+}
+
+#[test]
+fn test_customer_filters_count_costs_for_them() {
+    let mut moneybag = moneybag(&[
+        "add customer Acme",
+        "add customer Beta",
+        "add invoice 2025-05-31 1000 --customer Acme",
+        "add cost 2025-05-10 300 license --for Beta",
+        "add cost 2025-05-11 200 tool --for Acme",
+        "add cost 2025-05-12 50 rent",
+    ]);
+    let CommandOutput::Costs { costs, .. } = run(&mut moneybag, "list costs --customer Acme")
+    else {
+        panic!("Expected costs");
+    };
+    assert_eq!(costs.len(), 1);
+    assert_eq!(costs[0].name, "tool");
+    let CommandOutput::Balance(balance) = run(&mut moneybag, "balance --customer Acme") else {
+        panic!("Expected a balance");
+    };
+    assert_eq!(balance.costs, "200".parse().unwrap());
+    assert_eq!(balance.total, "800".parse().unwrap());
+    let CommandOutput::Costs { costs, .. } = run(&mut moneybag, "list costs --sort customer")
+    else {
+        panic!("Expected costs");
+    };
+    let names: Vec<_> = costs.iter().map(|cost| cost.name.as_str()).collect();
+    assert_eq!(names, ["rent", "tool", "license"]);
+}
+
+#[test]
+fn test_bill_expenses() {
+    let mut moneybag = moneybag(&[
+        "add customer Acme",
+        "add invoice 2025-05-31 1000 --customer Acme",
+        "add cost 2025-05-12 450 train --billable Acme",
+        "add cost 2025-05-13 90 taxi --billable Acme",
+    ]);
+    let CommandOutput::Messages(billed) = run(&mut moneybag, "bill 1 2") else {
+        panic!("Expected messages");
+    };
+    assert_eq!(billed, ["Billed 2: taxi 90.00"]);
+    assert_eq!(moneybag.invoices[0].total(), "1090".parse().unwrap());
+    run(&mut moneybag, "bill 1");
+    assert_eq!(moneybag.invoices[0].total(), "1540".parse().unwrap());
+    let CommandOutput::Costs { costs, .. } = run(&mut moneybag, "list billable") else {
+        panic!("Expected costs");
+    };
+    assert!(costs.is_empty());
+    // Deleting the invoice lets its expenses be billed again
+    run(&mut moneybag, "delete -y invoice 1");
+    let CommandOutput::Costs { costs, .. } = run(&mut moneybag, "list billable") else {
+        panic!("Expected costs");
+    };
+    assert_eq!(costs.len(), 2);
+}
+
+#[test]
+fn test_bill_expenses_bills_none_if_one_cannot_be() {
+    let mut moneybag = moneybag(&[
+        "add customer Acme",
+        "add invoice 2025-05-31 1000 --customer Acme",
+        "add cost 2025-05-12 450 train --billable Acme",
+        "add cost 2025-05-13 90 taxi",
+    ]);
+    assert!(matches!(
+        try_run(&mut moneybag, "bill 1 1 2"),
+        Err(MoneybagsError::Invalid(_))
+    ));
+    assert!(matches!(
+        try_run(&mut moneybag, "bill 1 7"),
+        Err(MoneybagsError::NotFound(_))
+    ));
+    moneybag.invoices[0].exchange_rate = Some(Money::default());
+    assert!(matches!(
+        try_run(&mut moneybag, "bill 1"),
+        Err(MoneybagsError::Invalid(_))
+    ));
+    assert!(moneybag.invoices[0].expenses.is_empty());
+    assert!(!moneybag.costs[0].billable.as_ref().unwrap().billed);
+}
+
+#[test]
+fn test_exchange_rate_is_positive() {
+    let mut moneybag = Moneybag::default();
+    assert!(matches!(
+        try_run(&mut moneybag, "set exchange-rate EUR 0"),
+        Err(MoneybagsError::Invalid(_))
+    ));
+    assert!(try_run(&mut moneybag, "set exchange-rate EUR -11.5").is_err());
+    run(&mut moneybag, "set exchange-rate EUR 11.5");
+    assert_eq!(
+        moneybag.settings.exchange_rates["EUR"],
+        "11.5".parse().unwrap()
+    );
+}
+
+#[test]
+fn test_search() {
+    let mut moneybag = moneybag(&[
+        "add rate 950 standard",
+        "add customer Acme",
+        "add invoice 2025-05-31 10 --rate standard --customer Acme",
+        "add cost 2025-05 100 acme-lunch",
+        "add cost 2025-05 100 rent",
+    ]);
+    let CommandOutput::Found {
+        invoices,
+        costs,
+        rates,
+    } = run(&mut moneybag, "search acme")
+    else {
+        panic!("Expected search results");
+    };
+    assert_eq!(invoices.len(), 1);
+    assert_eq!(costs.len(), 1);
+    assert!(rates.is_empty());
+}
+
+#[test]
+fn test_commands_return_what_they_show() {
+    let mut moneybag = moneybag(&["add rate 950 standard"]);
+    let CommandOutput::Added(added) = run(&mut moneybag, "add cost 2025-05 100 vpn") else {
+        panic!("Expected the added cost");
+    };
+    assert_eq!(added.costs.len(), 1);
+    assert!(added.invoices.is_empty() && added.rates.is_empty());
+    let CommandOutput::Cost(cost) = run(&mut moneybag, "show cost 1") else {
+        panic!("Expected the cost");
+    };
+    assert_eq!(cost.name, "vpn");
+    assert!(matches!(
+        run(&mut moneybag, "report yearly"),
+        CommandOutput::Report(_)
+    ));
+    let CommandOutput::Query(QueryResult::Value(count)) =
+        run(&mut moneybag, "query count from costs")
+    else {
+        panic!("Expected the value of the query");
+    };
+    assert_eq!(count, "1");
+    let CommandOutput::Forecast(forecast) = run(&mut moneybag, "forecast 2") else {
+        panic!("Expected the forecast");
+    };
+    assert_eq!(forecast.months.len(), 2);
+    run(&mut moneybag, "set revenue-goal 1000");
+    assert!(matches!(run(&mut moneybag, "goal"), CommandOutput::Goal(_)));
+    let CommandOutput::Messages(adjusted) = run(&mut moneybag, "adjust 2025-01 10% --name vpn")
+    else {
+        panic!("Expected what was adjusted");
+    };
+    assert_eq!(adjusted, ["Adjusted 1 costs by 10.00%"]);
+    assert!(matches!(
+        try_run(&mut moneybag, "adjust 25-1 10%"),
+        Err(MoneybagsError::Usage(_))
+    ));
+    let CommandOutput::Messages(deleted) = run(&mut moneybag, "delete -y cost 1") else {
+        panic!("Expected what was deleted");
+    };
+    assert_eq!(deleted, ["Deleted cost 1: 2025-05 110.00 vpn"]);
+    assert!(moneybag.costs.is_empty());
+}
+
+#[test]
+fn test_log_replays() {
+    let moneybag = moneybag(&[
+        "add rate 950 standard",
+        "add customer Acme",
+        "add invoice 2025-05-31 10 --rate standard --customer Acme",
+        "add cost 2025-05 100 vpn",
+        "add cost 2025-05 200 rent",
+        "delete -y cost 1",
+        "pay 1 2025-06-30",
+        "add budget office 500",
+        "set tax-rate 30",
+        "delete -y rate standard",
+    ]);
+    let replayed = moneybags::events::replay(&moneybag.log);
+    let json = |moneybag: &Moneybag| serde_json::to_string(moneybag).unwrap();
+    assert_eq!(json(&replayed), json(&moneybag));
+
+    let path = std::env::temp_dir().join(format!("moneybags-test-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    moneybags::save_moneybag(&moneybag, path).unwrap();
+    let loaded = moneybags::load_moneybag(path).unwrap();
+    assert_eq!(loaded.log.len(), moneybag.log.len());
+    assert_eq!(json(&loaded), json(&moneybag));
+    // Changed by hand, the log starts over from what's in the file
+    std::fs::write(path, json(&Moneybag::default())).unwrap();
+    let loaded = moneybags::load_moneybag(path).unwrap();
+    assert_eq!(loaded.log.len(), moneybag.log.len() + 1);
+    assert!(loaded.costs.is_empty());
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(moneybags::events::log_path(path)).unwrap();
+}
+
+#[test]
+fn test_log_is_appended() {
+    let path = std::env::temp_dir().join(format!("moneybags-append-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let log_path = moneybags::events::log_path(path);
+    let file = storage::JsonFile::new(path);
+    let mut moneybag = storage::open(&file).unwrap();
+    run(&mut moneybag, "add cost 2025-05 100 vpn");
+    file.save(&moneybag).unwrap();
+    // Entries already written stay as they are, even if they'd be written differently now
+    let written = std::fs::read_to_string(&log_path).unwrap();
+    let marked = written.replacen('\n', " \n", 1);
+    std::fs::write(&log_path, &marked).unwrap();
+    let file = storage::JsonFile::new(path);
+    let mut moneybag = storage::open(&file).unwrap();
+    run(&mut moneybag, "add cost 2025-06 100 vpn");
+    file.save(&moneybag).unwrap();
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    assert!(log.starts_with(&marked), "{log}");
+    assert_eq!(log.lines().count(), moneybag.log.len());
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(log_path).unwrap();
+}
+
+/// Keeps the moneybag as JSON in memory, to show storage doesn't have to be a file
+#[derive(Default)]
+struct Memory(std::cell::RefCell<Option<String>>);
+
+impl Storage for Memory {
+    fn load(&self) -> Result<Moneybag, MoneybagsError> {
+        Ok(self
+            .0
+            .borrow()
+            .as_deref()
+            .map(|json| serde_json::from_str(json).unwrap())
+            .unwrap_or_default())
+    }
+
+    fn save(&self, moneybag: &Moneybag) -> Result<(), MoneybagsError> {
+        *self.0.borrow_mut() = Some(serde_json::to_string(moneybag).unwrap());
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        "memory".to_string()
+    }
+}
+
+#[test]
+fn test_other_storage() {
+    let memory = Memory::default();
+    let mut moneybag = storage::open(&memory).unwrap();
+    run(&mut moneybag, "add customer Acme");
+    run(&mut moneybag, "add invoice 2025-05-31 1000 --customer Acme");
+    memory.save(&moneybag).unwrap();
+    let loaded = storage::open(&memory).unwrap();
+    assert_eq!(loaded.invoices[0].customer.as_deref(), Some("Acme"));
+    // This storage doesn't keep the log, so it starts over from what was loaded
+    assert!(matches!(
+        loaded.log[..],
+        [LogEntry { ref events, .. }] if matches!(events[..], [Event::Started { .. }])
+    ));
+}
+
+#[test]
+fn test_prompt_at_end_of_input_fails() {
+    // Out of input at the amount, the wizard fails rather than asking again forever
+    let (code, output) = run_program("eof", &["add invoice"], "2025-05-01\n\n");
+    assert!(output.contains("Could not read stdin"), "{output}");
+    assert_eq!(code, Some(4), "{output}");
+    let (code, output) = run_program("eof-edit", &["add cost 2025-05 100 vpn", "edit cost 1"], "");
+    assert!(output.contains("Could not read stdin"), "{output}");
+    assert_eq!(code, Some(4), "{output}");
+}
+
+#[test]
+fn test_handler_errors() {
+    let mut moneybag = moneybag(&["add rate 950 standard"]);
+    assert!(matches!(
+        try_run(&mut moneybag, "add asset 2025-05-01 1000 laptop 0"),
+        Err(MoneybagsError::Invalid(_))
+    ));
+    assert!(matches!(
+        try_run(&mut moneybag, "delete -y tier standard weekend"),
+        Err(MoneybagsError::NotFound(_))
+    ));
+    assert!(matches!(
+        try_run(&mut moneybag, "add cost mileage 2025-05-01 120"),
+        Err(MoneybagsError::Invalid(_))
+    ));
+    assert!(moneybag.assets.is_empty() && moneybag.costs.is_empty());
+}
+
+#[test]
+fn test_mistyped_rate_in_wizard_is_retried() {
+    let (code, output) = run_program(
+        "wizard",
+        &["add rate 100 standard", "add invoice"],
+        "2025-05-01\nstandrad\nstandard\n2\n\n",
+    );
+    assert!(
+        output.contains("Rate standrad not found in rates"),
+        "{output}"
+    );
+    assert_eq!(code, Some(0), "{output}");
+}
+
+#[test]
+fn test_running_totals_of_an_empty_scope() {
+    let mut moneybag = moneybag(&["add cost 2025-05 100 vpn"]);
+    let CommandOutput::Costs { costs, running } =
+        run(&mut moneybag, "list costs --running --scope personal")
+    else {
+        panic!("Expected costs");
+    };
+    assert!(costs.is_empty() && running);
+    assert!(matches!(
+        try_run(&mut moneybag, "list costs --running --scope work"),
+        Err(MoneybagsError::Usage(_))
+    ));
+}
+
+#[test]
+fn test_balance_of_an_empty_scope() {
+    let mut moneybag = moneybag(&["add invoice 2025-05-31 1000"]);
+    let CommandOutput::Balance(balance) = run(&mut moneybag, "balance --scope personal") else {
+        panic!("Expected a balance");
+    };
+    assert!(balance.invoices.is_zero() && balance.total.is_zero());
+    assert!(matches!(
+        try_run(&mut moneybag, "balance --scope work"),
+        Err(MoneybagsError::Usage(_))
+    ));
+}
+
+#[test]
+fn test_stats_without_invoices() {
+    let (code, output) = run_program("stats", &["stats invoices --period 2024"], "");
+    assert!(output.contains("No invoices to summarize"), "{output}");
+    assert_eq!(code, Some(3), "{output}");
+}
+
+#[test]
+fn test_stats_of_an_unsaved_file() {
+    let (code, output) = run_program("stats-file", &["stats file"], "");
+    assert!(output.contains("Size: not saved yet"), "{output}");
+    assert_eq!(code, Some(0), "{output}");
+}
+
+#[test]
+fn test_dashboard_of_a_new_moneybag() {
+    let (code, output) = run_program("dashboard", &["dashboard"], "");
+    assert!(output.contains("Unpaid: 0.00 in 0 invoices"), "{output}");
+    assert!(!output.contains("Overdue"), "{output}");
+    assert!(!output.contains("Goal"), "{output}");
+    assert_eq!(code, Some(0), "{output}");
+}
+
+#[test]
+fn test_library_refuses_shell_commands() {
+    let mut moneybag = Moneybag::default();
+    for line in ["", "quit", "preview", "save"] {
+        assert!(
+            matches!(try_run(&mut moneybag, line), Err(MoneybagsError::Usage(_))),
+            "{line}"
+        );
+    }
+    assert!(moneybag.log.is_empty());
+}
+
+#[test]
+fn test_missing_ids_are_not_found() {
+    let mut moneybag = moneybag(&["add invoice 2025-05-31 1000", "add cost 2025-05 100 vpn"]);
+    for line in [
+        "show invoice 2",
+        "show cost 0",
+        "edit cost 5",
+        "delete -y invoice 7",
+        "delete -y asset 1",
+    ] {
+        assert!(
+            matches!(
+                try_run(&mut moneybag, line),
+                Err(MoneybagsError::NotFound(_))
+            ),
+            "{line}"
+        );
+    }
+    assert!(matches!(
+        try_run(&mut moneybag, "show cost -1"),
+        Err(MoneybagsError::Usage(_))
+    ));
+    assert_eq!((moneybag.invoices.len(), moneybag.costs.len()), (1, 1));
+}
+
+#[test]
+fn test_commands_return_why_they_failed() {
+    let mut moneybag = moneybag(&["add invoice 2025-05-31 1000"]);
+    assert!(matches!(
+        try_run(&mut moneybag, "report vat --quarter 2025-Q5"),
+        Err(MoneybagsError::Invalid(_))
+    ));
+    assert!(matches!(
+        try_run(&mut moneybag, "show customer Nobody"),
+        Err(MoneybagsError::NotFound(message)) if message.starts_with("Customer Nobody not found")
+    ));
+    let path = std::env::temp_dir().join(format!("moneybags-no-dir-{}", std::process::id()));
+    let out = path.join("report.csv");
+    assert!(matches!(
+        try_run(
+            &mut moneybag,
+            &format!("report yearly --out {}", out.display())
+        ),
+        Err(MoneybagsError::Write { .. })
+    ));
+}
+
+#[test]
+fn test_script_stops_at_a_bad_line() {
+    let (code, output) = run_program(
+        "script-stops",
+        &[""],
+        "add cost 2025-05 1 a\nfrobnicate\nadd cost 2025-05 2 b\n",
+    );
+    assert!(
+        output.contains("line 2: error: unrecognized subcommand"),
+        "{output}"
+    );
+    assert!(output.contains("Stopping, nothing saved"), "{output}");
+    assert!(!output.contains("2.00  b"), "{output}");
+    assert_eq!(code, Some(2), "{output}");
+}
+
+#[test]
+fn test_unwritable_log_file() {
+    let path = std::env::temp_dir().join(format!("moneybags-no-dir-{}", std::process::id()));
+    let log = path.join("moneybags.log");
+    let (code, output) = run_program(
+        "log-file",
+        &[&format!("--log-file {} balance", log.display())],
+        "",
+    );
+    assert!(output.contains("Could not open"), "{output}");
+    assert_eq!(code, Some(4), "{output}");
+}
+
+#[test]
+fn test_export_without_a_place_to_save() {
+    let path = std::env::temp_dir().join(format!("moneybags-no-dir-{}", std::process::id()));
+    let out = path.join("moneybag.xlsx");
+    let (code, output) = run_program("xlsx", &[&format!("export xlsx {}", out.display())], "");
+    if cfg!(feature = "xlsx") {
+        assert!(output.contains("Could not save"), "{output}");
+        assert_eq!(code, Some(4), "{output}");
+    } else {
+        assert!(output.contains("built without xlsx"), "{output}");
+        assert_eq!(code, Some(5), "{output}");
+    }
+}